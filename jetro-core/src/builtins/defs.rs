@@ -2183,6 +2183,30 @@ impl Builtin for ToTsv {
     }
 }
 
+/// `to_msgpack()` — MessagePack serialiser, as base64 text.
+pub(crate) struct ToMsgpack;
+impl Builtin for ToMsgpack {
+    const METHOD: BuiltinMethod = BuiltinMethod::ToMsgpack;
+    const NAME: &'static str = "to_msgpack";
+    fn spec() -> BuiltinSpec { serialization_spec() }
+    #[inline]
+    fn apply_one(recv: &crate::data::value::Val) -> Option<crate::data::value::Val> {
+        Some(super::to_msgpack_apply(recv))
+    }
+}
+
+/// `to_cbor()` — CBOR serialiser, as base64 text.
+pub(crate) struct ToCbor;
+impl Builtin for ToCbor {
+    const METHOD: BuiltinMethod = BuiltinMethod::ToCbor;
+    const NAME: &'static str = "to_cbor";
+    fn spec() -> BuiltinSpec { serialization_spec() }
+    #[inline]
+    fn apply_one(recv: &crate::data::value::Val) -> Option<crate::data::value::Val> {
+        Some(super::to_cbor_apply(recv))
+    }
+}
+
 /// `equi_join(left, right, on)` — relational join barrier.
 pub(crate) struct EquiJoin;
 impl Builtin for EquiJoin {
@@ -2556,6 +2580,19 @@ impl Builtin for FromJson {
     }
 }
 
+/// `from_csv` / `parse_csv` — CSV string → array of row objects.
+pub(crate) struct FromCsv;
+impl Builtin for FromCsv {
+    const METHOD: BuiltinMethod = BuiltinMethod::FromCsv;
+    const NAME: &'static str = "from_csv";
+    const ALIASES: &'static [&'static str] = &["parse_csv"];
+    fn spec() -> BuiltinSpec { default_scalar_spec(BuiltinMethod::FromCsv) }
+    #[inline]
+    fn apply_one(recv: &crate::data::value::Val) -> Option<crate::data::value::Val> {
+        Some(super::from_csv_apply(recv).unwrap_or_else(|| recv.clone()))
+    }
+}
+
 /// `includes(item)` / `contains(item)` — array membership scalar.
 pub(crate) struct Includes;
 impl Builtin for Includes {
@@ -0,0 +1,252 @@
+//! Host functions: caller-registered Rust closures callable from queries.
+//!
+//! A `JetroEngine` owns a `HostRegistry`. Planning consults it to decide how
+//! host calls lower (element-wise calls in a root chain stream as `map` stages;
+//! impure calls become `GlobalCall`s so optimisers never duplicate or drop
+//! them, and a chain whose later steps would stop pulling rows before an impure
+//! stage is not lowered to a pipeline at all), and execution installs it as the
+//! thread's active registry so the VM can resolve `BuiltinMethod::Unknown`
+//! calls against it. The VM maps element-wise functions over array receivers
+//! itself, so every call form gives the same result and the `map` lowering is
+//! only an optimisation.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::builtins::BuiltinMethod;
+use crate::data::context::EvalError;
+use crate::data::runtime::RESERVED_GLOBALS;
+use crate::data::value::Val;
+
+/// Signature shared by every host closure: `(receiver, args) -> result`.
+type HostFn = dyn Fn(&Value, &[Value]) -> Result<Value, EvalError> + Send + Sync;
+
+/// A Rust closure exposed to queries under a name chosen at registration.
+///
+/// The closure receives the receiver value and `arity` positional arguments.
+/// It is callable both as a method, `$.price.to_usd()`, and as a global,
+/// `to_usd($.price)`; the global form passes its first argument as the
+/// receiver, and a zero-argument global call receives the document root.
+///
+/// ```rust
+/// use jetro_core::{HostFunction, Jetro, JetroEngine};
+/// use serde_json::json;
+///
+/// let mut engine = JetroEngine::new();
+/// engine
+///     .register_function(
+///         "to_usd",
+///         HostFunction::new(0, |cents, _| Ok(json!(cents.as_f64().unwrap_or(0.0) / 100.0)))
+///             .elementwise(),
+///     )
+///     .unwrap();
+/// let j = Jetro::from_bytes(br#"{"prices":[150,200]}"#.to_vec()).unwrap();
+/// assert_eq!(engine.collect(&j, "$.prices.to_usd()").unwrap(), json!([1.5, 2.0]));
+/// ```
+#[derive(Clone)]
+pub struct HostFunction {
+    /// Number of arguments, not counting the receiver.
+    arity: usize,
+    /// Whether calls may be duplicated, reordered, or dropped by optimisers.
+    pure: bool,
+    /// Whether the function maps one element to one element: called on an
+    /// array it runs per element, and a root-chain call streams as a
+    /// per-element pipeline stage.
+    elementwise: bool,
+    /// The closure itself.
+    func: Arc<HostFn>,
+}
+
+impl HostFunction {
+    /// Wrap `func`, which takes exactly `arity` arguments after the receiver.
+    /// The function starts out pure and whole-value (not element-wise).
+    pub fn new<F>(arity: usize, func: F) -> Self
+    where
+        F: Fn(&Value, &[Value]) -> Result<Value, EvalError> + Send + Sync + 'static,
+    {
+        Self {
+            arity,
+            pure: true,
+            elementwise: false,
+            func: Arc::new(func),
+        }
+    }
+
+    /// Mark the function as having side effects or non-deterministic output
+    /// (clocks, counters, remote lookups). Impure calls are evaluated exactly
+    /// as written: never fused into neighbouring stages or elided.
+    pub fn impure(mut self) -> Self {
+        self.pure = false;
+        self
+    }
+
+    /// Declare that the function transforms one element at a time. Called on
+    /// an array, in any form (`$.prices.to_usd()`, `@.prices.to_usd()`,
+    /// `to_usd($.prices)`), it runs on each element, descending into nested
+    /// arrays, so the closure only ever sees non-array values. A call directly
+    /// on a `$`-rooted chain also streams like `map`.
+    pub fn elementwise(mut self) -> Self {
+        self.elementwise = true;
+        self
+    }
+
+    /// Return the number of arguments the function takes after the receiver.
+    #[inline]
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Return `true` unless the function was marked `impure`.
+    #[inline]
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    /// Return `true` if the function was declared `elementwise`.
+    #[inline]
+    pub fn is_elementwise(&self) -> bool {
+        self.elementwise
+    }
+
+    /// Check the arity, convert the operands to JSON, and invoke the closure.
+    pub(crate) fn call(&self, name: &str, recv: &Val, args: Vec<Val>) -> Result<Val, EvalError> {
+        if args.len() != self.arity {
+            return Err(EvalError::invalid_argument(format!(
+                "{}: expected {} argument(s), got {}",
+                name,
+                self.arity,
+                args.len()
+            )));
+        }
+        let args: Vec<Value> = args.into_iter().map(Value::from).collect();
+        self.apply(recv, &args)
+    }
+
+    /// Invoke the closure on `recv`; an element-wise function given an array
+    /// is invoked on each element instead, keeping the array's shape.
+    fn apply(&self, recv: &Val, args: &[Value]) -> Result<Val, EvalError> {
+        if self.elementwise {
+            if let Some(items) = recv.as_vals() {
+                return items
+                    .iter()
+                    .map(|item| self.apply(item, args))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Val::arr);
+            }
+        }
+        (self.func)(&Value::from(recv.clone()), args).map(|out| Val::from(&out))
+    }
+}
+
+impl std::fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostFunction")
+            .field("arity", &self.arity)
+            .field("pure", &self.pure)
+            .field("elementwise", &self.elementwise)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Name-indexed set of host functions owned by a `JetroEngine`.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct HostRegistry {
+    functions: HashMap<Arc<str>, Arc<HostFunction>>,
+}
+
+impl HostRegistry {
+    /// Register `function` under `name`, replacing any previous registration.
+    /// Rejects names that are not identifiers or that would shadow a builtin.
+    pub(crate) fn insert(&mut self, name: &str, function: HostFunction) -> Result<(), EvalError> {
+        let mut chars = name.chars();
+        let is_ident = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_ident {
+            return Err(EvalError::invalid_argument(format!(
+                "host function name '{}' is not an identifier",
+                name
+            )));
+        }
+        if BuiltinMethod::from_name(name) != BuiltinMethod::Unknown
+            || RESERVED_GLOBALS.contains(&name)
+        {
+            return Err(EvalError::invalid_argument(format!(
+                "host function '{}' would shadow a builtin",
+                name
+            )));
+        }
+        self.functions.insert(Arc::from(name), Arc::new(function));
+        Ok(())
+    }
+
+    /// Look up the function registered under `name`.
+    #[inline]
+    pub(crate) fn get(&self, name: &str) -> Option<&Arc<HostFunction>> {
+        self.functions.get(name)
+    }
+
+    /// Return `true` when no functions are registered.
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Feed each function's name, arity and planning flags into `state`, in
+    /// name order; plans lowered against this registry depend on exactly these.
+    pub(crate) fn hash_signatures<H: std::hash::Hasher>(&self, state: &mut H) {
+        use std::hash::Hash;
+        let mut names: Vec<&Arc<str>> = self.functions.keys().collect();
+        names.sort();
+        for name in names {
+            let function = &self.functions[name];
+            name.hash(state);
+            (function.arity, function.pure, function.elementwise).hash(state);
+        }
+    }
+}
+
+// Registry consulted by the VM while an engine call is executing on this
+// thread. Thread-local so every VM the executors spin up sees it without
+// threading a handle through each backend.
+thread_local! {
+    static ACTIVE_HOST: RefCell<Option<Arc<HostRegistry>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previously active registry when dropped, so nested engine
+/// calls (a host function that itself queries) and unwinding both behave.
+pub(crate) struct HostScope {
+    previous: Option<Arc<HostRegistry>>,
+}
+
+impl Drop for HostScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACTIVE_HOST.with(|cell| *cell.borrow_mut() = previous);
+    }
+}
+
+/// Install `registry` as this thread's active registry until the returned
+/// scope is dropped.
+pub(crate) fn enter(registry: &Arc<HostRegistry>) -> HostScope {
+    let previous = ACTIVE_HOST.with(|cell| cell.borrow_mut().replace(Arc::clone(registry)));
+    HostScope { previous }
+}
+
+/// Return the active registry, if any, so it can be installed on worker threads.
+pub(crate) fn active() -> Option<Arc<HostRegistry>> {
+    ACTIVE_HOST.with(|cell| cell.borrow().clone())
+}
+
+/// Look up `name` in the active registry, if any.
+pub(crate) fn lookup(name: &str) -> Option<Arc<HostFunction>> {
+    ACTIVE_HOST.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .and_then(|registry| registry.get(name).cloned())
+    })
+}
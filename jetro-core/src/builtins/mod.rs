@@ -9,11 +9,12 @@
 use crate::data::context::EvalError;
 use crate::data::value::Val;
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 /// Pre-resolved method identifier. Carried by `CompiledCall` and pipeline
 /// plan nodes so method dispatch is an O(1) integer match, not a string hash.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum BuiltinMethod {
     // ── Object / structural inspection ────────────────────────────────────
@@ -398,6 +399,14 @@ pub enum BuiltinMethod {
 
     /// Sentinel returned by `from_name` when the method string is unrecognised.
     Unknown,
+
+    // ── Appended after the sentinel so earlier discriminants never move ────
+    /// Parses CSV text with a header row into an array of objects.
+    FromCsv,
+    /// Serialises a value to MessagePack, returned as base64 text.
+    ToMsgpack,
+    /// Serialises a value to CBOR, returned as base64 text.
+    ToCbor,
 }
 
 /// Expands `$macro!(...)` once per `BuiltinMethod` variant — the single source of truth for
@@ -413,7 +422,7 @@ macro_rules! for_each_builtin {
             DeepLike, DeepMerge, DeepShape, Defaults, DelPath, DelPaths, Diff, DiffWindow,
             DropWhile, EndsWith, Entries, Enumerate, EquiJoin, Explode, Fanout, Filter,
             FilterKeys, FilterValues, Find, FindAll, FindFirst, FindIndex, FindOne, First,
-            FlatMap, Flatten, FlattenKeys, Floor, FromBase64, FromJson, FromPairs, GetPath,
+            FlatMap, Flatten, FlattenKeys, Floor, FromBase64, FromCsv, FromJson, FromPairs, GetPath,
             GroupBy, GroupShape, Has, HasPath, HtmlEscape, HtmlUnescape, Implode, Includes,
             Indent, Index, IndexBy, IndexOf, IndicesOf, IndicesWhere, Intersect, Invert,
             IsAlpha, IsAscii, IsBlank, IsNumeric, Join, KebabCase, Keys, Lag, Last,
@@ -424,7 +433,7 @@ macro_rules! for_each_builtin {
             Rename, Repeat, Replace, ReplaceAll, ReReplace, ReReplaceAll, ReSplit, Reverse,
             ReverseStr, RollingAvg, RollingMax, RollingMin, RollingSum, Round, Scan, Schema,
             Set, SetPath, Skip, Slice, SnakeCase, Sort, Split, StartsWith, StripPrefix,
-            StripSuffix, Sum, Take, TakeWhile, TitleCase, ToBase64, ToBool, ToCsv, ToJson,
+            StripSuffix, Sum, Take, TakeWhile, TitleCase, ToBase64, ToBool, ToCbor, ToCsv, ToJson, ToMsgpack,
             ToNumber, ToPairs, ToString, ToTsv, TracePath, TransformKeys, TransformValues,
             Trim, TrimLeft, TrimRight, Type, UnflattenKeys, Union, Unique, UniqueBy, Unknown,
             Update, Upper, UrlDecode, UrlEncode, Values, Walk, WalkPre, Window, Words, Zip,
@@ -442,6 +451,11 @@ impl BuiltinMethod {
             .unwrap_or(Self::Unknown)
     }
 
+    /// Returns the canonical source-level name of the method (e.g. `"filter"`).
+    pub(crate) fn name(self) -> &'static str {
+        crate::builtins::registry::name_of(self)
+    }
+
     /// Returns true when the method requires a lambda expression as its first argument.
     /// The pipeline planner uses this to distinguish element vs. expression stages.
     pub(crate) fn is_lambda_method(self) -> bool {
@@ -474,7 +488,7 @@ impl BuiltinMethod {
 /// Statically-typed argument payload stored inside a [`BuiltinCall`].
 /// Each variant corresponds to the argument signature of a group of builtins,
 /// enabling argument decoding without heap allocation at call time.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BuiltinArgs {
     /// No arguments.
     None,
@@ -491,16 +505,16 @@ pub enum BuiltinArgs {
     /// A single unsigned-integer argument (window size, chunk size, etc.).
     Usize(usize),
     /// A single pre-evaluated `Val` argument.
-    Val(Val),
+    Val(#[serde(with = "crate::data::value::json_text")] Val),
     /// A list of pre-evaluated `Val` arguments (`diff`, `intersect`, `union`).
-    ValVec(Vec<Val>),
+    ValVec(#[serde(with = "crate::data::value::json_text_vec")] Vec<Val>),
     /// Padding width and fill character (`pad_left`, `pad_right`, `center`).
     Pad { width: usize, fill: char },
 }
 
 /// A pre-compiled builtin call ready for stateless execution.
 /// Stored in pipeline plan nodes and the `CompiledCall` opcode payload.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuiltinCall {
     /// Which builtin to invoke.
     pub method: BuiltinMethod,
@@ -523,7 +537,7 @@ where
 {
     /// Evaluates the argument at `idx`, returning an error if it is absent.
     fn val(&mut self, idx: usize) -> Result<Val, EvalError> {
-        (self.eval_arg)(idx)?.ok_or_else(|| EvalError(format!("{}: missing argument", self.name)))
+        (self.eval_arg)(idx)?.ok_or_else(|| EvalError::invalid_argument(format!("{}: missing argument", self.name)))
     }
 
     /// Evaluates the argument at `idx` as a string, accepting bare identifiers.
@@ -542,7 +556,7 @@ where
         match self.val(idx)? {
             Val::Int(n) => Ok(n),
             Val::Float(f) => Ok(f as i64),
-            _ => Err(EvalError(format!(
+            _ => Err(EvalError::invalid_argument(format!(
                 "{}: expected number argument",
                 self.name
             ))),
@@ -559,7 +573,7 @@ where
         self.val(idx).and_then(|value| {
             value
                 .into_vec()
-                .ok_or_else(|| EvalError(format!("{}: expected array arg", self.name)))
+                .ok_or_else(|| EvalError::invalid_argument(format!("{}: expected array arg", self.name)))
         })
     }
 
@@ -583,7 +597,7 @@ where
         }
         match self.str(idx)? {
             s if s.chars().count() == 1 => Ok(s.chars().next().unwrap()),
-            _ => Err(EvalError(format!(
+            _ => Err(EvalError::invalid_argument(format!(
                 "{}: filler must be a single-char string",
                 self.name
             ))),
@@ -689,7 +703,7 @@ pub enum BuiltinStructural {
 
 /// View-layer stage that a builtin can be lowered into.
 /// Each variant corresponds to a distinct operation in the view execution path.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuiltinViewStage {
     /// Predicate-driven row filter stage.
     Filter,
@@ -832,7 +846,7 @@ pub enum BuiltinStageMerge {
 
 /// Algebraic cancellation rule for a builtin.
 /// Two adjacent stages cancel when `a.cancels_with(b)` is true.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuiltinCancellation {
     /// The operation is its own inverse (`reverse().reverse()` = identity).
     SelfInverse(BuiltinCancelGroup),
@@ -846,7 +860,7 @@ pub enum BuiltinCancellation {
 }
 
 /// Identifies which encode/decode pair a cancellation belongs to.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuiltinCancelGroup {
     /// String reversal (`reverse_str` is self-inverse).
     Reverse,
@@ -859,7 +873,7 @@ pub enum BuiltinCancelGroup {
 }
 
 /// Which side of a forward/backward cancellation pair this builtin occupies.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuiltinCancelSide {
     /// The encoding or escaping direction.
     Forward,
@@ -1496,6 +1510,7 @@ impl BuiltinCall {
             (BuiltinMethod::Entries, BuiltinArgs::None) => return Some(entries_apply(recv)),
             (BuiltinMethod::Collect, BuiltinArgs::None) => return Some(collect_apply(recv)),
             (BuiltinMethod::FromJson, BuiltinArgs::None) => return from_json_apply(recv),
+            (BuiltinMethod::FromCsv, BuiltinArgs::None) => from_csv_apply(recv),
             (BuiltinMethod::Ceil, BuiltinArgs::None)
             | (BuiltinMethod::Floor, BuiltinArgs::None)
             | (BuiltinMethod::Round, BuiltinArgs::None)
@@ -1679,6 +1694,12 @@ impl BuiltinCall {
     /// Like [`BuiltinCall::apply`] but propagates evaluation errors (regex compilation,
     /// window-size-zero, JSON parse failures, etc.) as `EvalError`.
     pub fn try_apply(&self, recv: &Val) -> Result<Option<Val>, EvalError> {
+        // `ObjVec` is a pipeline-side columnar layout; builtins work on rows.
+        if let Val::ObjVec(_) = recv {
+            if let Ok(rows) = recv.clone().into_vals() {
+                return self.try_apply(&Val::arr(rows));
+            }
+        }
         match (self.method, &self.args) {
             (BuiltinMethod::ReMatch, BuiltinArgs::Str(p)) => try_re_match_apply(recv, p),
             (BuiltinMethod::ReMatchFirst, BuiltinArgs::Str(p)) => try_re_match_first_apply(recv, p),
@@ -1695,34 +1716,35 @@ impl BuiltinCall {
                 try_re_replace_all_apply(recv, first, second)
             }
             (BuiltinMethod::FromJson, BuiltinArgs::None) => try_from_json_apply(recv),
+            (BuiltinMethod::FromCsv, BuiltinArgs::None) => try_from_csv_apply(recv),
             (BuiltinMethod::Join, BuiltinArgs::Str(sep)) => join_apply(recv, sep)
                 .map(Some)
-                .ok_or_else(|| EvalError("join: expected array".into())),
+                .ok_or_else(|| EvalError::type_mismatch("join: expected array")),
             (BuiltinMethod::Enumerate, BuiltinArgs::None) => enumerate_apply(recv)
                 .map(Some)
-                .ok_or_else(|| EvalError("enumerate: expected array".into())),
+                .ok_or_else(|| EvalError::type_mismatch("enumerate: expected array")),
             (BuiltinMethod::Sort, BuiltinArgs::None) => sort_apply(recv.clone()).map(Some),
             (BuiltinMethod::Index, BuiltinArgs::Val(item)) => index_value_apply(recv, item)
                 .map(Some)
-                .ok_or_else(|| EvalError("index: expected array".into())),
+                .ok_or_else(|| EvalError::type_mismatch("index: expected array")),
             (BuiltinMethod::IndicesOf, BuiltinArgs::Val(item)) => indices_of_apply(recv, item)
                 .map(Some)
-                .ok_or_else(|| EvalError("indices_of: expected array".into())),
+                .ok_or_else(|| EvalError::type_mismatch("indices_of: expected array")),
             (BuiltinMethod::Ceil, BuiltinArgs::None) => try_ceil_apply(recv),
             (BuiltinMethod::Floor, BuiltinArgs::None) => try_floor_apply(recv),
             (BuiltinMethod::Round, BuiltinArgs::None) => try_round_apply(recv),
             (BuiltinMethod::Abs, BuiltinArgs::None) => try_abs_apply(recv),
             (BuiltinMethod::RollingSum, BuiltinArgs::Usize(0)) => {
-                Err(EvalError("rolling_sum: window must be > 0".into()))
+                Err(EvalError::invalid_argument("rolling_sum: window must be > 0"))
             }
             (BuiltinMethod::RollingAvg, BuiltinArgs::Usize(0)) => {
-                Err(EvalError("rolling_avg: window must be > 0".into()))
+                Err(EvalError::invalid_argument("rolling_avg: window must be > 0"))
             }
             (BuiltinMethod::RollingMin, BuiltinArgs::Usize(0)) => {
-                Err(EvalError("rolling_min: window must be > 0".into()))
+                Err(EvalError::invalid_argument("rolling_min: window must be > 0"))
             }
             (BuiltinMethod::RollingMax, BuiltinArgs::Usize(0)) => {
-                Err(EvalError("rolling_max: window must be > 0".into()))
+                Err(EvalError::invalid_argument("rolling_max: window must be > 0"))
             }
             (BuiltinMethod::RollingSum, BuiltinArgs::Usize(_))
             | (BuiltinMethod::RollingAvg, BuiltinArgs::Usize(_))
@@ -1737,7 +1759,7 @@ impl BuiltinCall {
             | (BuiltinMethod::Zscore, BuiltinArgs::None) => self
                 .apply(recv)
                 .map(Some)
-                .ok_or_else(|| EvalError("expected numeric array".into())),
+                .ok_or_else(|| EvalError::type_mismatch("expected numeric array")),
             _ => Ok(self.apply(recv)),
         }
     }
@@ -2166,14 +2188,14 @@ where
 
     let method = BuiltinMethod::from_name(name);
     if method == BuiltinMethod::Unknown {
-        return Err(EvalError(format!("unknown method '{}'", name)));
+        return Err(EvalError::unknown_method(format!("unknown method '{}'", name)));
     }
 
     macro_rules! arg_val {
         ($idx:expr) => {{
             let arg = args
                 .get($idx)
-                .ok_or_else(|| EvalError(format!("{}: missing argument", name)))?;
+                .ok_or_else(|| EvalError::invalid_argument(format!("{}: missing argument", name)))?;
             eval_arg(arg)
         }};
     }
@@ -2186,7 +2208,7 @@ where
                     Val::Str(s) => Ok(s),
                     other => Ok(Arc::from(crate::util::val_to_string(&other).as_str())),
                 },
-                None => Err(EvalError(format!("{}: missing argument", name))),
+                None => Err(EvalError::invalid_argument(format!("{}: missing argument", name))),
             }
         }};
     }
@@ -2196,7 +2218,7 @@ where
             match arg_val!($idx)? {
                 Val::Int(n) => Ok(n),
                 Val::Float(f) => Ok(f as i64),
-                _ => Err(EvalError(format!("{}: expected number argument", name))),
+                _ => Err(EvalError::invalid_argument(format!("{}: expected number argument", name))),
             }
         }};
     }
@@ -2205,13 +2227,13 @@ where
         ($idx:expr) => {{
             arg_val!($idx)?
                 .into_vec()
-                .ok_or_else(|| EvalError(format!("{}: expected array arg", name)))
+                .ok_or_else(|| EvalError::invalid_argument(format!("{}: expected array arg", name)))
         }};
     }
 
     macro_rules! str_vec_arg {
         ($idx:expr) => {{
-            Ok(vec_arg!($idx)?
+            Ok::<_, EvalError>(vec_arg!($idx)?
                 .iter()
                 .map(|v| match v {
                     Val::Str(s) => s.clone(),
@@ -2230,7 +2252,7 @@ where
                     if s.chars().count() == 1 {
                         Ok(s.chars().next().unwrap())
                     } else {
-                        Err(EvalError(format!(
+                        Err(EvalError::invalid_argument(format!(
                             "{}: filler must be a single-char string",
                             name
                         )))
@@ -2255,6 +2277,7 @@ where
         | BuiltinMethod::Collect
         | BuiltinMethod::Compact
         | BuiltinMethod::FromJson
+        | BuiltinMethod::FromCsv
         | BuiltinMethod::FromPairs
         | BuiltinMethod::ToPairs
         | BuiltinMethod::Invert
@@ -2308,6 +2331,8 @@ where
         | BuiltinMethod::ToJson
         | BuiltinMethod::ToCsv
         | BuiltinMethod::ToTsv
+        | BuiltinMethod::ToMsgpack
+        | BuiltinMethod::ToCbor
         | BuiltinMethod::Schema
             if args.is_empty() =>
         {
@@ -2321,7 +2346,7 @@ where
         BuiltinMethod::Count => {
             let items = recv
                 .as_vals()
-                .ok_or_else(|| EvalError("count: expected array".into()))?;
+                .ok_or_else(|| EvalError::type_mismatch("count: expected array"))?;
             let mut n: i64 = 0;
             for item in items.iter() {
                 if crate::util::is_truthy(&eval_item(item, &args[0])?) {
@@ -2342,13 +2367,13 @@ where
         BuiltinMethod::UniqueBy => {
             let key_arg = args
                 .first()
-                .ok_or_else(|| EvalError("unique_by: requires key fn".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("unique_by: requires key fn"))?;
             return unique_by_apply(recv, |item| eval_item(item, key_arg));
         }
         BuiltinMethod::MaxBy | BuiltinMethod::MinBy => {
             let key_arg = args
                 .first()
-                .ok_or_else(|| EvalError(format!("{}: requires a key expression", name)))?;
+                .ok_or_else(|| EvalError::invalid_argument(format!("{}: requires a key expression", name)))?;
             return extreme_by_apply(recv, method == BuiltinMethod::MaxBy, |item| {
                 eval_item(item, key_arg)
             });
@@ -2359,13 +2384,13 @@ where
         BuiltinMethod::DeepShape => {
             let arg = args
                 .first()
-                .ok_or_else(|| EvalError("shape: requires pattern".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("shape: requires pattern"))?;
             let expr = match arg {
                 Arg::Pos(e) | Arg::Named(_, e) => e,
             };
             let Expr::Object(fields) = expr else {
-                return Err(EvalError(
-                    "shape: expected `{k1, k2, ...}` object pattern".into(),
+                return Err(EvalError::invalid_argument(
+                    "shape: expected `{k1, k2, ...}` object pattern",
                 ));
             };
             let mut keys = Vec::with_capacity(fields.len());
@@ -2375,7 +2400,7 @@ where
                     ObjField::Kv { key, val, .. } if matches!(val, Expr::Ident(n) if n == key) => {
                         keys.push(Arc::from(key.as_str()));
                     }
-                    _ => return Err(EvalError("shape: unsupported pattern field".into())),
+                    _ => return Err(EvalError::invalid_argument("shape: unsupported pattern field")),
                 }
             }
             return deep_shape_apply(recv, &keys);
@@ -2383,13 +2408,13 @@ where
         BuiltinMethod::DeepLike => {
             let arg = args
                 .first()
-                .ok_or_else(|| EvalError("like: requires pattern".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("like: requires pattern"))?;
             let expr = match arg {
                 Arg::Pos(e) | Arg::Named(_, e) => e,
             };
             let Expr::Object(fields) = expr else {
-                return Err(EvalError(
-                    "like: expected `{k: lit, ...}` object pattern".into(),
+                return Err(EvalError::invalid_argument(
+                    "like: expected `{k: lit, ...}` object pattern",
                 ));
             };
             let mut pats = Vec::with_capacity(fields.len());
@@ -2404,7 +2429,7 @@ where
                             eval_arg(&Arg::Pos(Expr::Ident(k.clone())))?,
                         ));
                     }
-                    _ => return Err(EvalError("like: unsupported pattern field".into())),
+                    _ => return Err(EvalError::invalid_argument("like: unsupported pattern field")),
                 }
             }
             return deep_like_apply(recv, &pats);
@@ -2412,7 +2437,7 @@ where
         BuiltinMethod::Walk | BuiltinMethod::WalkPre => {
             let arg = args
                 .first()
-                .ok_or_else(|| EvalError("walk: requires fn".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("walk: requires fn"))?;
             let pre = method == BuiltinMethod::WalkPre;
             let mut eval = |value: Val| eval_item(&value, arg);
             return walk_apply(recv, pre, &mut eval);
@@ -2420,13 +2445,13 @@ where
         BuiltinMethod::Rec => {
             let arg = args
                 .first()
-                .ok_or_else(|| EvalError("rec: requires step expression".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("rec: requires step expression"))?;
             return rec_apply(recv, |value| eval_item(&value, arg));
         }
         BuiltinMethod::TracePath => {
             let arg = args
                 .first()
-                .ok_or_else(|| EvalError("trace_path: requires predicate".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("trace_path: requires predicate"))?;
             return trace_path_apply(recv, |value| eval_item(value, arg));
         }
         BuiltinMethod::Fanout => {
@@ -2439,8 +2464,8 @@ where
                     Arg::Named(n, _) => Arc::from(n.as_str()),
                     Arg::Pos(Expr::Ident(n)) => Arc::from(n.as_str()),
                     _ => {
-                        return Err(EvalError(
-                            "zip_shape: args must be `name = expr` or bare identifier".into(),
+                        return Err(EvalError::invalid_argument(
+                            "zip_shape: args must be `name = expr` or bare identifier",
                         ))
                     }
                 };
@@ -2451,10 +2476,10 @@ where
         BuiltinMethod::GroupShape => {
             let key_arg = args
                 .first()
-                .ok_or_else(|| EvalError("group_shape: requires key".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("group_shape: requires key"))?;
             let shape_arg = args
                 .get(1)
-                .ok_or_else(|| EvalError("group_shape: requires shape".into()))?;
+                .ok_or_else(|| EvalError::invalid_argument("group_shape: requires shape"))?;
             return group_shape_apply(recv, |value, idx| {
                 if idx == 0 {
                     eval_item(&value, key_arg)
@@ -2559,7 +2584,7 @@ where
                 return remove_predicate_apply(recv, |item| eval_item(item, &args[0]));
             }
             Some(_) => BuiltinCall::new(method, BuiltinArgs::Val(arg_val!(0)?)),
-            None => return Err(EvalError("remove: requires arg".into())),
+            None => return Err(EvalError::invalid_argument("remove: requires arg")),
         },
         BuiltinMethod::Zip => {
             let other = args
@@ -2700,7 +2725,7 @@ where
                 specs.push(PickSpec { out_key, source });
             }
             return pick_specs_apply(&recv, &specs)
-                .ok_or_else(|| EvalError("pick: expected object or array of objects".into()));
+                .ok_or_else(|| EvalError::type_mismatch("pick: expected object or array of objects"));
         }
         BuiltinMethod::Omit => {
             let mut keys = Vec::with_capacity(args.len());
@@ -2732,7 +2757,7 @@ where
         }
         BuiltinMethod::SetPath => {
             return set_path_apply(&recv, &str_arg!(0)?, &arg_val!(1)?)
-                .ok_or_else(|| EvalError("set_path: builtin unsupported".into()));
+                .ok_or_else(|| EvalError::unknown_method("set_path: builtin unsupported"));
         }
         BuiltinMethod::DelPaths => {
             let mut paths = Vec::with_capacity(args.len());
@@ -2740,10 +2765,10 @@ where
                 paths.push(str_arg!(idx)?);
             }
             return del_paths_apply(&recv, &paths)
-                .ok_or_else(|| EvalError("del_paths: builtin unsupported".into()));
+                .ok_or_else(|| EvalError::unknown_method("del_paths: builtin unsupported"));
         }
         _ => {
-            return Err(EvalError(format!(
+            return Err(EvalError::unknown_method(format!(
                 "{}: builtin not migrated to builtins.rs AST adapter",
                 name
             )));
@@ -2751,7 +2776,7 @@ where
     };
 
     call.try_apply(&recv)?
-        .ok_or_else(|| EvalError(format!("{}: builtin unsupported", name)))
+        .ok_or_else(|| EvalError::unknown_method(format!("{}: builtin unsupported", name)))
 }
 
 /// Convenience wrapper over [`eval_builtin_method`] for zero-argument builtins.
@@ -2762,13 +2787,13 @@ pub(crate) fn eval_builtin_no_args(recv: Val, name: &str) -> Result<Val, EvalErr
         name,
         &[],
         |_| {
-            Err(EvalError(format!(
+            Err(EvalError::internal(format!(
                 "{}: unexpected argument evaluation",
                 name
             )))
         },
-        |_, _| Err(EvalError(format!("{}: unexpected item evaluation", name))),
-        |_, _, _| Err(EvalError(format!("{}: unexpected pair evaluation", name))),
+        |_, _| Err(EvalError::internal(format!("{}: unexpected item evaluation", name))),
+        |_, _, _| Err(EvalError::internal(format!("{}: unexpected pair evaluation", name))),
     )
 }
 
@@ -2790,6 +2815,7 @@ pub(crate) mod builtin;
 pub(crate) mod defs;
 #[cfg_attr(not(test), allow(dead_code))]
 pub(crate) mod helpers;
+pub(crate) mod host;
 pub(crate) mod registry;
 
 pub use ops::array::*;
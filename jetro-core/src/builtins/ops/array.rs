@@ -156,7 +156,7 @@ pub fn sort_apply(recv: Val) -> Result<Val, EvalError> {
         other => {
             let mut items = other
                 .into_vec()
-                .ok_or_else(|| EvalError("sort: expected array".into()))?;
+                .ok_or_else(|| EvalError::type_mismatch("sort: expected array"))?;
             items.sort_by(cmp_vals);
             Ok(Val::arr(items))
         }
@@ -173,7 +173,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("sort: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("sort: expected array"))?;
     let mut keyed: Vec<(Vec<Val>, Val)> = Vec::with_capacity(items.len());
     for item in items {
         let mut keys = Vec::with_capacity(desc.len());
@@ -203,7 +203,7 @@ where
 {
     let mut items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("sort: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("sort: expected array"))?;
     let mut err_cell: Option<EvalError> = None;
     items.sort_by(|x, y| {
         if err_cell.is_some() {
@@ -233,7 +233,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("remove: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("remove: expected array"))?;
     let mut out = Vec::with_capacity(items.len());
     for item in items {
         if !is_truthy(&eval(&item)?) {
@@ -251,11 +251,11 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if pred_count == 0 {
-        return Err(EvalError("find: requires at least one predicate".into()));
+        return Err(EvalError::invalid_argument("find: requires at least one predicate"));
     }
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("find: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("find: expected array"))?;
     let mut out = Vec::with_capacity(items.len());
     'outer: for item in items {
         for idx in 0..pred_count {
@@ -276,7 +276,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("unique_by: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("unique_by: expected array"))?;
     let mut seen = std::collections::HashSet::new();
     let mut out = Vec::with_capacity(items.len());
     for item in items {
@@ -295,11 +295,11 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if pred_count == 0 {
-        return Err(EvalError("find_index: requires a predicate".into()));
+        return Err(EvalError::invalid_argument("find_index: requires a predicate"));
     }
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("find_index: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("find_index: expected array"))?;
     'outer: for (idx, item) in items.iter().enumerate() {
         for pred_idx in 0..pred_count {
             if !is_truthy(&eval(item, pred_idx)?) {
@@ -318,11 +318,11 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if pred_count == 0 {
-        return Err(EvalError("indices_where: requires a predicate".into()));
+        return Err(EvalError::invalid_argument("indices_where: requires a predicate"));
     }
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("indices_where: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("indices_where: expected array"))?;
     let mut out = Vec::new();
     'outer: for (idx, item) in items.iter().enumerate() {
         for pred_idx in 0..pred_count {
@@ -344,7 +344,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("max_by/min_by: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("max_by/min_by: expected array"))?;
     if items.is_empty() {
         return Ok(Val::Null);
     }
@@ -376,9 +376,12 @@ where
 pub fn collect_apply(recv: &Val) -> Val {
     match recv {
         Val::Null => Val::arr(Vec::new()),
-        Val::Arr(_) | Val::IntVec(_) | Val::FloatVec(_) | Val::StrVec(_) | Val::StrSliceVec(_) => {
-            recv.clone()
-        }
+        Val::Arr(_)
+        | Val::IntVec(_)
+        | Val::FloatVec(_)
+        | Val::StrVec(_)
+        | Val::StrSliceVec(_)
+        | Val::ObjVec(_) => recv.clone(),
         other => Val::arr(vec![other.clone()]),
     }
 }
@@ -430,18 +433,24 @@ pub fn global_zip_longest_apply(arrs: &[Val], fill: &Val) -> Val {
 }
 
 /// Computes the Cartesian product of N arrays, returning all combinations as `[[...], ...]`.
+/// The combination count is checked against the active `max_array_len` before
+/// anything is built.
 #[inline]
-pub fn global_product_apply(arrs: &[Val]) -> Val {
+pub fn global_product_apply(arrs: &[Val]) -> Result<Val, EvalError> {
     let arrays: Vec<Vec<Val>> = arrs
         .iter()
         .map(|v| v.clone().into_vec().unwrap_or_default())
         .collect();
-    Val::arr(
+    let combinations = arrays
+        .iter()
+        .fold(1usize, |acc, arr| acc.saturating_mul(arr.len()));
+    crate::exec::limits::check_len(combinations)?;
+    Ok(Val::arr(
         crate::util::cartesian(&arrays)
             .into_iter()
             .map(Val::arr)
             .collect(),
-    )
+    ))
 }
 
 /// Generates an integer range. Accepts 1–3 arguments: `(end)`, `(start, end)`, or
@@ -449,7 +458,7 @@ pub fn global_product_apply(arrs: &[Val]) -> Val {
 #[inline]
 pub fn range_apply(nums: &[i64]) -> Result<Val, EvalError> {
     if nums.is_empty() || nums.len() > 3 {
-        return Err(EvalError(format!(
+        return Err(EvalError::invalid_argument(format!(
             "range: expected 1..3 args, got {}",
             nums.len()
         )));
@@ -470,6 +479,7 @@ pub fn range_apply(nums: &[i64]) -> Result<Val, EvalError> {
     } else {
         0
     };
+    crate::exec::limits::check_len(len_hint)?;
     let mut out = Vec::with_capacity(len_hint);
     let mut i = from;
     if step > 0 {
@@ -499,10 +509,10 @@ pub fn equi_join_apply(
 
     let left = recv
         .into_vec()
-        .ok_or_else(|| EvalError("equi_join: lhs not array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("equi_join: lhs not array"))?;
     let right = other
         .into_vec()
-        .ok_or_else(|| EvalError("equi_join: rhs not array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("equi_join: rhs not array"))?;
     let mut idx: HashMap<String, Vec<Val>> = HashMap::new();
     for r in right {
         let key = match &r {
@@ -557,7 +567,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("pivot: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("pivot: expected array"))?;
 
     #[inline]
     fn to_key(v: Val) -> Arc<str> {
@@ -583,7 +593,7 @@ where
     }
 
     if arg_count < 2 {
-        return Err(EvalError("pivot: requires key arg and value arg".into()));
+        return Err(EvalError::invalid_argument("pivot: requires key arg and value arg"));
     }
 
     let mut map = IndexMap::with_capacity(items.len());
@@ -595,22 +605,21 @@ where
     Ok(Val::obj(map))
 }
 
-/// DFS pre-order visitor: calls `f` on every node (parents before children).
-fn walk_pre<F: FnMut(&Val)>(value: &Val, f: &mut F) {
-    f(value);
-    match value {
-        Val::Arr(items) => {
-            for child in items.iter() {
-                walk_pre(child, f);
-            }
-        }
-        Val::Obj(map) => {
-            for (_, child) in map.iter() {
-                walk_pre(child, f);
-            }
+/// DFS pre-order visitor: calls `f` on every node (parents before children)
+/// and stops at the first error. Keeps pending siblings on an explicit stack,
+/// so depth costs no call frames. Every node counts as a step for the limits.
+fn walk_pre<F: FnMut(&Val) -> Result<(), EvalError>>(value: &Val, f: &mut F) -> Result<(), EvalError> {
+    let mut stack = vec![value];
+    while let Some(node) = stack.pop() {
+        crate::exec::limits::poll()?;
+        f(node)?;
+        match node {
+            Val::Arr(items) => stack.extend(items.iter().rev()),
+            Val::Obj(map) => stack.extend(map.values().rev()),
+            _ => {}
         }
-        _ => {}
     }
+    Ok(())
 }
 
 /// DFS pre-order search: collects every node in the tree that satisfies all `pred_count` predicates.
@@ -621,38 +630,26 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if pred_count == 0 {
-        return Err(EvalError("find: requires at least one predicate".into()));
+        return Err(EvalError::invalid_argument("find: requires at least one predicate"));
     }
     let mut out = Vec::new();
-    let mut err_cell: Option<EvalError> = None;
     walk_pre(&recv, &mut |node| {
-        if err_cell.is_some() {
-            return;
-        }
         for idx in 0..pred_count {
-            match eval(node, idx) {
-                Ok(v) if is_truthy(&v) => {}
-                Ok(_) => return,
-                Err(e) => {
-                    err_cell = Some(e);
-                    return;
-                }
+            if !is_truthy(&eval(node, idx)?) {
+                return Ok(());
             }
         }
         out.push(node.clone());
-    });
-    if let Some(e) = err_cell {
-        Err(e)
-    } else {
-        Ok(Val::arr(out))
-    }
+        Ok(())
+    })?;
+    Ok(Val::arr(out))
 }
 
 /// DFS pre-order search: collects every object node that contains all of the given `keys`.
 #[inline]
 pub fn deep_shape_apply(recv: Val, keys: &[Arc<str>]) -> Result<Val, EvalError> {
     if keys.is_empty() {
-        return Err(EvalError("shape: empty pattern".into()));
+        return Err(EvalError::invalid_argument("shape: empty pattern"));
     }
     let mut out = Vec::new();
     walk_pre(&recv, &mut |node| {
@@ -661,7 +658,8 @@ pub fn deep_shape_apply(recv: Val, keys: &[Arc<str>]) -> Result<Val, EvalError>
                 out.push(node.clone());
             }
         }
-    });
+        Ok(())
+    })?;
     Ok(Val::arr(out))
 }
 
@@ -669,7 +667,7 @@ pub fn deep_shape_apply(recv: Val, keys: &[Arc<str>]) -> Result<Val, EvalError>
 #[inline]
 pub fn deep_like_apply(recv: Val, pats: &[(Arc<str>, Val)]) -> Result<Val, EvalError> {
     if pats.is_empty() {
-        return Err(EvalError("like: empty pattern".into()));
+        return Err(EvalError::invalid_argument("like: empty pattern"));
     }
     let mut out = Vec::new();
     walk_pre(&recv, &mut |node| {
@@ -683,55 +681,98 @@ pub fn deep_like_apply(recv: Val, pats: &[(Arc<str>, Val)]) -> Result<Val, EvalE
                 out.push(node.clone());
             }
         }
-    });
+        Ok(())
+    })?;
     Ok(Val::arr(out))
 }
 
-/// Recursive tree transform. When `pre = true` the transform runs top-down (pre-order);
+/// Tree transform. When `pre = true` the transform runs top-down (pre-order);
 /// when `pre = false` it runs bottom-up (post-order). All array and object children
-/// are recursively transformed, then the lambda is applied.
+/// are transformed, then the lambda is applied. Containers still being rebuilt are
+/// kept on an explicit stack rather than in recursive calls.
 pub fn walk_apply<F>(recv: Val, pre: bool, eval: &mut F) -> Result<Val, EvalError>
 where
     F: FnMut(Val) -> Result<Val, EvalError>,
 {
-    let transformed = if pre { eval(recv)? } else { recv };
-    let after_children = match transformed {
-        Val::Arr(a) => {
-            let items = Arc::try_unwrap(a).unwrap_or_else(|arc| (*arc).clone());
-            let mut out = Vec::with_capacity(items.len());
-            for child in items {
-                out.push(walk_apply(child, pre, eval)?);
-            }
-            Val::arr(out)
+    /// A container whose children are being transformed.
+    enum Frame {
+        Arr {
+            out: Vec<Val>,
+            rest: std::vec::IntoIter<Val>,
+        },
+        Obj {
+            out: IndexMap<Arc<str>, Val>,
+            rest: indexmap::map::IntoIter<Arc<str>, Val>,
+            key: Option<Arc<str>>,
+        },
+    }
+
+    fn arr_frame(items: Vec<Val>) -> Frame {
+        Frame::Arr {
+            out: Vec::with_capacity(items.len()),
+            rest: items.into_iter(),
         }
-        Val::IntVec(a) => {
-            let mut out = Vec::with_capacity(a.len());
-            for n in a.iter() {
-                out.push(walk_apply(Val::Int(*n), pre, eval)?);
+    }
+
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut next = recv;
+    loop {
+        let node = if pre { eval(next)? } else { next };
+        let mut done = match node {
+            Val::Arr(a) => {
+                stack.push(arr_frame(Arc::try_unwrap(a).unwrap_or_else(|arc| (*arc).clone())));
+                None
             }
-            Val::arr(out)
-        }
-        Val::FloatVec(a) => {
-            let mut out = Vec::with_capacity(a.len());
-            for n in a.iter() {
-                out.push(walk_apply(Val::Float(*n), pre, eval)?);
+            Val::IntVec(a) => {
+                stack.push(arr_frame(a.iter().map(|n| Val::Int(*n)).collect()));
+                None
             }
-            Val::arr(out)
-        }
-        Val::Obj(m) => {
-            let items = Arc::try_unwrap(m).unwrap_or_else(|arc| (*arc).clone());
-            let mut out = IndexMap::with_capacity(items.len());
-            for (k, child) in items {
-                out.insert(k, walk_apply(child, pre, eval)?);
+            Val::FloatVec(a) => {
+                stack.push(arr_frame(a.iter().map(|n| Val::Float(*n)).collect()));
+                None
             }
-            Val::obj(out)
-        }
-        other => other,
-    };
-    if pre {
-        Ok(after_children)
-    } else {
-        eval(after_children)
+            Val::Obj(m) => {
+                let items = Arc::try_unwrap(m).unwrap_or_else(|arc| (*arc).clone());
+                stack.push(Frame::Obj {
+                    out: IndexMap::with_capacity(items.len()),
+                    rest: items.into_iter(),
+                    key: None,
+                });
+                None
+            }
+            other if pre => Some(other),
+            other => Some(eval(other)?),
+        };
+        // Hand finished values to their parents until some container has a
+        // child left to transform.
+        next = loop {
+            if let Some(value) = done.take() {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Frame::Arr { out, .. }) => out.push(value),
+                    Some(Frame::Obj { out, key, .. }) => {
+                        out.insert(key.take().expect("object child has a key"), value);
+                    }
+                }
+            }
+            let child = match stack.last_mut() {
+                Some(Frame::Arr { rest, .. }) => rest.next(),
+                Some(Frame::Obj { rest, key, .. }) => rest.next().map(|(k, child)| {
+                    *key = Some(k);
+                    child
+                }),
+                None => unreachable!("a finished root returns above"),
+            };
+            if let Some(child) = child {
+                break child;
+            }
+            let rebuilt = match stack.pop() {
+                Some(Frame::Arr { out, .. }) => Val::arr(out),
+                Some(Frame::Obj { out, .. }) => Val::obj(out),
+                None => unreachable!("checked above"),
+            };
+            done = Some(if pre { rebuilt } else { eval(rebuilt)? });
+        };
     }
 }
 
@@ -749,8 +790,8 @@ where
         }
         recv = next;
     }
-    Err(EvalError(
-        "rec: exceeded 10000 iterations without reaching fixpoint".into(),
+    Err(EvalError::invalid_argument(
+        "rec: exceeded 10000 iterations without reaching fixpoint",
     ))
 }
 
@@ -809,7 +850,7 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if count == 0 {
-        return Err(EvalError("fanout: requires at least one expression".into()));
+        return Err(EvalError::invalid_argument("fanout: requires at least one expression"));
     }
     let mut out = Vec::with_capacity(count);
     for idx in 0..count {
@@ -826,7 +867,7 @@ where
     F: FnMut(&Val, usize) -> Result<Val, EvalError>,
 {
     if names.is_empty() {
-        return Err(EvalError("zip_shape: requires at least one field".into()));
+        return Err(EvalError::invalid_argument("zip_shape: requires at least one field"));
     }
     let mut out = IndexMap::with_capacity(names.len());
     for (idx, name) in names.iter().enumerate() {
@@ -844,7 +885,7 @@ where
 {
     let items = recv
         .into_vec()
-        .ok_or_else(|| EvalError("group_shape: expected array".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("group_shape: expected array"))?;
     let mut buckets: IndexMap<Arc<str>, Vec<Val>> = IndexMap::with_capacity(items.len());
     for item in items {
         let key = match eval(item.clone(), 0)? {
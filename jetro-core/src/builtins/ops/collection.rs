@@ -342,30 +342,26 @@ pub fn zscore_apply(recv: &Val) -> Option<Val> {
 /// Returns the first `n` elements of an array; when `n == 1` returns a scalar instead of a single-element array.
 #[inline]
 pub fn first_apply(recv: &Val, n: i64) -> Option<Val> {
-    if let Val::Arr(a) = recv {
-        Some(if n == 1 {
-            a.first().cloned().unwrap_or(Val::Null)
-        } else {
-            Val::arr(a.iter().take(n.max(0) as usize).cloned().collect())
-        })
-    } else {
-        Some(Val::Null)
+    if n == 1 {
+        return Some(recv.get_index(0));
     }
+    let Some(items) = recv.as_vals() else {
+        return Some(Val::Null);
+    };
+    Some(Val::arr(items.iter().take(n.max(0) as usize).cloned().collect()))
 }
 
 /// Returns the last `n` elements of an array; when `n == 1` returns a scalar instead of a single-element array.
 #[inline]
 pub fn last_apply(recv: &Val, n: i64) -> Option<Val> {
-    if let Val::Arr(a) = recv {
-        Some(if n == 1 {
-            a.last().cloned().unwrap_or(Val::Null)
-        } else {
-            let s = a.len().saturating_sub(n.max(0) as usize);
-            Val::arr(a[s..].to_vec())
-        })
-    } else {
-        Some(Val::Null)
+    if n == 1 {
+        return Some(recv.get_index(-1));
     }
+    let Some(items) = recv.as_vals() else {
+        return Some(Val::Null);
+    };
+    let s = items.len().saturating_sub(n.max(0) as usize);
+    Some(Val::arr(items[s..].to_vec()))
 }
 
 /// Returns the element at index `i` (negative indices count from the end); delegates to `Val::get_index`.
@@ -16,6 +16,18 @@ pub fn to_tsv_apply(recv: &Val) -> Option<Val> {
     )))
 }
 
+/// Serialises `recv` to MessagePack and returns the bytes as base64 text.
+#[inline]
+pub fn to_msgpack_apply(recv: &Val) -> Val {
+    crate::data::binary::bytes_val(&crate::data::msgpack::encode(recv))
+}
+
+/// Serialises `recv` to CBOR and returns the bytes as base64 text.
+#[inline]
+pub fn to_cbor_apply(recv: &Val) -> Val {
+    crate::data::binary::bytes_val(&crate::data::cbor::encode(recv))
+}
+
 /// Converts an object into `[{key, val}, …]`; returns an empty array for non-objects.
 #[inline]
 pub fn to_pairs_apply(recv: &Val) -> Option<Val> {
@@ -90,24 +102,42 @@ pub fn try_from_json_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
         let mut bytes = bytes_owned;
         return Val::from_json_simd(&mut bytes)
             .map(Some)
-            .map_err(|e| EvalError(format!("from_json: {}", e)));
+            .map_err(|e| EvalError::invalid_json(format!("from_json: {}", e)));
     }
     #[cfg(not(feature = "simd-json"))]
     {
         match recv {
             Val::Str(s) => Val::from_json_str(s.as_ref())
                 .map(Some)
-                .map_err(|e| EvalError(format!("from_json: {}", e))),
+                .map_err(|e| EvalError::invalid_json(format!("from_json: {}", e))),
             _ => {
                 let s = crate::util::val_to_string(recv);
                 Val::from_json_str(&s)
                     .map(Some)
-                    .map_err(|e| EvalError(format!("from_json: {}", e)))
+                    .map_err(|e| EvalError::invalid_json(format!("from_json: {}", e)))
             }
         }
     }
 }
 
+/// Parses a CSV string with a header row into an array of objects; returns
+/// `None` on malformed input or a non-string receiver.
+#[inline]
+pub fn from_csv_apply(recv: &Val) -> Option<Val> {
+    try_from_csv_apply(recv).ok().flatten()
+}
+
+/// Fallible variant of [`from_csv_apply`]; returns an `EvalError` on malformed CSV.
+#[inline]
+pub fn try_from_csv_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
+    let Some(text) = recv.as_str_ref() else {
+        return Err(EvalError::type_mismatch("from_csv: expected string"));
+    };
+    crate::data::csv::parse(text.as_bytes(), crate::data::csv::CsvOptions::new())
+        .map(Some)
+        .map_err(|e| EvalError::invalid_argument(format!("from_csv: {}", e)))
+}
+
 /// Returns `recv` if it is non-null, otherwise returns `default`.
 #[inline]
 pub fn or_apply(recv: &Val, default: &Val) -> Val {
@@ -3,7 +3,7 @@ use crate::data::value::Val;
 use std::sync::Arc;
 
 fn compile_regex_eval(pat: &str) -> Result<Arc<regex::Regex>, EvalError> {
-    crate::builtins::helpers::compile_regex(pat).map_err(EvalError)
+    crate::builtins::helpers::compile_regex(pat).map_err(EvalError::invalid_regex)
 }
 
 /// Returns `Val::Bool` indicating whether the full string matches `pat`; returns `None` for non-strings.
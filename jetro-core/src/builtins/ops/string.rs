@@ -447,7 +447,7 @@ pub fn ceil_apply(recv: &Val) -> Option<Val> {
 pub fn try_ceil_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
     ceil_apply(recv)
         .map(Some)
-        .ok_or_else(|| EvalError("ceil: expected number".into()))
+        .ok_or_else(|| EvalError::type_mismatch("ceil: expected number"))
 }
 
 /// Returns the floor (round-down) of a numeric value as `Val::Int`.
@@ -465,7 +465,7 @@ pub fn floor_apply(recv: &Val) -> Option<Val> {
 pub fn try_floor_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
     floor_apply(recv)
         .map(Some)
-        .ok_or_else(|| EvalError("floor: expected number".into()))
+        .ok_or_else(|| EvalError::type_mismatch("floor: expected number"))
 }
 
 /// Rounds a numeric value to the nearest integer.
@@ -483,7 +483,7 @@ pub fn round_apply(recv: &Val) -> Option<Val> {
 pub fn try_round_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
     round_apply(recv)
         .map(Some)
-        .ok_or_else(|| EvalError("round: expected number".into()))
+        .ok_or_else(|| EvalError::type_mismatch("round: expected number"))
 }
 
 /// Returns the absolute value of an integer or float.
@@ -501,7 +501,7 @@ pub fn abs_apply(recv: &Val) -> Option<Val> {
 pub fn try_abs_apply(recv: &Val) -> Result<Option<Val>, EvalError> {
     abs_apply(recv)
         .map(Some)
-        .ok_or_else(|| EvalError("abs: expected number".into()))
+        .ok_or_else(|| EvalError::type_mismatch("abs: expected number"))
 }
 
 /// Parses the string as a base-10 `i64`; returns `Val::Null` on failure.
@@ -665,7 +665,7 @@ where
 {
     let items = recv
         .as_vals()
-        .ok_or_else(|| EvalError("expected array for numeric aggregate".into()))?;
+        .ok_or_else(|| EvalError::type_mismatch("expected array for numeric aggregate"))?;
 
     let mut vals = Vec::with_capacity(items.len());
     for item in items.iter() {
@@ -321,6 +321,38 @@ pub(crate) fn by_name(name: &str) -> Option<BuiltinId> {
     None
 }
 
+/// Return the canonical `Builtin::NAME` for `method`.
+#[inline]
+pub(crate) fn name_of(method: BuiltinMethod) -> &'static str {
+    macro_rules! check {
+        ( $( $variant:ident ),* $(,)? ) => {
+            $(
+                if method == BuiltinMethod::$variant {
+                    return <crate::builtins::defs::$variant as crate::builtins::builtin::Builtin>::NAME;
+                }
+            )*
+        };
+    }
+    crate::for_each_builtin!(check);
+    "unknown"
+}
+
+/// Feed every builtin's discriminant, canonical name and aliases into `state`,
+/// so persisted bytecode can detect a builtin table that no longer matches.
+pub(crate) fn hash_table<H: std::hash::Hasher>(state: &mut H) {
+    use std::hash::Hash;
+    macro_rules! feed {
+        ( $( $variant:ident ),* $(,)? ) => {
+            $(
+                (BuiltinMethod::$variant as u8).hash(state);
+                <crate::builtins::defs::$variant as crate::builtins::builtin::Builtin>::NAME.hash(state);
+                <crate::builtins::defs::$variant as crate::builtins::builtin::Builtin>::ALIASES.hash(state);
+            )*
+        };
+    }
+    crate::for_each_builtin!(feed);
+}
+
 /// Return identity entries for all registered builtins: (method, canonical, aliases).
 #[cfg(test)]
 pub(crate) fn all_method_entries() -> Vec<(BuiltinMethod, &'static str, &'static [&'static str])> {
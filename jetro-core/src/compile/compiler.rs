@@ -5,6 +5,7 @@
 //! `PassConfig`. Split out of `vm.rs` to keep each file focused.
 
 use smallvec::SmallVec;
+use std::cell::Cell;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 
@@ -51,6 +52,33 @@ impl VarCtx {
     }
 }
 
+thread_local! {
+    /// Depth of the `emit_into` recursion on this thread.
+    static EMIT_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// One level of `emit_into` recursion; leaves it when dropped.
+struct EmitLevel(());
+
+impl EmitLevel {
+    /// Enter one level, or return `None` once the nesting limit is reached.
+    fn enter() -> Option<Self> {
+        EMIT_DEPTH.with(|depth| {
+            if depth.get() >= crate::exec::limits::max_nesting() {
+                return None;
+            }
+            depth.set(depth.get() + 1);
+            Some(EmitLevel(()))
+        })
+    }
+}
+
+impl Drop for EmitLevel {
+    fn drop(&mut self) {
+        EMIT_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
 
 /// Stateless unit struct that compiles an `Expr` AST into a flat `Program`.
 /// All methods are associated functions; no instance state is needed.
@@ -111,8 +139,8 @@ impl Compiler {
                         crate::parse::ast::Step::DynIndex(e) | crate::parse::ast::Step::InlineFilter(e) => {
                             Self::reorder_and_operands(e)
                         }
-                        crate::parse::ast::Step::Method(_, args)
-                        | crate::parse::ast::Step::OptMethod(_, args) => {
+                        crate::parse::ast::Step::Method(_, args, _)
+                        | crate::parse::ast::Step::OptMethod(_, args, _) => {
                             for a in args {
                                 match a {
                                     crate::parse::ast::Arg::Pos(e) | crate::parse::ast::Arg::Named(_, e) => {
@@ -205,14 +233,20 @@ impl Compiler {
     /// Parse and compile `input` with all default passes; available in test builds only.
     #[cfg(test)]
     pub fn compile_str(input: &str) -> Result<Program, EvalError> {
-        let expr = crate::parse::parser::parse(input).map_err(|e| EvalError(e.to_string()))?;
+        let expr = crate::parse::parser::parse(input).map_err(EvalError::from)?;
         Ok(Self::compile(&expr, input))
     }
 
     /// Parse and compile `input` with the passes controlled by `config`.
     /// Used by `VM::get_or_compile` so pass selection can vary per `VM` instance.
     pub fn compile_str_with_config(input: &str, config: PassConfig) -> Result<Program, EvalError> {
-        let expr = crate::parse::parser::parse(input).map_err(|e| EvalError(e.to_string()))?;
+        let expr = crate::parse::parser::parse(input).map_err(EvalError::from)?;
+        Ok(Self::compile_with_config(&expr, input, config))
+    }
+
+    /// Compile an already-parsed `expr` with the passes controlled by `config`.
+    /// `input` is recorded as the program source for cache keys and diagnostics.
+    pub fn compile_with_config(expr: &Expr, input: &str, config: PassConfig) -> Program {
         let mut e = expr.clone();
         if config.reorder_and {
             Self::reorder_and_operands(&mut e);
@@ -223,15 +257,15 @@ impl Compiler {
         if config.dedup_subprogs {
             let deduped = crate::plan::analysis::dedup_subprograms(&prog);
             let ics = fresh_ics(deduped.ops.len());
-            Ok(Program {
+            Program {
                 ops: deduped.ops.clone(),
                 source: prog.source,
                 id: prog.id,
                 is_structural: prog.is_structural,
                 ics,
-            })
+            }
         } else {
-            Ok(prog)
+            prog
         }
     }
 
@@ -267,8 +301,13 @@ impl Compiler {
     }
 
     /// Recursively emit opcodes for `expr` into `ops`, consulting `ctx` to distinguish
-    /// variable references from field/built-in names.
+    /// variable references from field/built-in names. Past the nesting limit the
+    /// sub-expression becomes a `NestingLimitErr` opcode instead of recursing further.
     fn emit_into(expr: &Expr, ctx: &VarCtx, ops: &mut Vec<Opcode>) {
+        let Some(_level) = EmitLevel::enter() else {
+            ops.push(Opcode::NestingLimitErr(crate::exec::limits::max_nesting()));
+            return;
+        };
         match expr {
             Expr::Null => ops.push(Opcode::PushNull),
             Expr::Bool(b) => ops.push(Opcode::PushBool(*b)),
@@ -532,13 +571,10 @@ impl Compiler {
                 }
             }
 
-            Expr::GlobalCall { name, args } => {
+            Expr::GlobalCall { name, args, span } => {
                 
                 
-                let is_special = matches!(
-                    name.as_str(),
-                    "coalesce" | "chain" | "join" | "zip" | "zip_longest" | "product" | "range"
-                );
+                let is_special = crate::data::runtime::RESERVED_GLOBALS.contains(&name.as_str());
                 if !is_special && !args.is_empty() {
                     
                     let first = match &args[0] {
@@ -558,6 +594,7 @@ impl Compiler {
                         sub_progs: sub_progs.into(),
                         orig_args: rest_args.into(),
                         demand_max_keep: None,
+                        span: *span,
                     });
                     ops.push(Opcode::CallMethod(call));
                 } else {
@@ -574,6 +611,7 @@ impl Compiler {
                         sub_progs: sub_progs.into(),
                         orig_args: args.iter().cloned().collect::<Vec<_>>().into(),
                         demand_max_keep: None,
+                        span: *span,
                     });
                     ops.push(Opcode::PushRoot);
                     ops.push(Opcode::CallMethod(call));
@@ -581,8 +619,15 @@ impl Compiler {
             }
 
             Expr::Cast { expr, ty } => {
-                Self::emit_into(expr, ctx, ops);
-                ops.push(Opcode::CastOp(*ty));
+                // `x as a as b …` nests left; emit its operand once, then the casts in order
+                let mut casts = vec![*ty];
+                let mut operand = &**expr;
+                while let Expr::Cast { expr, ty } = operand {
+                    casts.push(*ty);
+                    operand = &**expr;
+                }
+                Self::emit_into(operand, ctx, ops);
+                ops.extend(casts.into_iter().rev().map(Opcode::CastOp));
             }
 
             Expr::Patch {
@@ -613,12 +658,12 @@ impl Compiler {
             Step::Index(i) => ops.push(Opcode::GetIndex(*i)),
             Step::DynIndex(e) => ops.push(Opcode::DynIndex(Arc::new(Self::compile_sub(e, ctx)))),
             Step::Slice(a, b) => ops.push(Opcode::GetSlice(*a, *b)),
-            Step::Method(name, method_args) => {
-                let call = Self::compile_call(name, method_args, ctx);
+            Step::Method(name, method_args, span) => {
+                let call = Self::compile_call(name, method_args, *span, ctx);
                 ops.push(Opcode::CallMethod(Arc::new(call)));
             }
-            Step::OptMethod(name, method_args) => {
-                let call = Self::compile_call(name, method_args, ctx);
+            Step::OptMethod(name, method_args, span) => {
+                let call = Self::compile_call(name, method_args, *span, ctx);
                 ops.push(Opcode::CallOptMethod(Arc::new(call)));
             }
             Step::InlineFilter(pred) => {
@@ -630,7 +675,7 @@ impl Compiler {
 
     /// Build a `CompiledCall` descriptor for a method invocation, pre-compiling
     /// each argument expression into a sub-program.
-    fn compile_call(name: &str, args: &[Arg], ctx: &VarCtx) -> CompiledCall {
+    fn compile_call(name: &str, args: &[Arg], span: Span, ctx: &VarCtx) -> CompiledCall {
         let method = BuiltinMethod::from_name(name);
         let sub_progs: Vec<Arc<Program>> = args
             .iter()
@@ -644,6 +689,7 @@ impl Compiler {
             sub_progs: sub_progs.into(),
             orig_args: args.iter().cloned().collect::<Vec<_>>().into(),
             demand_max_keep: None,
+            span,
         }
     }
 
@@ -674,7 +720,10 @@ impl Compiler {
     }
 
     /// Emit the appropriate opcode(s) for a binary operator, using short-circuit
-    /// sub-programs for `&&`, `||`, and `??`.
+    /// sub-programs for `&&`, `||`, and `??`. A left-leaning run of eager
+    /// operators (`a + b - c * …` as the parser folds it) is walked down its
+    /// left spine iteratively, so a long flat chain costs one level of
+    /// recursion rather than one per operand.
     fn emit_binop(l: &Expr, op: BinOp, r: &Expr, ctx: &VarCtx, ops: &mut Vec<Opcode>) {
         match op {
             BinOp::And => {
@@ -687,69 +736,44 @@ impl Compiler {
                 let rhs_prog = Arc::new(Self::compile_sub(r, ctx));
                 ops.push(Opcode::OrOp(rhs_prog));
             }
-            BinOp::Add => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Add);
-            }
-            BinOp::Sub => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Sub);
-            }
-            BinOp::Mul => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Mul);
-            }
-            BinOp::Div => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Div);
-            }
-            BinOp::Mod => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Mod);
-            }
-            BinOp::Eq => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Eq);
-            }
-            BinOp::Neq => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Neq);
-            }
-            BinOp::Lt => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Lt);
-            }
-            BinOp::Lte => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Lte);
-            }
-            BinOp::Gt => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Gt);
-            }
-            BinOp::Gte => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Gte);
-            }
-            BinOp::Fuzzy => {
-                Self::emit_into(l, ctx, ops);
-                Self::emit_into(r, ctx, ops);
-                ops.push(Opcode::Fuzzy);
+            _ => {
+                let mut spine = vec![(op, r)];
+                let mut base = l;
+                while let Expr::BinOp(inner_l, inner_op, inner_r) = base {
+                    if matches!(inner_op, BinOp::And | BinOp::Or) {
+                        break;
+                    }
+                    spine.push((*inner_op, &**inner_r));
+                    base = &**inner_l;
+                }
+                Self::emit_into(base, ctx, ops);
+                for (op, rhs) in spine.into_iter().rev() {
+                    Self::emit_into(rhs, ctx, ops);
+                    ops.push(Self::eager_binop(op));
+                }
             }
         }
     }
 
+    /// Opcode for an operator that evaluates both operands before combining them.
+    fn eager_binop(op: BinOp) -> Opcode {
+        match op {
+            BinOp::Add => Opcode::Add,
+            BinOp::Sub => Opcode::Sub,
+            BinOp::Mul => Opcode::Mul,
+            BinOp::Div => Opcode::Div,
+            BinOp::Mod => Opcode::Mod,
+            BinOp::Eq => Opcode::Eq,
+            BinOp::Neq => Opcode::Neq,
+            BinOp::Lt => Opcode::Lt,
+            BinOp::Lte => Opcode::Lte,
+            BinOp::Gt => Opcode::Gt,
+            BinOp::Gte => Opcode::Gte,
+            BinOp::Fuzzy => Opcode::Fuzzy,
+            BinOp::And | BinOp::Or => unreachable!("short-circuit operators compile to sub-programs"),
+        }
+    }
+
     /// Emit a `PipelineRun` opcode for a `base | step1 | step2 | …` expression,
     /// compiling each forward and bind step while threading the variable context.
     fn emit_pipeline(base: &Expr, steps: &[PipeStep], ctx: &VarCtx, ops: &mut Vec<Opcode>) {
@@ -826,6 +850,7 @@ impl Compiler {
                     sub_progs: Arc::from(&[] as &[Arc<Program>]),
                     orig_args: Arc::from(&[] as &[Arg]),
                     demand_max_keep: None,
+                    span: Span::default(),
                 };
                 ops.push(Opcode::PushCurrent);
                 ops.push(Opcode::CallMethod(Arc::new(call)));
@@ -840,6 +865,7 @@ impl Compiler {
                             sub_progs: Arc::from(&[] as &[Arc<Program>]),
                             orig_args: Arc::from(&[] as &[Arg]),
                             demand_max_keep: None,
+                            span: Span::default(),
                         };
                         ops.push(Opcode::PushCurrent);
                         ops.push(Opcode::CallMethod(Arc::new(call)));
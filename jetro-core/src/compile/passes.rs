@@ -5,7 +5,7 @@
 
 use std::sync::Arc;
 
-use crate::parse::ast::Arg;
+use crate::parse::ast::{Arg, Span};
 use crate::builtins::BuiltinMethod;
 use crate::vm::{
     CompiledCall, FieldChainData, Opcode, Program,
@@ -19,6 +19,7 @@ fn make_noarg_call(method: BuiltinMethod, name: &str) -> Opcode {
         sub_progs: Arc::from(&[] as &[Arc<Program>]),
         orig_args: Arc::from(&[] as &[Arg]),
         demand_max_keep: None,
+        span: Span::default(),
     }))
 }
 
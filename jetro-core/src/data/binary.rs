@@ -0,0 +1,264 @@
+//! Shared parts of the MessagePack and CBOR codecs (`msgpack`, `cbor`).
+//!
+//! Both formats decode straight into `Val`, without a JSON or
+//! `serde_json::Value` step. Arrays go through the same promotion as
+//! `Jetro::from_serialize`, so homogeneous arrays become `IntVec` / `StrVec`
+//! lanes and arrays of same-shape maps become `ObjVec` rows. Values JSON has
+//! no place for are mapped as follows:
+//!
+//! - byte strings (MessagePack `bin`, CBOR major type 2) become their
+//!   standard, padded base64 text, which `from_base64()` decodes;
+//! - map keys that are not strings become text: integers and floats in
+//!   decimal, `true`, `false`, and `null` as spelled, byte strings as base64,
+//!   and arrays or maps as their compact JSON; when two keys end up equal the
+//!   later value wins, in the position of the first;
+//! - unsigned integers above `i64::MAX`, and negative ones below `i64::MIN`,
+//!   become the nearest float.
+//!
+//! Format-specific rules are described in each codec. Encoding writes every
+//! `Val` losslessly, including non-finite floats; strings that came from
+//! byte strings are written back as strings.
+
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use crate::data::value::{intern_key, Val};
+
+/// Malformed MessagePack or CBOR input, located by the byte offset at which
+/// decoding failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    message: String,
+    offset: usize,
+}
+
+impl DecodeError {
+    /// Return what was wrong, without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the 0-based offset of the byte at which decoding failed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Cursor over an encoded document that tracks container depth against the
+/// nesting limit in force.
+pub(crate) struct Input<'a> {
+    src: &'a [u8],
+    pub(crate) pos: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> Input<'a> {
+    pub(crate) fn new(src: &'a [u8]) -> Self {
+        Self {
+            src,
+            pos: 0,
+            depth: 0,
+            max_depth: crate::exec::limits::max_nesting(),
+        }
+    }
+
+    pub(crate) fn error_at(&self, offset: usize, message: impl Into<String>) -> DecodeError {
+        DecodeError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    pub(crate) fn error(&self, message: impl Into<String>) -> DecodeError {
+        self.error_at(self.pos, message)
+    }
+
+    /// Fails unless the whole input has been consumed.
+    pub(crate) fn finish(&self) -> Result<(), DecodeError> {
+        if self.pos < self.src.len() {
+            return Err(self.error("trailing bytes after document"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    pub(crate) fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = self
+            .peek()
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn take(&mut self, len: u64) -> Result<&'a [u8], DecodeError> {
+        let available = self.src.len() - self.pos;
+        if len > available as u64 {
+            return Err(self.error("unexpected end of input"));
+        }
+        let bytes = &self.src[self.pos..self.pos + len as usize];
+        self.pos += len as usize;
+        Ok(bytes)
+    }
+
+    /// Reads a big-endian unsigned integer of `N` bytes.
+    pub(crate) fn uint<const N: usize>(&mut self) -> Result<u64, DecodeError> {
+        let bytes = self.take(N as u64)?;
+        Ok(bytes.iter().fold(0, |n, &b| (n << 8) | u64::from(b)))
+    }
+
+    /// Reads `len` bytes as a UTF-8 string.
+    pub(crate) fn text(&mut self, len: u64) -> Result<Arc<str>, DecodeError> {
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(Arc::from(text)),
+            Err(_) => Err(self.error_at(start, "invalid UTF-8 in string")),
+        }
+    }
+
+    /// Capacity to reserve for `len` announced items: each takes at least
+    /// one byte, so a corrupt length cannot reserve more than the input.
+    pub(crate) fn capacity(&self, len: u64) -> usize {
+        len.min((self.src.len() - self.pos) as u64) as usize
+    }
+
+    /// Enters an array or map, failing past the nesting limit.
+    pub(crate) fn enter(&mut self) -> Result<(), DecodeError> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            let err = crate::exec::limits::nesting_exceeded("document", self.max_depth);
+            return Err(self.error(err.message()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+/// Value of a byte string: its base64 text.
+pub(crate) fn bytes_val(bytes: &[u8]) -> Val {
+    Val::Str(Arc::from(crate::builtins::helpers::base64_encode(bytes)))
+}
+
+/// Integer too large for `i64`, as the nearest float.
+pub(crate) fn wide_int(n: i128) -> Val {
+    match i64::try_from(n) {
+        Ok(n) => Val::Int(n),
+        Err(_) => Val::Float(n as f64),
+    }
+}
+
+/// Array of decoded items, promoted to a columnar lane where possible.
+pub(crate) fn array_val(items: Vec<Val>) -> Val {
+    crate::data::ser::promote_seq(items)
+}
+
+/// Map of decoded entries, with non-string keys rendered as text.
+pub(crate) fn map_val(entries: Vec<(Val, Val)>) -> Val {
+    let mut map = IndexMap::with_capacity(entries.len());
+    for (key, value) in entries {
+        map.insert(map_key(key), value);
+    }
+    Val::Obj(Arc::new(map))
+}
+
+fn map_key(key: Val) -> Arc<str> {
+    match key {
+        Val::Str(s) => intern_key(&s),
+        other => Arc::from(crate::util::val_to_string(&other)),
+    }
+}
+
+/// Writes the value kinds both formats share.
+pub(crate) trait Encoder {
+    fn null(out: &mut Vec<u8>);
+    fn bool(out: &mut Vec<u8>, b: bool);
+    fn int(out: &mut Vec<u8>, n: i64);
+    fn float(out: &mut Vec<u8>, f: f64);
+    fn str(out: &mut Vec<u8>, s: &str);
+    fn array(out: &mut Vec<u8>, len: usize);
+    fn map(out: &mut Vec<u8>, len: usize);
+}
+
+/// Append the encoding of `val` to `out`. Columnar lanes are written element
+/// by element, and `ObjVec` rows as maps.
+pub(crate) fn encode<E: Encoder>(val: &Val, out: &mut Vec<u8>) {
+    match val {
+        Val::Null => E::null(out),
+        Val::Bool(b) => E::bool(out, *b),
+        Val::Int(n) => E::int(out, *n),
+        Val::Float(f) => E::float(out, *f),
+        Val::Str(s) => E::str(out, s),
+        Val::StrSlice(r) => E::str(out, r.as_str()),
+        Val::Arr(items) => {
+            E::array(out, items.len());
+            for item in items.iter() {
+                encode::<E>(item, out);
+            }
+        }
+        Val::IntVec(xs) => {
+            E::array(out, xs.len());
+            for n in xs.iter() {
+                E::int(out, *n);
+            }
+        }
+        Val::FloatVec(xs) => {
+            E::array(out, xs.len());
+            for f in xs.iter() {
+                E::float(out, *f);
+            }
+        }
+        Val::StrVec(xs) => {
+            E::array(out, xs.len());
+            for s in xs.iter() {
+                E::str(out, s);
+            }
+        }
+        Val::StrSliceVec(xs) => {
+            E::array(out, xs.len());
+            for r in xs.iter() {
+                E::str(out, r.as_str());
+            }
+        }
+        Val::Obj(map) => {
+            E::map(out, map.len());
+            for (key, value) in map.iter() {
+                E::str(out, key);
+                encode::<E>(value, out);
+            }
+        }
+        Val::ObjSmall(pairs) => {
+            E::map(out, pairs.len());
+            for (key, value) in pairs.iter() {
+                E::str(out, key);
+                encode::<E>(value, out);
+            }
+        }
+        Val::ObjVec(data) => {
+            let nrows = data.nrows();
+            E::array(out, nrows);
+            for row in 0..nrows {
+                E::map(out, data.keys.len());
+                for (key, value) in data.keys.iter().zip(data.row_slice(row)) {
+                    E::str(out, key);
+                    encode::<E>(value, out);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! CBOR input and output: `Jetro::from_cbor`, `Jetro::collect_cbor`, and
+//! the `to_cbor()` builtin.
+//!
+//! Decoding follows the shared rules in `binary` and accepts definite and
+//! indefinite lengths. Tags 2 and 3 (bignums) become integers, or the
+//! nearest float beyond the `i64` range; any other tag is dropped and its
+//! content kept, so a tag-0 date is its RFC 3339 string and a tag-1 date its
+//! epoch number. `undefined` and the unassigned simple values become `null`,
+//! and half-precision floats are widened. Integers are written in their
+//! smallest encoding, floats as 64-bit, and all lengths as definite.
+
+use crate::data::binary::{self, DecodeError, Encoder, Input};
+use crate::data::value::Val;
+
+/// Initial byte that ends an indefinite-length item.
+const BREAK: u8 = 0xff;
+/// Additional information announcing an indefinite length.
+const INDEFINITE: u8 = 31;
+
+/// Decode one CBOR data item into a `Val`.
+pub(crate) fn decode(src: &[u8]) -> Result<Val, DecodeError> {
+    let mut input = Input::new(src);
+    let val = value(&mut input)?;
+    input.finish()?;
+    Ok(val)
+}
+
+/// Encode `val` as CBOR.
+pub(crate) fn encode(val: &Val) -> Vec<u8> {
+    let mut out = Vec::new();
+    binary::encode::<Cbor>(val, &mut out);
+    out
+}
+
+fn value(input: &mut Input<'_>) -> Result<Val, DecodeError> {
+    let start = input.pos;
+    let initial = input.byte()?;
+    let (major, info) = (initial >> 5, initial & 0x1f);
+    match major {
+        0 => Ok(binary::wide_int(i128::from(argument(input, info, start)?))),
+        1 => Ok(binary::wide_int(
+            -1 - i128::from(argument(input, info, start)?),
+        )),
+        2 => Ok(binary::bytes_val(&string(input, major, info, start)?)),
+        3 => {
+            let bytes = string(input, major, info, start)?;
+            match String::from_utf8(bytes) {
+                Ok(text) => Ok(Val::Str(text.into())),
+                Err(_) => Err(input.error_at(start, "invalid UTF-8 in string")),
+            }
+        }
+        4 => {
+            input.enter()?;
+            let mut items = Vec::new();
+            if info == INDEFINITE {
+                while !at_break(input) {
+                    items.push(value(input)?);
+                }
+            } else {
+                let len = argument(input, info, start)?;
+                items.reserve(input.capacity(len));
+                for _ in 0..len {
+                    items.push(value(input)?);
+                }
+            }
+            input.leave();
+            Ok(binary::array_val(items))
+        }
+        5 => {
+            input.enter()?;
+            let mut entries = Vec::new();
+            if info == INDEFINITE {
+                while !at_break(input) {
+                    let key = value(input)?;
+                    entries.push((key, value(input)?));
+                }
+            } else {
+                let len = argument(input, info, start)?;
+                entries.reserve(input.capacity(len));
+                for _ in 0..len {
+                    let key = value(input)?;
+                    entries.push((key, value(input)?));
+                }
+            }
+            input.leave();
+            Ok(binary::map_val(entries))
+        }
+        6 => {
+            let tag = argument(input, info, start)?;
+            input.enter()?;
+            let content = match (tag, input.peek().map(|b| b >> 5)) {
+                (2 | 3, Some(2)) => {
+                    let (at, initial) = (input.pos, input.byte()?);
+                    bignum(&string(input, 2, initial & 0x1f, at)?, tag == 3)
+                }
+                _ => value(input)?,
+            };
+            input.leave();
+            Ok(content)
+        }
+        _ => match info {
+            20 => Ok(Val::Bool(false)),
+            21 => Ok(Val::Bool(true)),
+            0..=19 | 22 | 23 => Ok(Val::Null),
+            24 => input.byte().map(|_| Val::Null),
+            25 => Ok(Val::Float(half(input.uint::<2>()? as u16))),
+            26 => Ok(Val::Float(f64::from(f32::from_bits(
+                input.uint::<4>()? as u32
+            )))),
+            27 => Ok(Val::Float(f64::from_bits(input.uint::<8>()?))),
+            INDEFINITE => Err(input.error_at(start, "unexpected break")),
+            _ => Err(input.error_at(start, "reserved additional information")),
+        },
+    }
+}
+
+/// The length or value that follows an initial byte with additional
+/// information `info`.
+fn argument(input: &mut Input<'_>, info: u8, start: usize) -> Result<u64, DecodeError> {
+    match info {
+        0..=23 => Ok(u64::from(info)),
+        24 => input.uint::<1>(),
+        25 => input.uint::<2>(),
+        26 => input.uint::<4>(),
+        27 => input.uint::<8>(),
+        INDEFINITE => Err(input.error_at(start, "unexpected indefinite length")),
+        _ => Err(input.error_at(start, "reserved additional information")),
+    }
+}
+
+/// Consumes a break byte if one is next.
+fn at_break(input: &mut Input<'_>) -> bool {
+    let found = input.peek() == Some(BREAK);
+    if found {
+        input.pos += 1;
+    }
+    found
+}
+
+/// The bytes of a byte or text string of type `major`, joining the chunks
+/// of an indefinite-length one.
+fn string(
+    input: &mut Input<'_>,
+    major: u8,
+    info: u8,
+    start: usize,
+) -> Result<Vec<u8>, DecodeError> {
+    if info != INDEFINITE {
+        let len = argument(input, info, start)?;
+        return Ok(input.take(len)?.to_vec());
+    }
+    let mut bytes = Vec::new();
+    while !at_break(input) {
+        let at = input.pos;
+        let initial = input.byte()?;
+        if initial >> 5 != major || initial & 0x1f == INDEFINITE {
+            return Err(input.error_at(at, "invalid chunk in indefinite-length string"));
+        }
+        let len = argument(input, initial & 0x1f, at)?;
+        bytes.extend_from_slice(input.take(len)?);
+    }
+    Ok(bytes)
+}
+
+/// A tag-2 bignum, or with `negative` a tag-3 one, from its big-endian
+/// magnitude `bytes`.
+fn bignum(bytes: &[u8], negative: bool) -> Val {
+    let digits = &bytes[bytes.iter().take_while(|&&b| b == 0).count()..];
+    if digits.len() < 16 {
+        let n = digits.iter().fold(0i128, |n, &b| (n << 8) | i128::from(b));
+        return binary::wide_int(if negative { -1 - n } else { n });
+    }
+    let n = digits.iter().fold(0.0, |n, &b| n * 256.0 + f64::from(b));
+    Val::Float(if negative { -1.0 - n } else { n })
+}
+
+/// Widens an IEEE 754 half-precision float.
+fn half(bits: u16) -> f64 {
+    let exp = i32::from((bits >> 10) & 0x1f);
+    let mant = f64::from(bits & 0x3ff);
+    let magnitude = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mant + 1024.0) * 2f64.powi(exp - 25),
+    };
+    if bits & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+struct Cbor;
+
+impl Cbor {
+    /// Writes an initial byte of type `major` and its argument `n` in the
+    /// fewest bytes.
+    fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+        let major = major << 5;
+        if n < 24 {
+            out.push(major | n as u8);
+        } else if n <= u64::from(u8::MAX) {
+            out.extend_from_slice(&[major | 24, n as u8]);
+        } else if n <= u64::from(u16::MAX) {
+            out.push(major | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        } else if n <= u64::from(u32::MAX) {
+            out.push(major | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        } else {
+            out.push(major | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+impl Encoder for Cbor {
+    fn null(out: &mut Vec<u8>) {
+        out.push(0xf6);
+    }
+
+    fn bool(out: &mut Vec<u8>, b: bool) {
+        out.push(if b { 0xf5 } else { 0xf4 });
+    }
+
+    fn int(out: &mut Vec<u8>, n: i64) {
+        if n >= 0 {
+            Self::head(out, 0, n as u64);
+        } else {
+            // -1 - n, without overflowing at i64::MIN
+            Self::head(out, 1, !n as u64);
+        }
+    }
+
+    fn float(out: &mut Vec<u8>, f: f64) {
+        out.push(0xfb);
+        out.extend_from_slice(&f.to_be_bytes());
+    }
+
+    fn str(out: &mut Vec<u8>, s: &str) {
+        Self::head(out, 3, s.len() as u64);
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn array(out: &mut Vec<u8>, len: usize) {
+        Self::head(out, 4, len as u64);
+    }
+
+    fn map(out: &mut Vec<u8>, len: usize) {
+        Self::head(out, 5, len as u64);
+    }
+}
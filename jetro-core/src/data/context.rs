@@ -5,22 +5,249 @@
 //! per-scope but kept cheap via `SmallVec` (inline storage for ≤4 vars).
 
 use crate::data::value::Val;
+use crate::parse::ast::Span;
 use smallvec::SmallVec;
+use std::ops::Range;
 use std::sync::Arc;
 
-/// Evaluation error carrying a human-readable message. Propagated through
-/// `Result<Val, EvalError>` across all execution layers.
+/// Broad category of an `EvalError`, stable enough for callers to branch on
+/// instead of matching message text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EvalErrorKind {
+    /// A value had the wrong type for the operation (e.g. `filter` on a number).
+    TypeMismatch,
+    /// An index or positional argument lookup fell outside the valid range.
+    IndexOutOfRange,
+    /// A regex pattern failed to compile.
+    InvalidRegex,
+    /// `DELETE` was used outside a patch-field value.
+    DeleteOutsidePatch,
+    /// The input document (or a `from_json` argument) is not valid JSON.
+    InvalidJson,
+    /// A builtin received a missing or malformed argument.
+    InvalidArgument,
+    /// Integer or float division by zero.
+    DivisionByZero,
+    /// The method or builtin name is not known, or has no implementation for the receiver.
+    UnknownMethod,
+    /// A let-bound local was referenced outside its scope.
+    UnboundVariable,
+    /// The expression source failed to parse on a path that compiles lazily.
+    Parse,
+    /// A query result could not be deserialized into the requested Rust type.
+    Deserialize,
+    /// A Rust value could not be serialized into a document.
+    Serialize,
+    /// Writing a serialized result to the caller's output failed.
+    Io,
+    /// Evaluation hit one of the engine's `ExecutionLimits`.
+    LimitExceeded,
+    /// Evaluation was stopped through a `CancellationToken`.
+    Cancelled,
+    /// An internal invariant of the executor was violated.
+    Internal,
+}
+
+impl EvalErrorKind {
+    /// Stable snake_case name, suitable for logs and metrics labels.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TypeMismatch => "type_mismatch",
+            Self::IndexOutOfRange => "index_out_of_range",
+            Self::InvalidRegex => "invalid_regex",
+            Self::DeleteOutsidePatch => "delete_outside_patch",
+            Self::InvalidJson => "invalid_json",
+            Self::InvalidArgument => "invalid_argument",
+            Self::DivisionByZero => "division_by_zero",
+            Self::UnknownMethod => "unknown_method",
+            Self::UnboundVariable => "unbound_variable",
+            Self::Parse => "parse",
+            Self::Deserialize => "deserialize",
+            Self::Serialize => "serialize",
+            Self::Io => "io",
+            Self::LimitExceeded => "limit_exceeded",
+            Self::Cancelled => "cancelled",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Evaluation error propagated through `Result<Val, EvalError>` across all
+/// execution layers. Carries an `EvalErrorKind`, a human-readable message, and
+/// optional context: the builtin or opcode that failed, the byte span of the
+/// offending sub-expression in the query source, and the JSON path of the
+/// value being processed. Boxed so the `Result` stays pointer-sized on hot paths.
+#[derive(Debug, Clone)]
+pub struct EvalError(Box<EvalErrorDetail>);
+
+/// Heap-allocated payload behind `EvalError`.
 #[derive(Debug, Clone)]
-pub struct EvalError(pub String);
+struct EvalErrorDetail {
+    kind: EvalErrorKind,
+    message: String,
+    operation: Option<Arc<str>>,
+    span: Option<Range<usize>>,
+    path: Option<String>,
+}
+
+impl EvalError {
+    /// Build an error of `kind` with no operation, span, or path attached.
+    pub fn new(kind: EvalErrorKind, message: impl Into<String>) -> Self {
+        Self(Box::new(EvalErrorDetail {
+            kind,
+            message: message.into(),
+            operation: None,
+            span: None,
+            path: None,
+        }))
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::TypeMismatch, ..)`.
+    pub fn type_mismatch(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::TypeMismatch, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::IndexOutOfRange, ..)`.
+    pub fn index_out_of_range(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::IndexOutOfRange, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::InvalidRegex, ..)`.
+    pub fn invalid_regex(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::InvalidRegex, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::InvalidJson, ..)`.
+    pub fn invalid_json(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::InvalidJson, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::InvalidArgument, ..)`.
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::InvalidArgument, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::UnknownMethod, ..)`.
+    pub fn unknown_method(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::UnknownMethod, message)
+    }
+
+    /// Shorthand for `EvalError::new(EvalErrorKind::Internal, ..)`.
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(EvalErrorKind::Internal, message)
+    }
+
+    /// Classify a failure from serializing a result into a writer: I/O failures
+    /// become `Io`, anything else is an `Internal` serializer fault.
+    pub(crate) fn from_write(err: serde_json::Error) -> Self {
+        let kind = if err.is_io() {
+            EvalErrorKind::Io
+        } else {
+            EvalErrorKind::Internal
+        };
+        Self::new(kind, format!("write result: {err}"))
+    }
+
+    /// Return the error category.
+    #[inline]
+    pub fn kind(&self) -> EvalErrorKind {
+        self.0.kind
+    }
+
+    /// Return the human-readable message without the `eval error:` prefix.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    /// Return the builtin or opcode name that raised the error, if known.
+    #[inline]
+    pub fn operation(&self) -> Option<&str> {
+        self.0.operation.as_deref()
+    }
+
+    /// Return the byte range of the offending sub-expression in the query
+    /// source, if it could be located. Parse errors carry an empty range at
+    /// the offset where parsing failed.
+    #[inline]
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.0.span.clone()
+    }
+
+    /// Return the JSON path (e.g. `$.orders`) of the value being processed, if known.
+    #[inline]
+    pub fn path(&self) -> Option<&str> {
+        self.0.path.as_deref()
+    }
+
+    /// Record `operation` unless an inner layer already attached one.
+    pub fn or_operation(mut self, operation: &str) -> Self {
+        if self.0.operation.is_none() {
+            self.0.operation = Some(Arc::from(operation));
+        }
+        self
+    }
+
+    /// Record the call `operation` and its source `span` unless an inner layer
+    /// already attached an operation.
+    pub(crate) fn or_call(mut self, operation: &str, span: Span) -> Self {
+        if self.0.operation.is_none() {
+            self.0.operation = Some(Arc::from(operation));
+            self.0.span = span.range();
+        }
+        self
+    }
+
+    /// Record `path` unless an inner layer already attached a more specific one.
+    pub fn or_path(mut self, path: impl FnOnce() -> String) -> Self {
+        if self.0.path.is_none() {
+            self.0.path = Some(path());
+        }
+        self
+    }
+
+    /// Prefix the message with `prefix: `, keeping kind and context intact.
+    pub(crate) fn with_message_prefix(mut self, prefix: &str) -> Self {
+        self.0.message = format!("{}: {}", prefix, self.0.message);
+        self
+    }
+}
 
 impl std::fmt::Display for EvalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "eval error: {}", self.0)
+        write!(f, "eval error: {}", self.0.message)
     }
 }
 
 impl std::error::Error for EvalError {}
 
+impl serde::de::Error for EvalError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::new(EvalErrorKind::Deserialize, msg.to_string())
+    }
+}
+
+impl serde::ser::Error for EvalError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::new(EvalErrorKind::Serialize, msg.to_string())
+    }
+}
+
+impl From<crate::parse::parser::ParseError> for EvalError {
+    /// Keep the failure offset as an empty `span` at that position.
+    fn from(err: crate::parse::parser::ParseError) -> Self {
+        let mut eval = Self::new(EvalErrorKind::Parse, err.to_string());
+        eval.0.span = Some(err.offset..err.offset);
+        eval
+    }
+}
 
 /// Saved-state token for the hot-loop lambda binding protocol.
 /// `push_lam` returns one; `pop_lam` consumes it. Avoids full `Env` clone
@@ -0,0 +1,429 @@
+//! CSV and TSV input: `Jetro::from_csv` and the `from_csv()` builtin.
+//!
+//! `parse` reads delimited text in one pass (RFC 4180 quoting: quoted fields
+//! may hold delimiters, newlines, and doubled quote characters) and writes
+//! the fields straight into the row-major cells of a `Val::ObjVec`, one row
+//! per record keyed by the header; a leading UTF-8 byte order mark is
+//! skipped. Column lanes are typed up front, so
+//! `filter`, `map`, and `group_by` over the rows take the columnar executor
+//! without first promoting an array of objects.
+
+use std::sync::Arc;
+
+use crate::data::value::{build_typed_cols_from_cells, intern_key, ObjVecData, Val};
+
+/// How `Jetro::from_csv` reads its input.
+///
+/// ```rust
+/// use jetro_core::{CsvOptions, Jetro};
+///
+/// let csv = b"name,age,active\nAda,36,true\nAlan,41,false\nGrace,,true\n";
+/// let j = Jetro::from_csv(csv, CsvOptions::new()).unwrap();
+/// assert_eq!(
+///     j.collect("$.filter(active).map(name)").unwrap(),
+///     serde_json::json!(["Ada", "Grace"])
+/// );
+/// assert_eq!(j.collect("$[2].age").unwrap(), serde_json::Value::Null);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    header: bool,
+    delimiter: u8,
+    quote: u8,
+    infer_types: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            header: true,
+            delimiter: b',',
+            quote: b'"',
+            infer_types: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Comma-separated with a header row, `"` quotes, and type inference;
+    /// same as `Default`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tab-separated, otherwise the same as `new`.
+    pub fn tsv() -> Self {
+        Self::new().delimiter(b'\t')
+    }
+
+    /// Take column names from the first record. Without a header, columns
+    /// are named by position: `c0`, `c1`, ...
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Field separator; must be an ASCII byte other than a line break or
+    /// the quote character, or parsing fails.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Quote character; must be an ASCII byte other than a line break or the
+    /// delimiter, or parsing fails. Doubling it inside a quoted field stands
+    /// for one literal quote.
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Type each column from its fields: a column whose fields all read as
+    /// integers becomes integers, one mixing integers and decimals becomes
+    /// floats, and one of `true`/`false` becomes booleans; any other column,
+    /// including one with a quoted field or a number with a leading zero,
+    /// stays strings. Empty unquoted fields become `null` in every column.
+    /// With this off every field is a string.
+    pub fn infer_types(mut self, infer: bool) -> Self {
+        self.infer_types = infer;
+        self
+    }
+
+    /// Reject a delimiter or quote the reader cannot tell apart from the
+    /// input's structure, reported at the start of the input.
+    fn validate(&self) -> Result<(), CsvError> {
+        let usable = |b: u8| b.is_ascii() && !matches!(b, b'\n' | b'\r');
+        let message = if !usable(self.delimiter) {
+            "delimiter must be an ASCII byte other than a line break"
+        } else if !usable(self.quote) {
+            "quote must be an ASCII byte other than a line break"
+        } else if self.delimiter == self.quote {
+            "delimiter and quote must differ"
+        } else {
+            return Ok(());
+        };
+        Err(CsvError {
+            message: message.to_owned(),
+            line: 1,
+            column: 1,
+        })
+    }
+}
+
+/// Malformed input to `Jetro::from_csv`, located by 1-based line and
+/// column (counted in characters).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl CsvError {
+    /// Return what was wrong, without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the 1-based line of the error.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the 1-based column of the error.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// One field as read from the input.
+struct Field {
+    text: String,
+    quoted: bool,
+}
+
+/// Parse `src` into a `Val::ObjVec` with one row per record.
+pub(crate) fn parse(src: &[u8], options: CsvOptions) -> Result<Val, CsvError> {
+    options.validate()?;
+    let mut reader = Reader {
+        src,
+        pos: 0,
+        line: 1,
+        line_start: 0,
+        options,
+    };
+    if let Err(err) = std::str::from_utf8(src) {
+        reader.pos = err.valid_up_to();
+        reader.line += src[..reader.pos].iter().filter(|&&b| b == b'\n').count();
+        reader.line_start = src[..reader.pos]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |nl| nl + 1);
+        return Err(reader.error("invalid UTF-8"));
+    }
+    // a byte order mark is not part of the first field
+    if src.starts_with(b"\xEF\xBB\xBF") {
+        reader.pos = 3;
+        reader.line_start = 3;
+    }
+    let mut keys: Option<Arc<[Arc<str>]>> = None;
+    let mut fields_by_row = Vec::new();
+    let mut nrows = 0;
+    while let Some((line, fields)) = reader.record()? {
+        let width = match &keys {
+            Some(keys) => keys.len(),
+            None if options.header => {
+                keys = Some(header_keys(line, fields)?);
+                continue;
+            }
+            None => {
+                let names = (0..fields.len()).map(|i| intern_key(&format!("c{}", i)));
+                keys = Some(names.collect());
+                fields.len()
+            }
+        };
+        if fields.len() != width {
+            return Err(CsvError {
+                message: format!("expected {} fields, found {}", width, fields.len()),
+                line,
+                column: 1,
+            });
+        }
+        fields_by_row.extend(fields);
+        nrows += 1;
+    }
+    let keys = keys.unwrap_or_else(|| Arc::from(Vec::new()));
+    let cells = if options.infer_types {
+        typed_cells(fields_by_row, keys.len())
+    } else {
+        let text = |field: Field| Val::Str(Arc::from(field.text));
+        fields_by_row.into_iter().map(text).collect()
+    };
+    let typed_cols = build_typed_cols_from_cells(&cells, keys.len(), nrows);
+    Ok(Val::ObjVec(Arc::new(ObjVecData {
+        keys,
+        cells,
+        typed_cols: Some(Arc::new(typed_cols)),
+    })))
+}
+
+/// Column names from the header record; they must be distinct.
+fn header_keys(line: usize, fields: Vec<Field>) -> Result<Arc<[Arc<str>]>, CsvError> {
+    let mut keys: Vec<Arc<str>> = Vec::with_capacity(fields.len());
+    for field in fields {
+        if keys.iter().any(|key| **key == *field.text) {
+            return Err(CsvError {
+                message: format!("duplicate column `{}`", field.text),
+                line,
+                column: 1,
+            });
+        }
+        keys.push(intern_key(&field.text));
+    }
+    Ok(keys.into())
+}
+
+/// What every non-empty field of a column reads as.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Float,
+    Bool,
+    Str,
+}
+
+impl Kind {
+    fn of(field: &Field) -> Kind {
+        let text = field.text.as_str();
+        if field.quoted {
+            return Kind::Str;
+        }
+        if matches!(text, "true" | "false" | "True" | "False" | "TRUE" | "FALSE") {
+            return Kind::Bool;
+        }
+        let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+        let bytes = digits.as_bytes();
+        // identifiers such as zip codes keep their leading zeros
+        if bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_digit() {
+            return Kind::Str;
+        }
+        if !bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit) && text.parse::<i64>().is_ok()
+        {
+            return Kind::Int;
+        }
+        // `f64::from_str` also takes `inf` and `NaN`, which are not numbers here
+        let numeric = bytes
+            .first()
+            .is_some_and(|b| b.is_ascii_digit() || *b == b'.')
+            && bytes
+                .iter()
+                .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+'));
+        match text.parse::<f64>() {
+            Ok(f) if numeric && f.is_finite() => Kind::Float,
+            _ => Kind::Str,
+        }
+    }
+
+    fn join(self, other: Kind) -> Kind {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Kind::Int, Kind::Float) | (Kind::Float, Kind::Int) => Kind::Float,
+            _ => Kind::Str,
+        }
+    }
+}
+
+/// Row-major `fields` as values, each column read as the one kind all its
+/// non-empty fields share.
+fn typed_cells(fields: Vec<Field>, stride: usize) -> Vec<Val> {
+    let mut kinds: Vec<Option<Kind>> = vec![None; stride];
+    for (i, field) in fields.iter().enumerate() {
+        if field.text.is_empty() && !field.quoted {
+            continue;
+        }
+        let slot = &mut kinds[i % stride];
+        let kind = Kind::of(field);
+        *slot = Some(slot.map_or(kind, |seen| seen.join(kind)));
+    }
+    fields
+        .into_iter()
+        .enumerate()
+        .map(|(i, field)| {
+            if field.text.is_empty() && !field.quoted {
+                return Val::Null;
+            }
+            match kinds[i % stride] {
+                Some(Kind::Int) => Val::Int(field.text.parse().unwrap_or_default()),
+                Some(Kind::Float) => Val::Float(field.text.parse().unwrap_or_default()),
+                Some(Kind::Bool) => Val::Bool(field.text.eq_ignore_ascii_case("true")),
+                _ => Val::Str(Arc::from(field.text)),
+            }
+        })
+        .collect()
+}
+
+struct Reader<'a> {
+    src: &'a [u8],
+    pos: usize,
+    /// 1-based line of `pos`.
+    line: usize,
+    /// Offset of the first byte of that line.
+    line_start: usize,
+    options: CsvOptions,
+}
+
+impl Reader<'_> {
+    /// The next non-blank record and the line it starts on, or `None` at
+    /// the end of input.
+    fn record(&mut self) -> Result<Option<(usize, Vec<Field>)>, CsvError> {
+        while self.pos < self.src.len() && matches!(self.src[self.pos], b'\n' | b'\r') {
+            self.newline();
+        }
+        if self.pos == self.src.len() {
+            return Ok(None);
+        }
+        let line = self.line;
+        let mut fields = Vec::new();
+        loop {
+            fields.push(self.field()?);
+            match self.src.get(self.pos) {
+                Some(&b) if b == self.options.delimiter => self.pos += 1,
+                Some(b'\n' | b'\r') => {
+                    self.newline();
+                    break;
+                }
+                None => break,
+                Some(_) => return Err(self.error("expected delimiter after closing quote")),
+            }
+        }
+        Ok(Some((line, fields)))
+    }
+
+    /// Reads one field, stopping at the delimiter or line break after it.
+    fn field(&mut self) -> Result<Field, CsvError> {
+        let CsvOptions {
+            delimiter, quote, ..
+        } = self.options;
+        if self.src.get(self.pos) != Some(&quote) {
+            let start = self.pos;
+            while let Some(&b) = self.src.get(self.pos) {
+                if b == delimiter || b == b'\n' || b == b'\r' {
+                    break;
+                }
+                self.pos += 1;
+            }
+            return Ok(Field {
+                text: self.text(&self.src[start..self.pos]),
+                quoted: false,
+            });
+        }
+        let (open, open_line, open_start) = (self.pos, self.line, self.line_start);
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&b) = self.src.get(self.pos) else {
+                self.pos = open;
+                self.line = open_line;
+                self.line_start = open_start;
+                return Err(self.error("unterminated quoted field"));
+            };
+            if b == quote {
+                if self.src.get(self.pos + 1) == Some(&quote) {
+                    bytes.push(quote);
+                    self.pos += 2;
+                    continue;
+                }
+                self.pos += 1;
+                break;
+            }
+            bytes.push(b);
+            if b == b'\n' || (b == b'\r' && self.src.get(self.pos + 1) != Some(&b'\n')) {
+                self.line += 1;
+                self.line_start = self.pos + 1;
+            }
+            self.pos += 1;
+        }
+        Ok(Field {
+            text: self.text(&bytes),
+            quoted: true,
+        })
+    }
+
+    /// Field bytes as a string. The input was checked to be UTF-8, and
+    /// fields split at ASCII delimiters and quotes stay valid.
+    fn text(&self, bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    /// Steps over a `\n`, `\r\n`, or lone `\r` line break at `pos`.
+    fn newline(&mut self) {
+        if self.src[self.pos] == b'\r' && self.src.get(self.pos + 1) == Some(&b'\n') {
+            self.pos += 1;
+        }
+        self.pos += 1;
+        self.line += 1;
+        self.line_start = self.pos;
+    }
+
+    fn error(&self, message: &str) -> CsvError {
+        let prefix = &self.src[self.line_start..self.pos];
+        let column = String::from_utf8_lossy(prefix).chars().count() + 1;
+        CsvError {
+            message: message.to_owned(),
+            line: self.line,
+            column,
+        }
+    }
+}
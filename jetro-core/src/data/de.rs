@@ -0,0 +1,424 @@
+//! `serde::Deserializer` over `Val`.
+//!
+//! Lets typed callers (`Jetro::collect_as`) deserialize a query result
+//! straight from the engine's value tree instead of converting it to a
+//! `serde_json::Value` first. Columnar lanes (`IntVec`, `FloatVec`, `StrVec`,
+//! `StrSliceVec`) and struct-of-arrays `ObjVec` rows are visited in place, so
+//! extraction allocates only what the target type itself owns.
+//!
+//! Numbers and non-finite floats follow the same rules as
+//! `impl From<Val> for serde_json::Value`, so `collect_as::<T>` agrees with
+//! `serde_json::from_value::<T>(collect(..))`.
+
+use std::sync::Arc;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::data::context::EvalError;
+use crate::data::value::Val;
+
+/// Deserialize a `T` from `val` without an intermediate `serde_json::Value`.
+pub(crate) fn from_val<T: DeserializeOwned>(val: &Val) -> Result<T, EvalError> {
+    T::deserialize(ValDeserializer(val))
+}
+
+/// Borrowing deserializer over one `Val` node.
+#[derive(Clone, Copy)]
+pub(crate) struct ValDeserializer<'de>(pub(crate) &'de Val);
+
+impl<'de> IntoDeserializer<'de, EvalError> for ValDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Visit a float the way `serde_json::Value::from(Val)` encodes it: non-finite
+/// values become integer zero.
+fn visit_float<'de, V: Visitor<'de>>(f: f64, visitor: V) -> Result<V::Value, EvalError> {
+    if f.is_finite() {
+        visitor.visit_f64(f)
+    } else {
+        visitor.visit_i64(0)
+    }
+}
+
+/// Visit `pairs` as a map whose keys are field names.
+fn visit_pairs<'de, V, I>(pairs: I, visitor: V) -> Result<V::Value, EvalError>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = (&'de Arc<str>, &'de Val)>,
+{
+    let mut map = MapDeserializer::new(pairs.map(|(k, v)| (k.as_ref(), ValDeserializer(v))));
+    let out = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(out)
+}
+
+/// Visit `items` as a sequence, rejecting leftovers the visitor did not consume.
+fn visit_items<'de, V, I, T>(items: I, visitor: V) -> Result<V::Value, EvalError>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = T>,
+    T: IntoDeserializer<'de, EvalError>,
+{
+    let mut seq = SeqDeserializer::new(items);
+    let out = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(out)
+}
+
+impl<'de> de::Deserializer<'de> for ValDeserializer<'de> {
+    type Error = EvalError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        match self.0 {
+            Val::Null => visitor.visit_unit(),
+            Val::Bool(b) => visitor.visit_bool(*b),
+            Val::Int(n) => visitor.visit_i64(*n),
+            Val::Float(f) => visit_float(*f, visitor),
+            Val::Str(s) => visitor.visit_borrowed_str(s),
+            Val::StrSlice(r) => visitor.visit_borrowed_str(r.as_str()),
+            Val::Arr(items) => visit_items(items.iter().map(ValDeserializer), visitor),
+            Val::IntVec(xs) => visit_items(xs.iter().copied(), visitor),
+            Val::FloatVec(xs) => visit_items(xs.iter().map(|f| FloatDeserializer(*f)), visitor),
+            Val::StrVec(xs) => visit_items(xs.iter().map(|s| s.as_ref()), visitor),
+            Val::StrSliceVec(xs) => visit_items(xs.iter().map(|r| r.as_str()), visitor),
+            Val::Obj(m) => visit_pairs(m.iter(), visitor),
+            Val::ObjSmall(pairs) => visit_pairs(pairs.iter().map(|(k, v)| (k, v)), visitor),
+            Val::ObjVec(d) => visit_items(
+                (0..d.nrows()).map(|row| RowDeserializer {
+                    keys: &d.keys,
+                    row: d.row_slice(row),
+                }),
+                visitor,
+            ),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        match self.0 {
+            Val::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, EvalError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EvalError> {
+        match self.0 {
+            Val::Str(_) | Val::StrSlice(_) => visitor.visit_enum(EnumDeserializer {
+                variant: self.0.as_str_ref().unwrap_or_default(),
+                value: None,
+            }),
+            Val::Obj(m) if m.len() == 1 => {
+                let (k, v) = m.first().expect("one entry");
+                visitor.visit_enum(EnumDeserializer {
+                    variant: k,
+                    value: Some(v),
+                })
+            }
+            Val::ObjSmall(pairs) if pairs.len() == 1 => visitor.visit_enum(EnumDeserializer {
+                variant: &pairs[0].0,
+                value: Some(&pairs[0].1),
+            }),
+            _ => Err(de::Error::invalid_type(
+                unexpected(self.0),
+                &"a string or single-key object",
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// Describe `val` for serde's `invalid_type` errors.
+fn unexpected(val: &Val) -> de::Unexpected<'_> {
+    match val {
+        Val::Null => de::Unexpected::Unit,
+        Val::Bool(b) => de::Unexpected::Bool(*b),
+        Val::Int(n) => de::Unexpected::Signed(*n),
+        Val::Float(f) => de::Unexpected::Float(*f),
+        Val::Str(_) | Val::StrSlice(_) => de::Unexpected::Str(val.as_str_ref().unwrap_or_default()),
+        Val::Arr(_)
+        | Val::IntVec(_)
+        | Val::FloatVec(_)
+        | Val::StrVec(_)
+        | Val::StrSliceVec(_)
+        | Val::ObjVec(_) => de::Unexpected::Seq,
+        Val::Obj(_) | Val::ObjSmall(_) => de::Unexpected::Map,
+    }
+}
+
+/// One `FloatVec` element, encoded like a standalone `Val::Float`.
+struct FloatDeserializer(f64);
+
+impl<'de> IntoDeserializer<'de, EvalError> for FloatDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for FloatDeserializer {
+    type Error = EvalError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        visit_float(self.0, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// One `ObjVec` row, visited as a map over the shared key schema.
+struct RowDeserializer<'de> {
+    keys: &'de [Arc<str>],
+    row: &'de [Val],
+}
+
+impl<'de> IntoDeserializer<'de, EvalError> for RowDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for RowDeserializer<'de> {
+    type Error = EvalError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        visit_pairs(self.keys.iter().zip(self.row.iter()), visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EvalError> {
+        visitor.visit_some(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Externally tagged enum: `"Variant"` or `{"Variant": value}`.
+struct EnumDeserializer<'de> {
+    variant: &'de str,
+    value: Option<&'de Val>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = EvalError;
+    type Variant = VariantDeserializer<'de>;
+
+    fn variant_seed<S: de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), EvalError> {
+        let variant = seed.deserialize(de::value::BorrowedStrDeserializer::<EvalError>::new(self.variant))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+/// Payload of an enum variant; `None` for unit variants written as strings.
+struct VariantDeserializer<'de>(Option<&'de Val>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer<'de> {
+    type Error = EvalError;
+
+    fn unit_variant(self) -> Result<(), EvalError> {
+        match self.0 {
+            None | Some(Val::Null) => Ok(()),
+            Some(other) => Err(de::Error::invalid_type(unexpected(other), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<S: de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, EvalError> {
+        match self.0 {
+            Some(value) => seed.deserialize(ValDeserializer(value)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, EvalError> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_seq(ValDeserializer(value), visitor),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EvalError> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_map(ValDeserializer(value), visitor),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use indexmap::IndexMap;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::from_val;
+    use crate::data::value::{ObjVecData, Val};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Book {
+        title: String,
+        price: f64,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Dot,
+        Circle(f64),
+        Rect { w: i64, h: i64 },
+    }
+
+    fn same_as_json<T>(val: Val)
+    where
+        T: serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let direct: T = from_val(&val).unwrap();
+        let via_json: T = serde_json::from_value(serde_json::Value::from(val)).unwrap();
+        assert_eq!(direct, via_json);
+    }
+
+    #[test]
+    fn scalars_and_options() {
+        assert_eq!(from_val::<i64>(&Val::Int(7)).unwrap(), 7);
+        assert_eq!(from_val::<u8>(&Val::Int(7)).unwrap(), 7);
+        assert_eq!(from_val::<f64>(&Val::Int(7)).unwrap(), 7.0);
+        assert_eq!(from_val::<Option<bool>>(&Val::Null).unwrap(), None);
+        assert_eq!(from_val::<Option<bool>>(&Val::Bool(true)).unwrap(), Some(true));
+        assert_eq!(from_val::<String>(&Val::Str("hi".into())).unwrap(), "hi");
+        assert!(from_val::<u8>(&Val::Int(-1)).is_err());
+    }
+
+    #[test]
+    fn columnar_lanes_deserialize_in_place() {
+        same_as_json::<Vec<i64>>(Val::IntVec(Arc::new(vec![1, 2, 3])));
+        same_as_json::<Vec<f64>>(Val::FloatVec(Arc::new(vec![1.5, f64::NAN])));
+        same_as_json::<Vec<String>>(Val::StrVec(Arc::new(vec!["a".into(), "b".into()])));
+        same_as_json::<(i64, i64)>(Val::IntVec(Arc::new(vec![4, 5])));
+    }
+
+    #[test]
+    fn objvec_rows_deserialize_as_structs() {
+        let keys: Arc<[Arc<str>]> = vec![Arc::from("title"), Arc::from("price")].into();
+        let data = ObjVecData {
+            keys,
+            cells: vec![
+                Val::Str("Dune".into()),
+                Val::Float(9.5),
+                Val::Str("Emma".into()),
+                Val::Int(4),
+            ],
+            typed_cols: None,
+        };
+        let books: Vec<Book> = from_val(&Val::ObjVec(Arc::new(data))).unwrap();
+        assert_eq!(books[0].title, "Dune");
+        assert_eq!(books[1].price, 4.0);
+        assert!(books[1].tags.is_empty());
+    }
+
+    #[test]
+    fn objects_and_enums_match_serde_json() {
+        let v = json!({"title": "Dune", "price": 9.5, "tags": ["sf"]});
+        same_as_json::<Book>(Val::from(&v));
+        same_as_json::<BTreeMap<String, serde_json::Value>>(Val::from(&v));
+        same_as_json::<Vec<Shape>>(Val::from(&json!([
+            "Dot",
+            {"Circle": 2.0},
+            {"Rect": {"w": 1, "h": 2}}
+        ])));
+
+        let mut small = IndexMap::new();
+        small.insert(Arc::<str>::from("Circle"), Val::Float(1.0));
+        let pairs: Arc<[(Arc<str>, Val)]> = small.into_iter().collect::<Vec<_>>().into();
+        assert_eq!(from_val::<Shape>(&Val::ObjSmall(pairs)).unwrap(), Shape::Circle(1.0));
+    }
+
+    #[test]
+    fn type_errors_are_deserialize_kind() {
+        let err = from_val::<Book>(&Val::Int(1)).unwrap_err();
+        assert_eq!(err.kind(), crate::EvalErrorKind::Deserialize);
+        let err = from_val::<(i64,)>(&Val::IntVec(Arc::new(vec![1, 2]))).unwrap_err();
+        assert_eq!(err.kind(), crate::EvalErrorKind::Deserialize);
+    }
+
+    #[test]
+    fn collect_as_matches_collect_then_from_value() {
+        let doc = json!({
+            "books": [
+                {"title": "Dune", "price": 9.5, "tags": ["sf"]},
+                {"title": "Emma", "price": 4, "tags": []}
+            ]
+        });
+        let engine = crate::JetroEngine::new();
+        for j in [
+            crate::Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+            crate::Jetro::from(doc.clone()),
+        ] {
+            for expr in ["$.books", "$.books.map({title, price})", "$.books.filter(price > 5)"] {
+                let direct: Vec<Book> = j.collect_as(expr).unwrap();
+                let via_json: Vec<Book> = serde_json::from_value(j.collect(expr).unwrap()).unwrap();
+                assert_eq!(direct, via_json, "{expr}");
+                let cached: Vec<Book> = engine.collect_as(&j, expr).unwrap();
+                assert_eq!(cached, via_json, "{expr}");
+            }
+            let prices: Vec<f64> = j.collect_as("$.books.map(price)").unwrap();
+            assert_eq!(prices, vec![9.5, 4.0]);
+        }
+    }
+}
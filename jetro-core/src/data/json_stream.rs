@@ -0,0 +1,402 @@
+//! Concatenated JSON input for `JetroEngine`: top-level values written back
+//! to back (`{...}{...}[...] 1 "x"`) with optional whitespace between them.
+//!
+//! `Documents` reads any `std::io::Read` in fixed-size chunks and splits it
+//! with a structural scan that tracks only bracket depth and string state,
+//! so each document's bytes are found without parsing them and only the
+//! document being scanned is held in memory. A bare number or literal ends
+//! where its JSON token does, so `-1-2` is two documents and `truefalse`
+//! is `true` then `false`. The bytes are then parsed by the same parser as
+//! `Jetro::from_bytes`. `JsonStreamDocs` is the iterator behind
+//! `JetroEngine::collect_json_stream_docs`.
+
+use std::io::Read;
+
+use serde_json::Value;
+
+use crate::data::context::EvalError;
+use crate::data::ndjson::parse_record;
+use crate::data::value::Val;
+use crate::{Jetro, JetroEngine, JetroEngineError};
+
+/// Bytes requested from the reader at a time.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Error returned while reading or evaluating a concatenated JSON stream.
+#[derive(Debug)]
+pub enum JsonStreamError {
+    /// Reading from the input failed.
+    Io(std::io::Error),
+    /// The document at `index` (0-based), starting `offset` bytes into the
+    /// stream, is not valid JSON, or evaluating the expression against it
+    /// failed.
+    Document {
+        index: usize,
+        offset: u64,
+        error: JetroEngineError,
+    },
+    /// Evaluating the expression over the whole stream failed.
+    Eval(EvalError),
+}
+
+impl JsonStreamError {
+    /// Return the 0-based position of the document that failed, if the error belongs to one.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Self::Document { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Return the byte offset at which the failing document starts, if the error belongs to one.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Document { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for JsonStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "JSON stream I/O error: {}", err),
+            Self::Document {
+                index,
+                offset,
+                error,
+            } => write!(f, "document {} at byte {}: {}", index, offset, error),
+            Self::Eval(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for JsonStreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Document { error, .. } => Some(error),
+            Self::Eval(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for JsonStreamError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<EvalError> for JsonStreamError {
+    fn from(err: EvalError) -> Self {
+        Self::Eval(err)
+    }
+}
+
+/// Where a document sits in the stream: its 0-based index and the byte
+/// offset of its first byte.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Position {
+    pub(crate) index: usize,
+    pub(crate) offset: u64,
+}
+
+impl Position {
+    fn error(self, error: JetroEngineError) -> JsonStreamError {
+        JsonStreamError::Document {
+            index: self.index,
+            offset: self.offset,
+            error,
+        }
+    }
+}
+
+/// Progress of the boundary scan through the current document, kept across
+/// reads so a document split over several chunks is scanned once.
+#[derive(Default)]
+struct Scan {
+    /// Bytes of the document scanned so far.
+    len: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scan {
+    /// Continues scanning `doc`, which starts at the document's first byte,
+    /// and returns its length once the document is complete.
+    fn feed(&mut self, doc: &[u8]) -> Option<usize> {
+        if !matches!(doc.first(), Some(b'{' | b'[' | b'"')) {
+            return scalar_len(doc);
+        }
+        while self.len < doc.len() {
+            let idx = self.len;
+            let byte = doc[idx];
+            self.len += 1;
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        return Some(self.len);
+                    }
+                } else if let Some(skip) = memchr::memchr2(b'"', b'\\', &doc[self.len..]) {
+                    self.len += skip;
+                } else {
+                    self.len = doc.len();
+                }
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        return Some(self.len);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Length of the number or literal token `doc` starts with, or `None` when
+/// the token may continue past the bytes read so far. Bytes that start no
+/// valid token run to the next delimiter and fail to parse as one document;
+/// that document always takes its first byte, so a stray `}` or `]` is a
+/// document of its own rather than an empty one.
+fn scalar_len(doc: &[u8]) -> Option<usize> {
+    let token = match doc[0] {
+        b't' => literal_len(doc, b"true"),
+        b'f' => literal_len(doc, b"false"),
+        b'n' => literal_len(doc, b"null"),
+        _ => number_len(doc),
+    };
+    match token? {
+        0 => doc[1..]
+            .iter()
+            .position(|&b| b.is_ascii_whitespace() || b"{}[]\"".contains(&b))
+            .map(|len| len + 1),
+        len => Some(len),
+    }
+}
+
+/// `Some(literal.len())` when `doc` starts with `literal`, `None` when it is
+/// a prefix of it, and `Some(0)` when it diverges.
+fn literal_len(doc: &[u8], literal: &[u8]) -> Option<usize> {
+    let n = doc.len().min(literal.len());
+    if doc[..n] != literal[..n] {
+        Some(0)
+    } else if n < literal.len() {
+        None
+    } else {
+        Some(n)
+    }
+}
+
+/// Length of the JSON number `doc` starts with (`-? int frac? exp?`), `None`
+/// when it may continue past the end of `doc`, and `Some(0)` when `doc` does
+/// not start with one.
+fn number_len(doc: &[u8]) -> Option<usize> {
+    let digits = |from: usize| {
+        doc[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut i = usize::from(doc[0] == b'-');
+    match doc.get(i)? {
+        b'0' => i += 1,
+        b'1'..=b'9' => i += digits(i),
+        _ => return Some(0),
+    }
+    if doc.get(i)? == &b'.' {
+        match digits(i + 1) {
+            0 if i + 1 == doc.len() => return None,
+            0 => return Some(0),
+            n => i += 1 + n,
+        }
+    }
+    if matches!(doc.get(i)?, b'e' | b'E') {
+        let sign = usize::from(matches!(doc.get(i + 1)?, b'+' | b'-'));
+        match digits(i + 1 + sign) {
+            0 if i + 1 + sign == doc.len() => return None,
+            0 => return Some(0),
+            n => i += 1 + sign + n,
+        }
+    }
+    doc.get(i)?;
+    Some(i)
+}
+
+/// The top-level documents of a concatenated JSON stream, in order. A
+/// document cut off by the end of input is still yielded, and fails to
+/// parse. Stops after the first read error.
+pub(crate) struct Documents<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Start of the unconsumed bytes in `buf`.
+    start: usize,
+    /// Stream offset of `buf[0]`.
+    base: u64,
+    index: usize,
+    scan: Scan,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> Documents<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            start: 0,
+            base: 0,
+            index: 0,
+            scan: Scan::default(),
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Parse each document into a `Val`, tagging malformed ones with their position.
+    pub(crate) fn vals(self) -> impl Iterator<Item = Result<Val, JsonStreamError>> {
+        self.map(|doc| {
+            let (pos, bytes) = doc?;
+            parse_record(bytes).map_err(|err| pos.error(err))
+        })
+    }
+
+    /// Hands out `buf[start..start + len]` as the next document.
+    fn emit(&mut self, len: usize) -> (Position, Vec<u8>) {
+        let pos = Position {
+            index: self.index,
+            offset: self.base + self.start as u64,
+        };
+        let doc = self.buf[self.start..self.start + len].to_vec();
+        self.start += len;
+        self.index += 1;
+        self.scan = Scan::default();
+        (pos, doc)
+    }
+
+    /// Drops consumed bytes and appends the next chunk of input.
+    fn fill(&mut self) -> std::io::Result<()> {
+        self.buf.drain(..self.start);
+        self.base += self.start as u64;
+        self.start = 0;
+        let len = self.buf.len();
+        self.buf.resize(len + READ_CHUNK, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                other => break other,
+            }
+        };
+        match read {
+            Ok(read) => {
+                self.buf.truncate(len + read);
+                self.eof = read == 0;
+                Ok(())
+            }
+            Err(err) => {
+                self.buf.truncate(len);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for Documents<R> {
+    type Item = Result<(Position, Vec<u8>), JsonStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.scan.len == 0 {
+                let pending = &self.buf[self.start..];
+                let blank = pending
+                    .iter()
+                    .take_while(|b| b.is_ascii_whitespace())
+                    .count();
+                self.start += blank;
+            }
+            if self.start < self.buf.len() {
+                if let Some(len) = self.scan.feed(&self.buf[self.start..]) {
+                    return Some(Ok(self.emit(len)));
+                }
+                if self.eof {
+                    // a trailing scalar ends with the input; anything else is cut off
+                    let len = self.buf.len() - self.start;
+                    self.done = true;
+                    return Some(Ok(self.emit(len)));
+                }
+            } else if self.eof {
+                self.done = true;
+                break;
+            }
+            if let Err(err) = self.fill() {
+                self.done = true;
+                return Some(Err(err.into()));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator returned by `JetroEngine::collect_json_stream_docs`: the result
+/// of the expression for each document, in input order.
+///
+/// A malformed or failing document yields a `JsonStreamError::Document`
+/// carrying its position, and iteration continues with the bytes after it.
+/// A read error is yielded once and ends the iteration, as does an
+/// expression that does not parse, which is reported as
+/// `JsonStreamError::Eval` before any input is read.
+pub struct JsonStreamDocs<'e, R> {
+    engine: &'e JetroEngine,
+    expr: String,
+    docs: Option<Documents<R>>,
+    invalid: Option<EvalError>,
+}
+
+impl<'e, R: Read> JsonStreamDocs<'e, R> {
+    pub(crate) fn new(engine: &'e JetroEngine, reader: R, expr: String) -> Self {
+        let invalid = crate::parse::parser::parse(&expr)
+            .err()
+            .map(EvalError::from);
+        Self {
+            engine,
+            expr,
+            docs: invalid.is_none().then(|| Documents::new(reader)),
+            invalid,
+        }
+    }
+}
+
+impl<R: Read> Iterator for JsonStreamDocs<'_, R> {
+    type Item = Result<Value, JsonStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.invalid.take() {
+            return Some(Err(err.into()));
+        }
+        let (pos, bytes) = match self.docs.as_mut()?.next()? {
+            Ok(doc) => doc,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(
+            Jetro::from_bytes(bytes)
+                .map_err(|err| pos.error(err.into()))
+                .and_then(|doc| {
+                    self.engine
+                        .collect(&doc, &self.expr)
+                        .map_err(|err| pos.error(err.into()))
+                }),
+        )
+    }
+}
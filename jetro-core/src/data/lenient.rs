@@ -0,0 +1,666 @@
+//! Lenient JSON input: JSONC comments and trailing commas, and the JSON5
+//! extensions (unquoted keys, single-quoted strings, hex numbers, ...).
+//!
+//! `to_json` rewrites lenient input into standard JSON in one pass, checking
+//! its syntax as it goes, and `Jetro::from_bytes_with` hands the result to
+//! the ordinary parser. Every document therefore becomes the same `Val` tree
+//! its strict JSON spelling would, and syntax errors are reported against
+//! the original text by line and column. A leading byte order mark is
+//! skipped, and numbers too large for an `f64` are rejected during the scan
+//! rather than by the parser. JSON5's `Infinity` and `NaN` are rejected too,
+//! since standard JSON has no spelling for them. The scan keeps its open
+//! containers on an explicit stack, so deeply nested input cannot exhaust
+//! the call stack; nesting limits are enforced later, as for any other
+//! document.
+
+/// Which extensions to plain JSON `Jetro::from_bytes_with` accepts.
+///
+/// ```rust
+/// use jetro_core::{Jetro, ParseOptions};
+///
+/// let config = br#"{
+///     // JSON5: comments, unquoted keys, single quotes, hex, trailing commas
+///     name: 'api',
+///     port: 0x1F90,
+///     hosts: ["a", "b",],
+/// }"#;
+/// let j = Jetro::from_bytes_with(config.to_vec(), ParseOptions::json5()).unwrap();
+/// assert_eq!(j.collect("$.port + 0").unwrap(), serde_json::json!(8080));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    comments: bool,
+    trailing_commas: bool,
+    json5: bool,
+}
+
+impl ParseOptions {
+    /// Plain JSON, the same syntax `Jetro::from_bytes` accepts; same as `Default`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// JSONC: `//` and `/* */` comments and trailing commas.
+    pub fn jsonc() -> Self {
+        Self::new().comments(true).trailing_commas(true)
+    }
+
+    /// JSON5: everything `jsonc` accepts plus unquoted identifier keys
+    /// (including `\uXXXX` escapes), single-quoted strings, the extra JSON5
+    /// string escapes and line continuations, hexadecimal numbers, leading
+    /// `+` signs, and leading or trailing decimal points. `Infinity`,
+    /// `-Infinity` and `NaN` are rejected with a syntax error, since the
+    /// parsed document must be representable as JSON.
+    pub fn json5() -> Self {
+        Self::jsonc().json5_syntax(true)
+    }
+
+    /// Accept `//` line comments and `/* */` block comments.
+    pub fn comments(mut self, allow: bool) -> Self {
+        self.comments = allow;
+        self
+    }
+
+    /// Accept a comma after the last element of an array or object.
+    pub fn trailing_commas(mut self, allow: bool) -> Self {
+        self.trailing_commas = allow;
+        self
+    }
+
+    /// Accept the JSON5 spellings of keys, strings, and numbers.
+    pub fn json5_syntax(mut self, allow: bool) -> Self {
+        self.json5 = allow;
+        self
+    }
+}
+
+/// Syntax error in a document read by `Jetro::from_bytes_with`, located by
+/// 1-based line and column (counted in characters).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonSyntaxError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl JsonSyntaxError {
+    /// Return what was wrong, without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the 1-based line of the error.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the 1-based column of the error.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl std::fmt::Display for JsonSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for JsonSyntaxError {}
+
+impl From<serde_json::Error> for JsonSyntaxError {
+    fn from(err: serde_json::Error) -> Self {
+        let text = err.to_string();
+        // serde_json appends the same location it reports separately
+        let message = match text.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_owned(),
+            None => text,
+        };
+        Self {
+            message,
+            line: err.line(),
+            column: err.column(),
+        }
+    }
+}
+
+/// Rewrite `src`, written with the extensions `options` allows, as standard JSON.
+pub(crate) fn to_json(src: &[u8], options: ParseOptions) -> Result<Vec<u8>, JsonSyntaxError> {
+    Translator {
+        src,
+        pos: if src.starts_with(BOM) { BOM.len() } else { 0 },
+        options,
+        out: Vec::with_capacity(src.len()),
+    }
+    .run()
+}
+
+/// UTF-8 byte order mark, skipped at the start of a document.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Magnitudes up to `f64::MAX` have at most this many integer digits.
+const MAX_F64_INT_DIGITS: usize = 309;
+
+/// An array or object that has been opened but not yet closed.
+struct Frame {
+    object: bool,
+    /// Elements (or keys) written so far.
+    len: usize,
+}
+
+/// What the next token must be.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    Key,
+    Colon,
+    CommaOrClose,
+}
+
+struct Translator<'a> {
+    src: &'a [u8],
+    pos: usize,
+    options: ParseOptions,
+    out: Vec<u8>,
+}
+
+impl<'a> Translator<'a> {
+    fn run(mut self) -> Result<Vec<u8>, JsonSyntaxError> {
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut expect = Expect::Value;
+        let mut after_comma = false;
+        loop {
+            self.skip_blank()?;
+            let Some(&byte) = self.src.get(self.pos) else {
+                if stack.is_empty() && expect == Expect::CommaOrClose {
+                    return Ok(self.out);
+                }
+                return Err(self.error(self.pos, "unexpected end of input"));
+            };
+            match expect {
+                Expect::Value => {
+                    if byte == b']' && stack.last().is_some_and(|top| !top.object) {
+                        self.close(&mut stack, after_comma)?;
+                        expect = Expect::CommaOrClose;
+                        continue;
+                    }
+                    if let Some(top) = stack.last_mut().filter(|top| !top.object) {
+                        if top.len > 0 {
+                            self.out.push(b',');
+                        }
+                        top.len += 1;
+                    }
+                    after_comma = false;
+                    match byte {
+                        b'{' | b'[' => {
+                            self.out.push(byte);
+                            self.pos += 1;
+                            stack.push(Frame {
+                                object: byte == b'{',
+                                len: 0,
+                            });
+                            if byte == b'{' {
+                                expect = Expect::Key;
+                            }
+                        }
+                        _ => {
+                            self.scalar(byte)?;
+                            expect = Expect::CommaOrClose;
+                        }
+                    }
+                }
+                Expect::Key => {
+                    if byte == b'}' {
+                        self.close(&mut stack, after_comma)?;
+                        expect = Expect::CommaOrClose;
+                        continue;
+                    }
+                    let top = stack.last_mut().expect("keys are read inside objects");
+                    if top.len > 0 {
+                        self.out.push(b',');
+                    }
+                    top.len += 1;
+                    after_comma = false;
+                    self.key(byte)?;
+                    expect = Expect::Colon;
+                }
+                Expect::Colon => {
+                    if byte != b':' {
+                        return Err(self.error(self.pos, "expected `:` after object key"));
+                    }
+                    self.out.push(b':');
+                    self.pos += 1;
+                    expect = Expect::Value;
+                }
+                Expect::CommaOrClose => {
+                    let Some(top) = stack.last() else {
+                        return Err(self.error(self.pos, "trailing characters after document"));
+                    };
+                    match byte {
+                        b',' => {
+                            self.pos += 1;
+                            after_comma = true;
+                            expect = if top.object {
+                                Expect::Key
+                            } else {
+                                Expect::Value
+                            };
+                        }
+                        b'}' if top.object => self.close(&mut stack, false)?,
+                        b']' if !top.object => self.close(&mut stack, false)?,
+                        _ if top.object => {
+                            return Err(self.error(self.pos, "expected `,` or `}`"));
+                        }
+                        _ => return Err(self.error(self.pos, "expected `,` or `]`")),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Closes the innermost container at the current byte; `after_comma`
+    /// means the last element was followed by a comma.
+    fn close(&mut self, stack: &mut Vec<Frame>, after_comma: bool) -> Result<(), JsonSyntaxError> {
+        if after_comma && !self.options.trailing_commas {
+            return Err(self.error(self.pos, "trailing comma"));
+        }
+        stack.pop();
+        self.out.push(self.src[self.pos]);
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Skips whitespace and, when allowed, comments.
+    fn skip_blank(&mut self) -> Result<(), JsonSyntaxError> {
+        loop {
+            let rest = &self.src[self.pos..];
+            match rest {
+                [b' ' | b'\t' | b'\n' | b'\r', ..] => self.pos += 1,
+                [0x0B | 0x0C, ..] if self.options.json5 => self.pos += 1,
+                // NBSP, LS, PS, BOM
+                [0xC2, 0xA0, ..] if self.options.json5 => self.pos += 2,
+                [0xE2, 0x80, 0xA8 | 0xA9, ..] | [0xEF, 0xBB, 0xBF, ..] if self.options.json5 => {
+                    self.pos += 3
+                }
+                [b'/', b'/' | b'*', ..] if !self.options.comments => {
+                    return Err(self.error(self.pos, "comments are not allowed"));
+                }
+                [b'/', b'/', ..] => {
+                    self.pos += memchr::memchr(b'\n', rest).unwrap_or(rest.len());
+                }
+                [b'/', b'*', ..] => match memchr::memmem::find(&rest[2..], b"*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => return Err(self.error(self.pos, "unterminated block comment")),
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Writes an object key: a string, or an identifier under JSON5.
+    fn key(&mut self, byte: u8) -> Result<(), JsonSyntaxError> {
+        match byte {
+            b'"' | b'\'' => {
+                let key = self.string(byte)?;
+                self.write_string(&key);
+                Ok(())
+            }
+            _ if self.options.json5 && (is_ident_start(byte) || byte == b'\\') => {
+                let key = self.identifier()?;
+                self.write_string(&key);
+                Ok(())
+            }
+            _ => Err(self.error(self.pos, "expected object key")),
+        }
+    }
+
+    /// Reads a JSON5 identifier key, decoding its `\uXXXX` escapes.
+    fn identifier(&mut self) -> Result<String, JsonSyntaxError> {
+        let mut key = String::new();
+        loop {
+            let start = self.pos;
+            while self
+                .src
+                .get(self.pos)
+                .is_some_and(|&b| is_ident_continue(b))
+            {
+                self.pos += 1;
+            }
+            let run = std::str::from_utf8(&self.src[start..self.pos])
+                .map_err(|_| self.error(start, "invalid UTF-8 in key"))?;
+            key.push_str(run);
+            if self.src.get(self.pos) != Some(&b'\\') {
+                return Ok(key);
+            }
+            let at = self.pos;
+            if self.src.get(at + 1) != Some(&b'u') {
+                return Err(self.error(at, "invalid escape in key"));
+            }
+            self.pos += 2;
+            let ch = self.unicode_escape(at)?;
+            // escapes spell identifier characters, with the same rules as raw bytes
+            let allowed = !ch.is_ascii()
+                || if key.is_empty() {
+                    is_ident_start(ch as u8)
+                } else {
+                    is_ident_continue(ch as u8)
+                };
+            if !allowed {
+                return Err(self.error(at, "escape is not an identifier character"));
+            }
+            key.push(ch);
+        }
+    }
+
+    /// Writes a string, number, or literal starting at `byte`.
+    fn scalar(&mut self, byte: u8) -> Result<(), JsonSyntaxError> {
+        match byte {
+            b'"' | b'\'' => {
+                let value = self.string(byte)?;
+                self.write_string(&value);
+                Ok(())
+            }
+            b'-' | b'+' | b'.' | b'0'..=b'9' => self.number(),
+            _ => {
+                for literal in ["true", "false", "null"] {
+                    if self.src[self.pos..].starts_with(literal.as_bytes()) {
+                        let end = self.pos + literal.len();
+                        if !self.src.get(end).is_some_and(|&b| is_word_byte(b)) {
+                            self.out.extend_from_slice(literal.as_bytes());
+                            self.pos = end;
+                            return Ok(());
+                        }
+                    }
+                }
+                if self.options.json5 && self.non_finite() {
+                    return Err(self.error(
+                        self.pos,
+                        "`Infinity` and `NaN` cannot be represented in JSON",
+                    ));
+                }
+                Err(self.error(self.pos, "expected value"))
+            }
+        }
+    }
+
+    /// Returns `true` when `Infinity` or `NaN` starts at the current byte.
+    fn non_finite(&self) -> bool {
+        let rest = &self.src[self.pos..];
+        rest.starts_with(b"Infinity") || rest.starts_with(b"NaN")
+    }
+
+    /// Reads a string quoted by `quote` and returns its decoded contents.
+    fn string(&mut self, quote: u8) -> Result<String, JsonSyntaxError> {
+        let start = self.pos;
+        if quote == b'\'' && !self.options.json5 {
+            return Err(self.error(start, "single-quoted strings are not allowed"));
+        }
+        self.pos += 1;
+        let mut buf = Vec::new();
+        loop {
+            let Some(&byte) = self.src.get(self.pos) else {
+                return Err(self.error(start, "unterminated string"));
+            };
+            match byte {
+                _ if byte == quote => {
+                    self.pos += 1;
+                    break;
+                }
+                b'\\' => self.escape(&mut buf)?,
+                b'\n' | b'\r' => return Err(self.error(self.pos, "unescaped line break in string")),
+                0x00..=0x1F if !self.options.json5 => {
+                    return Err(self.error(self.pos, "control character in string"));
+                }
+                _ => {
+                    buf.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(buf).map_err(|_| self.error(start, "invalid UTF-8 in string"))
+    }
+
+    /// Decodes the escape sequence at the current backslash into `buf`.
+    fn escape(&mut self, buf: &mut Vec<u8>) -> Result<(), JsonSyntaxError> {
+        let at = self.pos;
+        let Some(&byte) = self.src.get(at + 1) else {
+            return Err(self.error(at, "unterminated string"));
+        };
+        self.pos += 2;
+        let decoded = match byte {
+            b'"' | b'\\' | b'/' => byte,
+            b'b' => 0x08,
+            b'f' => 0x0C,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'u' => {
+                let ch = self.unicode_escape(at)?;
+                buf.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                return Ok(());
+            }
+            _ if !self.options.json5 => return Err(self.error(at, "invalid escape")),
+            b'\'' => b'\'',
+            b'v' => 0x0B,
+            b'0' if !self.src.get(self.pos).is_some_and(u8::is_ascii_digit) => 0,
+            b'1'..=b'9' | b'0' => return Err(self.error(at, "invalid escape")),
+            b'x' => {
+                let code = self.hex_digits(at, 2)?;
+                let ch = char::from_u32(code).expect("two hex digits are a valid char");
+                buf.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                return Ok(());
+            }
+            // line continuations
+            b'\n' => return Ok(()),
+            b'\r' => {
+                if self.src.get(self.pos) == Some(&b'\n') {
+                    self.pos += 1;
+                }
+                return Ok(());
+            }
+            _ if self.src[at + 1..].starts_with("\u{2028}".as_bytes())
+                || self.src[at + 1..].starts_with("\u{2029}".as_bytes()) =>
+            {
+                self.pos = at + 4;
+                return Ok(());
+            }
+            // any other character escapes to itself
+            _ => byte,
+        };
+        buf.push(decoded);
+        Ok(())
+    }
+
+    /// Decodes the four hex digits after `\u`, joining a surrogate pair.
+    fn unicode_escape(&mut self, at: usize) -> Result<char, JsonSyntaxError> {
+        let high = self.hex_digits(at, 4)?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error(at, "lone surrogate in escape"));
+        }
+        if !self.src[self.pos..].starts_with(b"\\u") {
+            return Err(self.error(at, "lone surrogate in escape"));
+        }
+        self.pos += 2;
+        let low = self.hex_digits(at, 4)?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error(at, "lone surrogate in escape"));
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        Ok(char::from_u32(code).expect("surrogate pairs decode to valid chars"))
+    }
+
+    /// Reads exactly `count` hex digits for the escape at `at`.
+    fn hex_digits(&mut self, at: usize, count: usize) -> Result<u32, JsonSyntaxError> {
+        let digits = self
+            .src
+            .get(self.pos..self.pos + count)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .ok_or_else(|| self.error(at, "invalid escape"))?;
+        let code = digits.iter().fold(0, |code, &digit| {
+            code * 16 + (digit as char).to_digit(16).unwrap_or(0)
+        });
+        self.pos += count;
+        Ok(code)
+    }
+
+    /// Writes the number at the current byte in standard JSON form.
+    fn number(&mut self) -> Result<(), JsonSyntaxError> {
+        let start = self.pos;
+        let out_start = self.out.len();
+        let json5 = self.options.json5;
+        match self.src[self.pos] {
+            b'-' => {
+                self.out.push(b'-');
+                self.pos += 1;
+            }
+            b'+' if json5 => self.pos += 1,
+            _ => {}
+        }
+        if json5 && self.non_finite() {
+            return Err(self.error(start, "`Infinity` and `NaN` cannot be represented in JSON"));
+        }
+        let rest = &self.src[self.pos..];
+        if json5 && (rest.starts_with(b"0x") || rest.starts_with(b"0X")) {
+            self.pos += 2;
+            let digits = self.digits(u8::is_ascii_hexdigit);
+            let value = (!digits.is_empty())
+                .then(|| u64::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok())
+                .flatten()
+                .ok_or_else(|| self.error(start, "invalid hexadecimal number"))?;
+            self.out
+                .extend_from_slice(itoa::Buffer::new().format(value).as_bytes());
+        } else {
+            let int = self.digits(u8::is_ascii_digit);
+            if int.is_empty() && !json5 {
+                return Err(self.error(start, "invalid number"));
+            }
+            if int.len() > 1 && int[0] == b'0' {
+                return Err(self.error(start, "leading zeros are not allowed"));
+            }
+            let int_len = int.len();
+            if int.is_empty() {
+                self.out.push(b'0');
+            } else {
+                self.out.extend_from_slice(int);
+            }
+            if self.src.get(self.pos) == Some(&b'.') {
+                self.pos += 1;
+                let frac = self.digits(u8::is_ascii_digit);
+                if frac.is_empty() && (!json5 || int_len == 0) {
+                    return Err(self.error(start, "invalid number"));
+                }
+                self.out.push(b'.');
+                if frac.is_empty() {
+                    self.out.push(b'0');
+                } else {
+                    self.out.extend_from_slice(frac);
+                }
+            } else if int_len == 0 {
+                return Err(self.error(start, "invalid number"));
+            }
+            if matches!(self.src.get(self.pos), Some(b'e' | b'E')) {
+                self.out.push(b'e');
+                self.pos += 1;
+                if let Some(&sign @ (b'+' | b'-')) = self.src.get(self.pos) {
+                    self.out.push(sign);
+                    self.pos += 1;
+                }
+                let exp = self.digits(u8::is_ascii_digit);
+                if exp.is_empty() {
+                    return Err(self.error(start, "invalid number"));
+                }
+                self.out.extend_from_slice(exp);
+                self.check_range(start, out_start)?;
+            } else if int_len >= MAX_F64_INT_DIGITS {
+                self.check_range(start, out_start)?;
+            }
+        }
+        if self
+            .src
+            .get(self.pos)
+            .is_some_and(|&b| is_word_byte(b) || b == b'.')
+        {
+            return Err(self.error(start, "invalid number"));
+        }
+        Ok(())
+    }
+
+    /// Rejects the number written from `out_start` when it overflows an `f64`,
+    /// which the parser would otherwise report without a location.
+    fn check_range(&self, start: usize, out_start: usize) -> Result<(), JsonSyntaxError> {
+        let finite = std::str::from_utf8(&self.out[out_start..])
+            .ok()
+            .and_then(|text| text.parse::<f64>().ok())
+            .is_some_and(f64::is_finite);
+        if finite {
+            Ok(())
+        } else {
+            Err(self.error(start, "number out of range"))
+        }
+    }
+
+    /// Consumes the run of bytes matching `accept` and returns it.
+    fn digits(&mut self, accept: fn(&u8) -> bool) -> &'a [u8] {
+        let start = self.pos;
+        while self.src.get(self.pos).is_some_and(accept) {
+            self.pos += 1;
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// Writes `value` as a JSON string literal.
+    fn write_string(&mut self, value: &str) {
+        self.out.push(b'"');
+        for byte in value.bytes() {
+            match byte {
+                b'"' => self.out.extend_from_slice(b"\\\""),
+                b'\\' => self.out.extend_from_slice(b"\\\\"),
+                0x00..=0x1F => {
+                    const HEX: &[u8; 16] = b"0123456789abcdef";
+                    self.out.extend_from_slice(b"\\u00");
+                    self.out.push(HEX[usize::from(byte >> 4)]);
+                    self.out.push(HEX[usize::from(byte & 0xF)]);
+                }
+                _ => self.out.push(byte),
+            }
+        }
+        self.out.push(b'"');
+    }
+
+    /// Builds an error at byte offset `at` of the source.
+    fn error(&self, at: usize, message: &str) -> JsonSyntaxError {
+        let before = &self.src[..at.min(self.src.len())];
+        let line_start = memchr::memrchr(b'\n', before).map_or(0, |idx| idx + 1);
+        let line = memchr::memchr_iter(b'\n', before).count() + 1;
+        let column = before[line_start..]
+            .iter()
+            .filter(|&&b| (b & 0xC0) != 0x80)
+            .count()
+            + 1;
+        JsonSyntaxError {
+            message: message.to_owned(),
+            line,
+            column,
+        }
+    }
+}
+
+/// Returns `true` for bytes that may start a JSON5 identifier key; bytes of
+/// non-ASCII characters are accepted as letters.
+fn is_ident_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+/// Returns `true` for bytes that may continue an identifier.
+fn is_ident_continue(byte: u8) -> bool {
+    is_ident_start(byte) || byte.is_ascii_digit()
+}
+
+/// Returns `true` for ASCII bytes that cannot directly follow a number or literal.
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$'
+}
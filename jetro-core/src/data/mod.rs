@@ -5,9 +5,26 @@
 //! - [`view`] — borrowed `ValueView` projections over tape-backed documents.
 //! - [`tape`] — simd-json tape representation and `StrRef` slices.
 //! - [`runtime`] — per-evaluation runtime state shared across the engine.
+//! - [`de`] — a `serde::Deserializer` over `Val` for typed result extraction.
+//! - [`ser`] — a `serde::Serializer` into `Val` for building documents from Rust data.
+//! - [`msgpack`], [`cbor`] — MessagePack and CBOR codecs; [`binary`] holds
+//!   their shared decoding rules and `Val` walk.
+//! - [`csv`] — CSV and TSV text read into columnar rows.
+//! - [`lenient`] — JSONC and JSON5 input rewritten as standard JSON.
+//! - [`ndjson`] — newline-delimited JSON records and their errors.
+//! - [`json_stream`] — concatenated top-level JSON documents read from a stream.
 
+pub(crate) mod binary;
+pub(crate) mod cbor;
 pub(crate) mod context;
+pub(crate) mod csv;
+pub(crate) mod de;
+pub(crate) mod json_stream;
+pub(crate) mod lenient;
+pub(crate) mod msgpack;
+pub(crate) mod ndjson;
 pub(crate) mod runtime;
+pub(crate) mod ser;
 pub(crate) mod tape;
 pub(crate) mod value;
 #[cfg_attr(not(test), allow(dead_code))]
@@ -0,0 +1,220 @@
+//! MessagePack input and output: `Jetro::from_msgpack`,
+//! `Jetro::collect_msgpack`, and the `to_msgpack()` builtin.
+//!
+//! Decoding follows the shared rules in `binary`. Extension values become
+//! `{"ext": type, "data": base64}` objects, keeping their type and payload.
+//! Integers are written in their smallest encoding and floats as float 64.
+
+use crate::data::binary::{self, DecodeError, Encoder, Input};
+use crate::data::value::Val;
+
+/// Decode one MessagePack document into a `Val`.
+pub(crate) fn decode(src: &[u8]) -> Result<Val, DecodeError> {
+    let mut input = Input::new(src);
+    let val = value(&mut input)?;
+    input.finish()?;
+    Ok(val)
+}
+
+/// Encode `val` as MessagePack.
+pub(crate) fn encode(val: &Val) -> Vec<u8> {
+    let mut out = Vec::new();
+    binary::encode::<MsgPack>(val, &mut out);
+    out
+}
+
+fn value(input: &mut Input<'_>) -> Result<Val, DecodeError> {
+    let start = input.pos;
+    let marker = input.byte()?;
+    Ok(match marker {
+        0x00..=0x7f => Val::Int(i64::from(marker)),
+        0x80..=0x8f => map(input, u64::from(marker & 0x0f))?,
+        0x90..=0x9f => array(input, u64::from(marker & 0x0f))?,
+        0xa0..=0xbf => Val::Str(input.text(u64::from(marker & 0x1f))?),
+        0xc0 => Val::Null,
+        0xc1 => return Err(input.error_at(start, "reserved marker byte 0xc1")),
+        0xc2 => Val::Bool(false),
+        0xc3 => Val::Bool(true),
+        0xc4 => bin(input, 1)?,
+        0xc5 => bin(input, 2)?,
+        0xc6 => bin(input, 4)?,
+        0xc7 => ext(input, 1)?,
+        0xc8 => ext(input, 2)?,
+        0xc9 => ext(input, 4)?,
+        0xca => Val::Float(f64::from(f32::from_bits(input.uint::<4>()? as u32))),
+        0xcb => Val::Float(f64::from_bits(input.uint::<8>()?)),
+        0xcc => Val::Int(input.uint::<1>()? as i64),
+        0xcd => Val::Int(input.uint::<2>()? as i64),
+        0xce => Val::Int(input.uint::<4>()? as i64),
+        0xcf => binary::wide_int(i128::from(input.uint::<8>()?)),
+        0xd0 => Val::Int(i64::from(input.uint::<1>()? as u8 as i8)),
+        0xd1 => Val::Int(i64::from(input.uint::<2>()? as u16 as i16)),
+        0xd2 => Val::Int(i64::from(input.uint::<4>()? as u32 as i32)),
+        0xd3 => Val::Int(input.uint::<8>()? as i64),
+        0xd4..=0xd8 => ext_payload(input, 1 << (marker - 0xd4))?,
+        0xd9 => {
+            let len = input.uint::<1>()?;
+            Val::Str(input.text(len)?)
+        }
+        0xda => {
+            let len = input.uint::<2>()?;
+            Val::Str(input.text(len)?)
+        }
+        0xdb => {
+            let len = input.uint::<4>()?;
+            Val::Str(input.text(len)?)
+        }
+        0xdc => {
+            let len = input.uint::<2>()?;
+            array(input, len)?
+        }
+        0xdd => {
+            let len = input.uint::<4>()?;
+            array(input, len)?
+        }
+        0xde => {
+            let len = input.uint::<2>()?;
+            map(input, len)?
+        }
+        0xdf => {
+            let len = input.uint::<4>()?;
+            map(input, len)?
+        }
+        0xe0..=0xff => Val::Int(i64::from(marker as i8)),
+    })
+}
+
+fn array(input: &mut Input<'_>, len: u64) -> Result<Val, DecodeError> {
+    input.enter()?;
+    let mut items = Vec::with_capacity(input.capacity(len));
+    for _ in 0..len {
+        items.push(value(input)?);
+    }
+    input.leave();
+    Ok(binary::array_val(items))
+}
+
+fn map(input: &mut Input<'_>, len: u64) -> Result<Val, DecodeError> {
+    input.enter()?;
+    let mut entries = Vec::with_capacity(input.capacity(len));
+    for _ in 0..len {
+        let key = value(input)?;
+        entries.push((key, value(input)?));
+    }
+    input.leave();
+    Ok(binary::map_val(entries))
+}
+
+/// `bin 8/16/32`, whose length takes `width` bytes.
+fn bin(input: &mut Input<'_>, width: usize) -> Result<Val, DecodeError> {
+    let len = length(input, width)?;
+    Ok(binary::bytes_val(input.take(len)?))
+}
+
+/// `ext 8/16/32`, whose length takes `width` bytes.
+fn ext(input: &mut Input<'_>, width: usize) -> Result<Val, DecodeError> {
+    let len = length(input, width)?;
+    ext_payload(input, len)
+}
+
+/// The type byte and `len` data bytes of an extension value.
+fn ext_payload(input: &mut Input<'_>, len: u64) -> Result<Val, DecodeError> {
+    let kind = input.byte()? as i8;
+    let data = input.take(len)?;
+    Ok(crate::util::obj2(
+        "ext",
+        Val::Int(i64::from(kind)),
+        "data",
+        binary::bytes_val(data),
+    ))
+}
+
+fn length(input: &mut Input<'_>, width: usize) -> Result<u64, DecodeError> {
+    match width {
+        1 => input.uint::<1>(),
+        2 => input.uint::<2>(),
+        _ => input.uint::<4>(),
+    }
+}
+
+struct MsgPack;
+
+impl MsgPack {
+    /// Writes a `fix` marker for short lengths, else the 16- or 32-bit form.
+    fn head(out: &mut Vec<u8>, len: usize, fix: (u8, usize), wide: [u8; 2]) {
+        if len < fix.1 {
+            out.push(fix.0 | len as u8);
+        } else if len <= u16::MAX as usize {
+            out.push(wide[0]);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            out.push(wide[1]);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+impl Encoder for MsgPack {
+    fn null(out: &mut Vec<u8>) {
+        out.push(0xc0);
+    }
+
+    fn bool(out: &mut Vec<u8>, b: bool) {
+        out.push(if b { 0xc3 } else { 0xc2 });
+    }
+
+    fn int(out: &mut Vec<u8>, n: i64) {
+        match n {
+            0..=0x7f => out.push(n as u8),
+            -32..=-1 => out.push(n as u8),
+            0x80..=0xff => out.extend_from_slice(&[0xcc, n as u8]),
+            0x100..=0xffff => {
+                out.push(0xcd);
+                out.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                out.push(0xce);
+                out.extend_from_slice(&(n as u32).to_be_bytes());
+            }
+            0x1_0000_0000.. => {
+                out.push(0xcf);
+                out.extend_from_slice(&(n as u64).to_be_bytes());
+            }
+            -0x80..=-33 => out.extend_from_slice(&[0xd0, n as u8]),
+            -0x8000..=-0x81 => {
+                out.push(0xd1);
+                out.extend_from_slice(&(n as i16).to_be_bytes());
+            }
+            -0x8000_0000..=-0x8001 => {
+                out.push(0xd2);
+                out.extend_from_slice(&(n as i32).to_be_bytes());
+            }
+            _ => {
+                out.push(0xd3);
+                out.extend_from_slice(&n.to_be_bytes());
+            }
+        }
+    }
+
+    fn float(out: &mut Vec<u8>, f: f64) {
+        out.push(0xcb);
+        out.extend_from_slice(&f.to_be_bytes());
+    }
+
+    fn str(out: &mut Vec<u8>, s: &str) {
+        if s.len() <= u8::MAX as usize && s.len() >= 32 {
+            out.extend_from_slice(&[0xd9, s.len() as u8]);
+        } else {
+            Self::head(out, s.len(), (0xa0, 32), [0xda, 0xdb]);
+        }
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn array(out: &mut Vec<u8>, len: usize) {
+        Self::head(out, len, (0x90, 16), [0xdc, 0xdd]);
+    }
+
+    fn map(out: &mut Vec<u8>, len: usize) {
+        Self::head(out, len, (0x80, 16), [0xde, 0xdf]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Newline-delimited JSON input for `JetroEngine`.
+//!
+//! `Lines` splits a `BufRead` into records, one per non-blank line, keeping
+//! the 1-based line number of each so malformed records can be reported
+//! where they are. `NdjsonLines` is the iterator behind
+//! `JetroEngine::collect_ndjson_lines`; the streamed mode, where the records
+//! are bound to `$` together, is driven from `JetroEngine::collect_ndjson`.
+
+use std::io::BufRead;
+
+use serde_json::Value;
+
+use crate::data::context::EvalError;
+use crate::data::value::Val;
+use crate::{Jetro, JetroEngine, JetroEngineError};
+
+/// Error returned while reading or evaluating newline-delimited JSON.
+#[derive(Debug)]
+pub enum NdjsonError {
+    /// Reading from the input failed.
+    Io(std::io::Error),
+    /// The record on `line` (1-based) is not valid JSON, or evaluating the
+    /// expression against it failed.
+    Record {
+        line: usize,
+        error: JetroEngineError,
+    },
+    /// Evaluating the expression over the whole stream failed.
+    Eval(EvalError),
+}
+
+impl NdjsonError {
+    /// Return the 1-based line of the record that failed, if the error belongs to one.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::Record { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for NdjsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "NDJSON I/O error: {}", err),
+            Self::Record { line, error } => write!(f, "line {}: {}", line, error),
+            Self::Eval(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for NdjsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Record { error, .. } => Some(error),
+            Self::Eval(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for NdjsonError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<EvalError> for NdjsonError {
+    fn from(err: EvalError) -> Self {
+        Self::Eval(err)
+    }
+}
+
+/// The records of an NDJSON input as `(line, bytes)` pairs, skipping blank
+/// lines. Stops after the first read error.
+pub(crate) struct Lines<R> {
+    reader: R,
+    line: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            done: false,
+        }
+    }
+
+    /// Parse each record into a `Val`, tagging malformed ones with their line.
+    pub(crate) fn vals(self) -> impl Iterator<Item = Result<Val, NdjsonError>> {
+        self.map(|record| {
+            let (line, bytes) = record?;
+            parse_record(bytes).map_err(|error| NdjsonError::Record { line, error })
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<(usize, Vec<u8>), NdjsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut buf = Vec::new();
+            match self.reader.read_until(b'\n', &mut buf) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    self.line += 1;
+                    if buf.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+                    while matches!(buf.last(), Some(b'\n' | b'\r')) {
+                        buf.pop();
+                    }
+                    return Some(Ok((self.line, buf)));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parse one record's bytes into a `Val` with the parser `Jetro::from_bytes` uses.
+pub(crate) fn parse_record(bytes: Vec<u8>) -> Result<Val, JetroEngineError> {
+    Ok(Jetro::from_bytes(bytes)?.root_val()?)
+}
+
+/// Iterator returned by `JetroEngine::collect_ndjson_lines`: the result of
+/// the expression for each record, in input order.
+///
+/// A malformed or failing record yields an `NdjsonError::Record` carrying
+/// its line number, and iteration continues with the next line. A read
+/// error is yielded once and ends the iteration, as does an expression that
+/// does not parse, which is reported as `NdjsonError::Eval` before any input
+/// is read.
+pub struct NdjsonLines<'e, R> {
+    engine: &'e JetroEngine,
+    expr: String,
+    lines: Option<Lines<R>>,
+    invalid: Option<EvalError>,
+}
+
+impl<'e, R: BufRead> NdjsonLines<'e, R> {
+    pub(crate) fn new(engine: &'e JetroEngine, reader: R, expr: String) -> Self {
+        let invalid = crate::parse::parser::parse(&expr)
+            .err()
+            .map(EvalError::from);
+        Self {
+            engine,
+            expr,
+            lines: invalid.is_none().then(|| Lines::new(reader)),
+            invalid,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonLines<'_, R> {
+    type Item = Result<Value, NdjsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.invalid.take() {
+            return Some(Err(err.into()));
+        }
+        let (line, bytes) = match self.lines.as_mut()?.next()? {
+            Ok(record) => record,
+            Err(err) => return Some(Err(err)),
+        };
+        let invalid = |error: JetroEngineError| NdjsonError::Record { line, error };
+        Some(
+            Jetro::from_bytes(bytes)
+                .map_err(|err| invalid(err.into()))
+                .and_then(|doc| {
+                    self.engine
+                        .collect(&doc, &self.expr)
+                        .map_err(|err| invalid(err.into()))
+                }),
+        )
+    }
+}
@@ -90,6 +90,19 @@ pub(crate) fn call_builtin_method_compiled(
     )
 }
 
+/// Global call names `eval_global_compiled` evaluates itself. The compiler and
+/// the VM resolve them before builtins and host functions, so host functions
+/// cannot take these names.
+pub(crate) const RESERVED_GLOBALS: &[&str] = &[
+    "coalesce",
+    "chain",
+    "join",
+    "zip",
+    "zip_longest",
+    "product",
+    "range",
+];
+
 /// Evaluate a top-level (non-method) global call such as `coalesce`, `chain`,
 /// `zip`, `zip_longest`, `product`, or `range`, falling back to
 /// `call_builtin_method_compiled` for unrecognised names.
@@ -148,14 +161,14 @@ pub(crate) fn eval_global_compiled(
             let arrs: Result<Vec<_>, _> = (0..args.len())
                 .map(|idx| eval_compiled_arg_at(vm, call, idx, env))
                 .collect();
-            Ok(crate::builtins::global_product_apply(&arrs?))
+            crate::builtins::global_product_apply(&arrs?)
         }
         "range" => {
             let mut nums = Vec::with_capacity(args.len());
             for idx in 0..args.len() {
                 let n = eval_compiled_arg_at(vm, call, idx, env)?
                     .as_i64()
-                    .ok_or_else(|| EvalError("range: expected integer arg".into()))?;
+                    .ok_or_else(|| EvalError::invalid_argument("range: expected integer arg"))?;
                 nums.push(n);
             }
             crate::builtins::range_apply(&nums)
@@ -166,7 +179,7 @@ pub(crate) fn eval_global_compiled(
         } else {
             call_builtin_method_compiled(vm, env.current.clone(), call, env)
         }
-        .map_err(|e| EvalError(format!("{}: {}", other, e.0))),
+        .map_err(|e| e.with_message_prefix(other)),
     }
 }
 
@@ -208,7 +221,7 @@ fn eval_compiled_arg_at(
     let prog = call
         .sub_progs
         .get(idx)
-        .ok_or_else(|| EvalError(format!("{}: missing compiled argument", call.name)))?;
+        .ok_or_else(|| EvalError::internal(format!("{}: missing compiled argument", call.name)))?;
     vm.exec_in_env(prog, env)
 }
 
@@ -223,11 +236,11 @@ fn apply_compiled_item(
     env: &mut Env,
 ) -> Result<Val, EvalError> {
     let idx = arg_index(call.orig_args.as_ref(), arg)
-        .ok_or_else(|| EvalError(format!("{}: argument lookup failed", call.name)))?;
+        .ok_or_else(|| EvalError::index_out_of_range(format!("{}: argument lookup failed", call.name)))?;
     let prog = call
         .sub_progs
         .get(idx)
-        .ok_or_else(|| EvalError(format!("{}: missing compiled argument", call.name)))?;
+        .ok_or_else(|| EvalError::internal(format!("{}: missing compiled argument", call.name)))?;
     match arg {
         Arg::Pos(Expr::Lambda { params, .. }) | Arg::Named(_, Expr::Lambda { params, .. }) => {
             let name = params.first().map(|s| s.as_str());
@@ -257,11 +270,11 @@ fn apply_compiled_pair(
     env: &mut Env,
 ) -> Result<Val, EvalError> {
     let idx = arg_index(call.orig_args.as_ref(), arg)
-        .ok_or_else(|| EvalError(format!("{}: argument lookup failed", call.name)))?;
+        .ok_or_else(|| EvalError::index_out_of_range(format!("{}: argument lookup failed", call.name)))?;
     let prog = call
         .sub_progs
         .get(idx)
-        .ok_or_else(|| EvalError(format!("{}: missing compiled argument", call.name)))?;
+        .ok_or_else(|| EvalError::internal(format!("{}: missing compiled argument", call.name)))?;
     match arg {
         Arg::Pos(Expr::Lambda { params, .. }) | Arg::Named(_, Expr::Lambda { params, .. }) => {
             match params.as_slice() {
@@ -0,0 +1,789 @@
+//! `serde::Serializer` into `Val`.
+//!
+//! Lets callers holding Rust data (`Jetro::from_serialize`) build the engine's
+//! value tree directly, without rendering JSON text or an intermediate
+//! `serde_json::Value`. Scalars, enums, and maps follow `serde_json::to_value`
+//! so both construction routes answer queries identically.
+//!
+//! Sequences are promoted to columnar form as they are finished: all-integer
+//! and all-string sequences become `IntVec` / `StrVec` (as in
+//! `impl From<&serde_json::Value> for Val`), and sequences of objects sharing
+//! one key schema, which is what a `Vec` of structs produces, become a
+//! struct-of-arrays `ObjVec`. Struct field names go through `intern_key`, so
+//! every row of such a sequence shares the same key allocations.
+
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::data::context::EvalError;
+use crate::data::value::{build_typed_cols_from_cells, intern_key, ObjVecData, Val};
+
+/// Serialize `value` into a `Val` tree.
+pub(crate) fn to_val<T: Serialize + ?Sized>(value: &T) -> Result<Val, EvalError> {
+    value.serialize(ValSerializer)
+}
+
+/// Stateless serializer producing one `Val` node.
+pub(crate) struct ValSerializer;
+
+/// Wrap `value` as the single-entry object `{variant: value}`, the externally
+/// tagged enum encoding used by `serde_json`.
+fn tagged(variant: &'static str, value: Val) -> Val {
+    let mut m = IndexMap::with_capacity(1);
+    m.insert(intern_key(variant), value);
+    Val::obj(m)
+}
+
+impl ser::Serializer for ValSerializer {
+    type Ok = Val;
+    type Error = EvalError;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = VariantSeqBuilder;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = StructBuilder;
+    type SerializeStructVariant = VariantStructBuilder;
+
+    fn serialize_bool(self, v: bool) -> Result<Val, EvalError> {
+        Ok(Val::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Val, EvalError> {
+        Ok(Val::Int(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Val, EvalError> {
+        Ok(i64::try_from(v).map_or(Val::Float(v as f64), Val::Int))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Val, EvalError> {
+        Ok(Val::Int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Val, EvalError> {
+        Ok(i64::try_from(v).map_or(Val::Float(v as f64), Val::Int))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Val, EvalError> {
+        Ok(i64::try_from(v).map_or(Val::Float(v as f64), Val::Int))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Val, EvalError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Val, EvalError> {
+        // serde_json has no encoding for NaN or infinities and maps them to null.
+        Ok(if v.is_finite() {
+            Val::Float(v)
+        } else {
+            Val::Null
+        })
+    }
+
+    fn serialize_char(self, v: char) -> Result<Val, EvalError> {
+        Ok(Val::Str(Arc::from(v.encode_utf8(&mut [0; 4]) as &str)))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Val, EvalError> {
+        Ok(Val::Str(Arc::from(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Val, EvalError> {
+        if v.is_empty() {
+            return Ok(Val::arr(Vec::new()));
+        }
+        Ok(Val::IntVec(Arc::new(v.iter().map(|&b| b.into()).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Val, EvalError> {
+        Ok(Val::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Val, EvalError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Val, EvalError> {
+        Ok(Val::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Val, EvalError> {
+        Ok(Val::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Val, EvalError> {
+        Ok(Val::Str(intern_key(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Val, EvalError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Val, EvalError> {
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, EvalError> {
+        Ok(SeqBuilder {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, EvalError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqBuilder, EvalError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSeqBuilder, EvalError> {
+        Ok(VariantSeqBuilder {
+            variant,
+            seq: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder, EvalError> {
+        Ok(MapBuilder {
+            map: IndexMap::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<StructBuilder, EvalError> {
+        Ok(StructBuilder {
+            map: IndexMap::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantStructBuilder, EvalError> {
+        Ok(VariantStructBuilder {
+            variant,
+            fields: self.serialize_struct(variant, len)?,
+        })
+    }
+}
+
+/// Accumulates sequence elements, then picks the tightest representation.
+pub(crate) struct SeqBuilder {
+    items: Vec<Val>,
+}
+
+impl SeqBuilder {
+    fn finish(self) -> Val {
+        promote_seq(self.items)
+    }
+}
+
+/// Promote sequence elements to a columnar lane when they are homogeneous,
+/// otherwise return a plain `Arr`.
+pub(crate) fn promote_seq(items: Vec<Val>) -> Val {
+    if items.is_empty() {
+        return Val::arr(items);
+    }
+    if items.iter().all(|v| matches!(v, Val::Int(_))) {
+        let out = items
+            .iter()
+            .map(|v| match v {
+                Val::Int(n) => *n,
+                _ => unreachable!("checked all-int"),
+            })
+            .collect();
+        return Val::IntVec(Arc::new(out));
+    }
+    if items.iter().all(|v| matches!(v, Val::Str(_))) {
+        let out = items
+            .into_iter()
+            .map(|v| match v {
+                Val::Str(s) => s,
+                _ => unreachable!("checked all-str"),
+            })
+            .collect();
+        return Val::StrVec(Arc::new(out));
+    }
+    match objvec_schema(&items) {
+        Some(keys) => objvec(keys, items),
+        None => Val::arr(items),
+    }
+}
+
+/// Return the shared key schema when every element is a non-empty object
+/// with the same keys in the same order.
+fn objvec_schema(items: &[Val]) -> Option<Arc<[Arc<str>]>> {
+    let Val::Obj(first) = &items[0] else {
+        return None;
+    };
+    if first.is_empty() {
+        return None;
+    }
+    let same_shape = items[1..].iter().all(|item| match item {
+        Val::Obj(m) => {
+            m.len() == first.len()
+                && m.keys()
+                    .zip(first.keys())
+                    .all(|(a, b)| Arc::ptr_eq(a, b) || a == b)
+        }
+        _ => false,
+    });
+    same_shape.then(|| first.keys().cloned().collect())
+}
+
+/// Flatten same-shape objects into a row-major `ObjVec` with typed lanes.
+fn objvec(keys: Arc<[Arc<str>]>, items: Vec<Val>) -> Val {
+    let stride = keys.len();
+    let nrows = items.len();
+    let mut cells = Vec::with_capacity(stride * nrows);
+    for item in items {
+        let Val::Obj(m) = item else {
+            unreachable!("schema checked every row");
+        };
+        match Arc::try_unwrap(m) {
+            Ok(m) => cells.extend(m.into_values()),
+            Err(m) => cells.extend(m.values().cloned()),
+        }
+    }
+    let typed_cols = build_typed_cols_from_cells(&cells, stride, nrows);
+    Val::ObjVec(Arc::new(ObjVecData {
+        keys,
+        cells,
+        typed_cols: Some(Arc::new(typed_cols)),
+    }))
+}
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EvalError> {
+        self.items.push(value.serialize(ValSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EvalError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EvalError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(self.finish())
+    }
+}
+
+/// Tuple variant `V(a, b)`, encoded as `{"V": [a, b]}`.
+pub(crate) struct VariantSeqBuilder {
+    variant: &'static str,
+    seq: SeqBuilder,
+}
+
+impl ser::SerializeTupleVariant for VariantSeqBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EvalError> {
+        ser::SerializeSeq::serialize_element(&mut self.seq, value)
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(tagged(self.variant, self.seq.finish()))
+    }
+}
+
+/// Map with caller-supplied keys; keys must serialize to strings or scalars.
+pub(crate) struct MapBuilder {
+    map: IndexMap<Arc<str>, Val>,
+    key: Option<Arc<str>>,
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EvalError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EvalError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| EvalError::internal("serialize_value called before serialize_key"))?;
+        self.map.insert(key, value.serialize(ValSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(Val::obj(self.map))
+    }
+}
+
+/// Struct with static field names, interned so sibling rows share keys.
+pub(crate) struct StructBuilder {
+    map: IndexMap<Arc<str>, Val>,
+}
+
+impl ser::SerializeStruct for StructBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EvalError> {
+        self.map
+            .insert(intern_key(key), value.serialize(ValSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(Val::obj(self.map))
+    }
+}
+
+/// Struct variant `V { a, b }`, encoded as `{"V": {"a": .., "b": ..}}`.
+pub(crate) struct VariantStructBuilder {
+    variant: &'static str,
+    fields: StructBuilder,
+}
+
+impl ser::SerializeStructVariant for VariantStructBuilder {
+    type Ok = Val;
+    type Error = EvalError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EvalError> {
+        ser::SerializeStruct::serialize_field(&mut self.fields, key, value)
+    }
+
+    fn end(self) -> Result<Val, EvalError> {
+        Ok(tagged(self.variant, Val::obj(self.fields.map)))
+    }
+}
+
+/// Serializer for map keys: strings pass through, and integers, booleans,
+/// and chars are stringified, as `serde_json` does. Anything else is an error.
+struct KeySerializer;
+
+/// Error for a map key that has no string form.
+fn key_must_be_string() -> EvalError {
+    <EvalError as ser::Error>::custom("map key must be a string")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Arc<str>;
+    type Error = EvalError;
+    type SerializeSeq = Impossible<Arc<str>, EvalError>;
+    type SerializeTuple = Impossible<Arc<str>, EvalError>;
+    type SerializeTupleStruct = Impossible<Arc<str>, EvalError>;
+    type SerializeTupleVariant = Impossible<Arc<str>, EvalError>;
+    type SerializeMap = Impossible<Arc<str>, EvalError>;
+    type SerializeStruct = Impossible<Arc<str>, EvalError>;
+    type SerializeStructVariant = Impossible<Arc<str>, EvalError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(if v { "true" } else { "false" }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.to_string()))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v.encode_utf8(&mut [0; 4]) as &str))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Arc<str>, EvalError> {
+        Ok(Arc::from(v))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_none(self) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_unit(self) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Arc<str>, EvalError> {
+        Ok(intern_key(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Arc<str>, EvalError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Arc<str>, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, EvalError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, EvalError> {
+        Err(key_must_be_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    use super::to_val;
+    use crate::data::value::{ObjVecCol, Val};
+    use crate::{EvalErrorKind, Jetro};
+
+    #[derive(Serialize)]
+    struct Row {
+        id: u32,
+        name: String,
+        score: f64,
+        tags: Vec<&'static str>,
+        parent: Option<u32>,
+    }
+
+    #[derive(Serialize)]
+    enum Shape {
+        Dot,
+        Circle(f64),
+        Pair(i32, i32),
+        Rect { w: u8, h: u8 },
+    }
+
+    #[derive(Serialize)]
+    struct Doc {
+        rows: Vec<Row>,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<u32, u64>,
+        unit: (),
+        big: u64,
+        nan: f64,
+        bytes: serde_bytes_like::Bytes,
+        letter: char,
+    }
+
+    mod serde_bytes_like {
+        /// Serializes through `serialize_bytes`, as `serde_bytes` would.
+        pub struct Bytes(pub &'static [u8]);
+
+        impl serde::Serialize for Bytes {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_bytes(self.0)
+            }
+        }
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row {
+                id: 1,
+                name: "ada".into(),
+                score: 9.5,
+                tags: vec!["a", "b"],
+                parent: None,
+            },
+            Row {
+                id: 2,
+                name: "bob".into(),
+                score: 4.0,
+                tags: vec![],
+                parent: Some(1),
+            },
+            Row {
+                id: 3,
+                name: "cy".into(),
+                score: 7.25,
+                tags: vec!["b"],
+                parent: Some(1),
+            },
+        ]
+    }
+
+    fn doc() -> Doc {
+        Doc {
+            rows: rows(),
+            shapes: vec![
+                Shape::Dot,
+                Shape::Circle(1.5),
+                Shape::Pair(1, -2),
+                Shape::Rect { w: 3, h: 4 },
+            ],
+            counts: BTreeMap::from([(1, 10), (2, 20)]),
+            unit: (),
+            big: u64::MAX,
+            nan: f64::NAN,
+            bytes: serde_bytes_like::Bytes(b"hi"),
+            letter: 'x',
+        }
+    }
+
+    #[test]
+    fn to_val_matches_serde_json_to_value() {
+        // Route the expectation through `Val` too: `u64` values past `i64::MAX`
+        // become floats either way.
+        let expected = Value::from(Val::from(&serde_json::to_value(doc()).unwrap()));
+        let val = to_val(&doc()).unwrap();
+        assert_eq!(Value::from(val), expected);
+    }
+
+    #[test]
+    fn homogeneous_structs_become_objvec_with_shared_keys() {
+        let Val::ObjVec(d) = to_val(&rows()).unwrap() else {
+            panic!("expected ObjVec");
+        };
+        assert_eq!(d.nrows(), 3);
+        let keys: Vec<&str> = d.keys.iter().map(|k| k.as_ref()).collect();
+        assert_eq!(keys, ["id", "name", "score", "tags", "parent"]);
+        let cols = d.typed_cols.as_ref().expect("typed lanes");
+        assert!(matches!(&cols[0], ObjVecCol::Ints(xs) if xs == &[1, 2, 3]));
+        assert!(matches!(&cols[2], ObjVecCol::Floats(_)));
+    }
+
+    #[test]
+    fn mixed_sequences_stay_plain_arrays() {
+        let mixed = vec![json!({"a": 1}), json!({"b": 1})];
+        assert!(matches!(to_val(&mixed).unwrap(), Val::Arr(_)));
+        assert!(matches!(to_val(&[1, 2]).unwrap(), Val::IntVec(_)));
+        assert!(matches!(to_val(&["x", "y"]).unwrap(), Val::StrVec(_)));
+    }
+
+    #[test]
+    fn from_serialize_answers_like_from_value() {
+        let exprs = [
+            "$.rows.filter(score > 5).map(name)",
+            "$.rows.map(score).sum()",
+            "$.rows.len()",
+            "$.rows[1].name",
+            "$.rows.sort_by(score).map(id)",
+            "$.rows.map({id, parent})",
+            "$.rows.filter(tags.len() > 0).map(tags)",
+            "$.shapes",
+            "$.counts",
+            "$.letter",
+            "$.rows[-1].tags",
+            "$.rows.group_by(parent)",
+            "$.rows.map(tags).flatten().unique()",
+            "$.rows.filter(parent == 1).map(score).max()",
+            "$.rows.map(name).join(\",\")",
+            "$.rows.first().name",
+            "$.rows.last()",
+            "$.rows.count(score > 5)",
+            "[x.id for x in $.rows if x.score > 5]",
+            "$..name",
+            "$.rows.sort_by(-score).take(2).map({name, score})",
+        ];
+        let from_ser = Jetro::from_serialize(&doc()).unwrap();
+        let from_value = Jetro::from(serde_json::to_value(doc()).unwrap());
+        for expr in exprs {
+            assert_eq!(
+                from_ser.collect(expr).unwrap(),
+                from_value.collect(expr).unwrap(),
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn non_string_map_keys_are_rejected() {
+        let bad = BTreeMap::from([((1, 2), "pair")]);
+        let err = Jetro::from_serialize(&bad)
+            .err()
+            .expect("tuple keys rejected");
+        assert_eq!(err.kind(), EvalErrorKind::Serialize);
+    }
+}
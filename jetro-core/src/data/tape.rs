@@ -5,7 +5,7 @@
 //! allocation. Used by the simd-json tape path so that string values returned
 //! from `Val::StrSlice` / `Val::StrSliceVec` never allocate.
 
-#[cfg(test)]
+#[cfg(feature = "simd-json")]
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
@@ -188,60 +188,63 @@ pub type TapeNode = simd_json::Node<'static>;
 #[cfg(feature = "simd-json")]
 pub struct TapeData {
     /// The raw JSON bytes with simd-json's in-place mutations applied; string
-    /// positions in `nodes` index into this buffer.
+    /// positions in `nodes` index into this buffer. This is a private copy of
+    /// the document's bytes, never the document's retained source.
     pub bytes_buf: Vec<u8>,
-    /// Structural-index scratch buffers owned by simd-json; must not be dropped
-    /// while `nodes` is in use because the tape borrows into them.
-    _buffers: simd_json::Buffers,
     /// The flat tape of parsed JSON nodes; string nodes borrow from `bytes_buf`.
     pub nodes: Vec<TapeNode>,
-    /// Counter of how many subtrees were materialised into `Val`; used in tests
-    /// to verify lazy-materialisation assumptions.
-    #[cfg(test)]
+    /// Counter of how many subtrees were materialised into `Val`; reported by
+    /// the query profile and used in tests to verify lazy-materialisation assumptions.
     materialized_subtrees: AtomicUsize,
+    /// Deepest container nesting in `nodes`; checked against the nesting limit
+    /// before anything walks the tape recursively.
+    pub depth: usize,
 }
 
 #[cfg(feature = "simd-json")]
 impl TapeData {
     /// Parse a JSON byte vector into a `TapeData` wrapped in an `Arc`.
     /// The input buffer is consumed and stored alongside the tape so that
-    /// string references remain valid.
+    /// string references remain valid; simd-json's scratch buffers are
+    /// freed once parsing finishes.
     pub fn parse(mut bytes: Vec<u8>) -> Result<Arc<Self>, String> {
         Self::parse_inner(&mut bytes)
             .map_err(|e| e.to_string())
-            .map(|(nodes, bytes_buf, buffers)| {
+            .map(|(nodes, bytes_buf)| {
                 Arc::new(Self {
                     bytes_buf,
-                    _buffers: buffers,
+                    depth: tape_depth(&nodes),
                     nodes,
-                    #[cfg(test)]
                     materialized_subtrees: AtomicUsize::new(0),
                 })
             })
     }
 
     /// Internal helper: run simd-json on the mutable byte slice, collect the tape
-    /// nodes, and take ownership of both the (now mutated) buffer and the Buffers.
-    fn parse_inner(
-        bytes: &mut Vec<u8>,
-    ) -> Result<(Vec<TapeNode>, Vec<u8>, simd_json::Buffers), simd_json::Error> {
+    /// nodes, and take ownership of the (now mutated) buffer. The scratch
+    /// `Buffers` (an aligned copy of the input, the string buffer and the
+    /// structural indexes) are dropped on return.
+    fn parse_inner(bytes: &mut Vec<u8>) -> Result<(Vec<TapeNode>, Vec<u8>), simd_json::Error> {
         let mut buffers = simd_json::Buffers::new(bytes.len());
         let tape = simd_json::to_tape_with_buffers(bytes, &mut buffers)?;
-        // SAFETY: we extend the lifetime of the tape nodes to `'static` because
-        // both `bytes_buf` and `buffers` are stored in the same `TapeData` struct
-        // and will not be freed while `nodes` lives.
+        // SAFETY: the tape borrows only from `bytes`, never from `buffers`, so we
+        // extend the nodes' lifetime to `'static`: `bytes_buf` is stored in the
+        // same `TapeData` and is neither freed nor moved while `nodes` lives.
         let nodes =
             unsafe { std::mem::transmute::<Vec<simd_json::Node<'_>>, Vec<TapeNode>>(tape.0) };
         let bytes_buf = std::mem::take(bytes);
-        Ok((nodes, bytes_buf, buffers))
+        Ok((nodes, bytes_buf))
     }
 
     /// Increment the materialised-subtree counter; called when a tape subtree is
-    /// converted to a `Val` tree.  Only compiled in test builds.
-    #[cfg(test)]
+    /// converted to a `Val` tree. Outside test builds the count is only kept
+    /// while a profile is being recorded, so unprofiled queries skip the
+    /// atomic increment.
     #[inline]
     pub(crate) fn observe_materialized_subtree(&self) {
-        self.materialized_subtrees.fetch_add(1, Ordering::Relaxed);
+        if cfg!(test) || crate::exec::profile::is_recording() {
+            self.materialized_subtrees.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Reset the materialised-subtree counter to zero for a fresh test assertion.
@@ -251,8 +254,7 @@ impl TapeData {
         self.materialized_subtrees.store(0, Ordering::Relaxed);
     }
 
-    /// Read the current materialised-subtree count for test assertions.
-    #[cfg(test)]
+    /// Read the current materialised-subtree count.
     #[inline]
     pub(crate) fn materialized_subtrees(&self) -> usize {
         self.materialized_subtrees.load(Ordering::Relaxed)
@@ -302,4 +304,32 @@ impl TapeData {
             _ => 1,
         }
     }
+
+    /// Fail when the document nests deeper than the thread's nesting limit
+    /// (see `ExecutionLimits::max_nesting`).
+    pub(crate) fn check_nesting(&self) -> Result<(), crate::data::context::EvalError> {
+        let max = crate::exec::limits::max_nesting();
+        if self.depth > max {
+            return Err(crate::exec::limits::nesting_exceeded("document", max));
+        }
+        Ok(())
+    }
+}
+
+/// Return the deepest container nesting in a simd-json tape, tracking where
+/// each open container ends instead of recursing.
+#[cfg(feature = "simd-json")]
+pub(crate) fn tape_depth(nodes: &[simd_json::Node<'_>]) -> usize {
+    let mut open_until: Vec<usize> = Vec::new();
+    let mut depth = 0;
+    for (idx, node) in nodes.iter().enumerate() {
+        while open_until.last().is_some_and(|&end| end <= idx) {
+            open_until.pop();
+        }
+        if let simd_json::Node::Object { count, .. } | simd_json::Node::Array { count, .. } = node {
+            open_until.push(idx + count + 1);
+            depth = depth.max(open_until.len());
+        }
+    }
+    depth
 }
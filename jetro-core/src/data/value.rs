@@ -228,6 +228,30 @@ impl ObjVecData {
         }
         Val::Obj(Arc::new(m))
     }
+
+    /// Copy the rows in `rows` into a new `ObjVecData` with the same columns,
+    /// slicing any typed lanes alongside the cells.
+    pub fn slice_rows(&self, rows: std::ops::Range<usize>) -> ObjVecData {
+        let stride = self.stride();
+        let typed_cols = self.typed_cols.as_ref().map(|cols| {
+            let cols = cols
+                .iter()
+                .map(|col| match col {
+                    ObjVecCol::Mixed => ObjVecCol::Mixed,
+                    ObjVecCol::Ints(v) => ObjVecCol::Ints(v[rows.clone()].to_vec()),
+                    ObjVecCol::Floats(v) => ObjVecCol::Floats(v[rows.clone()].to_vec()),
+                    ObjVecCol::Strs(v) => ObjVecCol::Strs(v[rows.clone()].to_vec()),
+                    ObjVecCol::Bools(v) => ObjVecCol::Bools(v[rows.clone()].to_vec()),
+                })
+                .collect();
+            Arc::new(cols)
+        });
+        ObjVecData {
+            keys: Arc::clone(&self.keys),
+            cells: self.cells[rows.start * stride..rows.end * stride].to_vec(),
+            typed_cols,
+        }
+    }
 }
 
 impl Val {
@@ -339,6 +363,18 @@ impl Val {
                 };
                 a.get(idx).cloned().map(Val::StrSlice).unwrap_or(Val::Null)
             }
+            Val::ObjVec(d) => {
+                let idx = if i < 0 {
+                    d.nrows().saturating_sub(i.unsigned_abs() as usize)
+                } else {
+                    i as usize
+                };
+                if idx < d.nrows() {
+                    d.row_val(idx)
+                } else {
+                    Val::Null
+                }
+            }
             _ => Val::Null,
         }
     }
@@ -871,6 +907,16 @@ impl Val {
         serde_json::to_vec(&ValRef(self)).unwrap_or_default()
     }
 
+    /// Stream `self` as JSON text into `writer` via `ValRef`, indenting when `pretty` is set.
+    /// Columnar lanes are written element by element; nothing is materialised first.
+    pub(crate) fn write_json<W: std::io::Write>(&self, writer: W, pretty: bool) -> serde_json::Result<()> {
+        if pretty {
+            serde_json::to_writer_pretty(writer, &ValRef(self))
+        } else {
+            serde_json::to_writer(writer, &ValRef(self))
+        }
+    }
+
     /// Parse a JSON string into `Val`, automatically promoting homogeneous arrays to columnar lanes.
     pub fn from_json_str(s: &str) -> serde_json::Result<Val> {
         let mut de = serde_json::Deserializer::from_str(s);
@@ -888,6 +934,10 @@ impl Val {
         
         let tape = simd_json::to_tape(bytes).map_err(|e| e.to_string())?;
         let nodes = tape.0;
+        let max = crate::exec::limits::max_nesting();
+        if crate::data::tape::tape_depth(&nodes) > max {
+            return Err(crate::exec::limits::nesting_exceeded("document", max).to_string());
+        }
         let mut idx = 0usize;
         Ok(Self::from_simd_tape(&nodes, &mut idx))
     }
@@ -1098,8 +1148,19 @@ impl Val {
 
     /// Materialise a `Val` from a `TapeData` (a pre-parsed, Arc-owned simd-json tape), producing
     /// `StrSlice` views into the tape buffer instead of allocating new `Arc<str>` for strings.
+    /// Fails with `LimitExceeded` when the document nests deeper than the thread's
+    /// nesting limit (see `ExecutionLimits::max_nesting`).
     #[cfg(feature = "simd-json")]
-    pub fn from_tape_data(tape: &Arc<crate::data::tape::TapeData>) -> Val {
+    pub fn from_tape_data(
+        tape: &Arc<crate::data::tape::TapeData>,
+    ) -> Result<Val, crate::data::context::EvalError> {
+        tape.check_nesting()?;
+        Ok(Self::from_checked_tape(tape))
+    }
+
+    /// `from_tape_data` for a tape whose nesting the caller has already checked.
+    #[cfg(feature = "simd-json")]
+    pub(crate) fn from_checked_tape(tape: &Arc<crate::data::tape::TapeData>) -> Val {
         let mut idx = 0usize;
         Self::from_tape_walk(tape, &mut idx)
     }
@@ -1429,6 +1490,46 @@ impl<'de> serde::Deserialize<'de> for Val {
     }
 }
 
+/// Serde adapter for `Val` fields of persisted plans. Binary formats cannot
+/// drive `deserialize_any`, so the value travels as JSON text and is parsed
+/// back on load.
+pub(crate) mod json_text {
+    use super::Val;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(val: &Val, s: S) -> Result<S::Ok, S::Error> {
+        let text = val.to_json_vec();
+        s.serialize_str(std::str::from_utf8(&text).map_err(serde::ser::Error::custom)?)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Val, D::Error> {
+        let text = String::deserialize(d)?;
+        serde_json::from_str(&text).map_err(D::Error::custom)
+    }
+}
+
+/// `json_text` for a `Vec<Val>`, one JSON text per element.
+pub(crate) mod json_text_vec {
+    use super::Val;
+    use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(vals: &[Val], s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(vals.len()))?;
+        for val in vals {
+            let text = val.to_json_vec();
+            seq.serialize_element(std::str::from_utf8(&text).map_err(serde::ser::Error::custom)?)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Val>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|text| serde_json::from_str(text).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod valref_tests {
     use super::*;
@@ -1481,7 +1582,7 @@ mod valref_tests {
         let js =
             br#"{"title":"Dune","tags":["sci-fi","classic"],"nested":{"name":"Paul"}}"#.to_vec();
         let tape = crate::data::tape::TapeData::parse(js.clone()).unwrap();
-        let val = Val::from_tape_data(&tape);
+        let val = Val::from_tape_data(&tape).unwrap();
 
         assert_eq!(val.to_json_vec(), js);
 
@@ -1503,7 +1604,7 @@ mod valref_tests {
     fn from_tape_data_promotes_float_arrays_and_indexes_str_slice_vec() {
         let js = br#"{"nums":[1,2.5,3],"names":["a","b"]}"#.to_vec();
         let tape = crate::data::tape::TapeData::parse(js).unwrap();
-        let val = Val::from_tape_data(&tape);
+        let val = Val::from_tape_data(&tape).unwrap();
         let obj = val.as_object().unwrap();
 
         match obj.get("nums").unwrap() {
@@ -126,6 +126,7 @@ impl<'a> ValueView<'a> for ValView<'a> {
                 .map(Val::StrSlice)
                 .map(Self::Owned)
                 .unwrap_or_else(|| Self::Owned(Val::Null)),
+            Self::Borrowed(value @ Val::ObjVec(_)) => Self::Owned(value.get_index(idx)),
             Self::Borrowed(_) => Self::Owned(Val::Null),
             Self::Owned(value) => Self::Owned(value.get_index(idx)),
         }
@@ -147,6 +148,9 @@ impl<'a> ValueView<'a> for ValView<'a> {
             Self::Borrowed(Val::StrSliceVec(items)) => Some(Box::new(
                 items.iter().cloned().map(Val::StrSlice).map(Self::Owned),
             )),
+            Self::Borrowed(Val::ObjVec(data)) => Some(Box::new(
+                (0..data.nrows()).map(|row| Self::Owned(data.row_val(row))),
+            )),
             Self::Borrowed(_) => None,
             Self::Owned(value) => match value {
                 Val::Arr(items) => Some(Box::new(
@@ -188,6 +192,12 @@ impl<'a> ValueView<'a> for ValView<'a> {
                         .map(Val::StrSlice)
                         .map(Self::Owned),
                 )),
+                Val::ObjVec(data) => {
+                    let data = Arc::clone(data);
+                    Some(Box::new(
+                        (0..data.nrows()).map(move |row| Self::Owned(data.row_val(row))),
+                    ))
+                }
                 _ => None,
             },
         }
@@ -359,7 +369,6 @@ impl<'a> ValueView<'a> for TapeView<'a> {
     fn materialize(&self) -> Val {
         match self {
             Self::Node { tape, idx } => {
-                #[cfg(test)]
                 tape.observe_materialized_subtree();
                 let mut idx = *idx;
                 Self::materialize_at(tape, &mut idx)
@@ -479,7 +488,7 @@ mod tests {
         let bytes =
             br#"{"books":[{"title":"low","score":1},{"title":"Dune","score":901}]}"#.to_vec();
         let tape = crate::data::tape::TapeData::parse(bytes).unwrap();
-        let val = Val::from_tape_data(&tape);
+        let val = Val::from_tape_data(&tape).unwrap();
 
         let tape_score_view = TapeView::root(&tape).field("books").index(1).field("score");
         let tape_score = tape_score_view.scalar();
@@ -274,6 +274,9 @@ where
         if matches!(demand, PullDemand::FirstInput(n) if pulled_inputs >= n) {
             break;
         }
+        if crate::exec::limits::should_stop() {
+            break;
+        }
         pulled_inputs += 1;
         match stages.apply(v.borrow()) {
             StageOutput::Pass(cow) => {
@@ -333,6 +336,9 @@ where
         if matches!(demand, PullDemand::FirstInput(n) if pulled_inputs >= n) {
             break;
         }
+        if crate::exec::limits::should_stop() {
+            break;
+        }
         pulled_inputs += 1;
         match stages.apply(v.borrow()) {
             StageOutput::Pass(cow) => {
@@ -585,6 +591,11 @@ impl Sink for CollectSink {
         acc.push(v.clone());
         acc
     }
+    /// Stop once the collected vector outgrows `max_array_len`.
+    #[inline]
+    fn done(acc: &Self::Acc) -> bool {
+        crate::exec::limits::check_len(acc.len()).is_err()
+    }
     /// Wrap the collected vector in `Val::Arr`.
     fn finalise(acc: Self::Acc) -> Val {
         Val::Arr(std::sync::Arc::new(acc))
@@ -8,32 +8,63 @@
 use std::sync::Arc;
 
 use crate::parse::ast::BinOp;
-use crate::data::context::{Env, EvalError};
+use crate::data::context::{Env, EvalError, EvalErrorKind};
 use crate::ir::physical::{
     BackendPreference, NodeId, PhysicalArrayElem, PhysicalChainStep, PhysicalObjField,
     PhysicalPathStep, PipelinePlanSource, PlanNode, QueryPlan,
 };
 use crate::exec::pipeline;
+use crate::exec::{limits, profile};
 use crate::data::runtime::{PipelineSourceResolver, ResolvedPipelineSource};
 use crate::data::value::Val;
 use crate::data::view::{ValView, ValueView};
 use crate::exec::view as view_pipeline;
 use crate::{Jetro, VM};
 
-/// Entry point: constructs an `ExecCtx` and evaluates the plan DAG starting from `root_id`.
-pub(crate) fn run(j: &Jetro, plan: &QueryPlan, root_id: NodeId) -> Result<Val, EvalError> {
+/// Entry point: constructs an `ExecCtx` and evaluates the plan DAG starting from `root_id`,
+/// running `Vm` and `Structural` fallback nodes on `vm`.
+pub(crate) fn run(
+    j: &Jetro,
+    plan: &QueryPlan,
+    root_id: NodeId,
+    vm: &mut VM,
+) -> Result<Val, EvalError> {
+    run_with_locals(j, plan, root_id, Vec::new(), vm)
+}
+
+/// Like `run`, but seeds the fast-local stack (and therefore every `Env` built from it)
+/// with caller-supplied bindings; `plan` must have been planned with the same names bound.
+pub(crate) fn run_with_locals(
+    j: &Jetro,
+    plan: &QueryPlan,
+    root_id: NodeId,
+    locals: Vec<(Arc<str>, Val)>,
+    vm: &mut VM,
+) -> Result<Val, EvalError> {
+    vm.reset_document();
     let mut ctx = ExecCtx {
         j,
         plan,
         root_id,
         root: None,
         env: None,
-        locals: Vec::new(),
-        vm: VM::new(),
+        locals,
+        vm,
+        scans: Vec::new(),
     };
     ctx.eval(root_id)
 }
 
+/// Renders a pipeline's field-chain source as the `$.a.b` path reported in errors.
+pub(crate) fn field_chain_path(keys: &[Arc<str>]) -> String {
+    let mut path = String::from("$");
+    for key in keys {
+        path.push('.');
+        path.push_str(key);
+    }
+    path
+}
+
 /// Applies a sequence of field/index navigation steps to a borrowed `ValueView` without
 /// materialising intermediate values.
 fn walk_path_view<'a, V>(mut cur: V, steps: &[PhysicalPathStep]) -> V
@@ -49,6 +80,9 @@ where
     cur
 }
 
+/// Per-branch results of one `SharedScan` run; `None` once a `ScanSlot` has taken its result.
+type ScanResults = Vec<Option<Result<Val, EvalError>>>;
+
 /// Stateful execution context that drives tree-walking evaluation of a `QueryPlan`.
 struct ExecCtx<'a> {
     /// The document handle providing raw bytes, tape, structural index, and `Val` root.
@@ -63,15 +97,17 @@ struct ExecCtx<'a> {
     env: Option<Env>,
     /// Stack of let-bound variable values visible to `FastChildren` evaluation paths.
     locals: Vec<(Arc<str>, Val)>,
-    /// Private VM instance used for `Vm` and `Structural` fallback nodes.
-    vm: VM,
+    /// Caller's VM, used for `Vm` and `Structural` fallback nodes.
+    vm: &'a mut VM,
+    /// Branch results of each `SharedScan` run so far, taken by the `ScanSlot`s that read them.
+    scans: Vec<(NodeId, ScanResults)>,
 }
 
 impl ExecCtx<'_> {
     /// Evaluates node `id`, returning an error if no backend in its preference list could run.
     fn eval(&mut self, id: NodeId) -> Result<Val, EvalError> {
         self.eval_fast(id).unwrap_or_else(|| {
-            Err(EvalError(format!(
+            Err(EvalError::internal(format!(
                 "no planned backend could execute physical node {}",
                 id.0
             )))
@@ -95,7 +131,7 @@ impl ExecCtx<'_> {
                 .env()?
                 .get_var(name.as_ref())
                 .cloned()
-                .ok_or_else(|| EvalError(format!("unbound local {}", name))),
+                .ok_or_else(|| EvalError::new(EvalErrorKind::UnboundVariable, format!("unbound local {}", name))),
             PlanNode::Pipeline { source, body } => {
                 let source = self.resolve_pipeline_source(source, body)?;
                 let pipeline = body.clone().with_source(source.into_pipeline_source());
@@ -109,19 +145,20 @@ impl ExecCtx<'_> {
                 receiver,
                 call,
                 optional,
+                ..
             } => {
                 let receiver = self.eval(*receiver)?;
                 if *optional && receiver.is_null() {
                     Ok(Val::Null)
                 } else {
                     call.try_apply(&receiver)?
-                        .ok_or_else(|| EvalError(format!("{:?}: builtin unsupported", call.method)))
+                        .ok_or_else(|| EvalError::unknown_method(format!("{:?}: builtin unsupported", call.method)))
                 }
             }
             PlanNode::UnaryNeg(inner) => match self.eval(*inner)? {
                 Val::Int(n) => Ok(Val::Int(-n)),
                 Val::Float(f) => Ok(Val::Float(-f)),
-                _ => Err(EvalError("unary minus requires a number".into())),
+                _ => Err(EvalError::type_mismatch("unary minus requires a number")),
             },
             PlanNode::Not(inner) => {
                 let value = self.eval(*inner)?;
@@ -155,6 +192,32 @@ impl ExecCtx<'_> {
             },
             PlanNode::Object(fields) => self.eval_object(fields),
             PlanNode::Array(elems) => self.eval_array(elems),
+            PlanNode::SharedScan {
+                keys,
+                prefix,
+                branches,
+            } => {
+                let root = self.root()?;
+                let env = self.env()?.clone();
+                let bodies: Vec<&pipeline::PipelineBody> = branches.iter().collect();
+                let results = pipeline::fused_exec::run_field_chain(
+                    prefix.as_ref(),
+                    &bodies,
+                    keys,
+                    &root,
+                    &env,
+                );
+                self.store_scan(id, keys, results);
+                Ok(Val::Null)
+            }
+            PlanNode::ScanSlot { scan, slot } => {
+                self.eval_scan_slot(*scan, *slot).unwrap_or_else(|| {
+                    Err(EvalError::internal(format!(
+                        "no planned backend could execute physical node {}",
+                        scan.0
+                    )))
+                })
+            }
             PlanNode::Structural { fallback, .. } => {
                 let mut env = self.take_env()?;
                 let result = self.vm.exec_in_env(fallback, &mut env);
@@ -233,18 +296,131 @@ impl ExecCtx<'_> {
     ///
     /// Returns `None` when every backend is ineligible or declines to handle the node.
     fn eval_fast(&mut self, id: NodeId) -> Option<Result<Val, EvalError>> {
+        let probe = profile::NodeProbe::start(self.j, id);
         let capabilities = self.plan.backend_capabilities(id);
         for backend in self.plan.backend_preferences(id) {
             if !capabilities.contains(backend.backend_set()) {
                 continue;
             }
+            if let Err(err) = limits::check() {
+                probe.finish(self.j, None);
+                return Some(Err(err));
+            }
             if let Some(result) = self.eval_backend(id, *backend) {
-                return Some(result);
+                #[cfg(test)]
+                self.record_source_scan(id);
+                probe.finish(self.j, Some(*backend));
+                return Some(result.map_err(|err| self.annotate_error(id, err)));
+            }
+            probe.declined(*backend);
+        }
+        probe.finish(self.j, None);
+        None
+    }
+
+    /// Counts a completed scan of a field-chain source on the document, for tests that check
+    /// sibling pipelines share one.
+    #[cfg(test)]
+    fn record_source_scan(&self, id: NodeId) {
+        if matches!(
+            self.plan.node(id),
+            PlanNode::SharedScan { .. }
+                | PlanNode::Pipeline {
+                    source: PipelinePlanSource::FieldChain { .. },
+                    ..
+                }
+        ) {
+            self.j
+                .source_scans
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Records the branch results of `SharedScan` node `id`, tagging errors with the scan's
+    /// source path, replacing any results left over from an earlier run.
+    fn store_scan(&mut self, id: NodeId, keys: &[Arc<str>], results: Vec<Result<Val, EvalError>>) {
+        let results = results
+            .into_iter()
+            .map(|result| Some(result.map_err(|err| err.or_path(|| field_chain_path(keys)))))
+            .collect();
+        match self.scans.iter_mut().find(|(scan, _)| *scan == id) {
+            Some((_, slots)) => *slots = results,
+            None => self.scans.push((id, results)),
+        }
+    }
+
+    /// Returns branch `slot` of `SharedScan` node `scan`, running the scan unless an earlier
+    /// sibling already did. Each result is handed out once; asking again re-runs the scan.
+    fn eval_scan_slot(&mut self, scan: NodeId, slot: usize) -> Option<Result<Val, EvalError>> {
+        if let Some(result) = self.take_scan_slot(scan, slot) {
+            return Some(result);
+        }
+        if let Err(err) = self.eval_fast(scan)? {
+            return Some(Err(err));
+        }
+        self.take_scan_slot(scan, slot)
+    }
+
+    /// Removes and returns the stored result of branch `slot` of `scan`, if present.
+    fn take_scan_slot(&mut self, scan: NodeId, slot: usize) -> Option<Result<Val, EvalError>> {
+        self.scans
+            .iter_mut()
+            .find(|(id, _)| *id == scan)
+            .and_then(|(_, slots)| slots.get_mut(slot)?.take())
+    }
+
+    /// Runs a `SharedScan` over rows pulled from the simd-json tape, materialising each row
+    /// once for every branch; requires branches that need no document root.
+    fn eval_tape_shared_scan(
+        &mut self,
+        id: NodeId,
+        keys: &Arc<[Arc<str>]>,
+        prefix: Option<&pipeline::PipelineBody>,
+        branches: &[pipeline::PipelineBody],
+    ) -> Option<Result<Val, EvalError>> {
+        if !self.plan.execution_facts(id).can_avoid_root_materialization {
+            return None;
+        }
+        #[cfg(feature = "simd-json")]
+        {
+            if let Some(tape) = match self.j.lazy_tape() {
+                Ok(tape) => tape,
+                Err(err) => return Some(Err(err)),
+            } {
+                let env = self.null_env_with_fast_locals();
+                let bodies: Vec<&pipeline::PipelineBody> = branches.iter().collect();
+                let results = pipeline::fused_exec::run_tape_field_chain(
+                    prefix, &bodies, tape, keys, &env,
+                )
+                .unwrap_or_else(|| {
+                    let root = crate::data::view::TapeView::root(tape);
+                    let source = view_pipeline::walk_fields(root, keys).materialize();
+                    pipeline::fused_exec::run_receiver(prefix, &bodies, &source, &env)
+                });
+                self.store_scan(id, keys, results);
+                return Some(Ok(Val::Null));
             }
         }
+        #[cfg(not(feature = "simd-json"))]
+        let _ = (keys, prefix, branches);
         None
     }
 
+    /// Attaches the failing builtin name or the pipeline's source path to `err`, keeping any
+    /// more specific context that an inner node already recorded.
+    fn annotate_error(&self, id: NodeId, err: EvalError) -> EvalError {
+        match self.plan.node(id) {
+            PlanNode::Call { call, span, .. } => err.or_call(call.method.name(), *span),
+            PlanNode::UnaryNeg(_) => err.or_operation("Neg"),
+            PlanNode::Binary { op, .. } => err.or_operation(&format!("{:?}", op)),
+            PlanNode::Pipeline {
+                source: PipelinePlanSource::FieldChain { keys },
+                ..
+            } => err.or_path(|| field_chain_path(keys)),
+            _ => err,
+        }
+    }
+
     /// Attempts to run node `id` under the specific `backend`; returns `None` when the backend
     /// cannot handle the node (wrong node kind, missing tape/index, or preconditions not met).
     fn eval_backend(
@@ -280,6 +456,17 @@ impl ExecCtx<'_> {
             (BackendPreference::FastChildren, PlanNode::Pipeline { source, body }) => {
                 self.eval_pipeline_backend(backend, source, body)
             }
+            (
+                BackendPreference::TapeRows,
+                PlanNode::SharedScan {
+                    keys,
+                    prefix,
+                    branches,
+                },
+            ) => self.eval_tape_shared_scan(id, keys, prefix.as_ref(), branches),
+            (BackendPreference::FastChildren, PlanNode::ScanSlot { scan, slot }) => {
+                self.eval_scan_slot(*scan, *slot)
+            }
             (BackendPreference::TapePath, PlanNode::RootPath(steps)) => {
                 self.eval_root_path_fast(steps)
             }
@@ -299,6 +486,7 @@ impl ExecCtx<'_> {
                     receiver,
                     call,
                     optional,
+                    ..
                 },
             ) => {
                 let receiver = match self.eval_fast(*receiver)? {
@@ -310,7 +498,7 @@ impl ExecCtx<'_> {
                 }
                 Some(call.try_apply(&receiver).and_then(|result| {
                     result
-                        .ok_or_else(|| EvalError(format!("{:?}: builtin unsupported", call.method)))
+                        .ok_or_else(|| EvalError::unknown_method(format!("{:?}: builtin unsupported", call.method)))
                 }))
             }
             (BackendPreference::FastChildren, PlanNode::UnaryNeg(inner)) => {
@@ -321,7 +509,7 @@ impl ExecCtx<'_> {
                 Some(match value {
                     Val::Int(n) => Ok(Val::Int(-n)),
                     Val::Float(f) => Ok(Val::Float(-f)),
-                    _ => Err(EvalError("unary minus requires a number".into())),
+                    _ => Err(EvalError::type_mismatch("unary minus requires a number")),
                 })
             }
             (BackendPreference::FastChildren, PlanNode::Not(inner)) => {
@@ -538,8 +726,29 @@ impl ExecCtx<'_> {
                 Ok(root) => root,
                 Err(err) => return Some(Err(err)),
             };
-            let source = view_pipeline::walk_fields(ValView::new(&root), keys);
             let env = self.null_env_with_fast_locals();
+            if crate::exec::parallel::is_enabled() {
+                let root_env = match self.env() {
+                    Ok(env) => env.clone(),
+                    Err(err) => return Some(Err(err)),
+                };
+                let pipeline = body.clone().with_source(pipeline::Source::FieldChain {
+                    keys: Arc::from(keys),
+                });
+                // each partition runs the view path, then the pipeline it falls back to
+                let sequential = |rows: Val| {
+                    view_pipeline::run_with_env(ValView::new(&rows), body, None, &env)
+                        .unwrap_or_else(|| {
+                            body.clone()
+                                .with_source(pipeline::Source::Receiver(rows.clone()))
+                                .run_with_env(&root, &root_env, None)
+                        })
+                };
+                if let Some(result) = pipeline::parallel_exec::run(&pipeline, &root, &root_env, &sequential) {
+                    return Some(result);
+                }
+            }
+            let source = view_pipeline::walk_fields(ValView::new(&root), keys);
             if let Some(result) = view_pipeline::run_with_env(source, body, Some(self.j), &env) {
                 return Some(result);
             }
@@ -658,6 +867,9 @@ impl ExecCtx<'_> {
                 }
             }
         }
+        if let Err(err) = limits::check_len(out.len()) {
+            return Some(Err(err));
+        }
         Some(Ok(Val::arr(out)))
     }
 
@@ -791,7 +1003,7 @@ impl ExecCtx<'_> {
             BinOp::Div => {
                 let denom = rhs.as_f64().unwrap_or(0.0);
                 if denom == 0.0 {
-                    Err(EvalError("division by zero".into()))
+                    Err(EvalError::new(EvalErrorKind::DivisionByZero, "division by zero"))
                 } else {
                     Ok(Val::Float(lhs.as_f64().unwrap_or(0.0) / denom))
                 }
@@ -894,6 +1106,7 @@ impl ExecCtx<'_> {
                 }
             }
         }
+        limits::check_len(out.len())?;
         Ok(Val::arr(out))
     }
 }
@@ -0,0 +1,454 @@
+//! Resource limits and cooperative cancellation for untrusted queries.
+//!
+//! `JetroEngine` installs a guard on the current thread for the duration of
+//! each `collect*` call when it has `ExecutionLimits` configured or the caller
+//! passed a `CancellationToken`. The VM counts every opcode through `step`,
+//! tracks nesting through `descend`, and checks the array it just pushed; the
+//! pipeline executors, barrier stages and recursive descents count every row
+//! or node through `poll`, and call `check_len` on every buffer and collected
+//! output they materialise, as do array literals. Deadline and cancellation are polled every `POLL_INTERVAL`
+//! steps so the hot loops stay cheap.
+//!
+//! The first limit hit is remembered. Executors whose stages swallow errors
+//! (composed chains degrade a failing row to `Filtered`) stop at their next
+//! `should_stop`, and the engine reports the remembered error instead of the
+//! partial result.
+//!
+//! Every hook is a single thread-local flag check when no guard is installed.
+//!
+//! Nesting is the exception to "off by default": the parser, the compiler,
+//! and document materialisation recurse once per level, so `max_nesting`
+//! always bounds queries and documents and falls back to `DEFAULT_MAX_NESTING`
+//! outside an engine call.
+
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::data::context::{EvalError, EvalErrorKind};
+use crate::data::value::Val;
+
+/// Ticks between deadline and cancellation checks.
+const POLL_INTERVAL: u32 = 1024;
+
+/// Nesting depth accepted for queries and documents when no engine sets
+/// `ExecutionLimits::max_nesting`. Matches `serde_json`'s recursion limit.
+pub const DEFAULT_MAX_NESTING: usize = 128;
+
+/// Resource limits applied to every query a `JetroEngine` evaluates. All
+/// limits except nesting are off by default; set the ones you need:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use jetro_core::{EvalErrorKind, ExecutionLimits, Jetro, JetroEngine};
+///
+/// let mut engine = JetroEngine::new();
+/// engine.set_limits(
+///     ExecutionLimits::new()
+///         .timeout(Duration::from_millis(50))
+///         .max_array_len(10_000),
+/// );
+/// let j = Jetro::from(serde_json::json!({"n": 100000}));
+/// let err = engine.collect(&j, "range($.n)").unwrap_err();
+/// assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    timeout: Option<Duration>,
+    max_steps: Option<u64>,
+    max_output_elements: Option<usize>,
+    max_output_bytes: Option<usize>,
+    max_depth: Option<usize>,
+    max_array_len: Option<usize>,
+    max_nesting: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// No limits; same as `Default`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail once evaluation has run for longer than `timeout` of wall-clock time.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail after evaluation has taken `steps` steps. A step is one VM
+    /// opcode, counting every nested lambda body and sub-program, one row a
+    /// pipeline pulls from its source, or one node a recursive descent
+    /// (`..`) visits.
+    pub fn max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Fail when the result is an array of more than `elements` items; any
+    /// other result counts as one element.
+    pub fn max_output_elements(mut self, elements: usize) -> Self {
+        self.max_output_elements = Some(elements);
+        self
+    }
+
+    /// Fail when the result would serialize to more than `bytes` of compact JSON.
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = Some(bytes);
+        self
+    }
+
+    /// Fail when sub-programs (lambda bodies, arguments, nested expressions)
+    /// nest more than `depth` levels deep in the VM.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Fail when an array evaluation builds would hold more than `len`
+    /// elements: an array literal, a pipeline's collected output, a buffer a
+    /// barrier stage materialises, or any array the VM pushes. Arrays read
+    /// from the document count once a query collects them.
+    pub fn max_array_len(mut self, len: usize) -> Self {
+        self.max_array_len = Some(len);
+        self
+    }
+
+    /// Fail when the query or the input document nests more than `depth`
+    /// levels deep, instead of `DEFAULT_MAX_NESTING`. Checked while parsing
+    /// and compiling the query and while materialising a byte document.
+    /// Building and dropping a document still recurses once per level, so a
+    /// limit far above the default needs a correspondingly larger thread stack.
+    pub fn max_nesting(mut self, depth: usize) -> Self {
+        self.max_nesting = Some(depth);
+        self
+    }
+
+    /// Return `true` when no limit is set.
+    fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Cooperative cancellation flag for a running query. Clones share the flag,
+/// so keep one and hand another to `JetroEngine::collect_cancellable`; calling
+/// `cancel` from any thread makes the query fail with
+/// `EvalErrorKind::Cancelled` at its next check.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every query observing this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Return `true` once `cancel` has been called on this token or a clone.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+    static GUARD: RefCell<Option<Guard>> = const { RefCell::new(None) };
+}
+
+/// Per-thread limit state while an engine call runs.
+struct Guard {
+    limits: ExecutionLimits,
+    deadline: Option<Instant>,
+    token: Option<CancellationToken>,
+    steps: u64,
+    depth: usize,
+    until_poll: u32,
+    /// First limit hit; returned by every later check.
+    tripped: Option<EvalError>,
+}
+
+impl Guard {
+    /// Remember `err` as the reason evaluation stopped and return it.
+    fn trip(&mut self, err: EvalError) -> EvalError {
+        self.tripped = Some(err.clone());
+        err
+    }
+
+    /// Check the deadline and the token right away.
+    fn check_now(&mut self) -> Result<(), EvalError> {
+        if let Some(err) = &self.tripped {
+            return Err(err.clone());
+        }
+        if self
+            .token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(self.trip(EvalError::new(EvalErrorKind::Cancelled, "query cancelled")));
+        }
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.limits.timeout) {
+            if Instant::now() >= deadline {
+                return Err(self.trip(limit_exceeded(format!(
+                    "query exceeded its {:?} deadline",
+                    timeout
+                ))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count one step of work against `max_steps`, then `poll`.
+    fn tick(&mut self) -> Result<(), EvalError> {
+        self.steps += 1;
+        if let Some(max) = self.limits.max_steps {
+            if self.steps > max {
+                return Err(self.trip(limit_exceeded(format!(
+                    "query exceeded the limit of {} steps",
+                    max
+                ))));
+            }
+        }
+        self.poll()
+    }
+
+    /// Check the deadline and the token every `POLL_INTERVAL` calls.
+    fn poll(&mut self) -> Result<(), EvalError> {
+        if let Some(err) = &self.tripped {
+            return Err(err.clone());
+        }
+        self.until_poll -= 1;
+        if self.until_poll > 0 {
+            return Ok(());
+        }
+        self.until_poll = POLL_INTERVAL;
+        self.check_now()
+    }
+
+    fn check_len(&mut self, len: usize) -> Result<(), EvalError> {
+        match self.limits.max_array_len {
+            Some(max) if len > max => Err(self.trip(limit_exceeded(format!(
+                "intermediate array of {} elements exceeds the limit of {}",
+                len, max
+            )))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn limit_exceeded(message: String) -> EvalError {
+    EvalError::new(EvalErrorKind::LimitExceeded, message)
+}
+
+/// Run `f` against the installed guard; `None` when no guard is installed.
+#[inline]
+fn with_guard<R>(f: impl FnOnce(&mut Guard) -> R) -> Option<R> {
+    if !ACTIVE.with(Cell::get) {
+        return None;
+    }
+    GUARD.with(|guard| guard.borrow_mut().as_mut().map(f))
+}
+
+/// Return `true` when a guard is installed on this thread.
+#[inline]
+pub(crate) fn is_active() -> bool {
+    ACTIVE.with(Cell::get)
+}
+
+/// Count one VM opcode as a step, check the array `top` of the operand
+/// stack, and poll the deadline and token.
+#[inline]
+pub(crate) fn step(top: Option<&Val>) -> Result<(), EvalError> {
+    with_guard(|guard| {
+        if let Some(len) = top.and_then(Val::array_len) {
+            guard.check_len(len)?;
+        }
+        guard.tick()
+    })
+    .unwrap_or(Ok(()))
+}
+
+/// Count one row of a row loop, or one node of a tree walk, as a step and
+/// poll the deadline and token.
+#[inline]
+pub(crate) fn poll() -> Result<(), EvalError> {
+    with_guard(Guard::tick).unwrap_or(Ok(()))
+}
+
+/// Like `poll`, for loops that cannot return an error: `true` means stop
+/// pulling rows. The error itself is kept for the engine to report.
+#[inline]
+pub(crate) fn should_stop() -> bool {
+    with_guard(|guard| guard.tick().is_err()).unwrap_or(false)
+}
+
+/// Check the deadline, the token, and any limit hit so far without waiting
+/// for the next poll interval.
+#[inline]
+pub(crate) fn check() -> Result<(), EvalError> {
+    with_guard(Guard::check_now).unwrap_or(Ok(()))
+}
+
+/// Fail when an array of `len` elements exceeds `max_array_len`.
+#[inline]
+pub(crate) fn check_len(len: usize) -> Result<(), EvalError> {
+    with_guard(|guard| guard.check_len(len)).unwrap_or(Ok(()))
+}
+
+/// Nesting limit in force on this thread: the engine's `max_nesting` while a
+/// guard is installed, `DEFAULT_MAX_NESTING` otherwise.
+pub(crate) fn max_nesting() -> usize {
+    with_guard(|guard| guard.limits.max_nesting)
+        .flatten()
+        .unwrap_or(DEFAULT_MAX_NESTING)
+}
+
+/// Error for a query or document nesting deeper than `max` levels.
+pub(crate) fn nesting_exceeded(what: &str, max: usize) -> EvalError {
+    limit_exceeded(format!(
+        "{} nests deeper than the limit of {} levels",
+        what, max
+    ))
+}
+
+/// Leaves one VM nesting level when dropped.
+pub(crate) struct DepthGuard(());
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        with_guard(|guard| guard.depth -= 1);
+    }
+}
+
+/// Enter one VM nesting level, failing past `max_depth`. Returns `None` when
+/// no guard is installed.
+#[inline]
+pub(crate) fn descend() -> Result<Option<DepthGuard>, EvalError> {
+    with_guard(|guard| {
+        if let Some(max) = guard.limits.max_depth {
+            if guard.depth >= max {
+                return Err(guard.trip(limit_exceeded(format!(
+                    "query exceeded the maximum evaluation depth of {}",
+                    max
+                ))));
+            }
+        }
+        guard.depth += 1;
+        Ok(Some(DepthGuard(())))
+    })
+    .unwrap_or(Ok(None))
+}
+
+/// Restores the previously installed guard when dropped, so nested engine
+/// calls and unwinding both behave.
+pub(crate) struct LimitScope {
+    /// `None` when this scope installed nothing.
+    previous: Option<Option<Guard>>,
+}
+
+impl Drop for LimitScope {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            ACTIVE.with(|active| active.set(previous.is_some()));
+            GUARD.with(|guard| *guard.borrow_mut() = previous);
+        }
+    }
+}
+
+impl LimitScope {
+    /// Replace `result` with the first limit hit during the call, if any.
+    pub(crate) fn finish<T>(&self, result: Result<T, EvalError>) -> Result<T, EvalError> {
+        if self.previous.is_none() {
+            return result;
+        }
+        match with_guard(|guard| guard.tripped.clone()).flatten() {
+            Some(err) => Err(err),
+            None => result,
+        }
+    }
+
+    /// Check a finished result of `elements` top-level items against the
+    /// output limits.
+    pub(crate) fn check_output<T: Serialize>(
+        &self,
+        value: &T,
+        elements: usize,
+    ) -> Result<(), EvalError> {
+        if self.previous.is_none() {
+            return Ok(());
+        }
+        let (max_elements, max_bytes) = with_guard(|guard| {
+            (
+                guard.limits.max_output_elements,
+                guard.limits.max_output_bytes,
+            )
+        })
+        .unwrap_or_default();
+        if let Some(max) = max_elements {
+            if elements > max {
+                return Err(limit_exceeded(format!(
+                    "result has {} elements, exceeding the limit of {}",
+                    elements, max
+                )));
+            }
+        }
+        if let Some(max) = max_bytes {
+            let mut budget = ByteBudget { left: max };
+            if serde_json::to_writer(&mut budget, value).is_err() {
+                return Err(limit_exceeded(format!(
+                    "result exceeds the limit of {} serialized bytes",
+                    max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Install a guard for `limits` and `token` on this thread until the returned
+/// scope is dropped. With no limits and no token nothing is installed, and an
+/// enclosing call's guard stays in force.
+pub(crate) fn enter(limits: &ExecutionLimits, token: Option<&CancellationToken>) -> LimitScope {
+    if limits.is_unlimited() && token.is_none() {
+        return LimitScope { previous: None };
+    }
+    let guard = Guard {
+        limits: limits.clone(),
+        deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        token: token.cloned(),
+        steps: 0,
+        depth: 0,
+        until_poll: 1,
+        tripped: None,
+    };
+    let previous = GUARD.with(|cell| cell.borrow_mut().replace(guard));
+    ACTIVE.with(|active| active.set(true));
+    LimitScope {
+        previous: Some(previous),
+    }
+}
+
+/// Writer that discards output and fails once more than `left` bytes arrive.
+struct ByteBudget {
+    left: usize,
+}
+
+impl io::Write for ByteBudget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.left = self
+            .left
+            .checked_sub(buf.len())
+            .ok_or_else(|| io::Error::other("output byte limit exceeded"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -8,10 +8,18 @@
 //! - `view` — borrowed traversal over `ValueView`s, no `Val` materialisation.
 //! - `pipeline` — pull-based stage chain for streamable shapes.
 //! - `composed` — fused multi-stage variant of `pipeline`.
+//!
+//! `profile` records what the backends actually did when a query runs under
+//! `JetroEngine::collect_profiled`; `limits` enforces an engine's
+//! `ExecutionLimits` and `CancellationToken` inside the same backends, and
+//! `parallel` carries its `Parallelism` setting to the pipeline executor.
 
 pub(crate) mod composed;
 pub(crate) mod interpreted;
+pub(crate) mod limits;
+pub(crate) mod parallel;
 pub(crate) mod pipeline;
+pub(crate) mod profile;
 pub(crate) mod router;
 pub(crate) mod structural;
 pub(crate) mod view;
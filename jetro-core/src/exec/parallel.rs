@@ -0,0 +1,122 @@
+//! Opt-in parallel execution of streaming pipelines over large arrays.
+//!
+//! A `JetroEngine` carries a `Parallelism` setting and installs it as the
+//! thread's active setting for the duration of each `collect*` call, the same
+//! way `limits` installs an engine's `ExecutionLimits`. Pipeline execution
+//! reads it back through `current` to decide whether a source is large enough
+//! to split across worker threads; see `pipeline::parallel_exec`.
+
+use std::cell::Cell;
+use std::num::NonZeroUsize;
+
+/// Default for `Parallelism::min_rows`: sources shorter than this run on the
+/// calling thread, where spawning workers would cost more than it saves.
+const DEFAULT_MIN_ROWS: usize = 100_000;
+
+/// How many threads a `JetroEngine` may use to run one pipeline, and how
+/// large its source must be before it does.
+///
+/// Disabled by default. When enabled, a pipeline whose source array has at
+/// least `min_rows` rows is split into contiguous partitions, each run on its
+/// own thread by the executor a sequential run would use, and the partial
+/// results are combined in source order, so a query returns what it returns
+/// sequentially. Only pipelines whose stages are element-wise (`filter`,
+/// `map`, ...) and whose result can be combined (collect, `count`, `sum`,
+/// `avg`, `min`, `max`, `approx_count_distinct`, `count_by`, `group_by`,
+/// `index_by`) are split; everything else, and every query run under
+/// `ExecutionLimits` or a `CancellationToken`, stays on one thread. Floating
+/// point sums and averages may differ from a sequential run in the last
+/// bits, since the partitions are added in a different order.
+///
+/// ```rust
+/// use jetro_core::{Jetro, JetroEngine, Parallelism};
+///
+/// let mut engine = JetroEngine::new();
+/// engine.set_parallelism(Parallelism::new().threads(4).min_rows(1_000));
+/// let rows: Vec<_> = (0..10_000).map(|n| serde_json::json!({"n": n})).collect();
+/// let j = Jetro::from(serde_json::json!({"rows": rows}));
+/// let total = engine.collect(&j, "$.rows.filter(n % 2 == 0).map(n).sum()").unwrap();
+/// assert_eq!(total, serde_json::json!(24_995_000));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parallelism {
+    threads: usize,
+    min_rows: usize,
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            min_rows: DEFAULT_MIN_ROWS,
+        }
+    }
+}
+
+impl Parallelism {
+    /// Sequential execution; same as `Default`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use up to `threads` threads per pipeline, counting the calling thread.
+    /// `0` means one per available CPU; `1` disables parallel execution.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = if threads == 0 {
+            std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        } else {
+            threads
+        };
+        self
+    }
+
+    /// Only split sources of at least `rows` rows. Defaults to 100,000.
+    pub fn min_rows(mut self, rows: usize) -> Self {
+        self.min_rows = rows;
+        self
+    }
+
+    /// Number of partitions to split a source of `rows` rows into; `1` means
+    /// run it sequentially.
+    pub(crate) fn partitions(&self, rows: usize) -> usize {
+        if self.threads <= 1 || rows < self.min_rows.max(2) {
+            return 1;
+        }
+        self.threads.min(rows)
+    }
+}
+
+thread_local! {
+    static ACTIVE: Cell<Parallelism> = Cell::new(Parallelism::default());
+}
+
+/// Return the setting installed on this thread; sequential outside an engine call.
+#[inline]
+pub(crate) fn current() -> Parallelism {
+    ACTIVE.with(Cell::get)
+}
+
+/// Return `true` when the installed setting allows more than one thread.
+#[inline]
+pub(crate) fn is_enabled() -> bool {
+    current().threads > 1
+}
+
+/// Restores the previously installed setting when dropped, so nested engine
+/// calls and unwinding both behave.
+pub(crate) struct ParallelScope {
+    previous: Parallelism,
+}
+
+impl Drop for ParallelScope {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.set(self.previous));
+    }
+}
+
+/// Install `parallelism` on this thread until the returned scope is dropped.
+pub(crate) fn enter(parallelism: &Parallelism) -> ParallelScope {
+    ParallelScope {
+        previous: ACTIVE.with(|active| active.replace(*parallelism)),
+    }
+}
@@ -9,6 +9,8 @@
 
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use crate::parse::ast::Expr;
 use crate::builtins::{
     BuiltinCancellation, BuiltinMethod, BuiltinNumericReducer, BuiltinViewStage,
@@ -22,6 +24,7 @@ mod columnar;
 mod common;
 mod composed;
 mod exec;
+pub(crate) mod fused_exec;
 mod indexed_exec;
 mod kernels;
 pub(crate) mod materialized_exec;
@@ -29,11 +32,13 @@ mod ir;
 mod lower;
 pub(crate) mod logical_lower;
 mod operator;
+pub(crate) mod parallel_exec;
 mod plan;
 mod symbolic;
 mod reducer;
 mod row_source;
 mod sink_accumulator;
+pub(crate) mod stream_exec;
 mod val_stage_flow;
 pub(crate) use capability::{
     view_capabilities, view_prefix_capabilities, SourceAccessMode, SourceCapabilities,
@@ -116,7 +121,7 @@ pub(crate) fn trace_enabled() -> bool {
 }
 
 /// Returns a short human-readable label for `s`, used in pipeline trace output.
-fn sink_name(s: &Sink) -> &'static str {
+pub(crate) fn sink_name(s: &Sink) -> &'static str {
     match s {
         Sink::Collect => "collect",
         Sink::Reducer(spec) => match spec.op {
@@ -178,7 +183,7 @@ pub enum Source {
 pub type PipelineBuiltinCall = crate::builtins::BuiltinCall;
 
 /// Describes the sort order for a `Stage::Sort` stage, optionally with a key-extraction program.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SortSpec {
     /// Compiled key-extraction expression, or `None` for natural (value-level) ordering.
     pub key: Option<Arc<crate::vm::Program>>,
@@ -208,7 +213,7 @@ impl SortSpec {
 ///
 /// Each variant carries the compiled predicate / projection program and any metadata needed
 /// to select the correct execution path (view-native, VM fallback, etc.).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Stage {
     /// Retains only elements for which the predicate program yields a truthy value.
     Filter(Arc<crate::vm::Program>, BuiltinViewStage),
@@ -283,7 +288,7 @@ pub enum Stage {
 }
 
 /// The four numeric fold operations supported by the `Reducer` sink.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NumOp {
     /// Adds all numeric elements together.
     Sum,
@@ -339,7 +344,7 @@ impl Sink {
 }
 
 /// The terminal accumulator of a pipeline — consumes the element stream and produces the final value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Sink {
     /// Gathers all passing elements into a `Val::Arr`.
     Collect,
@@ -382,7 +387,7 @@ pub struct Pipeline {
 
 /// The source-independent half of a `Pipeline`; can be combined with any `Source` via
 /// `with_source` to produce a runnable `Pipeline`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipelineBody {
     /// Ordered transformation stages.
     pub stages: Vec<Stage>,
@@ -447,7 +452,7 @@ impl Pipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parse::ast::{Arg, BinOp, Expr, Step};
+    use crate::parse::ast::{Arg, BinOp, Expr, Span, Step};
     use crate::parse::parser;
 
     fn lower_query(q: &str) -> Option<Pipeline> {
@@ -575,24 +580,29 @@ mod tests {
     fn receiver_pipeline_start_uses_builtin_metadata() {
         assert!(Pipeline::is_receiver_pipeline_start(&Step::Method(
             "filter".into(),
-            vec![Arg::Pos(Expr::Bool(true))]
+            vec![Arg::Pos(Expr::Bool(true))],
+            Span::default()
         )));
         assert!(Pipeline::is_receiver_pipeline_start(&Step::Method(
             "sum".into(),
-            Vec::new()
+            Vec::new(),
+            Span::default()
         )));
         assert!(Pipeline::is_receiver_pipeline_start(&Step::Method(
             "first".into(),
-            Vec::new()
+            Vec::new(),
+            Span::default()
         )));
         assert!(Pipeline::is_receiver_pipeline_start(&Step::Method(
             "count_by".into(),
-            vec![Arg::Pos(Expr::Ident("kind".into()))]
+            vec![Arg::Pos(Expr::Ident("kind".into()))],
+            Span::default()
         )));
 
         assert!(!Pipeline::is_receiver_pipeline_start(&Step::Method(
             "from_json".into(),
-            Vec::new()
+            Vec::new(),
+            Span::default()
         )));
     }
 
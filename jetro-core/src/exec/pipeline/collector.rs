@@ -8,6 +8,7 @@ use std::sync::Arc;
 
 use crate::data::value::{ObjVecData, Val};
 use crate::data::view::{scalar_view_to_owned_val, ValueView};
+use crate::exec::limits;
 
 use super::{BodyKernel, CollectLayout, ObjectKernel, ViewKernelValue};
 
@@ -71,7 +72,18 @@ impl<'a> TerminalCollector<'a> {
             Self::Values(values) => values.push(super::eval_kernel(kernel, item, fallback)?),
             Self::UniformObject(collector) => collector.push_val_row(item),
         }
-        Ok(())
+        limits::check_len(self.len())
+    }
+
+    /// Number of rows collected so far.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Values(values) => values.len(),
+            Self::UniformObject(collector) => match &collector.rows {
+                Some(rows) => rows.len(),
+                None => collector.cells.len() / collector.object.len().max(1),
+            },
+        }
     }
 
     /// Consumes the collector and returns either `Val::Arr` or `Val::ObjVec`.
@@ -14,6 +14,7 @@ use std::sync::Arc;
 use crate::builtins::{BuiltinNumericReducer, BuiltinSelectionPosition, BuiltinSinkAccumulator};
 use crate::parse::chain_ir::PullDemand;
 use crate::exec::composed as cmp;
+use crate::exec::{limits, profile};
 use crate::data::context::{Env, EvalError};
 use crate::data::value::Val;
 use crate::vm::Program;
@@ -197,6 +198,48 @@ fn segment_collect(rows: &[Val], chain: &dyn cmp::Stage) -> Option<Vec<Val>> {
     }
 }
 
+/// Rows entering and leaving the final composed chain; only counted while a
+/// profile is being recorded.
+#[derive(Default)]
+struct ChainCounts(Rc<Cell<(usize, usize)>>);
+
+impl ChainCounts {
+    /// Wraps `chain` in a counting stage when profiling, else returns it unchanged.
+    fn wrap(&self, chain: Box<dyn cmp::Stage>) -> Box<dyn cmp::Stage> {
+        if !profile::is_recording() {
+            return chain;
+        }
+        Box::new(CountedStage {
+            inner: chain,
+            counts: Rc::clone(&self.0),
+        })
+    }
+
+    fn get(&self) -> (usize, usize) {
+        self.0.get()
+    }
+}
+
+/// Passes rows through `inner`, counting every input and every output row.
+struct CountedStage {
+    inner: Box<dyn cmp::Stage>,
+    counts: Rc<Cell<(usize, usize)>>,
+}
+
+impl cmp::Stage for CountedStage {
+    fn apply<'a>(&self, x: &'a Val) -> cmp::StageOutput<'a> {
+        let out = self.inner.apply(x);
+        let produced = match &out {
+            cmp::StageOutput::Pass(_) => 1,
+            cmp::StageOutput::Many(items) => items.len(),
+            cmp::StageOutput::Filtered | cmp::StageOutput::Done => 0,
+        };
+        let (rows_in, rows_out) = self.counts.get();
+        self.counts.set((rows_in + 1, rows_out + produced));
+        out
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Barrier-stage execution
 // ---------------------------------------------------------------------------
@@ -395,6 +438,8 @@ pub(super) fn run(
     let stage_builder = ComposedStageBuilder::new(base_env);
 
     let mut buf = source_rows(&pipeline.source, root)?;
+    let source_len = buf.as_slice().len();
+    let counts = ChainCounts::default();
 
     let kernels = &eff_kernels;
     let stages_ref = &eff_stages;
@@ -416,7 +461,7 @@ pub(super) fn run(
         let strategy = strategies.get(i).copied().unwrap_or(StageStrategy::Default);
         if let StageStrategy::SortUntilOutput(target_outputs) = strategy {
             let _ = target_outputs;
-            if let Some(out) = run_lazy_ordered_suffix(
+            let out = run_lazy_ordered_suffix(
                 stage,
                 kernel,
                 &eff_sink,
@@ -426,10 +471,10 @@ pub(super) fn run(
                 i,
                 &stage_builder,
                 buf.into_vec(),
-            ) {
-                return Some(out);
-            }
-            return None;
+                &counts,
+            )?;
+            profile::executor("composed", 0..stages_ref.len(), source_len, counts.get().1);
+            return Some(out);
         }
         match run_barrier(
             stage,
@@ -439,8 +484,16 @@ pub(super) fn run(
             i + 1 == stages_ref.len(),
             buf.into_vec(),
         )? {
-            BarrierOutput::Rows(rows) => buf = super::row_source::Rows::Owned(rows),
-            BarrierOutput::Done(val) => return Some(Ok(val)),
+            BarrierOutput::Rows(rows) => {
+                if let Err(err) = limits::check_len(rows.len()).and_then(|()| limits::check()) {
+                    return Some(Err(err));
+                }
+                buf = super::row_source::Rows::Owned(rows);
+            }
+            BarrierOutput::Done(val) => {
+                profile::executor("composed", 0..stages_ref.len(), source_len, 1);
+                return Some(Ok(val));
+            }
         };
 
         last_split = i + 1;
@@ -452,8 +505,12 @@ pub(super) fn run(
         .pull;
     let (sink, chain) =
         append_reducer_sink_stages(&eff_sink, &pipeline.sink_kernels, &stage_builder, chain)?;
+    let chain = counts.wrap(chain);
     let out = run_sink(&sink, buf.as_slice(), chain.as_ref(), final_demand)?;
 
+    let (chain_in, chain_out) = counts.get();
+    let rows_in = if last_split == 0 { chain_in } else { source_len };
+    profile::executor("composed", 0..stages_ref.len(), rows_in, chain_out);
     Some(Ok(out))
 }
 
@@ -468,6 +525,7 @@ fn run_lazy_ordered_suffix(
     sort_idx: usize,
     stage_builder: &ComposedStageBuilder<'_>,
     rows: Vec<Val>,
+    counts: &ChainCounts,
 ) -> Option<Result<Val, EvalError>> {
     let Stage::Sort(spec) = stage else {
         return None;
@@ -502,6 +560,7 @@ fn run_lazy_ordered_suffix(
     };
     let chain = build_chain(stages, kernels, sort_idx + 1..stages.len(), stage_builder)?;
     let (sink, chain) = append_reducer_sink_stages(sink, sink_kernels, stage_builder, chain)?;
+    let chain = counts.wrap(chain);
     run_sink_owned_iter(&sink, ordered, chain.as_ref(), final_demand).map(Ok)
 }
 
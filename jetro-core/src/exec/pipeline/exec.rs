@@ -6,13 +6,16 @@
 use crate::{
     data::context::{Env, EvalError},
     data::value::Val,
+    exec::{limits, profile},
 };
 
 use super::columnar;
 use super::composed;
 use super::indexed_exec;
 use super::materialized_exec;
-use super::{PhysicalExecPath, Pipeline, PipelineData};
+use super::parallel_exec;
+use super::row_source;
+use super::{PhysicalExecPath, Pipeline, PipelineData, Source};
 
 impl Pipeline {
     /// Executes the pipeline against `root` using a freshly constructed environment.
@@ -34,16 +37,25 @@ impl Pipeline {
         base_env: &Env,
         cache: Option<&dyn PipelineData>,
     ) -> Result<Val, EvalError> {
+        limits::check()?;
+        let sequential = |rows: Val| {
+            let mut partition = self.clone();
+            partition.source = Source::Receiver(rows);
+            partition.run_with_env(root, base_env, None)
+        };
+        if let Some(out) = parallel_exec::run(self, root, base_env, &sequential) {
+            return out;
+        }
         match self.exec_path {
             PhysicalExecPath::Indexed => {
                 if let Some(out) = indexed_exec::run(self, root, base_env) {
                     return out;
                 }
+                profile::executor_declined("indexed");
                 self.run_columnar_or_below(root, base_env, cache)
             }
             PhysicalExecPath::Columnar => self.run_columnar_or_below(root, base_env, cache),
-            PhysicalExecPath::Composed => composed::run(self, root, base_env)
-                .unwrap_or_else(|| materialized_exec::run(self, root, base_env)),
+            PhysicalExecPath::Composed => self.run_composed_or_legacy(root, base_env),
             PhysicalExecPath::Legacy => materialized_exec::run(self, root, base_env),
         }
     }
@@ -56,15 +68,42 @@ impl Pipeline {
         base_env: &Env,
         cache: Option<&dyn PipelineData>,
     ) -> Result<Val, EvalError> {
-        if let Some(out) = columnar::run_cached(self, root, cache) {
+        let columnar = columnar::run_cached(self, root, cache).or_else(|| {
+            cache
+                .is_none()
+                .then(|| columnar::run_uncached(self, root))
+                .flatten()
+        });
+        if let Some(out) = columnar {
+            if profile::is_recording() {
+                self.profile_columnar(root, &out);
+            }
             return out;
         }
-        if cache.is_none() {
-            if let Some(out) = columnar::run_uncached(self, root) {
-                return out;
-            }
+        profile::executor_declined("columnar");
+        self.run_composed_or_legacy(root, base_env)
+    }
+
+    /// Tries the composed path, falling back to the legacy materialised path.
+    fn run_composed_or_legacy(&self, root: &Val, base_env: &Env) -> Result<Val, EvalError> {
+        if let Some(out) = composed::run(self, root, base_env) {
+            return out;
         }
-        composed::run(self, root, base_env)
-            .unwrap_or_else(|| materialized_exec::run(self, root, base_env))
+        profile::executor_declined("composed");
+        materialized_exec::run(self, root, base_env)
+    }
+
+    /// Reports a columnar run to the active profile. Columnar kernels scan the
+    /// whole source column, so every source row counts as pulled; they do not
+    /// expose how many rows reached a reducing sink, so a scalar result counts
+    /// as one row out.
+    fn profile_columnar(&self, root: &Val, out: &Result<Val, EvalError>) {
+        let recv = row_source::resolve(&self.source, root);
+        let rows_in = row_source::row_count(&recv).unwrap_or(0);
+        let rows_out = match out {
+            Ok(value) => value.array_len().unwrap_or(1),
+            Err(_) => 0,
+        };
+        profile::executor("columnar", 0..self.stages.len(), rows_in, rows_out);
     }
 }
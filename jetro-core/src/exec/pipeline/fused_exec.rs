@@ -0,0 +1,177 @@
+//! Fused execution of several pipelines that read the same field-chain source.
+//!
+//! Used by `JetroEngine::collect_all`: instead of one scan per query, the
+//! source rows are pulled once and each row is pushed into every pipeline's
+//! `StreamingRun`, so the stages and sinks of all queries advance together.
+//! A pipeline that finishes early (`first`, `take`, …) drops out of the scan;
+//! the scan ends when every pipeline is done or the rows run out.
+//!
+//! The physical planner uses the same scan for sibling pipelines inside one
+//! object or array constructor (`PlanNode::SharedScan`). There the siblings
+//! may also share a run of leading filters, which is then evaluated once per
+//! row as a `prefix` before the row reaches the individual pipelines.
+
+use std::sync::Arc;
+
+use crate::{
+    data::context::{Env, EvalError},
+    data::value::Val,
+};
+
+use super::materialized_exec::{can_stream, StreamingRun};
+use super::row_source;
+use super::{Pipeline, PipelineBody, Source, StageFlow};
+
+/// Returns `true` when `body` can take part in a fused scan.
+pub(crate) fn can_fuse(body: &PipelineBody) -> bool {
+    can_stream(&body.stages)
+}
+
+/// Runs every body in `bodies` over the rows found at `keys` under `root`
+/// in a single scan, returning one result per body in order. Rows are first
+/// run through the stages of `prefix`, if any, and only the rows it keeps
+/// reach `bodies`. Every body, and the prefix, must satisfy `can_fuse`.
+pub(crate) fn run_field_chain(
+    prefix: Option<&PipelineBody>,
+    bodies: &[&PipelineBody],
+    keys: &Arc<[Arc<str>]>,
+    root: &Val,
+    base_env: &Env,
+) -> Vec<Result<Val, EvalError>> {
+    let source = Source::FieldChain {
+        keys: Arc::clone(keys),
+    };
+    let recv = row_source::resolve(&source, root);
+    let prefix = prefix.map(|body| body.clone().with_source(source.clone()));
+    let pipelines: Vec<Pipeline> = bodies
+        .iter()
+        .map(|body| (*body).clone().with_source(source.clone()))
+        .collect();
+    run(
+        prefix.as_ref(),
+        &pipelines,
+        base_env,
+        row_source::source_iter(&recv),
+    )
+}
+
+/// Like `run_field_chain`, for a source that has already been resolved to
+/// `recv` (e.g. materialised from the tape because it is not an array).
+pub(crate) fn run_receiver(
+    prefix: Option<&PipelineBody>,
+    bodies: &[&PipelineBody],
+    recv: &Val,
+    base_env: &Env,
+) -> Vec<Result<Val, EvalError>> {
+    let source = Source::Receiver(Val::Null);
+    let prefix = prefix.map(|body| body.clone().with_source(source.clone()));
+    let pipelines: Vec<Pipeline> = bodies
+        .iter()
+        .map(|body| (*body).clone().with_source(source.clone()))
+        .collect();
+    run(
+        prefix.as_ref(),
+        &pipelines,
+        base_env,
+        row_source::source_iter(recv),
+    )
+}
+
+/// Like `run_field_chain`, but pulls the rows straight from a simd-json tape,
+/// materialising each row once for all bodies. Returns `None` when `keys` does
+/// not lead to an array. Every body must also be able to run without the
+/// document root, since `base_env` carries none.
+#[cfg(feature = "simd-json")]
+pub(crate) fn run_tape_field_chain(
+    prefix: Option<&PipelineBody>,
+    bodies: &[&PipelineBody],
+    tape: &crate::data::tape::TapeData,
+    keys: &[Arc<str>],
+    base_env: &Env,
+) -> Option<Vec<Result<Val, EvalError>>> {
+    let source = row_source::TapeRowSource::from_field_chain(tape, keys);
+    if !source.is_array_provider() {
+        return None;
+    }
+    let prefix = prefix.map(|body| body.clone().with_source(Source::Receiver(Val::Null)));
+    let pipelines: Vec<Pipeline> = bodies
+        .iter()
+        .map(|body| (*body).clone().with_source(Source::Receiver(Val::Null)))
+        .collect();
+    Some(run(
+        prefix.as_ref(),
+        &pipelines,
+        base_env,
+        source.iter_materialized(),
+    ))
+}
+
+/// Pushes each row from `rows` that survives `prefix` into a `StreamingRun`
+/// per pipeline until all of them are done, then collects their results. An
+/// error in the prefix becomes the result of every pipeline still running.
+fn run<I>(
+    prefix: Option<&Pipeline>,
+    pipelines: &[Pipeline],
+    base_env: &Env,
+    rows: I,
+) -> Vec<Result<Val, EvalError>>
+where
+    I: IntoIterator<Item = Val>,
+{
+    let mut prefix = prefix.map(|pipeline| StreamingRun::new(pipeline, base_env));
+    let mut runs: Vec<Option<StreamingRun<'_>>> = pipelines
+        .iter()
+        .map(|pipeline| Some(StreamingRun::new(pipeline, base_env)))
+        .collect();
+    let mut results: Vec<Option<Result<Val, EvalError>>> = std::iter::repeat_with(|| None)
+        .take(pipelines.len())
+        .collect();
+    let mut live = runs.len();
+
+    for item in rows {
+        if live == 0 {
+            break;
+        }
+        let flow = match prefix.as_mut() {
+            Some(run) => run.advance(item),
+            None => Ok(StageFlow::Continue(item)),
+        };
+        let item = match flow {
+            Ok(StageFlow::Continue(item)) => item,
+            Ok(StageFlow::SkipRow) => continue,
+            Ok(StageFlow::Stop | StageFlow::TerminalCollected) => break,
+            Err(err) => {
+                for (slot, run) in runs.iter_mut().enumerate() {
+                    if run.take().is_some() {
+                        results[slot] = Some(Err(err.clone()));
+                    }
+                }
+                break;
+            }
+        };
+        for (slot, run) in runs.iter_mut().enumerate() {
+            let Some(active) = run else {
+                continue;
+            };
+            let done = match active.push(item.clone()) {
+                Ok(false) => continue,
+                Ok(true) => Ok(run.take().expect("run is active").finish()),
+                Err(err) => {
+                    *run = None;
+                    Err(err)
+                }
+            };
+            results[slot] = Some(done);
+            live -= 1;
+        }
+    }
+
+    runs.into_iter()
+        .zip(results)
+        .map(|(run, result)| match (run, result) {
+            (_, Some(result)) => result,
+            (Some(run), None) => Ok(run.finish()),
+            (None, None) => unreachable!("finished runs record a result"),
+        })
+        .collect()
+}
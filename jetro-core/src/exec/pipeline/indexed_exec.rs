@@ -5,6 +5,7 @@
 use crate::{
     data::context::{Env, EvalError},
     data::value::Val,
+    exec::profile,
 };
 
 use crate::parse::chain_ir::PullDemand;
@@ -31,6 +32,7 @@ pub(super) fn run(
         },
     };
     if idx >= len {
+        profile::executor("indexed", 0..pipeline.stages.len(), 0, 0);
         return Some(Ok(Val::Null));
     }
 
@@ -59,5 +61,6 @@ pub(super) fn run(
         }
     }
 
+    profile::executor("indexed", 0..pipeline.stages.len(), 1, 1);
     Some(Ok(cur))
 }
@@ -8,18 +8,20 @@
 
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use crate::parse::ast::Expr;
 use crate::builtins::registry::{
     participates_in_demand, pipeline_materialization, pipeline_order_effect,
     pipeline_shape, BuiltinId,
 };
 use crate::builtins::{
-    BuiltinMethod, BuiltinPipelineMaterialization,
+    host, BuiltinMethod, BuiltinPipelineMaterialization,
     BuiltinPipelineOrderEffect, BuiltinSelectionPosition, BuiltinSinkAccumulator,
     BuiltinSinkDemand, BuiltinSinkSpec, BuiltinSinkValueNeed, BuiltinViewStage,
 };
 use crate::parse::chain_ir::{ChainOp, Demand as ChainDemand, PullDemand, ValueNeed};
-use crate::vm::{CompiledObjEntry, Opcode, Program};
+use crate::vm::{CompiledCall, CompiledObjEntry, Opcode, Program};
 
 use super::{BodyKernel, Pipeline, PipelineBody, Sink, Stage, ViewSinkCapability, ViewStageCapability};
 
@@ -423,6 +425,14 @@ impl Stage {
         )
     }
 
+    /// Returns `true` when this stage folds its rows into a keyed map
+    /// (`group_by`, `count_by`, `index_by`).
+    pub(crate) fn is_keyed_reducer(&self) -> bool {
+        self.descriptor()
+            .and_then(|desc| desc.method)
+            .is_some_and(|method| method.spec().keyed_reducer.is_some())
+    }
+
     /// Returns the `ViewStageCapability` for this stage at position `idx` in the kernel list,
     /// or `None` if the stage or its kernel cannot operate in the borrowed `ValueView` domain.
     pub(crate) fn view_capability(
@@ -688,7 +698,7 @@ impl Stage {
     }
 
     // Hard-coded overrides for CompiledMap (Preserves) and SortedDedup (Blocks).
-    fn pipeline_order_effect(&self) -> BuiltinPipelineOrderEffect {
+    pub(crate) fn pipeline_order_effect(&self) -> BuiltinPipelineOrderEffect {
         match self {
             Stage::CompiledMap(_) => BuiltinPipelineOrderEffect::Preserves,
             Stage::SortedDedup(_) => BuiltinPipelineOrderEffect::Blocks,
@@ -853,7 +863,7 @@ pub enum PhysicalExecPath {
 
 /// An optimised stage/sink plan produced by `plan_with_exprs`, ready for execution or further
 /// wrapping into a `Pipeline`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plan {
     /// Optimised, fused, and reordered stages.
     pub stages: Vec<Stage>,
@@ -937,7 +947,8 @@ pub(super) fn opcode_is_current_only(opcode: &Opcode) -> bool {
         | Opcode::CastOp(_)
         | Opcode::KindCheck { .. }
         | Opcode::SetCurrent
-        | Opcode::DeleteMarkErr => true,
+        | Opcode::DeleteMarkErr
+        | Opcode::NestingLimitErr(_) => true,
     }
 }
 
@@ -995,4 +1006,42 @@ impl Pipeline {
     pub fn source_demand(&self) -> SinkDemand {
         Self::segment_source_demand(&self.stages, &self.sink)
     }
+
+    /// Returns `true` when a stage or the sink, including those of compiled
+    /// sub-pipelines, calls a host function registered as impure.
+    pub(crate) fn calls_impure_host(stages: &[Stage], sink: &Sink) -> bool {
+        stages.iter().any(Stage::calls_impure_host)
+            || sink
+                .reducer_spec()
+                .is_some_and(|spec| spec.sink_programs().any(|prog| prog.any_call(&mut is_impure_host)))
+    }
+
+    /// Returns `true` when later stages or the sink would stop pulling rows
+    /// before they all reach a stage that calls an impure host function.
+    /// Such chains must run unfused, so every call the query makes happens.
+    pub(crate) fn limits_impure_stage(stages: &[Stage], sink: &Sink) -> bool {
+        stages.iter().enumerate().any(|(idx, stage)| {
+            let demand = Self::segment_source_demand(&stages[idx + 1..], sink);
+            (demand.chain.pull != PullDemand::All || demand.positional.is_some())
+                && stage.calls_impure_host()
+        })
+    }
+}
+
+impl Stage {
+    // Sub-pipelines of `CompiledMap` are checked stage by stage.
+    fn calls_impure_host(&self) -> bool {
+        match self {
+            Stage::CompiledMap(plan) => Pipeline::calls_impure_host(&plan.stages, &plan.sink),
+            _ => self
+                .body_program()
+                .is_some_and(|prog| prog.any_call(&mut is_impure_host)),
+        }
+    }
+}
+
+// Host calls compile to `BuiltinMethod::Unknown` and resolve against the active registry.
+fn is_impure_host(call: &CompiledCall) -> bool {
+    call.method == BuiltinMethod::Unknown
+        && host::lookup(&call.name).is_some_and(|function| !function.is_pure())
 }
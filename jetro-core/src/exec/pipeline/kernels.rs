@@ -7,6 +7,8 @@
 
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use crate::builtins::BuiltinCall;
 use crate::data::context::EvalError;
 use crate::util::JsonView;
@@ -14,7 +16,7 @@ use crate::data::value::Val;
 use crate::data::view::{scalar_view_to_owned_val, ValueView};
 
 /// Pre-classified stage body expression; variants are ordered least-to-most expensive, `Generic` re-enters the VM.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BodyKernel {
     /// Expression not classifiable into a faster form; falls back to full VM evaluation.
     Generic,
@@ -45,20 +47,29 @@ pub enum BodyKernel {
         /// The comparison operator.
         op: crate::parse::ast::BinOp,
         /// The literal right-hand side value.
+        #[serde(with = "crate::data::value::json_text")]
         lit: Val,
     },
     /// Short-circuits through a list of predicates, returning `false` on the first failure.
     And(Arc<[BodyKernel]>),
     /// Reads a single field and compares it to a literal in one fused step.
-    FieldCmpLit(Arc<str>, crate::parse::ast::BinOp, Val),
+    FieldCmpLit(
+        Arc<str>,
+        crate::parse::ast::BinOp,
+        #[serde(with = "crate::data::value::json_text")] Val,
+    ),
     /// Traverses a field chain and compares the result to a literal in one fused step.
-    FieldChainCmpLit(Arc<[Arc<str>]>, crate::parse::ast::BinOp, Val),
+    FieldChainCmpLit(
+        Arc<[Arc<str>]>,
+        crate::parse::ast::BinOp,
+        #[serde(with = "crate::data::value::json_text")] Val,
+    ),
     /// Compares the current element directly to a literal.
-    CurrentCmpLit(crate::parse::ast::BinOp, Val),
+    CurrentCmpLit(crate::parse::ast::BinOp, #[serde(with = "crate::data::value::json_text")] Val),
     /// Always produces the given boolean constant, regardless of the current element.
     ConstBool(bool),
     /// Always produces the given `Val` constant.
-    Const(Val),
+    Const(#[serde(with = "crate::data::value::json_text")] Val),
     /// Evaluates an interpolated format string by evaluating each part kernel.
     FString(FStringKernel),
     /// Evaluates an object literal by evaluating each field-value kernel.
@@ -66,7 +77,7 @@ pub enum BodyKernel {
 }
 
 /// Pre-classified kernel for a format-string expression, avoiding VM re-entry for each part.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FStringKernel {
     // ordered parts (literals and interpolated sub-kernels) that make up the format string
     parts: Arc<[FStringKernelPart]>,
@@ -75,7 +86,7 @@ pub struct FStringKernel {
 }
 
 /// A single part of an `FStringKernel`: either a fixed literal or a dynamic interpolation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FStringKernelPart {
     /// A constant string segment that is copied verbatim into the output.
     Lit(Arc<str>),
@@ -84,14 +95,14 @@ pub enum FStringKernelPart {
 }
 
 /// Pre-classified kernel for an object-literal expression; bypasses the VM's object-construction opcodes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectKernel {
     // ordered key/value entries that constitute the produced object
     entries: Arc<[ObjectKernelEntry]>,
 }
 
 /// A single key/value entry in an `ObjectKernel`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectKernelEntry {
     // key name in the produced object
     key: Arc<str>,
@@ -570,7 +581,7 @@ fn eval_native_kernel(kernel: &BodyKernel, item: &Val) -> Result<Val, EvalError>
         BodyKernel::BuiltinCall { receiver, call } => {
             let recv = eval_native_kernel(receiver, item)?;
             call.try_apply(&recv)?
-                .ok_or_else(|| EvalError(format!("{:?}: unsupported receiver", call.method)))
+                .ok_or_else(|| EvalError::unknown_method(format!("{:?}: unsupported receiver", call.method)))
         }
         BodyKernel::Compose { first, then } => {
             let recv = eval_native_kernel(first, item)?;
@@ -90,8 +90,12 @@ impl Pipeline {
     }
 
     /// Decodes `trailing` steps into stages and a sink, runs rewrite passes, and classifies body kernels.
+    /// Returns `None` for chains whose demand would skip impure host calls.
     pub(crate) fn lower_body_from_steps(trailing: &[crate::parse::ast::Step]) -> Option<PipelineBody> {
         let (stages, stage_exprs, sink) = decode_method_chain(trailing)?;
+        if Self::limits_impure_stage(&stages, &sink) {
+            return None;
+        }
         let mut p = PipelineBody {
             stages,
             stage_exprs,
@@ -133,11 +137,18 @@ impl Pipeline {
         Some(p)
     }
 
+    /// Returns `true` when `trailing` decodes to a chain whose demand would
+    /// skip impure host calls; see `Pipeline::limits_impure_stage`.
+    pub(crate) fn steps_limit_impure_stage(trailing: &[crate::parse::ast::Step]) -> bool {
+        decode_method_chain(trailing)
+            .is_some_and(|(stages, _, sink)| Self::limits_impure_stage(&stages, &sink))
+    }
+
     /// Returns `true` when `step` is a method call that can open a receiver-based pipeline without a field-chain prefix.
     pub(crate) fn is_receiver_pipeline_start(step: &crate::parse::ast::Step) -> bool {
         use crate::parse::ast::Step;
 
-        let Step::Method(name, args) = step else {
+        let Step::Method(name, args, _) = step else {
             return false;
         };
         is_receiver_pipeline_start_method(name.as_str(), args.len())
@@ -245,7 +256,7 @@ fn decode_method_chain(
     for (i, s) in trailing.iter().enumerate() {
         let is_last = i == trailing.len() - 1;
         match s {
-            Step::Method(name, args) => {
+            Step::Method(name, args, _) => {
                 if let Some(call) =
                     crate::builtins::BuiltinCall::from_pipeline_literal_args(name.as_str(), args)
                 {
@@ -295,6 +306,7 @@ fn rewrite_step(p: &mut PipelineBody) -> bool {
         }
     }
     if const_false_at.is_some() {
+        crate::plan::explain::record_pass("const_false_filter");
         p.stages.clear();
         p.stage_exprs.clear();
         return true;
@@ -327,6 +339,7 @@ fn rewrite_step(p: &mut PipelineBody) -> bool {
                     _ => None,
                 };
                 if let Some(keys) = chain {
+                    crate::plan::explain::record_pass("map_field_fusion");
                     let fcd = Arc::new(crate::vm::FieldChainData {
                         keys: keys.into(),
                         ics: (0..0)
@@ -344,6 +357,7 @@ fn rewrite_step(p: &mut PipelineBody) -> bool {
                 }
             }
             (Stage::Filter(p_prog, _), Stage::Filter(q_prog, _)) => {
+                crate::plan::explain::record_pass("filter_fusion");
                 let mut ops: Vec<Opcode> = p_prog.ops.as_ref().to_vec();
                 ops.push(Opcode::AndOp(Arc::clone(q_prog)));
                 let merged = Arc::new(crate::vm::Program {
@@ -365,6 +379,7 @@ fn rewrite_step(p: &mut PipelineBody) -> bool {
 
     for i in 0..p.stages.len().saturating_sub(1) {
         if matches!(&p.stages[i], Stage::Map(_, _)) && is_take_stage(&p.stages[i + 1]) {
+            crate::plan::explain::record_pass("take_before_map");
             p.stages.swap(i, i + 1);
             p.stage_exprs.swap(i, i + 1);
             return true;
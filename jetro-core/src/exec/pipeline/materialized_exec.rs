@@ -10,6 +10,7 @@ use std::sync::Arc;
 use crate::{
     data::context::{Env, EvalError},
     data::value::Val,
+    exec::{limits, profile},
 };
 
 use super::lower::run_compiled_map;
@@ -37,16 +38,15 @@ pub(super) fn run(pipeline: &Pipeline, root: &Val, base_env: &Env) -> Result<Val
 
     let mut sink_acc = SinkAccumulator::new(&pipeline.sink);
 
-    let needs_barrier = pipeline
-        .stages
-        .iter()
-        .any(Stage::requires_legacy_materialization);
-    if !needs_barrier {
+    if can_stream(&pipeline.stages) {
         return run_streaming_rows(pipeline, base_env, row_source::source_iter(&recv));
     }
 
+    let source_rows;
     let pre_iter: LegacyPreIter = {
         let mut buf: Vec<Val> = row_source::materialize_source(&recv);
+        source_rows = buf.len();
+        limits::check_len(source_rows)?;
         let strategies = compute_strategies_with_kernels(
             &pipeline.stages,
             &pipeline.stage_kernels,
@@ -64,6 +64,7 @@ pub(super) fn run(pipeline: &Pipeline, root: &Val, base_env: &Env) -> Result<Val
             if let Stage::CompiledMap(plan) = stage {
                 let mut out: Vec<Val> = Vec::with_capacity(buf.len());
                 for v in buf.into_iter() {
+                    limits::poll()?;
                     out.push(run_compiled_map(plan, v)?);
                 }
                 buf = out;
@@ -79,6 +80,8 @@ pub(super) fn run(pipeline: &Pipeline, root: &Val, base_env: &Env) -> Result<Val
                 strategy,
             ) {
                 applied?;
+                limits::check()?;
+                limits::check_len(buf.len())?;
                 continue;
             }
             unreachable!("descriptor-backed stage was not handled by materialized adapter")
@@ -90,6 +93,7 @@ pub(super) fn run(pipeline: &Pipeline, root: &Val, base_env: &Env) -> Result<Val
         if matches!(source_demand, PullDemand::FirstInput(n) if pulled_inputs >= n) {
             break 'outer;
         }
+        limits::poll()?;
         pulled_inputs += 1;
 
         let sink_done = match &pipeline.sink {
@@ -110,6 +114,12 @@ pub(super) fn run(pipeline: &Pipeline, root: &Val, base_env: &Env) -> Result<Val
         }
     }
 
+    profile::executor(
+        "materialized",
+        0..pipeline.stages.len(),
+        source_rows,
+        emitted_outputs,
+    );
     // group_by wraps its output in a single-element array; unwrap it so the caller sees the map
     let unwrap_single_collect_obj = pipeline
         .stages
@@ -127,11 +137,7 @@ pub(super) fn run_tape_field_chain(
     keys: &[Arc<str>],
     base_env: &Env,
 ) -> Option<Result<Val, EvalError>> {
-    if body
-        .stages
-        .iter()
-        .any(Stage::requires_legacy_materialization)
-    {
+    if !can_stream(&body.stages) {
         return None;
     }
     if !body.can_run_with_materialized_receiver() {
@@ -153,41 +159,134 @@ fn run_streaming_rows<I>(pipeline: &Pipeline, base_env: &Env, iter: I) -> Result
 where
     I: IntoIterator<Item = Val>,
 {
-    let mut vm = crate::vm::VM::new();
-    let mut loop_env = base_env.clone();
-    let source_demand = pipeline.source_demand().chain.pull;
-    let mut pulled_inputs: usize = 0;
-    let mut emitted_outputs: usize = 0;
-    let mut stage_taken: Vec<usize> = vec![0; pipeline.stages.len()];
-    let mut stage_skipped: Vec<usize> = vec![0; pipeline.stages.len()];
-    let mut sink_acc = SinkAccumulator::new(&pipeline.sink);
-    let terminal_map_idx = if matches!(pipeline.sink, Sink::Collect)
-        && pipeline
-            .stages
-            .last()
-            .is_some_and(Stage::can_use_terminal_map_collector)
-    {
-        pipeline.stages.len().checked_sub(1)
-    } else {
-        None
-    };
-    let terminal_map_kernel = terminal_map_idx.map(|idx| {
-        pipeline
-            .stage_kernels
-            .get(idx)
-            .unwrap_or(&BodyKernel::Generic)
-    });
-    let mut terminal_map_collect = terminal_map_kernel.map(TerminalMapCollector::new);
-
-    'outer: for mut item in iter {
-        if matches!(source_demand, PullDemand::FirstInput(n) if pulled_inputs >= n) {
-            break 'outer;
+    let mut run = StreamingRun::new(pipeline, base_env);
+    for item in iter {
+        if run.push(item)? {
+            break;
         }
-        if matches!(source_demand, PullDemand::NthInput(n) if pulled_inputs < n) {
-            pulled_inputs += 1;
-            continue 'outer;
+    }
+    Ok(run.finish())
+}
+
+/// Returns `true` when a pipeline of `stages` can run row by row through
+/// `StreamingRun`, i.e. no stage needs the whole source materialised first.
+/// Keyed reducers (`count_by`, `index_by`) are not barriers for the composed
+/// path, but the streaming adapters would pass their rows through untouched.
+pub(crate) fn can_stream(stages: &[Stage]) -> bool {
+    !stages
+        .iter()
+        .any(|stage| stage.requires_legacy_materialization() || stage.is_keyed_reducer())
+}
+
+/// One streaming execution of a pipeline whose rows are pushed in by the
+/// caller, so several pipelines over the same source can share a single scan.
+pub(crate) struct StreamingRun<'p> {
+    pipeline: &'p Pipeline,
+    vm: crate::vm::VM,
+    loop_env: Env,
+    source_demand: PullDemand,
+    pulled_inputs: usize,
+    emitted_outputs: usize,
+    stage_taken: Vec<usize>,
+    stage_skipped: Vec<usize>,
+    sink_acc: SinkAccumulator<'p>,
+    terminal_map_idx: Option<usize>,
+    terminal_map_collect: Option<TerminalMapCollector<'p>>,
+    // set once an `nth` pipeline has pulled exactly the row it selects
+    nth_input: Option<Val>,
+}
+
+impl<'p> StreamingRun<'p> {
+    /// Prepares a run of `pipeline`, which must satisfy `can_stream`.
+    pub(crate) fn new(pipeline: &'p Pipeline, base_env: &Env) -> Self {
+        let terminal_map_idx = if matches!(pipeline.sink, Sink::Collect)
+            && pipeline
+                .stages
+                .last()
+                .is_some_and(Stage::can_use_terminal_map_collector)
+        {
+            pipeline.stages.len().checked_sub(1)
+        } else {
+            None
+        };
+        let terminal_map_kernel = terminal_map_idx.map(|idx| {
+            pipeline
+                .stage_kernels
+                .get(idx)
+                .unwrap_or(&BodyKernel::Generic)
+        });
+        Self {
+            pipeline,
+            vm: crate::vm::VM::new(),
+            loop_env: base_env.clone(),
+            source_demand: pipeline.source_demand().chain.pull,
+            pulled_inputs: 0,
+            emitted_outputs: 0,
+            stage_taken: vec![0; pipeline.stages.len()],
+            stage_skipped: vec![0; pipeline.stages.len()],
+            sink_acc: SinkAccumulator::new(&pipeline.sink),
+            terminal_map_idx,
+            terminal_map_collect: terminal_map_kernel.map(TerminalMapCollector::new),
+            nth_input: None,
+        }
+    }
+
+    /// Feeds the next source row through the stages into the sink. Returns
+    /// `true` once the run needs no further rows.
+    pub(crate) fn push(&mut self, item: Val) -> Result<bool, EvalError> {
+        let pipeline = self.pipeline;
+        let item = match self.advance(item)? {
+            StageFlow::Continue(item) => item,
+            StageFlow::SkipRow => return Ok(false),
+            StageFlow::Stop | StageFlow::TerminalCollected => return Ok(true),
+        };
+
+        if matches!(self.source_demand, PullDemand::NthInput(_))
+            && matches!(pipeline.sink, Sink::Nth(_))
+        {
+            self.nth_input = Some(item);
+            return Ok(true);
         }
-        pulled_inputs += 1;
+
+        let sink_done = match &pipeline.sink {
+            Sink::Reducer(_) => match observe_reducer_item(
+                pipeline,
+                item,
+                &mut self.sink_acc,
+                &mut self.vm,
+                &mut self.loop_env,
+            )? {
+                ReducerItemFlow::Observed => false,
+                ReducerItemFlow::Skipped => return Ok(false),
+            },
+            _ => self.sink_acc.push(item),
+        };
+        if sink_done {
+            return Ok(true);
+        }
+        self.emitted_outputs += 1;
+        Ok(matches!(
+            self.source_demand,
+            PullDemand::UntilOutput(n) if self.emitted_outputs >= n
+        ))
+    }
+
+    /// Runs the next source row through the stages only, leaving the sink
+    /// untouched. `Continue` carries the row that reached the end of the
+    /// stages, `SkipRow` means it was dropped, and `Stop` (or
+    /// `TerminalCollected` for the last wanted row) means no further rows
+    /// are needed.
+    pub(crate) fn advance(&mut self, mut item: Val) -> Result<StageFlow<Val>, EvalError> {
+        let pipeline = self.pipeline;
+        if matches!(self.source_demand, PullDemand::FirstInput(n) if self.pulled_inputs >= n) {
+            return Ok(StageFlow::Stop);
+        }
+        limits::poll()?;
+        if matches!(self.source_demand, PullDemand::NthInput(n) if self.pulled_inputs < n) {
+            self.pulled_inputs += 1;
+            return Ok(StageFlow::SkipRow);
+        }
+        self.pulled_inputs += 1;
 
         for (stage_idx, stage) in pipeline.stages.iter().enumerate() {
             let kernel = pipeline
@@ -202,55 +301,87 @@ where
                     stage,
                     stage_idx,
                     item,
-                    &mut vm,
-                    &mut loop_env,
+                    &mut self.vm,
+                    &mut self.loop_env,
                     kernel,
-                    &mut stage_taken,
-                    &mut stage_skipped,
-                    terminal_map_idx,
-                    &mut terminal_map_collect,
+                    &mut self.stage_taken,
+                    &mut self.stage_skipped,
+                    self.terminal_map_idx,
+                    &mut self.terminal_map_collect,
                 )? {
                     StageFlow::Continue(next) => item = next,
-                    StageFlow::SkipRow => continue 'outer,
-                    StageFlow::Stop => break 'outer,
+                    StageFlow::SkipRow => return Ok(StageFlow::SkipRow),
+                    StageFlow::Stop => return Ok(StageFlow::Stop),
                     StageFlow::TerminalCollected => {
-                        emitted_outputs += 1;
-                        if matches!(source_demand, PullDemand::UntilOutput(n) if emitted_outputs >= n)
-                        {
-                            break 'outer;
-                        }
-                        continue 'outer;
+                        self.emitted_outputs += 1;
+                        let done = matches!(
+                            self.source_demand,
+                            PullDemand::UntilOutput(n) if self.emitted_outputs >= n
+                        );
+                        return Ok(if done {
+                            StageFlow::TerminalCollected
+                        } else {
+                            StageFlow::SkipRow
+                        });
                     }
                 },
             }
         }
+        Ok(StageFlow::Continue(item))
+    }
 
-        if matches!(source_demand, PullDemand::NthInput(_)) && matches!(pipeline.sink, Sink::Nth(_)) {
-            return Ok(item);
-        }
+    /// Hands back the sink state built from the rows pushed so far, for a
+    /// caller that merges several runs before finishing the sink once.
+    pub(crate) fn into_sink(self) -> SinkAccumulator<'p> {
+        self.sink_acc
+    }
 
-        let sink_done = match &pipeline.sink {
-            Sink::Reducer(_) => {
-                match observe_reducer_item(pipeline, item, &mut sink_acc, &mut vm, &mut loop_env)? {
-                    ReducerItemFlow::Observed => false,
-                    ReducerItemFlow::Skipped => continue 'outer,
-                }
-            }
-            _ => sink_acc.push(item),
-        };
-        if sink_done {
-            break 'outer;
+    /// Produces the pipeline's result from the rows pushed so far.
+    pub(crate) fn finish(self) -> Val {
+        let stages = 0..self.pipeline.stages.len();
+        if let Some(item) = self.nth_input {
+            profile::executor("streaming", stages, self.pulled_inputs, 1);
+            return item;
         }
-        emitted_outputs += 1;
-        if matches!(source_demand, PullDemand::UntilOutput(n) if emitted_outputs >= n) {
-            break 'outer;
+        profile::executor("streaming", stages, self.pulled_inputs, self.emitted_outputs);
+        if let Some(collector) = self.terminal_map_collect {
+            return collector.finish();
         }
+        self.sink_acc.finish(false)
     }
+}
 
-    if let Some(collector) = terminal_map_collect {
-        return Ok(collector.finish());
-    }
-    Ok(sink_acc.finish(false))
+/// Applies the barrier stage at `stage_idx` of `pipeline` to `buf`, as `run`
+/// does for each stage of a materialised pipeline.
+pub(super) fn apply_barrier_stage(
+    pipeline: &Pipeline,
+    stage_idx: usize,
+    buf: &mut Vec<Val>,
+    base_env: &Env,
+) -> Result<(), EvalError> {
+    let mut vm = crate::vm::VM::new();
+    let mut loop_env = base_env.clone();
+    let strategy = compute_strategies_with_kernels(
+        &pipeline.stages,
+        &pipeline.stage_kernels,
+        &pipeline.sink,
+    )
+    .get(stage_idx)
+    .copied()
+    .unwrap_or(StageStrategy::Default);
+    let kernel = pipeline
+        .stage_kernels
+        .get(stage_idx)
+        .unwrap_or(&BodyKernel::Generic);
+    apply_adapter_materialized(
+        &pipeline.stages[stage_idx],
+        buf,
+        &mut vm,
+        &mut loop_env,
+        kernel,
+        strategy,
+    )
+    .unwrap_or_else(|| unreachable!("barrier stage was not handled by materialized adapter"))
 }
 
 // barrier stages always produce a Vec<Val>, so only the Owned variant is needed here
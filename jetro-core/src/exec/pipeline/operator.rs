@@ -3,6 +3,8 @@
 
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use crate::parse::ast::Expr;
 use crate::builtins::BuiltinMethod;
 use crate::vm::Program;
@@ -10,7 +12,7 @@ use crate::vm::Program;
 use super::NumOp;
 
 /// Specification for a terminal reducer sink (`count`, `sum`, `avg`, `min`, `max`).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReducerSpec {
     /// The aggregation operation to perform.
     pub op: ReducerOp,
@@ -25,7 +27,7 @@ pub struct ReducerSpec {
 }
 
 /// The kind of reduction a `ReducerSpec` performs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReducerOp {
     /// Counts the number of (predicate-passing) rows.
     Count,
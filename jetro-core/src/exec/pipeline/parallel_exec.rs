@@ -0,0 +1,523 @@
+//! Parallel pipeline execution over large in-memory arrays.
+//!
+//! When the engine's `Parallelism` allows it, `run` splits the source rows
+//! (an array or the rows of an `ObjVec`) into contiguous partitions and runs
+//! each on its own thread through the same sequential executor the caller
+//! would have used for the whole source, then combines the partition results
+//! in source order. Running the sequential executor keeps its per-row
+//! semantics, such as a row whose `map` body fails being dropped or failing
+//! the query, identical to a sequential run. Only element-wise stages are
+//! split: a stage that `Blocks` order, or one whose output depends on the rows
+//! before it (`take`, `skip`, `take_while`, `unique`, ...), keeps the whole
+//! pipeline sequential, as does any call to an impure host function, since
+//! those run exactly as written. A keyed reducer (`group_by`, `count_by`,
+//! `index_by`) as the last stage is applied per partition and the resulting
+//! maps merged. `avg` and `approx_count_distinct` cannot combine finished
+//! results, so each partition streams its rows into its own copy of the sink
+//! and the running sums, counts and sketches are merged before finishing it
+//! once. When partition results cannot be combined into exactly the
+//! sequential result, `run` declines and the pipeline runs sequentially.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use crate::{
+    builtins::{
+        host, BuiltinDemandLaw, BuiltinMethod, BuiltinNumericReducer, BuiltinPipelineOrderEffect,
+        BuiltinSelectionPosition, BuiltinSinkAccumulator,
+    },
+    data::context::{Env, EvalError},
+    data::value::{ObjVecData, Val},
+    exec::{limits, parallel, profile},
+    parse::chain_ir::PullDemand,
+};
+
+use super::materialized_exec::{self, StreamingRun};
+use super::row_source;
+use super::sink_accumulator::SinkAccumulator;
+use super::{Pipeline, PipelineBody, Sink, Source, Stage};
+
+/// How a pipeline is split across partitions.
+#[derive(Clone, Copy)]
+pub(super) enum Split {
+    /// Every stage is element-wise and partition results combine by `Combine`.
+    Stream(Combine),
+    /// Element-wise stages followed by the keyed reducer `method`.
+    Keyed(BuiltinMethod),
+    /// Every stage is element-wise and the sink's accumulators merge (`avg`,
+    /// `approx_count_distinct`).
+    Fold,
+}
+
+/// How the finished results of consecutive partitions combine.
+#[derive(Clone, Copy)]
+pub(super) enum Combine {
+    /// Collected rows, concatenated.
+    Concat,
+    /// `count`, added.
+    Count,
+    /// `sum`, added.
+    Sum,
+    /// `min`, the smallest partition minimum.
+    Min,
+    /// `max`, the largest partition maximum.
+    Max,
+}
+
+/// Rows of a resolved source, readable by index from any worker.
+#[derive(Clone, Copy)]
+enum SourceRows<'a> {
+    Slice(&'a [Val]),
+    ObjVec(&'a ObjVecData),
+}
+
+impl<'a> SourceRows<'a> {
+    /// The rows in `range`, as a receiver for one partition; columnar rows
+    /// stay columnar.
+    fn partition(self, range: Range<usize>) -> Val {
+        match self {
+            Self::Slice(rows) => Val::arr(rows[range].to_vec()),
+            Self::ObjVec(data) => Val::ObjVec(Arc::new(data.slice_rows(range))),
+        }
+    }
+
+    /// The rows in `range`, one at a time.
+    fn rows(self, range: Range<usize>) -> impl Iterator<Item = Val> + 'a {
+        range.map(move |idx| match self {
+            Self::Slice(rows) => rows[idx].clone(),
+            Self::ObjVec(data) => data.row_val(idx),
+        })
+    }
+}
+
+/// Runs `pipeline` across worker threads when the active `Parallelism` and
+/// the pipeline's shape allow it; `None` means run it sequentially.
+/// `sequential` runs the pipeline, with its source replaced by the given
+/// rows, exactly as the caller would run it without parallelism; sinks that
+/// merge by state stream each partition under `base_env` instead.
+pub(crate) fn run(
+    pipeline: &Pipeline,
+    root: &Val,
+    base_env: &Env,
+    sequential: &(dyn Fn(Val) -> Result<Val, EvalError> + Sync),
+) -> Option<Result<Val, EvalError>> {
+    if !parallel::is_enabled() || limits::is_active() {
+        return None;
+    }
+    let split = split(pipeline)?;
+    let recv = row_source::resolve(&pipeline.source, root);
+    let rows;
+    let source = match &recv {
+        Val::ObjVec(data) => (data.nrows(), SourceRows::ObjVec(data)),
+        _ if row_source::row_count(&recv).is_some() => {
+            rows = row_source::array_like_rows(&recv)?;
+            let slice = rows.as_slice();
+            (slice.len(), SourceRows::Slice(slice))
+        }
+        _ => return None,
+    };
+    let (len, source) = source;
+    let partitions = parallel::current().partitions(len);
+    if partitions <= 1 {
+        return None;
+    }
+    let chunk = len.div_ceil(partitions);
+    let ranges: Vec<Range<usize>> = (0..len)
+        .step_by(chunk)
+        .map(|start| start..(start + chunk).min(len))
+        .collect();
+    let out = match split {
+        Split::Stream(combine) => finished_partials(&ranges, source, sequential)
+            .map(|results| combine_partials(combine, results)),
+        Split::Keyed(method) => finished_partials(&ranges, source, sequential)
+            .map(|results| merge_keyed_partials(method, results)),
+        Split::Fold => fold_partials(pipeline, base_env, &ranges, source).map(Some),
+    };
+    let out = match out {
+        Ok(out) => out?,
+        Err(err) => return Some(Err(err)),
+    };
+    profile::executor(
+        "parallel",
+        0..pipeline.stages.len(),
+        len,
+        out.array_len().unwrap_or(1),
+    );
+    Some(Ok(out))
+}
+
+/// Classifies `pipeline`, returning `None` when it must run sequentially.
+pub(super) fn split(pipeline: &Pipeline) -> Option<Split> {
+    if !matches!(pipeline.source_demand().chain.pull, PullDemand::All) {
+        return None;
+    }
+    let keyed = pipeline
+        .stages
+        .last()
+        .and_then(Stage::descriptor)
+        .and_then(|desc| desc.method)
+        .filter(|method| {
+            matches!(
+                method,
+                BuiltinMethod::GroupBy | BuiltinMethod::CountBy | BuiltinMethod::IndexBy
+            )
+        });
+    let head = match keyed {
+        Some(_) => &pipeline.stages[..pipeline.stages.len() - 1],
+        None => &pipeline.stages[..],
+    };
+    if !head.iter().all(is_element_wise) || Pipeline::calls_impure_host(&pipeline.stages, &pipeline.sink) {
+        return None;
+    }
+    match keyed {
+        Some(method) => keyed_sink_merges(&pipeline.sink).then_some(Split::Keyed(method)),
+        None => sink_combine(&pipeline.sink)
+            .map(Split::Stream)
+            .or_else(|| sink_folds(&pipeline.sink).then_some(Split::Fold)),
+    }
+}
+
+/// Returns `true` when `stage` maps each row independently of the rows
+/// around it, so partitions can run it without seeing each other.
+fn is_element_wise(stage: &Stage) -> bool {
+    if matches!(stage, Stage::CompiledMap(_)) {
+        return true;
+    }
+    if stage.requires_legacy_materialization()
+        || stage.pipeline_order_effect() == BuiltinPipelineOrderEffect::Blocks
+    {
+        return false;
+    }
+    let Some(desc) = stage.descriptor() else {
+        return false;
+    };
+    desc.usize_arg.is_none()
+        && desc.method.is_some_and(|method| {
+            matches!(
+                method.spec().demand_law,
+                BuiltinDemandLaw::Identity
+                    | BuiltinDemandLaw::FilterLike
+                    | BuiltinDemandLaw::MapLike
+            )
+        })
+}
+
+/// Returns how finished results of `sink` over consecutive stretches of
+/// rows combine into its result over all of them, or `None` when they do
+/// not (`avg`, `approx_count_distinct`, `first`, `last`, `nth`).
+fn sink_combine(sink: &Sink) -> Option<Combine> {
+    match sink.builtin_sink_spec().map(|spec| spec.accumulator) {
+        Some(BuiltinSinkAccumulator::Count) => Some(Combine::Count),
+        Some(BuiltinSinkAccumulator::Numeric) => {
+            match sink.reducer_spec()?.method()?.spec().numeric_reducer? {
+                BuiltinNumericReducer::Sum => Some(Combine::Sum),
+                BuiltinNumericReducer::Min => Some(Combine::Min),
+                BuiltinNumericReducer::Max => Some(Combine::Max),
+                BuiltinNumericReducer::Avg => None,
+            }
+        }
+        Some(_) => None,
+        None => matches!(sink, Sink::Collect).then_some(Combine::Concat),
+    }
+}
+
+/// Returns `true` for sinks whose finished results do not combine but whose
+/// accumulators do: `avg` keeps a sum and count, `approx_count_distinct` a
+/// sketch.
+fn sink_folds(sink: &Sink) -> bool {
+    match sink.builtin_sink_spec().map(|spec| spec.accumulator) {
+        Some(BuiltinSinkAccumulator::ApproxDistinct) => true,
+        Some(BuiltinSinkAccumulator::Numeric) => matches!(
+            sink.reducer_spec()
+                .and_then(|spec| spec.method())
+                .and_then(|method| method.spec().numeric_reducer),
+            Some(BuiltinNumericReducer::Avg)
+        ),
+        _ => false,
+    }
+}
+
+/// Returns `true` for the sinks keyed reducers lower with: the map itself,
+/// either collected and unwrapped or selected as the first row.
+fn keyed_sink_merges(sink: &Sink) -> bool {
+    matches!(sink, Sink::Collect)
+        || matches!(
+            sink.builtin_sink_spec().map(|spec| spec.accumulator),
+            Some(BuiltinSinkAccumulator::SelectOne(
+                BuiltinSelectionPosition::First
+            ))
+        )
+}
+
+/// Runs `work` once per range, the first on the calling thread and the rest
+/// on scoped workers, returning the results in range order. Every range runs
+/// sequentially and outside the active profile, which records the parallel
+/// run as a whole; workers see the calling thread's host functions.
+fn run_partitions<T, F>(ranges: &[Range<usize>], work: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(Range<usize>) -> T + Sync,
+{
+    let registry = host::active();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = ranges[1..]
+            .iter()
+            .map(|range| {
+                let (work, registry) = (&work, registry.as_ref());
+                scope.spawn(move || {
+                    let _host = registry.map(host::enter);
+                    work(range.clone())
+                })
+            })
+            .collect();
+        let mut out = Vec::with_capacity(ranges.len());
+        out.push(profile::paused(|| {
+            let _sequential = parallel::enter(&parallel::Parallelism::new());
+            work(ranges[0].clone())
+        }));
+        out.extend(workers.into_iter().map(|worker| match worker.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }));
+        out
+    })
+}
+
+/// Runs `sequential` over each partition, returning the finished results in
+/// partition order or the first partition's error.
+fn finished_partials(
+    ranges: &[Range<usize>],
+    source: SourceRows<'_>,
+    sequential: &(dyn Fn(Val) -> Result<Val, EvalError> + Sync),
+) -> Result<Vec<Val>, EvalError> {
+    run_partitions(ranges, |range| sequential(source.partition(range)))
+        .into_iter()
+        .collect()
+}
+
+/// Streams each partition's rows through the pipeline into its own sink
+/// accumulator, then merges the accumulators in partition order and
+/// finishes the sink once.
+fn fold_partials(
+    pipeline: &Pipeline,
+    base_env: &Env,
+    ranges: &[Range<usize>],
+    source: SourceRows<'_>,
+) -> Result<Val, EvalError> {
+    let partials = run_partitions(ranges, |range| {
+        let mut run = StreamingRun::new(pipeline, base_env);
+        for row in source.rows(range) {
+            if run.push(row)? {
+                break;
+            }
+        }
+        Ok::<_, EvalError>(run.into_sink())
+    });
+    let mut merged: Option<SinkAccumulator<'_>> = None;
+    for partial in partials {
+        let partial = partial?;
+        match &mut merged {
+            Some(acc) => acc.merge(partial),
+            None => merged = Some(partial),
+        }
+    }
+    Ok(match merged {
+        Some(acc) => acc.finish(false),
+        None => SinkAccumulator::new(&pipeline.sink).finish(false),
+    })
+}
+
+/// Combines the finished results of consecutive partitions, or returns
+/// `None` when one of them does not have the shape `combine` expects.
+fn combine_partials(combine: Combine, partials: Vec<Val>) -> Option<Val> {
+    match combine {
+        Combine::Concat => {
+            let mut rows = Vec::new();
+            for partial in partials {
+                rows.extend(partial.into_vals().ok()?);
+            }
+            Some(Val::arr(rows))
+        }
+        Combine::Count => {
+            let mut count = 0i64;
+            for partial in partials {
+                let Val::Int(n) = partial else {
+                    return None;
+                };
+                count = count.checked_add(n)?;
+            }
+            Some(Val::Int(count))
+        }
+        Combine::Sum => {
+            let mut sum = Val::Int(0);
+            for partial in partials {
+                sum = match (sum, partial) {
+                    (Val::Int(a), Val::Int(b)) => Val::Int(a.checked_add(b)?),
+                    (a, b) => Val::Float(as_f64(&a)? + as_f64(&b)?),
+                };
+            }
+            Some(sum)
+        }
+        Combine::Min | Combine::Max => {
+            let mut best: Option<Val> = None;
+            for partial in partials {
+                if partial.is_null() {
+                    continue;
+                }
+                let candidate = as_f64(&partial)?;
+                let replace = match &best {
+                    None => true,
+                    Some(seen) if matches!(combine, Combine::Min) => candidate < as_f64(seen)?,
+                    Some(seen) => candidate > as_f64(seen)?,
+                };
+                if replace {
+                    best = Some(partial);
+                }
+            }
+            Some(best.unwrap_or(Val::Null))
+        }
+    }
+}
+
+/// The value of a numeric result as `f64`; `None` for anything else.
+fn as_f64(value: &Val) -> Option<f64> {
+    match value {
+        Val::Int(n) => Some(*n as f64),
+        Val::Float(x) => Some(*x),
+        _ => None,
+    }
+}
+
+/// Merges the keyed maps partitions produced, in order. Each partition's
+/// result is the map or, on executors that collect it, a one-element array
+/// holding it; the merged map keeps that shape. Returns `None` when the
+/// partitions disagree on the shape.
+fn merge_keyed_partials(method: BuiltinMethod, partials: Vec<Val>) -> Option<Val> {
+    let mut wrapped = None;
+    let mut merged: IndexMap<Arc<str>, Val> = IndexMap::new();
+    for partial in partials {
+        let (map, is_wrapped) = match partial {
+            Val::Obj(map) => (map, false),
+            Val::Arr(rows) => match rows.as_slice() {
+                [Val::Obj(map)] => (Arc::clone(map), true),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        if *wrapped.get_or_insert(is_wrapped) != is_wrapped {
+            return None;
+        }
+        let map = Arc::try_unwrap(map).unwrap_or_else(|map| (*map).clone());
+        for (key, value) in map {
+            match merged.get_mut(&key) {
+                Some(seen) => merge_keyed(method, seen, value),
+                None => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    let map = Val::obj(merged);
+    Some(if wrapped == Some(true) {
+        Val::arr(vec![map])
+    } else {
+        map
+    })
+}
+
+/// A pipeline ending in a keyed reducer, run over independent chunks of rows
+/// whose maps are merged in the order they are handed to `merge`; used by
+/// `stream_exec` for streamed rows.
+pub(super) struct KeyedRun<'p> {
+    pipeline: &'p Pipeline,
+    method: BuiltinMethod,
+    head: Pipeline,
+    merged: IndexMap<Arc<str>, Val>,
+}
+
+impl<'p> KeyedRun<'p> {
+    /// Prepares `pipeline`, whose last stage is the keyed reducer `method`.
+    pub(super) fn new(pipeline: &'p Pipeline, method: BuiltinMethod) -> Self {
+        let keyed_idx = pipeline.stages.len() - 1;
+        let head = PipelineBody {
+            stages: pipeline.stages[..keyed_idx].to_vec(),
+            stage_exprs: pipeline.stage_exprs[..keyed_idx].to_vec(),
+            sink: Sink::Collect,
+            stage_kernels: pipeline.stage_kernels
+                [..keyed_idx.min(pipeline.stage_kernels.len())]
+                .to_vec(),
+            sink_kernels: Vec::new(),
+        }
+        .with_source(Source::Receiver(Val::Null));
+        Self {
+            pipeline,
+            method,
+            head,
+            merged: IndexMap::new(),
+        }
+    }
+
+    /// Runs the head and the keyed reducer over one partition's `rows`.
+    pub(super) fn partition(
+        &self,
+        rows: impl Iterator<Item = Val>,
+        base_env: &Env,
+    ) -> Result<Option<Val>, EvalError> {
+        let mut run = StreamingRun::new(&self.head, base_env);
+        for item in rows {
+            if run.push(item)? {
+                break;
+            }
+        }
+        let mut buf = run.finish().into_vals().unwrap_or_default();
+        let keyed_idx = self.pipeline.stages.len() - 1;
+        materialized_exec::apply_barrier_stage(self.pipeline, keyed_idx, &mut buf, base_env)?;
+        Ok(buf.into_iter().next())
+    }
+
+    /// Folds the map of the next partition into the maps merged so far.
+    pub(super) fn merge(&mut self, partial: Option<Val>) {
+        let Some(Val::Obj(map)) = partial else {
+            return;
+        };
+        let map = Arc::try_unwrap(map).unwrap_or_else(|map| (*map).clone());
+        for (key, value) in map {
+            match self.merged.get_mut(&key) {
+                Some(seen) => merge_keyed(self.method, seen, value),
+                None => {
+                    self.merged.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Hands the merged map to the pipeline's sink.
+    pub(super) fn finish(self) -> Val {
+        // a collected keyed reducer yields its map, not a one-element array
+        let mut sink = SinkAccumulator::new(&self.pipeline.sink);
+        sink.push(Val::obj(self.merged));
+        sink.finish(true)
+    }
+}
+
+/// Folds a later partition's `value` for one key into the `seen` value.
+fn merge_keyed(method: BuiltinMethod, seen: &mut Val, value: Val) {
+    match (method, &mut *seen, value) {
+        (BuiltinMethod::CountBy, Val::Int(count), Val::Int(more)) => *count += more,
+        (BuiltinMethod::GroupBy, Val::Arr(bucket), Val::Arr(more)) => {
+            Arc::make_mut(bucket).extend(more.iter().cloned());
+        }
+        (BuiltinMethod::GroupBy, bucket, more)
+            if bucket.array_len().is_some() && more.array_len().is_some() =>
+        {
+            let mut rows = std::mem::replace(bucket, Val::Null)
+                .into_vals()
+                .unwrap_or_default();
+            rows.extend(more.into_vals().unwrap_or_default());
+            *bucket = Val::arr(rows);
+        }
+        (_, seen, value) => *seen = value,
+    }
+}
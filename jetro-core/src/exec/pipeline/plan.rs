@@ -288,22 +288,27 @@ fn reorder_filter_runs(
             j += 1;
         }
         if j - i >= 2 {
-            let mut run: Vec<(Stage, Option<Arc<Expr>>, BodyKernel)> = Vec::with_capacity(j - i);
+            let mut run: Vec<(usize, Stage, Option<Arc<Expr>>, BodyKernel)> =
+                Vec::with_capacity(j - i);
             for idx in i..j {
                 run.push((
+                    idx,
                     stages[idx].clone(),
                     exprs[idx].clone(),
                     kernels[idx].clone(),
                 ));
             }
             run.sort_by(|a, b| {
-                let (ca, sa) = kernel_cost_selectivity(&a.0, &a.2);
-                let (cb, sb) = kernel_cost_selectivity(&b.0, &b.2);
+                let (ca, sa) = kernel_cost_selectivity(&a.1, &a.3);
+                let (cb, sb) = kernel_cost_selectivity(&b.1, &b.3);
                 let ra = ca / (1.0 - sa).max(1e-6);
                 let rb = cb / (1.0 - sb).max(1e-6);
                 ra.partial_cmp(&rb).unwrap_or(std::cmp::Ordering::Equal)
             });
-            for (idx, (s, e, k)) in run.into_iter().enumerate() {
+            if run.iter().enumerate().any(|(idx, entry)| entry.0 != i + idx) {
+                crate::plan::explain::record_pass("reorder_filters");
+            }
+            for (idx, (_, s, e, k)) in run.into_iter().enumerate() {
                 stages[i + idx] = s;
                 exprs[i + idx] = e;
                 kernels[i + idx] = k;
@@ -330,6 +335,7 @@ fn fuse_filter_runs(
             continue;
         }
 
+        crate::plan::explain::record_pass("filter_fusion");
         let merged = merge_filter_programs(&stages[i..j]);
         let merged_kernel = BodyKernel::classify(&merged);
         stages[i] = Stage::Filter(merged, BuiltinViewStage::Filter);
@@ -374,6 +380,7 @@ fn fold_merge_with_kernels(
         if matches!(&stages[i], Stage::Filter(_, _))
             && matches!(kernels.get(i), Some(BodyKernel::ConstBool(true)))
         {
+            crate::plan::explain::record_pass("drop_true_filter");
             stages.remove(i);
             exprs.remove(i);
             kernels.remove(i);
@@ -385,6 +392,7 @@ fn fold_merge_with_kernels(
     let mut i = 0;
     while i + 1 < stages.len() {
         if stages[i].cancels_with(&stages[i + 1]) {
+            crate::plan::explain::record_pass("cancel_stages");
             stages.drain(i..=i + 1);
             exprs.drain(i..=i + 1);
             kernels.drain(i..=i + 1);
@@ -392,6 +400,7 @@ fn fold_merge_with_kernels(
             continue;
         }
         if let Some(merged) = stages[i].merge_with(&stages[i + 1]) {
+            crate::plan::explain::record_pass("merge_stages");
             stages[i] = merged;
             stages.remove(i + 1);
             exprs[i] = None;
@@ -58,6 +58,28 @@ impl ReducerAccumulator {
         }
     }
 
+    /// Folds the running statistics of `other`, accumulated over later rows
+    /// under the same spec, into this accumulator.
+    pub(crate) fn merge(&mut self, other: Self) {
+        self.count += other.count;
+        if self.sum_floated || other.sum_floated {
+            let total = |acc: &Self| {
+                if acc.sum_floated {
+                    acc.sum_f
+                } else {
+                    acc.sum_i as f64
+                }
+            };
+            self.sum_f = total(self) + total(&other);
+            self.sum_floated = true;
+        } else {
+            self.sum_i = self.sum_i.wrapping_add(other.sum_i);
+        }
+        self.min_f = self.min_f.min(other.min_f);
+        self.max_f = self.max_f.max(other.max_f);
+        self.n_obs += other.n_obs;
+    }
+
     /// Consumes the accumulator and returns the final aggregate `Val`.
     pub(crate) fn finish(self) -> Val {
         match self.spec.op {
@@ -6,6 +6,7 @@
 use crate::{
     builtins::{BuiltinSelectionPosition, BuiltinSinkAccumulator},
     data::value::Val,
+    exec::limits,
 };
 
 use super::{ReducerAccumulator, Sink};
@@ -50,10 +51,7 @@ impl<'a> SinkAccumulator<'a> {
             return self.observe_builtin(spec.accumulator, item);
         }
         match self.sink {
-            Sink::Collect => {
-                self.observe_collect(item);
-                false
-            }
+            Sink::Collect => self.observe_collect(item),
             Sink::Reducer(_) => {
                 self.observe_reducer(&item);
                 false
@@ -118,9 +116,11 @@ impl<'a> SinkAccumulator<'a> {
         }
     }
 
-    /// Appends `item` to the collect buffer for later array construction.
-    pub(crate) fn observe_collect(&mut self, item: Val) {
+    /// Appends `item` to the collect buffer for later array construction;
+    /// returns `true` once the buffer outgrows `max_array_len`.
+    pub(crate) fn observe_collect(&mut self, item: Val) -> bool {
         self.collect.push(item);
+        limits::check_len(self.collect.len()).is_err()
     }
 
     /// Forwards `item` into the numeric reducer accumulator if one is present.
@@ -192,6 +192,18 @@ impl<'a> SinkAccumulator<'a> {
         self.observe_reducer(numeric_item);
     }
 
+    /// Folds `other`, which observed rows after this accumulator's under the
+    /// same sink, into this one. Only the reducer and HyperLogLog state
+    /// merge; the collect buffer and selected rows of `other` are dropped.
+    pub(crate) fn merge(&mut self, other: Self) {
+        if let (Some(reducer), Some(more)) = (&mut self.reducer, other.reducer) {
+            reducer.merge(more);
+        }
+        for (reg, more) in self.hll.iter_mut().zip(other.hll) {
+            *reg = (*reg).max(more);
+        }
+    }
+
     /// Consumes the accumulator and produces the final `Val` according to the sink kind.
     pub(crate) fn finish(self, unwrap_single_collect_obj: bool) -> Val {
         if let Some(spec) = self.sink.builtin_sink_spec() {
@@ -0,0 +1,77 @@
+//! Pipelines over rows pulled one at a time from an external stream.
+//!
+//! `RootStream` covers queries of the form `$.<method>...` whose source is
+//! the whole input, such as the records of an NDJSON stream, so the rows
+//! never have to be collected into an array. Streamable stages push each row
+//! through a single `StreamingRun` and stop reading as soon as the sink is
+//! satisfied. A trailing keyed reducer (`group_by`, `count_by`, `index_by`)
+//! after element-wise stages is applied to fixed-size chunks of rows, and the
+//! chunk maps are merged as `parallel_exec` merges its partitions.
+
+use crate::{
+    builtins::BuiltinMethod,
+    data::context::{Env, EvalError},
+    data::value::Val,
+    parse::ast::{Expr, Step},
+};
+
+use super::materialized_exec::{can_stream, StreamingRun};
+use super::parallel_exec::{self, KeyedRun, Split};
+use super::{Pipeline, Source};
+
+/// Rows per chunk handed to a keyed reducer at a time.
+const KEYED_CHUNK_ROWS: usize = 4096;
+
+/// A root query lowered to run over a stream of rows bound as `$`.
+pub(crate) struct RootStream {
+    pipeline: Pipeline,
+    keyed: Option<BuiltinMethod>,
+}
+
+impl RootStream {
+    /// Lowers `expr` when it is a method chain on `$` that can consume its
+    /// rows in order without holding all of them; `None` means the caller
+    /// must materialise the stream and evaluate `expr` over the array.
+    pub(crate) fn lower(expr: &Expr) -> Option<Self> {
+        let Expr::Chain(base, steps) = expr else {
+            return None;
+        };
+        if !matches!(**base, Expr::Root) || !matches!(steps.first(), Some(Step::Method(..))) {
+            return None;
+        }
+        let body = Pipeline::lower_body_from_steps(steps)?;
+        // stage bodies that read `$` would need the whole stream
+        if !body.can_run_with_materialized_receiver() {
+            return None;
+        }
+        let pipeline = body.with_source(Source::Receiver(Val::Null));
+        let keyed = match parallel_exec::split(&pipeline) {
+            Some(Split::Keyed(method)) => Some(method),
+            _ if can_stream(&pipeline.stages) => None,
+            _ => return None,
+        };
+        Some(Self { pipeline, keyed })
+    }
+
+    /// Runs the pipeline over `rows`, pulling no more of them than the sink needs.
+    pub(crate) fn run(&self, rows: impl Iterator<Item = Val>) -> Result<Val, EvalError> {
+        let base_env = Env::new(Val::Null);
+        let Some(method) = self.keyed else {
+            let mut run = StreamingRun::new(&self.pipeline, &base_env);
+            for item in rows {
+                if run.push(item)? {
+                    break;
+                }
+            }
+            return Ok(run.finish());
+        };
+        let mut keyed = KeyedRun::new(&self.pipeline, method);
+        let mut rows = rows.peekable();
+        while rows.peek().is_some() {
+            let chunk = rows.by_ref().take(KEYED_CHUNK_ROWS);
+            let partial = keyed.partition(chunk, &base_env)?;
+            keyed.merge(partial);
+        }
+        Ok(keyed.finish())
+    }
+}
@@ -82,9 +82,15 @@ pub(super) fn normalize_symbolic(
             }
             _ if stage.is_symbolic_filter_stage() => {
                 if let Some(expr) = expr.as_ref().filter(|e| is_pure_expr(e)) {
+                    if !matches!(out.item, Expr::Current) {
+                        crate::plan::explain::record_pass("filter_through_map");
+                    }
                     let pred = simplify_expr(substitute_current(expr, &out.item));
                     out.predicate = Some(match out.predicate.take() {
-                        Some(prev) => and_expr(prev, pred),
+                        Some(prev) => {
+                            crate::plan::explain::record_pass("filter_fusion");
+                            and_expr(prev, pred)
+                        }
                         None => pred,
                     });
                 } else {
@@ -100,8 +106,10 @@ pub(super) fn normalize_symbolic(
                 if out.demand.order || suffix_needs_order(&in_stages[idx + 1..]) {
                     out.flush_all();
                     out.push_stage(stage, expr);
+                } else {
+                    // stage dropped: order is not required by any downstream consumer
+                    crate::plan::explain::record_pass("drop_unused_order");
                 }
-                // stage dropped: order is not required by any downstream consumer
             }
             _ if stage.can_drop_when_value_unused()
                 && out.demand.value == ValueDemand::None
@@ -109,6 +117,7 @@ pub(super) fn normalize_symbolic(
                 && !suffix_consumes_value(&in_stages[idx + 1..]) =>
             {
                 // stage dropped: value is never observed by any downstream consumer
+                crate::plan::explain::record_pass("drop_unused_value");
             }
             other => {
                 out.flush_all();
@@ -197,6 +206,7 @@ impl SymbolicEmitter {
                     && spec.projection.is_none()
                     && !matches!(self.item, Expr::Current) =>
             {
+                crate::plan::explain::record_pass("map_into_reducer");
                 let item = simplify_expr(std::mem::replace(&mut self.item, Expr::Current));
                 spec.projection = Some(compile_stage_expr(&item));
             }
@@ -384,8 +394,9 @@ fn simplify_expr(expr: Expr) -> Expr {
             body: Box::new(simplify_expr(*body)),
             default: Box::new(simplify_expr(*default)),
         },
-        Expr::GlobalCall { name, args } => Expr::GlobalCall {
+        Expr::GlobalCall { name, args, span } => Expr::GlobalCall {
             name,
+            span,
             args: args
                 .into_iter()
                 .map(|arg| match arg {
@@ -503,8 +514,8 @@ fn project_array_index(elems: &[ArrayElem], idx: i64) -> Option<Expr> {
 fn simplify_step(step: Step) -> Step {
     match step {
         Step::DynIndex(e) => Step::DynIndex(Box::new(simplify_expr(*e))),
-        Step::Method(name, args) => Step::Method(name, simplify_args(args)),
-        Step::OptMethod(name, args) => Step::OptMethod(name, simplify_args(args)),
+        Step::Method(name, args, span) => Step::Method(name, simplify_args(args), span),
+        Step::OptMethod(name, args, span) => Step::OptMethod(name, simplify_args(args), span),
         Step::InlineFilter(e) => Step::InlineFilter(Box::new(simplify_expr(*e))),
         step => step,
     }
@@ -674,8 +685,9 @@ fn substitute_current(expr: &Expr, replacement: &Expr) -> Expr {
             body: Box::new(substitute_current(body, replacement)),
             default: Box::new(substitute_current(default, replacement)),
         },
-        Expr::GlobalCall { name, args } => Expr::GlobalCall {
+        Expr::GlobalCall { name, args, span } => Expr::GlobalCall {
             name: name.clone(),
+            span: *span,
             args: args
                 .iter()
                 .map(|arg| substitute_current_arg(arg, replacement))
@@ -706,17 +718,19 @@ fn substitute_current(expr: &Expr, replacement: &Expr) -> Expr {
 fn substitute_current_step(step: &Step, replacement: &Expr) -> Step {
     match step {
         Step::DynIndex(e) => Step::DynIndex(Box::new(substitute_current(e, replacement))),
-        Step::Method(name, args) => Step::Method(
+        Step::Method(name, args, span) => Step::Method(
             name.clone(),
             args.iter()
                 .map(|arg| substitute_current_arg(arg, replacement))
                 .collect(),
+            *span,
         ),
-        Step::OptMethod(name, args) => Step::OptMethod(
+        Step::OptMethod(name, args, span) => Step::OptMethod(
             name.clone(),
             args.iter()
                 .map(|arg| substitute_current_arg(arg, replacement))
                 .collect(),
+            *span,
         ),
         Step::InlineFilter(e) => Step::InlineFilter(Box::new(substitute_current(e, replacement))),
         _ => step.clone(),
@@ -773,7 +787,7 @@ fn is_pure_expr(expr: &Expr) -> bool {
             is_pure_expr(base)
                 && steps.iter().all(|step| match step {
                     Step::DynIndex(e) | Step::InlineFilter(e) => is_pure_expr(e),
-                    Step::Method(_, args) | Step::OptMethod(_, args) => {
+                    Step::Method(_, args, _) | Step::OptMethod(_, args, _) => {
                         args.iter().all(is_pure_arg)
                     }
                     _ => true,
@@ -0,0 +1,570 @@
+//! `EXPLAIN ANALYZE` support: runtime numbers for one evaluated query.
+//!
+//! `JetroEngine::collect_profiled` installs a recorder on the current thread
+//! and runs the plan as usual. `ExecCtx::eval_fast` wraps every physical node
+//! in a `NodeProbe` that notes which backends declined, which one ran, the
+//! time spent, and which document representations were built meanwhile. The
+//! pipeline executors report the stages they ran and the rows that crossed
+//! them through `executor`, and planned executors that missed through
+//! `executor_declined`.
+//!
+//! Every hook is a thread-local check when no recorder is installed, so the
+//! unprofiled path pays nothing else.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::ir::physical::{BackendPreference, NodeId, QueryPlan, QueryRoot};
+use crate::plan::explain::{backend_name, kind_name, node_outline};
+use crate::Jetro;
+
+/// Runtime profile of one query, as returned by `JetroEngine::collect_profiled`.
+/// `Display` prints an indented tree; `Serialize` yields the same information
+/// as structured data.
+///
+/// ```rust
+/// use jetro_core::{Jetro, JetroEngine};
+///
+/// let engine = JetroEngine::new();
+/// let j = Jetro::from_bytes(br#"{"books":[{"price":12},{"price":3}]}"#.to_vec()).unwrap();
+/// let (value, profile) = engine
+///     .collect_profiled(&j, "$.books.filter(price > 5).count()")
+///     .unwrap();
+/// assert_eq!(value, serde_json::json!(1));
+/// let pipeline = profile.root.pipeline.as_ref().unwrap();
+/// assert_eq!(pipeline.rows_pulled, 2);
+/// println!("{profile}");
+/// ```
+#[derive(Clone, Debug, Serialize)]
+pub struct Profile {
+    /// Input representation the plan was built for: `"bytes"` or `"val"`.
+    pub input: &'static str,
+    /// Wall-clock time for the whole evaluation, in nanoseconds.
+    pub elapsed_ns: u64,
+    /// Document representations the query caused to be built.
+    pub materialized: ProfileMaterialization,
+    /// Root of the plan tree, annotated with what happened at run time.
+    pub root: ProfileNode,
+}
+
+/// Document representations built while a query or node ran. A flag is only
+/// set when the representation did not exist beforehand.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ProfileMaterialization {
+    /// The simd-json tape was parsed.
+    pub tape: bool,
+    /// The structural bitmap index was built.
+    pub structural_index: bool,
+    /// The whole document was materialised as a `Val` tree.
+    pub val: bool,
+    /// Number of tape subtrees converted into `Val`s.
+    pub tape_subtrees: usize,
+}
+
+/// One node of a `Profile` tree. Times and materialisation include the
+/// node's children.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileNode {
+    /// `PlanNode` variant name, as in `ExplainNode::kind`.
+    pub kind: &'static str,
+    /// Variant-specific detail, as in `ExplainNode::detail`.
+    pub detail: Option<String>,
+    /// Backend that produced the node's value; `None` if the node never ran.
+    pub backend: Option<&'static str>,
+    /// Preferred backends that declined the node before `backend` ran.
+    pub fallbacks: Vec<&'static str>,
+    /// Number of times the node was evaluated.
+    pub calls: u64,
+    /// Time spent evaluating the node, in nanoseconds.
+    pub elapsed_ns: u64,
+    /// Document representations built while the node ran.
+    pub materialized: ProfileMaterialization,
+    /// Executor and row counts for `Pipeline` nodes.
+    pub pipeline: Option<ProfilePipeline>,
+    /// Child nodes in evaluation order.
+    pub children: Vec<ProfileNode>,
+}
+
+/// What the pipeline executors did for one `Pipeline` node.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfilePipeline {
+    /// Rows the first executor pulled from the source.
+    pub rows_pulled: u64,
+    /// Rows the last executor handed to the sink.
+    pub rows_emitted: u64,
+    /// Executors that ran part of the pipeline, in stage order.
+    pub executors: Vec<ProfileExecutor>,
+    /// Planned executors that declined the pipeline before one ran.
+    pub fallbacks: Vec<&'static str>,
+    /// Stages after optimisation, with the executor that ran each.
+    pub stages: Vec<ProfileStage>,
+}
+
+/// One executor's share of a pipeline run.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ProfileExecutor {
+    /// Executor name, e.g. `"view"`, `"columnar"`, `"composed"`, `"streaming"`.
+    pub name: &'static str,
+    /// Index of the first stage this executor ran.
+    pub first_stage: usize,
+    /// Number of stages this executor ran; the last executor also ran the sink.
+    pub stage_count: usize,
+    /// Rows this executor pulled from its input.
+    pub rows_in: u64,
+    /// Rows this executor produced for the next executor or the sink.
+    pub rows_out: u64,
+}
+
+/// One optimised pipeline stage.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileStage {
+    /// Builtin the stage runs, as in `ExplainStage::name`.
+    pub name: &'static str,
+    /// Executor that ran the stage; `None` when the run stopped before it.
+    pub executor: Option<&'static str>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Per-thread profile state while `profile_query` runs.
+#[derive(Default)]
+struct Recorder {
+    nodes: HashMap<usize, NodeStats>,
+    /// Nodes currently being evaluated, innermost last; executors report to
+    /// the innermost one.
+    frames: Vec<Frame>,
+}
+
+/// A node under evaluation.
+struct Frame {
+    node: usize,
+    /// Added to stage indices reported by executors, so a suffix pipeline run
+    /// after a view prefix reports positions in the original stage list.
+    stage_offset: usize,
+}
+
+/// Accumulated numbers for one node.
+#[derive(Default)]
+struct NodeStats {
+    calls: u64,
+    elapsed: Duration,
+    backend: Option<&'static str>,
+    fallbacks: Vec<&'static str>,
+    materialized: ProfileMaterialization,
+    executors: Vec<ProfileExecutor>,
+    executor_fallbacks: Vec<&'static str>,
+}
+
+/// Which document representations exist at one point in time.
+#[derive(Clone, Copy)]
+struct DocState {
+    tape: bool,
+    structural_index: bool,
+    val: bool,
+    tape_subtrees: usize,
+}
+
+impl DocState {
+    fn capture(j: &Jetro) -> Self {
+        #[cfg(feature = "simd-json")]
+        let (tape, tape_subtrees) = (j.tape_is_built(), j.tape_materialized_subtrees());
+        #[cfg(not(feature = "simd-json"))]
+        let (tape, tape_subtrees) = (false, 0);
+        Self {
+            tape,
+            structural_index: j.structural_index_is_built(),
+            val: j.root_val_is_materialized(),
+            tape_subtrees,
+        }
+    }
+
+    /// What was built between `self` and `after`.
+    fn built_until(self, after: DocState) -> ProfileMaterialization {
+        ProfileMaterialization {
+            tape: after.tape && !self.tape,
+            structural_index: after.structural_index && !self.structural_index,
+            val: after.val && !self.val,
+            tape_subtrees: after.tape_subtrees.saturating_sub(self.tape_subtrees),
+        }
+    }
+}
+
+impl ProfileMaterialization {
+    /// Fold a later observation of the same node into `self`.
+    fn merge(&mut self, other: ProfileMaterialization) {
+        self.tape |= other.tape;
+        self.structural_index |= other.structural_index;
+        self.val |= other.val;
+        self.tape_subtrees += other.tape_subtrees;
+    }
+
+    /// Names of the representations that were built, for compact printing.
+    fn names(&self) -> Vec<&'static str> {
+        [
+            (self.tape, "tape"),
+            (self.structural_index, "structural_index"),
+            (self.val, "val"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
+/// Run `f` with the recorder of the innermost frame's node, if recording.
+fn with_frame(f: impl FnOnce(&mut NodeStats, usize)) {
+    RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        let Some(recorder) = recorder.as_mut() else {
+            return;
+        };
+        let Some(frame) = recorder.frames.last() else {
+            return;
+        };
+        let (node, offset) = (frame.node, frame.stage_offset);
+        f(recorder.nodes.entry(node).or_default(), offset);
+    });
+}
+
+/// Return `true` while a profile is being recorded on this thread; lets
+/// executors skip counting work that only the profile needs.
+#[inline]
+pub(crate) fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().is_some())
+}
+
+/// Note that executor `name` ran `stages` of the current pipeline node,
+/// pulling `rows_in` rows and producing `rows_out`.
+pub(crate) fn executor(name: &'static str, stages: Range<usize>, rows_in: usize, rows_out: usize) {
+    with_frame(|stats, offset| {
+        let first_stage = stages.start + offset;
+        let stage_count = stages.len();
+        if let Some(seen) = stats.executors.iter_mut().find(|seen| {
+            seen.name == name && seen.first_stage == first_stage && seen.stage_count == stage_count
+        }) {
+            seen.rows_in += rows_in as u64;
+            seen.rows_out += rows_out as u64;
+            return;
+        }
+        stats.executors.push(ProfileExecutor {
+            name,
+            first_stage,
+            stage_count,
+            rows_in: rows_in as u64,
+            rows_out: rows_out as u64,
+        });
+    });
+}
+
+/// Note that the planned executor `name` declined the current pipeline node.
+pub(crate) fn executor_declined(name: &'static str) {
+    with_frame(|stats, _| {
+        if !stats.executor_fallbacks.contains(&name) {
+            stats.executor_fallbacks.push(name);
+        }
+    });
+}
+
+/// Run `f` without recording into this thread's profile; used for work the
+/// caller reports itself, such as the partitions of a parallel run.
+pub(crate) fn paused<R>(f: impl FnOnce() -> R) -> R {
+    let recorder = RECORDER.with(|recorder| recorder.borrow_mut().take());
+    let out = f();
+    RECORDER.with(|slot| *slot.borrow_mut() = recorder);
+    out
+}
+
+/// Run `f` with executor stage indices shifted by `offset`; used when the
+/// remaining stages of a pipeline run as a separate suffix pipeline.
+pub(crate) fn with_stage_offset<R>(offset: usize, f: impl FnOnce() -> R) -> R {
+    let set = |offset: Option<usize>| {
+        RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            let frame = recorder.as_mut()?.frames.last_mut()?;
+            let previous = frame.stage_offset;
+            frame.stage_offset = offset.unwrap_or(previous);
+            Some(previous)
+        })
+    };
+    let previous = set(None);
+    set(previous.map(|previous| previous + offset));
+    let out = f();
+    set(previous);
+    out
+}
+
+/// Times one evaluation of a physical node and records the backends it tried.
+/// Inert when no profile is being recorded.
+pub(crate) struct NodeProbe {
+    started: Option<(Instant, DocState)>,
+    node: usize,
+}
+
+impl NodeProbe {
+    /// Start timing node `id`, making it the target of executor reports.
+    pub(crate) fn start(j: &Jetro, id: NodeId) -> Self {
+        let recording = RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            let Some(recorder) = recorder.as_mut() else {
+                return false;
+            };
+            recorder.frames.push(Frame {
+                node: id.0,
+                stage_offset: 0,
+            });
+            true
+        });
+        Self {
+            started: recording.then(|| (Instant::now(), DocState::capture(j))),
+            node: id.0,
+        }
+    }
+
+    /// Note that `backend` declined the node.
+    pub(crate) fn declined(&self, backend: BackendPreference) {
+        if self.started.is_none() {
+            return;
+        }
+        self.update(|stats| {
+            let name = backend_name(backend);
+            if stats.backend.is_none() && !stats.fallbacks.contains(&name) {
+                stats.fallbacks.push(name);
+            }
+        });
+    }
+
+    /// Stop timing; `backend` is the backend that produced the value, if any.
+    pub(crate) fn finish(self, j: &Jetro, backend: Option<BackendPreference>) {
+        let Some((started, before)) = self.started else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        let materialized = before.built_until(DocState::capture(j));
+        self.update(|stats| {
+            stats.calls += 1;
+            stats.elapsed += elapsed;
+            stats.materialized.merge(materialized);
+            if stats.backend.is_none() {
+                stats.backend = backend.map(backend_name);
+            }
+        });
+        RECORDER.with(|recorder| {
+            if let Some(recorder) = recorder.borrow_mut().as_mut() {
+                recorder.frames.pop();
+            }
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut NodeStats)) {
+        RECORDER.with(|recorder| {
+            if let Some(recorder) = recorder.borrow_mut().as_mut() {
+                f(recorder.nodes.entry(self.node).or_default());
+            }
+        });
+    }
+}
+
+/// Restores the previous recorder when dropped, so unwinding out of a
+/// profiled query never leaves recording switched on.
+struct RecorderScope {
+    previous: Option<Recorder>,
+}
+
+impl Drop for RecorderScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        RECORDER.with(|recorder| *recorder.borrow_mut() = previous);
+    }
+}
+
+/// Run `f`, which evaluates `plan` against `j`, with profiling enabled and
+/// return its result alongside the profile.
+pub(crate) fn profile_query<R>(
+    j: &Jetro,
+    plan: &QueryPlan,
+    input: &'static str,
+    f: impl FnOnce() -> R,
+) -> (R, Profile) {
+    let scope = RecorderScope {
+        previous: RECORDER.with(|recorder| recorder.borrow_mut().replace(Recorder::default())),
+    };
+    let before = DocState::capture(j);
+    let started = Instant::now();
+    let out = f();
+    let elapsed = started.elapsed();
+    let materialized = before.built_until(DocState::capture(j));
+    let mut recorder = RECORDER
+        .with(|recorder| recorder.borrow_mut().take())
+        .unwrap_or_default();
+    drop(scope);
+
+    let root = match plan.root() {
+        QueryRoot::Node(id) => profile_node(plan, *id, &mut recorder.nodes),
+        QueryRoot::SourceVm(source) => ProfileNode {
+            kind: "SourceVm",
+            detail: Some(source.to_string()),
+            backend: Some(backend_name(BackendPreference::Interpreted)),
+            fallbacks: Vec::new(),
+            calls: 1,
+            elapsed_ns: nanos(elapsed),
+            materialized,
+            pipeline: None,
+            children: Vec::new(),
+        },
+    };
+    let profile = Profile {
+        input,
+        elapsed_ns: nanos(elapsed),
+        materialized,
+        root,
+    };
+    (out, profile)
+}
+
+/// Describe node `id` and its children, annotated with the recorded numbers.
+fn profile_node(
+    plan: &QueryPlan,
+    id: NodeId,
+    nodes: &mut HashMap<usize, NodeStats>,
+) -> ProfileNode {
+    let outline = node_outline(plan, id);
+    let stats = nodes.remove(&id.0).unwrap_or_default();
+    let pipeline = outline.pipeline.map(|described| {
+        let mut executors = stats.executors;
+        executors.sort_by_key(|executor| executor.first_stage);
+        let stages = described
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(idx, stage)| ProfileStage {
+                name: stage.name,
+                executor: executors
+                    .iter()
+                    .find(|executor| {
+                        (executor.first_stage..executor.first_stage + executor.stage_count)
+                            .contains(&idx)
+                    })
+                    .map(|executor| executor.name),
+            })
+            .collect();
+        ProfilePipeline {
+            rows_pulled: executors.first().map_or(0, |executor| executor.rows_in),
+            rows_emitted: executors.last().map_or(0, |executor| executor.rows_out),
+            executors,
+            fallbacks: stats.executor_fallbacks,
+            stages,
+        }
+    });
+    ProfileNode {
+        kind: kind_name(plan.node(id)),
+        detail: outline.detail,
+        backend: stats.backend,
+        fallbacks: stats.fallbacks,
+        calls: stats.calls,
+        elapsed_ns: nanos(stats.elapsed),
+        materialized: stats.materialized,
+        pipeline,
+        children: outline
+            .children
+            .into_iter()
+            .map(|child| profile_node(plan, child, nodes))
+            .collect(),
+    }
+}
+
+fn nanos(elapsed: Duration) -> u64 {
+    u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input: {}", self.input)?;
+        write!(f, "elapsed: {:?}", Duration::from_nanos(self.elapsed_ns))?;
+        write_materialized(f, &self.materialized)?;
+        writeln!(f)?;
+        self.root.write_tree(f, 0)
+    }
+}
+
+impl fmt::Display for ProfileNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// Append ` built=[..]` and ` tape_subtrees=N` when anything was built.
+fn write_materialized(f: &mut fmt::Formatter<'_>, built: &ProfileMaterialization) -> fmt::Result {
+    let names = built.names();
+    if !names.is_empty() {
+        write!(f, " built=[{}]", names.join(", "))?;
+    }
+    if built.tape_subtrees > 0 {
+        write!(f, " tape_subtrees={}", built.tape_subtrees)?;
+    }
+    Ok(())
+}
+
+impl ProfileNode {
+    /// Write this node and its subtree, indented two spaces per level.
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let pad = "  ".repeat(depth);
+        write!(f, "{pad}{}", self.kind)?;
+        if let Some(detail) = &self.detail {
+            write!(f, " {detail}")?;
+        }
+        match self.backend {
+            Some(backend) => write!(f, " backend={backend}")?,
+            None => write!(f, " not run")?,
+        }
+        if !self.fallbacks.is_empty() {
+            write!(f, " fallbacks=[{}]", self.fallbacks.join(", "))?;
+        }
+        if self.calls > 1 {
+            write!(f, " calls={}", self.calls)?;
+        }
+        write!(f, " time={:?}", Duration::from_nanos(self.elapsed_ns))?;
+        write_materialized(f, &self.materialized)?;
+        writeln!(f)?;
+        if let Some(pipeline) = &self.pipeline {
+            write!(
+                f,
+                "{pad}  rows pulled={} emitted={}",
+                pipeline.rows_pulled, pipeline.rows_emitted
+            )?;
+            if !pipeline.fallbacks.is_empty() {
+                write!(f, " fallbacks=[{}]", pipeline.fallbacks.join(", "))?;
+            }
+            writeln!(f)?;
+            for executor in &pipeline.executors {
+                writeln!(
+                    f,
+                    "{pad}  executor {} stages={}..{} rows={}->{}",
+                    executor.name,
+                    executor.first_stage,
+                    executor.first_stage + executor.stage_count,
+                    executor.rows_in,
+                    executor.rows_out
+                )?;
+            }
+            for stage in &pipeline.stages {
+                writeln!(
+                    f,
+                    "{pad}  stage {} executor={}",
+                    stage.name,
+                    stage.executor.unwrap_or("-")
+                )?;
+            }
+        }
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
@@ -2,13 +2,20 @@
 //!
 //! Receives a `Jetro` document and a `QueryPlan` produced by `planner`,
 //! then dispatches to either `physical_eval` (for structured IR nodes) or the
-//! thread-local VM (for the `SourceVm` fallback when planning is bypassed).
+//! VM (for the `SourceVm` fallback when planning is bypassed). Both run on the
+//! caller's `VM`: `JetroEngine` passes a pooled one, and one-shot `Jetro`
+//! calls borrow the thread-local one.
 //! The only job here is routing — no evaluation logic lives in this module.
 
+use std::sync::Arc;
+
 use serde_json::Value;
 
 use crate::data::context::EvalError;
-use crate::ir::physical::{QueryPlan, QueryRoot};
+use crate::data::value::Val;
+use crate::data::context::Env;
+use crate::exec::pipeline::{fused_exec, PipelineBody};
+use crate::ir::physical::{PipelinePlanSource, PlanNode, QueryPlan, QueryRoot};
 use crate::exec::interpreted as physical_eval;
 use crate::plan::physical as planner;
 use crate::{with_vm, Jetro, VM};
@@ -17,35 +24,97 @@ use crate::{with_vm, Jetro, VM};
 ///
 /// This is the single call path used by `Jetro::collect` for one-shot queries.
 pub(crate) fn collect_json(j: &Jetro, expr: &str) -> Result<Value, EvalError> {
+    collect_val(j, expr).map(Value::from)
+}
+
+/// Like `collect_json`, but returns the raw `Val` so typed extraction can deserialize it
+/// without building a `serde_json::Value` tree first.
+pub(crate) fn collect_val(j: &Jetro, expr: &str) -> Result<Val, EvalError> {
     let plan = planner::plan_query_with_context(expr, planning_context(j));
-    collect_plan_json(j, &plan)
+    collect_plan_val(j, &plan)
 }
 
-/// Executes a pre-built `QueryPlan` against `j`, routing to `physical_eval` or the VM fallback.
+/// Plans `expr` with the names in `bindings` pre-bound as `let` locals and executes it.
 ///
-/// Used by `JetroEngine::collect` when the plan was already retrieved from cache.
-pub(crate) fn collect_plan_json(j: &Jetro, plan: &QueryPlan) -> Result<Value, EvalError> {
+/// Used by `Jetro::collect_with`; `bindings` must come from `bindings_from_json`.
+pub(crate) fn collect_json_with_bindings(
+    j: &Jetro,
+    expr: &str,
+    bindings: Vec<(Arc<str>, Val)>,
+) -> Result<Value, EvalError> {
+    let names = binding_names(&bindings);
+    let plan = planner::plan_query_with_bindings(expr, planning_context(j), &names, None);
+    with_thread_vm(|vm| collect_bound_plan_json(j, &plan, bindings, vm))
+}
+
+/// Executes a plan that was built with `plan_query_with_bindings`, seeding `bindings`
+/// into the executor. A `SourceVm` root only arises from a parse failure, so it is routed
+/// through the VM unchanged to surface that error.
+pub(crate) fn collect_bound_plan_json(
+    j: &Jetro,
+    plan: &QueryPlan,
+    bindings: Vec<(Arc<str>, Val)>,
+    vm: &mut VM,
+) -> Result<Value, EvalError> {
     match plan.root() {
-        QueryRoot::Node(root) => physical_eval::run(j, plan, *root).map(Value::from),
-        QueryRoot::SourceVm(source) => run_vm_json(j, source.as_ref()),
+        QueryRoot::Node(root) => {
+            physical_eval::run_with_locals(j, plan, *root, bindings, vm).map(Value::from)
+        }
+        QueryRoot::SourceVm(source) => run_source_vm(j, source.as_ref(), vm).map(Value::from),
     }
 }
 
-/// Executes `expr` via the thread-local VM, acquiring a fresh `VM` if the cell is already borrowed.
-fn run_vm_json(j: &Jetro, expr: &str) -> Result<Value, EvalError> {
-    with_vm(|cell| match cell.try_borrow_mut() {
-        Ok(mut vm) => {
-            let prog = vm.get_or_compile(expr)?;
-            vm.execute_val(&prog, j.root_val()?)
-        }
-        Err(_) => {
-            let mut vm = VM::new();
-            let prog = vm.get_or_compile(expr)?;
-            vm.execute_val(&prog, j.root_val()?)
+/// Converts caller-supplied JSON variables into `let`-style bindings sorted by name,
+/// keeping the last value when a name repeats.
+pub(crate) fn bindings_from_json<'v, K, I>(vars: I) -> Vec<(Arc<str>, Val)>
+where
+    K: AsRef<str>,
+    I: IntoIterator<Item = (K, &'v Value)>,
+{
+    let mut bindings: Vec<(Arc<str>, Val)> = Vec::new();
+    for (name, value) in vars {
+        let name = name.as_ref();
+        let value = Val::from(value);
+        match bindings.binary_search_by(|(k, _)| k.as_ref().cmp(name)) {
+            Ok(pos) => bindings[pos].1 = value,
+            Err(pos) => bindings.insert(pos, (Arc::from(name), value)),
         }
+    }
+    bindings
+}
+
+/// Returns the bound names of `bindings`, in order, for planning and plan-cache keys.
+#[inline]
+pub(crate) fn binding_names(bindings: &[(Arc<str>, Val)]) -> Vec<Arc<str>> {
+    bindings.iter().map(|(name, _)| Arc::clone(name)).collect()
+}
+
+/// JSON-returning form of `collect_plan_val`, used by the plan-level tests below.
+#[cfg(test)]
+pub(crate) fn collect_plan_json(j: &Jetro, plan: &QueryPlan) -> Result<Value, EvalError> {
+    collect_plan_val(j, plan).map(Value::from)
+}
+
+/// Executes a pre-built `QueryPlan` against `j` on the thread-local VM.
+pub(crate) fn collect_plan_val(j: &Jetro, plan: &QueryPlan) -> Result<Val, EvalError> {
+    with_thread_vm(|vm| collect_plan_val_with_vm(j, plan, vm))
+}
+
+/// Runs `f` on the thread-local VM, or on a fresh `VM` if a caller further up
+/// this thread's stack is already using it.
+fn with_thread_vm<R>(f: impl FnOnce(&mut VM) -> R) -> R {
+    with_vm(|cell| match cell.try_borrow_mut() {
+        Ok(mut vm) => f(&mut vm),
+        Err(_) => f(&mut VM::new()),
     })
 }
 
+/// Compiles `expr` through `vm`'s program cache and runs it against the document root.
+fn run_source_vm(j: &Jetro, expr: &str, vm: &mut VM) -> Result<Val, EvalError> {
+    let prog = vm.get_or_compile(expr)?;
+    vm.execute_val_raw(&prog, j.root_val()?)
+}
+
 /// Executes a pre-built plan using a caller-supplied `VM` instance owned by `JetroEngine`,
 /// avoiding the thread-local VM cell and enabling re-entrant use within the same thread.
 pub(crate) fn collect_plan_json_with_vm(
@@ -53,13 +122,110 @@ pub(crate) fn collect_plan_json_with_vm(
     plan: &QueryPlan,
     vm: &mut VM,
 ) -> Result<Value, EvalError> {
+    collect_plan_val_with_vm(j, plan, vm).map(Value::from)
+}
+
+/// `Val`-returning core of `collect_plan_json_with_vm`, routing to `physical_eval` or the
+/// VM fallback.
+pub(crate) fn collect_plan_val_with_vm(
+    j: &Jetro,
+    plan: &QueryPlan,
+    vm: &mut VM,
+) -> Result<Val, EvalError> {
     match plan.root() {
-        QueryRoot::Node(root) => physical_eval::run(j, plan, *root).map(Value::from),
-        QueryRoot::SourceVm(source) => {
-            let prog = vm.get_or_compile(source.as_ref())?;
-            vm.execute_val(&prog, j.root_val()?)
+        QueryRoot::Node(root) => physical_eval::run(j, plan, *root, vm),
+        QueryRoot::SourceVm(source) => run_source_vm(j, source.as_ref(), vm),
+    }
+}
+
+/// A field chain plus the plans (by index) whose pipelines read it.
+type FusionGroup<'p> = (&'p Arc<[Arc<str>]>, Vec<(usize, &'p PipelineBody)>);
+
+/// Executes several plans against `j`, returning one result per plan in order.
+/// Plans rooted at a streamable pipeline over the same field chain share one
+/// scan of that array; every other plan runs on its own through `vm`.
+pub(crate) fn collect_plans_json_with_vm(
+    j: &Jetro,
+    plans: &[&QueryPlan],
+    vm: &mut VM,
+) -> Vec<Result<Value, EvalError>> {
+    let mut results: Vec<Option<Result<Value, EvalError>>> =
+        std::iter::repeat_with(|| None).take(plans.len()).collect();
+    let mut groups: Vec<FusionGroup<'_>> = Vec::new();
+    for (idx, plan) in plans.iter().enumerate() {
+        let Some((keys, body)) = fusable_pipeline(plan) else {
+            continue;
+        };
+        match groups.iter_mut().find(|(group_keys, _)| *group_keys == keys) {
+            Some((_, members)) => members.push((idx, body)),
+            None => groups.push((keys, vec![(idx, body)])),
+        }
+    }
+    for (keys, members) in groups {
+        if members.len() < 2 {
+            continue;
+        }
+        let bodies: Vec<&PipelineBody> = members.iter().map(|(_, body)| *body).collect();
+        // a document that cannot produce its rows leaves the group to the
+        // per-plan path, which reports the error against each query
+        let Ok(fused) = run_fused(j, keys, &bodies) else {
+            continue;
+        };
+        for ((idx, _), result) in members.into_iter().zip(fused) {
+            results[idx] = Some(
+                result
+                    .map(Value::from)
+                    .map_err(|err| err.or_path(|| physical_eval::field_chain_path(keys))),
+            );
+        }
+    }
+    results
+        .into_iter()
+        .zip(plans)
+        .map(|(result, plan)| {
+            result.unwrap_or_else(|| collect_plan_json_with_vm(j, plan, vm))
+        })
+        .collect()
+}
+
+/// Returns the source keys and body of `plan` when its root is a pipeline over
+/// a field chain that can share a fused scan.
+fn fusable_pipeline(plan: &QueryPlan) -> Option<(&Arc<[Arc<str>]>, &PipelineBody)> {
+    let QueryRoot::Node(root) = plan.root() else {
+        return None;
+    };
+    match plan.node(*root) {
+        PlanNode::Pipeline {
+            source: PipelinePlanSource::FieldChain { keys },
+            body,
+        } if fused_exec::can_fuse(body) => Some((keys, body)),
+        _ => None,
+    }
+}
+
+/// Runs `bodies` over the rows at `keys` in one scan, straight from the tape
+/// when the root has not been materialised and no body reads `$`.
+fn run_fused(
+    j: &Jetro,
+    keys: &Arc<[Arc<str>]>,
+    bodies: &[&PipelineBody],
+) -> Result<Vec<Result<Val, EvalError>>, EvalError> {
+    #[cfg(feature = "simd-json")]
+    if !j.root_val_is_materialized()
+        && bodies
+            .iter()
+            .all(|body| body.can_run_with_materialized_receiver())
+    {
+        if let Some(tape) = j.lazy_tape()? {
+            let env = Env::new(Val::Null);
+            if let Some(results) = fused_exec::run_tape_field_chain(None, bodies, tape, keys, &env) {
+                return Ok(results);
+            }
         }
     }
+    let root = j.root_val()?;
+    let env = Env::new(root.clone());
+    Ok(fused_exec::run_field_chain(None, bodies, keys, &root, &env))
 }
 
 /// Derives the appropriate `PlanningContext` from the document handle's backing representation.
@@ -100,7 +266,9 @@ mod tests {
             | PlanNode::Ident(_)
             | PlanNode::Local(_)
             | PlanNode::RootPath(_)
+            | PlanNode::SharedScan { .. }
             | PlanNode::Structural { .. } => {}
+            PlanNode::ScanSlot { scan, .. } => assert_no_vm_fallback(plan, *scan),
             PlanNode::Pipeline { source, .. } => {
                 if let PipelinePlanSource::Expr(source) = source {
                     assert_no_vm_fallback(plan, *source);
@@ -173,7 +341,9 @@ mod tests {
     fn collect_test_val(j: &Jetro, expr: &str) -> Val {
         let plan = planner::plan_query(expr);
         match plan.root() {
-            QueryRoot::Node(root) => crate::exec::interpreted::run(j, &plan, *root).unwrap(),
+            QueryRoot::Node(root) => {
+                crate::exec::interpreted::run(j, &plan, *root, &mut crate::VM::new()).unwrap()
+            }
             QueryRoot::SourceVm(_) => panic!("unexpected source VM fallback"),
         }
     }
@@ -1410,7 +1580,7 @@ mod tests {
         let err = super::collect_plan_json(&j, &plan).unwrap_err();
 
         assert!(err
-            .0
+            .message()
             .contains("no planned backend could execute physical node"));
         assert!(!j.root_val_is_materialized());
     }
@@ -1432,7 +1602,7 @@ mod tests {
         let err = super::collect_plan_json(&j, &plan).unwrap_err();
 
         assert!(err
-            .0
+            .message()
             .contains("no planned backend could execute physical node"));
     }
 
@@ -1605,11 +1775,18 @@ mod tests {
         let PlanNode::Object(fields) = plan.node(*body) else {
             panic!("expected object body");
         };
-        for idx in [0usize, 1] {
-            let PhysicalObjField::Kv { val, .. } = &fields[idx] else {
+        for (idx, field) in fields.iter().enumerate() {
+            let PhysicalObjField::Kv { val, .. } = field else {
                 panic!("expected pipeline kv field");
             };
-            assert!(matches!(plan.node(*val), PlanNode::Pipeline { .. }));
+            let PlanNode::ScanSlot { scan, slot } = plan.node(*val) else {
+                panic!("expected sibling pipelines to share a scan");
+            };
+            assert_eq!(*slot, idx);
+            let PlanNode::SharedScan { prefix, .. } = plan.node(*scan) else {
+                panic!("expected shared scan");
+            };
+            assert!(prefix.is_some());
         }
 
         let j = Jetro::from(json!({
@@ -18,10 +18,11 @@ use crate::builtins::registry::{self, BuiltinId};
 use crate::builtins::{BuiltinMethod, BuiltinStructural};
 use crate::data::context::EvalError;
 use crate::data::value::Val;
+use crate::exec::limits;
 
 /// A compiled structural deep-search plan. Carried inside `PlanNode::Structural`
 /// and evaluated by `physical_eval` against a `StructuralIndex`.
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub(crate) enum StructuralPlan {
     /// DFS search that returns all descendant objects satisfying every predicate
     /// in `predicates`, starting from the node reached by `anchor`.
@@ -51,7 +52,7 @@ pub(crate) enum StructuralPlan {
 
 /// One step along the anchor path from the document root to the search subtree.
 /// Mirrors `PathStep` but restricted to the subset the structural index can resolve.
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub(crate) enum StructuralPathStep {
     /// Descend into the named field of an object token.
     Field(Arc<str>),
@@ -62,7 +63,7 @@ pub(crate) enum StructuralPathStep {
 /// A literal value pattern used in `StructuralPlan::DeepLike` and
 /// `StructuralPredicate::FieldEqLiteral` to compare against raw JSON bytes
 /// without deserialising the full subtree.
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub(crate) enum StructuralLiteral {
     /// Match JSON `null`.
     Null,
@@ -78,7 +79,7 @@ pub(crate) enum StructuralLiteral {
 
 /// A composable predicate evaluated against candidate object tokens in the
 /// structural index. All matching is done directly on raw JSON byte spans.
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub(crate) enum StructuralPredicate {
     /// The candidate token must have kind `Object`.
     KindObject,
@@ -266,7 +267,7 @@ fn run_deep_find(
     predicates: &[StructuralPredicate],
 ) -> Result<Val, EvalError> {
     if predicates.is_empty() {
-        return Err(EvalError("find: requires at least one predicate".into()));
+        return Err(EvalError::invalid_argument("find: requires at least one predicate"));
     }
     let Some(anchor) = anchor_token(idx, anchor) else {
         return Ok(Val::arr(Vec::new()));
@@ -293,7 +294,7 @@ fn run_deep_shape(
     keys: &[Arc<str>],
 ) -> Result<Val, EvalError> {
     if keys.is_empty() {
-        return Err(EvalError("shape: empty pattern".into()));
+        return Err(EvalError::invalid_argument("shape: empty pattern"));
     }
     let Some(anchor) = anchor_token(idx, anchor) else {
         return Ok(Val::arr(Vec::new()));
@@ -317,7 +318,7 @@ fn run_deep_like(
     patterns: &[(Arc<str>, StructuralLiteral)],
 ) -> Result<Val, EvalError> {
     if patterns.is_empty() {
-        return Err(EvalError("like: empty pattern".into()));
+        return Err(EvalError::invalid_argument("like: empty pattern"));
     }
     let Some(anchor) = anchor_token(idx, anchor) else {
         return Ok(Val::arr(Vec::new()));
@@ -356,6 +357,7 @@ where
     };
     let mut seen = HashSet::new();
     for key_tok in idx.keys_named_in(first_key, anchor) {
+        limits::poll()?;
         let Some(parent) = idx.parent(key_tok) else {
             continue;
         };
@@ -393,6 +395,7 @@ where
         .skip(anchor.raw() as usize)
         .take_while(|tok| tok.raw() <= close)
     {
+        limits::poll()?;
         if idx.kind(tok) == TokenKind::Object {
             visit(tok)?;
         }
@@ -522,15 +525,15 @@ fn materialize_token(idx: &StructuralIndex, bytes: &[u8], tok: TokenId) -> Resul
     {
         let mut owned = raw.to_vec();
         return Val::from_json_simd(&mut owned)
-            .map_err(|err| EvalError(format!("Invalid JSON subtree: {err}")));
+            .map_err(|err| EvalError::invalid_json(format!("Invalid JSON subtree: {err}")));
     }
     #[cfg(not(feature = "simd-json"))]
     {
         let mut de = serde_json::Deserializer::from_slice(raw);
         let v = Val::deserialize(&mut de)
-            .map_err(|err| EvalError(format!("Invalid JSON subtree: {err}")))?;
+            .map_err(|err| EvalError::invalid_json(format!("Invalid JSON subtree: {err}")))?;
         de.end()
-            .map_err(|err| EvalError(format!("Invalid JSON subtree: {err}")))?;
+            .map_err(|err| EvalError::invalid_json(format!("Invalid JSON subtree: {err}")))?;
         Ok(v)
     }
 }
@@ -6,11 +6,13 @@
 //! method call), calls `materialize()`. Used by `physical_eval` when the
 //! planner selects the `View` backend preference.
 
+use std::ops::Range;
 use std::sync::Arc;
 
 use crate::parse::chain_ir::PullDemand;
 use crate::data::context::{Env, EvalError};
 use crate::exec::pipeline;
+use crate::exec::{limits, profile};
 use crate::data::value::Val;
 use crate::data::view::{scalar_view_to_owned_val, ValueView};
 use crate::util::JsonView;
@@ -86,7 +88,7 @@ where
         (_, sink) => sink,
     };
 
-    drive_view_frontier(
+    let counts = drive_view_frontier(
         source,
         &capabilities.stages,
         &body.stage_kernels,
@@ -94,6 +96,7 @@ where
         |item| observe_view_sink(item, sink, &mut sink_acc, &body.sink_kernels),
     )?;
 
+    profile::executor("view", 0..body.stages.len(), counts.pulled, counts.emitted);
     Some(Ok(sink_acc.finish(false)))
 }
 
@@ -115,7 +118,9 @@ where
                 sink.materialization(),
                 pipeline::ViewMaterialization::SinkOutputRows
             );
-            sink_acc.observe_collect(item.materialize());
+            if sink_acc.observe_collect(item.materialize()) {
+                return Some(ViewRowAction::Stop);
+            }
             Some(ViewRowAction::Emit)
         }
         pipeline::ViewSinkCapability::Builtin {
@@ -197,7 +202,7 @@ where
     let mut boundary_rows = Vec::new();
     let source_demand = PullDemand::All;
 
-    drive_view_frontier(
+    let counts = drive_view_frontier(
         source,
         &prefix.stages,
         &body.stage_kernels,
@@ -208,13 +213,21 @@ where
         },
     )?;
 
-    Some(run_materialized_suffix(
-        body,
-        prefix.consumed_stages,
-        boundary_rows,
-        cache,
-        base_env,
-    ))
+    profile::executor(
+        "view",
+        0..prefix.consumed_stages,
+        counts.pulled,
+        boundary_rows.len(),
+    );
+    Some(profile::with_stage_offset(prefix.consumed_stages, || {
+        run_materialized_suffix(
+            body,
+            prefix.consumed_stages,
+            boundary_rows,
+            cache,
+            base_env,
+        )
+    }))
 }
 
 /// Optimised path for pipelines whose suffix is a pure collect sink. Builds a
@@ -233,17 +246,21 @@ where
         .chain
         .pull;
 
-    drive_view_frontier(
+    let counts = drive_view_frontier(
         source,
         &plan.prefix,
         &body.stage_kernels,
         source_demand,
         |item| {
             collector.push_view_row(item, &plan.collect_kernel)?;
+            if limits::check_len(collector.len()).is_err() {
+                return Some(ViewRowAction::Stop);
+            }
             Some(ViewRowAction::Emit)
         },
     )?;
 
+    profile::executor("view", 0..body.stages.len(), counts.pulled, counts.emitted);
     Some(Ok(collector.finish()))
 }
 
@@ -253,10 +270,18 @@ enum ViewRowAction {
     Skip,
     /// The row was accepted and counted as an emitted output.
     Emit,
-    /// The sink has reached its output limit; stop iterating immediately.
+    /// The row was accepted and the sink has reached its output limit; stop
+    /// iterating immediately.
     Stop,
 }
 
+/// Rows a drive loop pulled from its source and handed to its observer.
+#[derive(Clone, Copy, Default)]
+struct ViewDriveCounts {
+    pulled: usize,
+    emitted: usize,
+}
+
 /// Control flow returned by the item-level drive helpers.
 enum ViewDriveFlow {
     /// Processing of the current item is complete; continue with the next row.
@@ -274,7 +299,7 @@ fn drive_view_frontier<'a, V, F>(
     stage_kernels: &[pipeline::BodyKernel],
     source_demand: PullDemand,
     observe: F,
-) -> Option<()>
+) -> Option<ViewDriveCounts>
 where
     V: ValueView<'a>,
     F: FnMut(&V) -> Option<ViewRowAction>,
@@ -295,7 +320,7 @@ where
                 _ => return None,
             };
             if idx >= len {
-                return Some(());
+                return Some(ViewDriveCounts::default());
             }
             let items = std::iter::once(source.index(idx as i64));
             return drive_view_iter(items, stages, stage_kernels, PullDemand::All, observe);
@@ -317,7 +342,7 @@ fn drive_view_iter<'a, V, I, F>(
     stage_kernels: &[pipeline::BodyKernel],
     source_demand: PullDemand,
     mut observe: F,
-) -> Option<()>
+) -> Option<ViewDriveCounts>
 where
     V: ValueView<'a>,
     I: IntoIterator<Item = V>,
@@ -333,6 +358,9 @@ where
         if matches!(source_demand, PullDemand::FirstInput(n) if pulled_inputs >= n) {
             break;
         }
+        if limits::should_stop() {
+            break;
+        }
         pulled_inputs += 1;
 
         if matches!(
@@ -355,7 +383,10 @@ where
         }
     }
 
-    Some(())
+    Some(ViewDriveCounts {
+        pulled: pulled_inputs,
+        emitted: emitted_outputs,
+    })
 }
 
 /// Recursively applies one view stage to `item`, then advances to the next stage.
@@ -390,7 +421,10 @@ where
                     },
                 )
             }
-            ViewRowAction::Stop => Some(ViewDriveFlow::Stop),
+            ViewRowAction::Stop => {
+                *emitted_outputs += 1;
+                Some(ViewDriveFlow::Stop)
+            }
         };
     };
 
@@ -602,7 +636,7 @@ where
         .chain
         .pull;
 
-    drive_view_frontier(
+    let counts = drive_view_frontier(
         source,
         &plan.prefix,
         &body.stage_kernels,
@@ -613,13 +647,11 @@ where
         },
     )?;
 
-    Some(run_materialized_value_suffix(
-        body,
-        plan.consumed_stages,
-        plan.reducer.finish(),
-        cache,
-        base_env,
-    ))
+    profile::executor("view", 0..plan.consumed_stages, counts.pulled, 1);
+    let reduced = plan.reducer.finish();
+    Some(profile::with_stage_offset(plan.consumed_stages, || {
+        run_materialized_value_suffix(body, plan.consumed_stages, reduced, cache, base_env)
+    }))
 }
 
 /// Handles pipelines with a `Sort` barrier. Runs any preceding view-native
@@ -654,7 +686,7 @@ where
 
     let mut sorter =
         pipeline::BoundedKeySorter::new(plan.descending, strategy, pipeline::cmp_val_total);
-    drive_view_frontier(
+    let counts = drive_view_frontier(
         source,
         &plan.prefix,
         &body.stage_kernels,
@@ -667,47 +699,55 @@ where
     )?;
 
     let winners = sorter.finish();
+    if let Err(err) = limits::check_len(winners.len()) {
+        return Some(Err(err));
+    }
+    let suffix_start = plan.sort_stage + 1;
+    profile::executor("view", 0..suffix_start, counts.pulled, winners.len());
     if let Some(collect_plan) = collect_suffix {
         return run_sorted_rows_terminal_collect_suffix(
             winners,
             &collect_plan,
             &body.stage_kernels,
+            suffix_start..body.stages.len(),
         );
     }
     let boundary_rows: Vec<Val> = winners.into_iter().map(|row| row.materialize()).collect();
 
-    Some(run_materialized_suffix(
-        body,
-        plan.sort_stage + 1,
-        boundary_rows,
-        cache,
-        base_env,
-    ))
+    Some(profile::with_stage_offset(suffix_start, || {
+        run_materialized_suffix(body, suffix_start, boundary_rows, cache, base_env)
+    }))
 }
 
 /// Feeds a pre-sorted vec of view rows through the terminal-collect plan,
 /// applying any remaining prefix stages and the fused projection kernel without
-/// a separate materialisation step.
+/// a separate materialisation step. `stages` is the suffix's position in the
+/// pipeline, for the profile.
 fn run_sorted_rows_terminal_collect_suffix<'a, V>(
     rows: Vec<V>,
     plan: &TerminalCollectPlan,
     stage_kernels: &[pipeline::BodyKernel],
+    stages: Range<usize>,
 ) -> Option<Result<Val, EvalError>>
 where
     V: ValueView<'a>,
 {
     let mut collector = pipeline::TerminalCollector::new(&plan.collect_kernel);
-    drive_view_iter(
+    let counts = drive_view_iter(
         rows,
         &plan.prefix,
         stage_kernels,
         plan.source_demand,
         |item| {
             collector.push_view_row(item, &plan.collect_kernel)?;
+            if limits::check_len(collector.len()).is_err() {
+                return Some(ViewRowAction::Stop);
+            }
             Some(ViewRowAction::Emit)
         },
     )?;
 
+    profile::executor("view", stages, counts.pulled, counts.emitted);
     Some(Ok(collector.finish()))
 }
 
@@ -734,7 +774,7 @@ where
     };
     let mut sorter = pipeline::OrderedKeySorter::new(ordered_descending, pipeline::cmp_val_total);
 
-    drive_view_frontier(
+    let prefix_counts = drive_view_frontier(
         source,
         &plan.prefix,
         &body.stage_kernels,
@@ -745,10 +785,13 @@ where
             Some(ViewRowAction::Emit)
         },
     )?;
+    if let Err(err) = limits::check_len(prefix_counts.emitted) {
+        return Some(Err(err));
+    }
 
     let mut sink_acc = pipeline::SinkAccumulator::new(&body.sink);
 
-    drive_view_iter(
+    let suffix_counts = drive_view_iter(
         sorter.finish(),
         &suffix.stages,
         &body.stage_kernels,
@@ -756,6 +799,12 @@ where
         |item| observe_view_sink(item, suffix.sink, &mut sink_acc, &body.sink_kernels),
     )?;
 
+    profile::executor(
+        "view",
+        0..body.stages.len(),
+        prefix_counts.pulled,
+        suffix_counts.emitted,
+    );
     Some(Ok(sink_acc.finish(false)))
 }
 
@@ -847,6 +896,7 @@ fn run_materialized_suffix(
     cache: Option<&dyn pipeline::PipelineData>,
     base_env: &Env,
 ) -> Result<Val, EvalError> {
+    limits::check_len(boundary_rows.len())?;
     let suffix = suffix_body(body, consumed_stages)
         .with_source(pipeline::Source::Receiver(Val::arr(boundary_rows)));
     let root = Val::Null;
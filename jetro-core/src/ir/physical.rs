@@ -8,7 +8,9 @@
 
 use std::sync::Arc;
 
-use crate::parse::ast::{BinOp, KindType};
+use serde::{Deserialize, Serialize};
+
+use crate::parse::ast::{BinOp, KindType, Span};
 use crate::builtins::BuiltinCall;
 use crate::exec::pipeline::PipelineBody;
 use crate::exec::structural::StructuralPlan;
@@ -17,7 +19,7 @@ use crate::vm::Program;
 
 /// Compiled query plan: a DAG of `PhysicalNode`s plus a root selector.
 /// Cloneable so `JetroEngine` can cache and reuse plans across calls.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct QueryPlan {
     root: QueryRoot,
     nodes: Vec<PhysicalNode>,
@@ -97,7 +99,7 @@ impl QueryPlan {
 }
 
 /// Selects the execution entry point for a `QueryPlan`.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum QueryRoot {
     /// The plan was successfully lowered; evaluation begins at the given node.
     Node(NodeId),
@@ -106,14 +108,14 @@ pub enum QueryRoot {
 }
 
 /// Typed index into the `QueryPlan`'s node arena.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeId(pub(crate) usize);
 
 /// Describes the computational role of a single node in the physical plan DAG.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PlanNode {
     /// A compile-time constant value requiring no document access.
-    Literal(Val),
+    Literal(#[serde(with = "crate::data::value::json_text")] Val),
     /// The document root (`$`); materialises or navigates the whole document.
     Root,
     /// The current pipeline item (`@`); valid only inside pipeline stage bodies.
@@ -129,6 +131,24 @@ pub enum PlanNode {
         /// Stages, sink, and their associated kernels and programs.
         body: PipelineBody,
     },
+    /// Sibling field-chain pipelines of one object or array constructor, run
+    /// together in a single scan of the rows at `keys`. Evaluates to `null`;
+    /// each branch result is read through a `ScanSlot`.
+    SharedScan {
+        /// Dot-separated key sequence rooted at `$` shared by every branch.
+        keys: Arc<[Arc<str>]>,
+        /// Leading filter stages common to every branch, applied once per row.
+        prefix: Option<PipelineBody>,
+        /// The remaining stages and sink of each sibling, in constructor order.
+        branches: Vec<PipelineBody>,
+    },
+    /// The result of branch `slot` of the `SharedScan` node `scan`.
+    ScanSlot {
+        /// The `SharedScan` node producing the result.
+        scan: NodeId,
+        /// Index into the scan's `branches`.
+        slot: usize,
+    },
     /// A deep-search operation that the structural (bitmap index) backend can satisfy.
     Structural {
         /// The structural search plan describing the index traversal.
@@ -153,6 +173,8 @@ pub enum PlanNode {
         call: BuiltinCall,
         /// If `true`, propagate `null` from the receiver instead of calling the method.
         optional: bool,
+        /// Source range of the call, attached to errors it raises.
+        span: Span,
     },
     /// Arithmetic negation of a numeric node.
     UnaryNeg(NodeId),
@@ -217,7 +239,7 @@ pub enum PlanNode {
 }
 
 /// A fully-annotated plan node combining its kind with planner-selected metadata.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct PhysicalNode {
     /// The logical operation this node performs.
     kind: PlanNode,
@@ -291,6 +313,12 @@ impl PhysicalNode {
         }
     }
 
+    /// Returns the logical operation this node performs.
+    #[inline]
+    pub(crate) fn kind(&self) -> &PlanNode {
+        &self.kind
+    }
+
     /// Returns a copy of the `ExecutionFacts` stored on this node.
     #[inline]
     pub(crate) fn execution_facts(&self) -> ExecutionFacts {
@@ -299,11 +327,11 @@ impl PhysicalNode {
 }
 
 /// A compact bitset recording which backend families a node can use.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct BackendSet(u16);
 
 /// Identifies the concrete execution strategy the executor should attempt for a node.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) enum BackendPreference {
     /// Use the bitmap structural index (deep-search operations on raw bytes).
     Structural,
@@ -324,7 +352,7 @@ pub(crate) enum BackendPreference {
 }
 
 /// Propagated metadata about what a physical node produces, used to avoid unnecessary work.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ExecutionFacts {
     /// `true` when every leaf can execute without materialising the entire document as a `Val`.
     pub(crate) can_avoid_root_materialization: bool,
@@ -397,6 +425,18 @@ impl ExecutionFacts {
                     may_materialize_source: materialized_source,
                 }
             }
+            PlanNode::SharedScan {
+                prefix, branches, ..
+            } => Self {
+                can_avoid_root_materialization: prefix
+                    .iter()
+                    .chain(branches)
+                    .all(PipelineBody::can_run_with_materialized_receiver),
+                can_stream_rows: true,
+                can_use_tape: true,
+                contains_vm_fallback: false,
+                may_materialize_source: true,
+            },
             PlanNode::Structural { .. } => Self {
                 can_avoid_root_materialization: true,
                 can_stream_rows: false,
@@ -421,7 +461,7 @@ impl ExecutionFacts {
 }
 
 /// An inline-stored ordered list of up to five backend preferences chosen by the planner.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct BackendPlan {
     /// Number of valid entries in `items`.
     len: u8,
@@ -554,7 +594,9 @@ impl PlanNode {
                 BackendPreference::Interpreted,
             ],
             Self::RootPath(_) => &[BackendPreference::TapePath, BackendPreference::Interpreted],
+            Self::SharedScan { .. } => &[BackendPreference::TapeRows, BackendPreference::Interpreted],
             Self::Literal(_)
+            | Self::ScanSlot { .. }
             | Self::Local(_)
             | Self::Object(_)
             | Self::Array(_)
@@ -600,7 +642,7 @@ impl BackendPreference {
 }
 
 /// Describes where a `Pipeline` node draws its input rows from.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PipelinePlanSource {
     /// A dot-separated key sequence rooted at `$`, eligible for tape/view backends.
     FieldChain { keys: Arc<[Arc<str>]> },
@@ -609,7 +651,7 @@ pub enum PipelinePlanSource {
 }
 
 /// A single step in a `RootPath` — purely field or integer-index navigation.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PhysicalPathStep {
     /// Access a named object field.
     Field(Arc<str>),
@@ -618,7 +660,7 @@ pub enum PhysicalPathStep {
 }
 
 /// A single step in a `Chain` node, including dynamically-computed subscripts.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PhysicalChainStep {
     /// Access a named object field.
     Field(Arc<str>),
@@ -629,7 +671,7 @@ pub enum PhysicalChainStep {
 }
 
 /// Describes one field in a physical `Object` node.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PhysicalObjField {
     /// A key/value pair with optional omit-when-null and guard-condition flags.
     Kv {
@@ -658,7 +700,7 @@ pub enum PhysicalObjField {
 }
 
 /// Describes one element in a physical `Array` node.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PhysicalArrayElem {
     /// A single element produced by the given node.
     Expr(NodeId),
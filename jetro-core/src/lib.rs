@@ -28,6 +28,7 @@ pub(crate) mod exec;
 pub(crate) mod ir;
 pub(crate) mod parse;
 pub(crate) mod plan;
+pub(crate) mod query;
 pub(crate) mod util;
 pub(crate) mod vm;
 
@@ -36,14 +37,27 @@ mod tests;
 
 use serde_json::Value;
 use std::cell::{OnceCell, RefCell};
-use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::OnceLock;
 use data::value::Val;
 
-pub use data::context::EvalError;
-#[cfg(test)]
-use parse::parser::ParseError;
+pub use builtins::host::HostFunction;
+pub use data::context::{EvalError, EvalErrorKind};
+pub use exec::limits::{CancellationToken, ExecutionLimits, DEFAULT_MAX_NESTING};
+pub use data::binary::DecodeError;
+pub use data::csv::{CsvError, CsvOptions};
+pub use data::json_stream::{JsonStreamDocs, JsonStreamError};
+pub use data::lenient::{JsonSyntaxError, ParseOptions};
+pub use data::ndjson::{NdjsonError, NdjsonLines};
+pub use exec::parallel::Parallelism;
+pub use parse::parser::ParseError;
+pub use exec::profile::{
+    Profile, ProfileExecutor, ProfileMaterialization, ProfileNode, ProfilePipeline, ProfileStage,
+};
+pub use plan::bundle::CacheBundleError;
+pub use plan::cache::PlanCacheStats;
+pub use plan::explain::{Explain, ExplainFacts, ExplainNode, ExplainPipeline, ExplainStage};
+pub use query::Query;
 use vm::VM;
 
 /// Internal parser surface re-exported only when the `fuzz_internal` feature
@@ -115,36 +129,70 @@ where
     })
 }
 
+/// Byte buffer a `Jetro` reads its document from, kept as the caller handed it over.
+type SharedBytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
 
 /// Primary entry point. Holds a JSON document and evaluates expressions against
 /// it. Lazy fields (`root_val`, `tape`, `structural_index`, `objvec_cache`)
 /// are populated on first use so callers only pay for the representations a
 /// particular query actually needs.
+///
+/// `Jetro` is `Send + Sync`: put one in an `Arc` and query it from many
+/// threads. Each lazy representation is built exactly once, by whichever
+/// query needs it first; concurrent queries wait for it instead of building
+/// their own copy.
 pub struct Jetro {
     /// The `serde_json::Value` root document; unused when `simd-json` is enabled
     /// (the tape is the authoritative source in that case).
     document: Value,
     /// Cached `Val` tree — built once and reused across `collect()` calls.
-    root_val: OnceCell<Val>,
-    /// Retained raw bytes for lazy tape and structural-index materialisation.
-    raw_bytes: Option<Arc<[u8]>>,
+    root_val: OnceLock<Val>,
+    /// Retained raw bytes for lazy tape and structural-index materialisation;
+    /// shared with the caller when built by `from_shared_bytes`.
+    raw_bytes: Option<SharedBytes>,
 
     /// Lazily parsed simd-json tape; `Err` is cached to avoid re-parsing after failure.
     #[cfg(feature = "simd-json")]
-    tape: OnceCell<std::result::Result<Arc<crate::data::tape::TapeData>, String>>,
+    tape: OnceLock<std::result::Result<Arc<crate::data::tape::TapeData>, String>>,
     /// Unused placeholder so the field name is consistent regardless of features.
     #[cfg(not(feature = "simd-json"))]
     #[allow(dead_code)]
-    tape: OnceCell<()>,
+    tape: OnceLock<()>,
 
     /// Lazily built bitmap structural index for accelerated key-presence queries.
     structural_index:
-        OnceCell<std::result::Result<Arc<jetro_experimental::StructuralIndex>, String>>,
+        OnceLock<std::result::Result<Arc<jetro_experimental::StructuralIndex>, String>>,
 
     /// Per-document cache from `Arc<Vec<Val>>` pointer addresses to promoted
     /// `ObjVecData` columnar representations; keyed by pointer to avoid re-promotion.
     pub(crate) objvec_cache:
         std::sync::Mutex<std::collections::HashMap<usize, Arc<crate::data::value::ObjVecData>>>,
+
+    /// How many times each lazy representation was built; tests assert that
+    /// concurrent queries never build one twice.
+    #[cfg(test)]
+    pub(crate) builds: LazyBuilds,
+
+    /// How many times the plan executor scanned a field-chain source of this
+    /// document; tests assert that sibling pipelines over one source share a scan.
+    #[cfg(test)]
+    pub(crate) source_scans: std::sync::atomic::AtomicUsize,
+}
+
+/// Build counters for the lazy fields of a `Jetro`.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct LazyBuilds {
+    pub(crate) tape: std::sync::atomic::AtomicUsize,
+    pub(crate) structural_index: std::sync::atomic::AtomicUsize,
+    pub(crate) objvec: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(test)]
+impl LazyBuilds {
+    fn record(counter: &std::sync::atomic::AtomicUsize) {
+        counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 
@@ -153,12 +201,18 @@ pub struct Jetro {
 /// parse/lower/compile work is amortised by this object, not hidden in
 /// thread-local state.
 pub struct JetroEngine {
-    /// Maps `"<context_key>\0<expr>"` to compiled `QueryPlan`; evicted wholesale when full.
-    plan_cache: Mutex<HashMap<String, ir::physical::QueryPlan>>,
-    /// Maximum number of entries before the cache is cleared; 0 disables caching.
-    plan_cache_limit: usize,
-    /// The shared `VM` used by all `collect*` calls on this engine instance.
-    vm: Mutex<VM>,
+    /// LRU of planned expressions keyed on context, bound names and normalised source.
+    plan_cache: plan::cache::PlanCache,
+    /// VMs handed out to `collect*` calls, one per concurrent caller, sharing
+    /// one compile cache.
+    vms: vm::VmPool,
+    /// Host functions registered with `register_function`; installed as the
+    /// thread's active registry for the duration of each `collect*` call.
+    host: Arc<builtins::host::HostRegistry>,
+    /// Resource limits enforced on every `collect*` call; unlimited by default.
+    limits: ExecutionLimits,
+    /// Threads a single pipeline may use during `collect*` calls; sequential by default.
+    parallelism: Parallelism,
 }
 
 /// Error returned by `JetroEngine::collect_bytes` and similar methods that
@@ -184,9 +238,42 @@ impl std::error::Error for JetroEngineError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Json(err) => Some(err),
-            Self::Eval(_) => None,
+            Self::Eval(err) => Some(err),
+        }
+    }
+}
+
+impl JetroEngineError {
+    /// Return the error category; JSON input failures report `InvalidJson`.
+    pub fn kind(&self) -> EvalErrorKind {
+        match self {
+            Self::Json(_) => EvalErrorKind::InvalidJson,
+            Self::Eval(err) => err.kind(),
+        }
+    }
+
+    /// Return the underlying `EvalError` when evaluation (not JSON parsing) failed.
+    pub fn eval_error(&self) -> Option<&EvalError> {
+        match self {
+            Self::Json(_) => None,
+            Self::Eval(err) => Some(err),
         }
     }
+
+    /// Return the builtin or opcode that failed, if known.
+    pub fn operation(&self) -> Option<&str> {
+        self.eval_error().and_then(EvalError::operation)
+    }
+
+    /// Return the byte span of the offending sub-expression in the query source, if known.
+    pub fn span(&self) -> Option<std::ops::Range<usize>> {
+        self.eval_error().and_then(EvalError::span)
+    }
+
+    /// Return the JSON path of the value being processed, if known.
+    pub fn path(&self) -> Option<&str> {
+        self.eval_error().and_then(EvalError::path)
+    }
 }
 
 impl From<serde_json::Error> for JetroEngineError {
@@ -208,7 +295,7 @@ impl Default for JetroEngine {
 }
 
 impl JetroEngine {
-    /// Default maximum plan-cache size; the cache is cleared wholesale when reached.
+    /// Default maximum plan-cache size; the least recently used plan is evicted beyond it.
     const DEFAULT_PLAN_CACHE_LIMIT: usize = 256;
 
     /// Create a `JetroEngine` with the default plan-cache limit of 256 entries.
@@ -220,27 +307,186 @@ impl JetroEngine {
     /// Set `plan_cache_limit` to 0 to disable caching entirely.
     pub fn with_plan_cache_limit(plan_cache_limit: usize) -> Self {
         Self {
-            plan_cache: Mutex::new(HashMap::new()),
-            plan_cache_limit,
-            vm: Mutex::new(VM::new()),
+            plan_cache: plan::cache::PlanCache::new(plan_cache_limit),
+            vms: vm::VmPool::new(),
+            host: Arc::default(),
+            limits: ExecutionLimits::default(),
+            parallelism: Parallelism::default(),
         }
     }
 
-    /// Discard all cached query plans, forcing re-compilation on the next call.
+    /// Enforce `limits` on every query this engine evaluates from now on.
+    /// Hitting one fails the query with `EvalErrorKind::LimitExceeded`. Clears
+    /// the plan and program caches, since both are compiled under the nesting
+    /// limit.
+    pub fn set_limits(&mut self, limits: ExecutionLimits) {
+        self.limits = limits;
+        self.clear_cache();
+    }
+
+    /// Return the limits currently enforced by this engine.
+    pub fn limits(&self) -> &ExecutionLimits {
+        &self.limits
+    }
+
+    /// Let pipelines over large arrays run across several threads, as
+    /// `parallelism` allows, in every query this engine evaluates from now on.
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    /// Return the parallel execution setting currently used by this engine.
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.parallelism
+    }
+
+    /// Register `function` so queries evaluated by this engine can call it as
+    /// `recv.name(args)` or `name(recv, args)`. Re-registering a name replaces
+    /// the previous function. Fails if `name` is not an identifier or would
+    /// shadow a builtin. Clears the plan cache, since plans depend on which
+    /// names are host functions.
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        function: HostFunction,
+    ) -> std::result::Result<(), EvalError> {
+        Arc::make_mut(&mut self.host).insert(name, function)?;
+        self.clear_cache();
+        Ok(())
+    }
+
+    /// Discard all cached query plans and the bytecode the engine's VMs
+    /// compiled, forcing re-compilation on the next call.
     pub fn clear_cache(&self) {
-        self.plan_cache.lock().expect("plan cache poisoned").clear();
+        self.plan_cache.clear();
+        self.vms.clear_programs();
+    }
+
+    /// Return the plan cache's hit, miss and eviction counters, its size, and
+    /// the planning time hits have saved.
+    pub fn plan_cache_stats(&self) -> PlanCacheStats {
+        self.plan_cache.stats()
+    }
+
+    /// Write every cached plan, with its compiled bytecode, to `writer` as a
+    /// versioned binary bundle, returning how many plans were written. Load
+    /// it into another engine with `import_cache` to skip planning there.
+    pub fn export_cache<W: std::io::Write>(
+        &self,
+        writer: W,
+    ) -> std::result::Result<usize, CacheBundleError> {
+        let _limits = exec::limits::enter(&self.limits, None);
+        plan::bundle::export(&self.plan_cache, &self.host, exec::limits::max_nesting(), writer)
+    }
+
+    /// Add the plans in a bundle written by `export_cache` to this engine's
+    /// cache, returning how many were added. Fails without adding anything if
+    /// the bundle came from another crate version or builtin table, or from an
+    /// engine with different host functions or nesting limit, or if it is
+    /// corrupt.
+    pub fn import_cache<R: std::io::Read>(
+        &self,
+        reader: R,
+    ) -> std::result::Result<usize, CacheBundleError> {
+        let _limits = exec::limits::enter(&self.limits, None);
+        plan::bundle::import(&self.plan_cache, &self.host, exec::limits::max_nesting(), reader)
+    }
+
+    /// Plan every expression in `exprs` ahead of time for both byte-backed and
+    /// `Value`-backed documents, so the first real query is a cache hit. Stops
+    /// at the first expression that does not parse; those before it stay cached.
+    pub fn warm<I, S>(&self, exprs: I) -> std::result::Result<(), ParseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let _limits = exec::limits::enter(&self.limits, None);
+        for expr in exprs {
+            let expr = expr.as_ref();
+            parse::parser::parse(expr)?;
+            for context in [
+                plan::physical::PlanningContext::bytes(),
+                plan::physical::PlanningContext::val(),
+            ] {
+                self.cached_plan(expr, context, &[]);
+            }
+        }
+        Ok(())
     }
 
     /// Evaluate a Jetro expression against an already-constructed `Jetro` document,
-    /// using the engine's shared plan cache and `VM`.
+    /// using the engine's shared plan cache and a `VM` from its pool. Callers on
+    /// different threads evaluate in parallel.
     pub fn collect<S: AsRef<str>>(
         &self,
         document: &Jetro,
         expr: S,
     ) -> std::result::Result<Value, EvalError> {
-        let plan = self.cached_plan(expr.as_ref(), exec::router::planning_context(document));
-        let mut vm = self.vm.lock().expect("vm cache poisoned");
-        exec::router::collect_plan_json_with_vm(document, &plan, &mut vm)
+        self.collect_guarded(document, expr.as_ref(), None)
+    }
+
+    /// Like `collect`, but stops with `EvalErrorKind::Cancelled` soon after
+    /// `token` is cancelled from another thread.
+    pub fn collect_cancellable<S: AsRef<str>>(
+        &self,
+        document: &Jetro,
+        expr: S,
+        token: &CancellationToken,
+    ) -> std::result::Result<Value, EvalError> {
+        self.collect_guarded(document, expr.as_ref(), Some(token))
+    }
+
+    /// Like `collect`, but deserializes the result directly into `T` without
+    /// building a `serde_json::Value` tree first.
+    pub fn collect_as<T, S>(&self, document: &Jetro, expr: S) -> std::result::Result<T, EvalError>
+    where
+        T: serde::de::DeserializeOwned,
+        S: AsRef<str>,
+    {
+        let limits = exec::limits::enter(&self.limits, None);
+        let plan = self.cached_plan(expr.as_ref(), exec::router::planning_context(document), &[]);
+        let _host = builtins::host::enter(&self.host);
+        let _parallel = exec::parallel::enter(&self.parallelism);
+        let val = {
+            let mut vm = self.vms.checkout();
+            let result = exec::router::collect_plan_val_with_vm(document, &plan, &mut vm);
+            limits.finish(result)?
+        };
+        limits.check_output(&data::value::ValRef(&val), val.array_len().unwrap_or(1))?;
+        data::de::from_val(&val)
+    }
+
+    /// Evaluate `expr` with external variables bound as enclosing `let` identifiers,
+    /// so `{"limit": 10}` makes `$.rows.filter(score > limit)` valid. A variable
+    /// shadows a row field of the same name, which stays reachable as `@.name`.
+    /// The cached plan is keyed on the variable names only; values never enter the
+    /// plan cache.
+    pub fn collect_with<'v, S, K, I>(
+        &self,
+        document: &Jetro,
+        expr: S,
+        vars: I,
+    ) -> std::result::Result<Value, EvalError>
+    where
+        S: AsRef<str>,
+        K: AsRef<str>,
+        I: IntoIterator<Item = (K, &'v Value)>,
+    {
+        let bindings = exec::router::bindings_from_json(vars);
+        let names = exec::router::binding_names(&bindings);
+        let limits = exec::limits::enter(&self.limits, None);
+        let plan = self.cached_plan(
+            expr.as_ref(),
+            exec::router::planning_context(document),
+            &names,
+        );
+        let _host = builtins::host::enter(&self.host);
+        let _parallel = exec::parallel::enter(&self.parallelism);
+        let mut vm = self.vms.checkout();
+        let result = exec::router::collect_bound_plan_json(document, &plan, bindings, &mut vm);
+        let value = limits.finish(result)?;
+        limits.check_output(&value, value.as_array().map_or(1, Vec::len))?;
+        Ok(value)
     }
 
     /// Convenience wrapper: wrap a `serde_json::Value` in a `Jetro` and evaluate `expr`.
@@ -264,23 +510,271 @@ impl JetroEngine {
         Ok(self.collect(&document, expr)?)
     }
 
-    /// Look up a compiled `QueryPlan` by expression string and planning context,
-    /// compiling and inserting it if not already cached; evicts the whole cache if full.
-    fn cached_plan(&self, expr: &str, context: plan::physical::PlanningContext) -> ir::physical::QueryPlan {
-        let mut cache = self.plan_cache.lock().expect("plan cache poisoned");
-        let cache_key = format!("{}\0{}", context.cache_key(), expr);
-        if let Some(plan) = cache.get(&cache_key) {
-            return plan.clone();
+    /// Evaluate `expr` against each record of the newline-delimited JSON in
+    /// `reader`, yielding one result per non-blank line as it is read. A
+    /// malformed or failing record yields an `NdjsonError::Record` with its
+    /// 1-based line number and the iteration moves on to the next line. An
+    /// expression that does not parse yields a single `NdjsonError::Eval`
+    /// without reading `reader`.
+    ///
+    /// ```rust
+    /// use jetro_core::JetroEngine;
+    /// let input = "{\"n\": 1}\n{\"n\": 2}\n{oops\n";
+    /// let engine = JetroEngine::new();
+    /// let out: Vec<_> = engine.collect_ndjson_lines(input.as_bytes(), "$.n * 10").collect();
+    /// assert_eq!(out[1].as_ref().unwrap(), &serde_json::json!(20));
+    /// assert_eq!(out[2].as_ref().unwrap_err().line(), Some(3));
+    /// ```
+    pub fn collect_ndjson_lines<R: std::io::BufRead, S: AsRef<str>>(
+        &self,
+        reader: R,
+        expr: S,
+    ) -> NdjsonLines<'_, R> {
+        NdjsonLines::new(self, reader, expr.as_ref().to_owned())
+    }
+
+    /// Evaluate `expr` with `$` bound to the records of the newline-delimited
+    /// JSON in `reader`, as if they formed one array.
+    ///
+    /// A method chain on `$` whose stages read only the current record, such
+    /// as `$.filter(level == "error").count_by(service)`, pulls the records
+    /// through the pipeline one at a time without collecting them, and stops
+    /// reading once its result is known (`$.first()`, `$.take(10)`). Any
+    /// other expression reads the whole stream into an array first. The first
+    /// malformed record read is reported as `NdjsonError::Record` with its
+    /// line number.
+    ///
+    /// ```rust
+    /// use jetro_core::JetroEngine;
+    /// let input = r#"{"level": "error", "service": "api"}
+    /// {"level": "info", "service": "api"}
+    /// {"level": "error", "service": "db"}
+    /// {"level": "error", "service": "api"}
+    /// "#;
+    /// let engine = JetroEngine::new();
+    /// let out = engine
+    ///     .collect_ndjson(input.as_bytes(), r#"$.filter(level == "error").count_by(service)"#)
+    ///     .unwrap();
+    /// assert_eq!(out, serde_json::json!({"api": 2, "db": 1}));
+    /// ```
+    pub fn collect_ndjson<R: std::io::BufRead, S: AsRef<str>>(
+        &self,
+        reader: R,
+        expr: S,
+    ) -> std::result::Result<Value, NdjsonError> {
+        self.collect_records(data::ndjson::Lines::new(reader).vals(), expr.as_ref())
+    }
+
+    /// Evaluate `expr` against each top-level value of a concatenated JSON
+    /// stream, such as `{"a":1}{"a":2}[3]`, yielding one result per document
+    /// as it is read. Documents may be separated by whitespace or nothing at
+    /// all, and only the document being evaluated is held in memory. A
+    /// malformed or failing document yields a `JsonStreamError::Document`
+    /// with its index and byte offset, and the iteration moves on. An
+    /// expression that does not parse yields a single `JsonStreamError::Eval`
+    /// without reading `reader`.
+    ///
+    /// ```rust
+    /// use jetro_core::JetroEngine;
+    /// let input = r#"{"n":1}{"n":2} {"n":3}"#;
+    /// let engine = JetroEngine::new();
+    /// let out: Vec<_> = engine
+    ///     .collect_json_stream_docs(input.as_bytes(), "$.n * 10")
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(out, vec![serde_json::json!(10), serde_json::json!(20), serde_json::json!(30)]);
+    /// ```
+    pub fn collect_json_stream_docs<R: std::io::Read, S: AsRef<str>>(
+        &self,
+        reader: R,
+        expr: S,
+    ) -> JsonStreamDocs<'_, R> {
+        JsonStreamDocs::new(self, reader, expr.as_ref().to_owned())
+    }
+
+    /// Evaluate `expr` with `$` bound to the top-level values of a
+    /// concatenated JSON stream, as if they formed one array. Streams the
+    /// documents through the pipeline where `collect_ndjson` would stream
+    /// lines, and otherwise reads them all into an array first. The first
+    /// malformed document read is reported with its index and byte offset.
+    ///
+    /// ```rust
+    /// use jetro_core::JetroEngine;
+    /// let input = r#"{"n":1}{"n":2}{"n":3}"#;
+    /// let engine = JetroEngine::new();
+    /// let total = engine.collect_json_stream(input.as_bytes(), "$.map(n).sum()").unwrap();
+    /// assert_eq!(total, serde_json::json!(6));
+    /// ```
+    pub fn collect_json_stream<R: std::io::Read, S: AsRef<str>>(
+        &self,
+        reader: R,
+        expr: S,
+    ) -> std::result::Result<Value, JsonStreamError> {
+        self.collect_records(data::json_stream::Documents::new(reader).vals(), expr.as_ref())
+    }
+
+    /// Shared body of `collect_ndjson` and `collect_json_stream`: evaluates
+    /// `expr` with `$` bound to `records`, streaming them through the
+    /// pipeline when the query allows and collecting them into an array
+    /// otherwise. The first failing record ends the stream with its error.
+    fn collect_records<E, I>(&self, records: I, expr: &str) -> std::result::Result<Value, E>
+    where
+        E: From<EvalError>,
+        I: Iterator<Item = std::result::Result<Val, E>>,
+    {
+        let ast = parse::parser::parse(expr).map_err(EvalError::from)?;
+        let Some(stream) = exec::pipeline::stream_exec::RootStream::lower(&ast) else {
+            let rows = records.collect::<std::result::Result<Vec<Val>, _>>()?;
+            let document = Jetro::from_val(Val::arr(rows));
+            return Ok(self.collect(&document, expr)?);
+        };
+        let limits = exec::limits::enter(&self.limits, None);
+        let _host = builtins::host::enter(&self.host);
+        let mut failed = None;
+        let rows = records.map_while(|record| record.map_err(|err| failed = Some(err)).ok());
+        let result = stream.run(rows);
+        if let Some(err) = failed {
+            return Err(err);
         }
+        let value = Value::from(limits.finish(result)?);
+        limits.check_output(&value, value.as_array().map_or(1, Vec::len))?;
+        Ok(value)
+    }
 
-        let plan = plan::physical::plan_query_with_context(expr, context);
-        if self.plan_cache_limit > 0 {
-            if cache.len() >= self.plan_cache_limit {
-                cache.clear();
-            }
-            cache.insert(cache_key, plan.clone());
+    /// Evaluate `expr` against every document in `documents` in parallel, one
+    /// std thread per available core, and return the results in input order.
+    /// A failing document does not stop the others.
+    pub fn collect_many<S: AsRef<str>>(
+        &self,
+        documents: &[Jetro],
+        expr: S,
+    ) -> Vec<std::result::Result<Value, EvalError>> {
+        let expr = expr.as_ref();
+        let workers = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(documents.len());
+        if workers <= 1 {
+            return documents.iter().map(|doc| self.collect(doc, expr)).collect();
         }
-        plan
+        let chunk = documents.len().div_ceil(workers);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = documents
+                .chunks(chunk)
+                .map(|docs| {
+                    scope.spawn(move || {
+                        docs.iter()
+                            .map(|doc| self.collect(doc, expr))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_else(|p| std::panic::resume_unwind(p)))
+                .collect()
+        })
+    }
+
+    /// Evaluate every expression in `exprs` against `document` and return the
+    /// results in input order. Queries that are streamable pipelines over the
+    /// same field chain, such as `$.orders.count()` and
+    /// `$.orders.map(total).sum()`, are fused into a single scan of that array;
+    /// the rest run as `collect` would. A failing query does not stop the
+    /// others. The engine's limits cover the batch as a whole.
+    pub fn collect_all<S: AsRef<str>>(
+        &self,
+        document: &Jetro,
+        exprs: &[S],
+    ) -> Vec<std::result::Result<Value, EvalError>> {
+        let limits = exec::limits::enter(&self.limits, None);
+        let context = exec::router::planning_context(document);
+        let plans: Vec<Arc<ir::physical::QueryPlan>> = exprs
+            .iter()
+            .map(|expr| self.cached_plan(expr.as_ref(), context, &[]))
+            .collect();
+        let plans: Vec<&ir::physical::QueryPlan> = plans.iter().map(Arc::as_ref).collect();
+        let _host = builtins::host::enter(&self.host);
+        let _parallel = exec::parallel::enter(&self.parallelism);
+        let mut vm = self.vms.checkout();
+        exec::router::collect_plans_json_with_vm(document, &plans, &mut vm)
+            .into_iter()
+            .map(|result| {
+                let value = limits.finish(result)?;
+                limits.check_output(&value, value.as_array().map_or(1, Vec::len))?;
+                Ok(value)
+            })
+            .collect()
+    }
+
+    /// Describe how `collect` would execute `expr` against `document`: the
+    /// physical plan tree with each node's backends and facts, pipeline stage
+    /// strategies and sink demand, and the rewrite passes that fired. The plan
+    /// is built fresh, so the cache is neither consulted nor filled.
+    pub fn explain<S: AsRef<str>>(
+        &self,
+        expr: S,
+        document: &Jetro,
+    ) -> std::result::Result<Explain, EvalError> {
+        let context = exec::router::planning_context(document);
+        let _limits = exec::limits::enter(&self.limits, None);
+        Ok(plan::explain::explain_query(expr.as_ref(), context, &self.host)?)
+    }
+
+    /// Like `collect`, but also records what actually happened: for each plan
+    /// node the backend that ran, the backends that declined, time spent, and
+    /// the document representations it built; for each pipeline the executors
+    /// that ran its stages and the rows they pulled and emitted. Profiling is
+    /// opt-in: plain `collect` calls never pay for it.
+    pub fn collect_profiled<S: AsRef<str>>(
+        &self,
+        document: &Jetro,
+        expr: S,
+    ) -> std::result::Result<(Value, Profile), EvalError> {
+        let context = exec::router::planning_context(document);
+        let limits = exec::limits::enter(&self.limits, None);
+        let plan = self.cached_plan(expr.as_ref(), context, &[]);
+        let _host = builtins::host::enter(&self.host);
+        let _parallel = exec::parallel::enter(&self.parallelism);
+        let mut vm = self.vms.checkout();
+        let (result, profile) =
+            exec::profile::profile_query(document, &plan, context.cache_key(), || {
+                exec::router::collect_plan_json_with_vm(document, &plan, &mut vm)
+            });
+        let value = limits.finish(result)?;
+        limits.check_output(&value, value.as_array().map_or(1, Vec::len))?;
+        Ok((value, profile))
+    }
+
+    /// Shared body of `collect` and `collect_cancellable`: evaluates under the
+    /// engine's host registry and limits, observing `token` when given.
+    fn collect_guarded(
+        &self,
+        document: &Jetro,
+        expr: &str,
+        token: Option<&CancellationToken>,
+    ) -> std::result::Result<Value, EvalError> {
+        let limits = exec::limits::enter(&self.limits, token);
+        let plan = self.cached_plan(expr, exec::router::planning_context(document), &[]);
+        let _host = builtins::host::enter(&self.host);
+        let _parallel = exec::parallel::enter(&self.parallelism);
+        let mut vm = self.vms.checkout();
+        let result = exec::router::collect_plan_json_with_vm(document, &plan, &mut vm);
+        let value = limits.finish(result)?;
+        limits.check_output(&value, value.as_array().map_or(1, Vec::len))?;
+        Ok(value)
+    }
+
+    /// Look up a compiled `QueryPlan` by expression string, planning context, and bound
+    /// variable names, planning and inserting it if not already cached.
+    fn cached_plan(
+        &self,
+        expr: &str,
+        context: plan::physical::PlanningContext,
+        bound: &[Arc<str>],
+    ) -> Arc<ir::physical::QueryPlan> {
+        self.plan_cache.get_or_plan(expr, context, bound, || {
+            plan::physical::plan_query_with_bindings(expr, context, bound, Some(&self.host))
+        })
     }
 }
 
@@ -292,47 +786,44 @@ impl exec::pipeline::PipelineData for Jetro {
 
 impl Jetro {
     /// Return a reference to the lazily parsed simd-json `TapeData`, parsing raw bytes
-    /// on first access. Returns `Ok(None)` when no raw bytes are stored.
+    /// on first access. Returns `Ok(None)` when no raw bytes are stored, and fails when
+    /// the document nests deeper than the nesting limit.
     #[cfg(feature = "simd-json")]
     pub(crate) fn lazy_tape(
         &self,
     ) -> std::result::Result<Option<&Arc<crate::data::tape::TapeData>>, EvalError> {
-        if let Some(result) = self.tape.get() {
-            return result
-                .as_ref()
-                .map(Some)
-                .map_err(|err| EvalError(format!("Invalid JSON: {err}")));
-        }
-        let Some(raw) = self.raw_bytes.as_ref() else {
+        let Some(raw) = self.raw_bytes() else {
             return Ok(None);
         };
-        let bytes: Vec<u8> = (**raw).to_vec();
-        let parsed = crate::data::tape::TapeData::parse(bytes).map_err(|err| err.to_string());
-        let _ = self.tape.set(parsed);
-        self.tape
-            .get()
-            .expect("tape cache initialized")
+        let tape = self
+            .tape
+            .get_or_init(|| {
+                #[cfg(test)]
+                LazyBuilds::record(&self.builds.tape);
+                crate::data::tape::TapeData::parse(raw.to_vec())
+            })
             .as_ref()
-            .map(Some)
-            .map_err(|err| EvalError(format!("Invalid JSON: {err}")))
+            .map_err(|err| EvalError::invalid_json(format!("Invalid JSON: {err}")))?;
+        tape.check_nesting()?;
+        Ok(Some(tape))
     }
 
     /// Look up or build an `ObjVecData` columnar representation for the given
-    /// `Arc<Vec<Val>>` array, caching the result by pointer address.
+    /// `Arc<Vec<Val>>` array, caching the result by pointer address. The cache
+    /// lock is held while promoting, so concurrent queries promote an array once.
     pub(crate) fn get_or_promote_objvec(
         &self,
         arr: &Arc<Vec<Val>>,
     ) -> Option<Arc<crate::data::value::ObjVecData>> {
         let key = Arc::as_ptr(arr) as usize;
-        if let Ok(cache) = self.objvec_cache.lock() {
-            if let Some(d) = cache.get(&key) {
-                return Some(Arc::clone(d));
-            }
+        let mut cache = self.objvec_cache.lock().ok()?;
+        if let Some(d) = cache.get(&key) {
+            return Some(Arc::clone(d));
         }
         let promoted = exec::pipeline::Pipeline::try_promote_objvec_arr(arr)?;
-        if let Ok(mut cache) = self.objvec_cache.lock() {
-            cache.entry(key).or_insert_with(|| Arc::clone(&promoted));
-        }
+        #[cfg(test)]
+        LazyBuilds::record(&self.builds.objvec);
+        cache.insert(key, Arc::clone(&promoted));
         Some(promoted)
     }
 
@@ -340,49 +831,167 @@ impl Jetro {
     pub(crate) fn new(document: Value) -> Self {
         Self {
             document,
-            root_val: OnceCell::new(),
+            root_val: OnceLock::new(),
             objvec_cache: Default::default(),
+            #[cfg(test)]
+            builds: Default::default(),
+            #[cfg(test)]
+            source_scans: Default::default(),
             raw_bytes: None,
-            tape: OnceCell::new(),
-            structural_index: OnceCell::new(),
+            tape: OnceLock::new(),
+            structural_index: OnceLock::new(),
         }
     }
 
     /// Parse raw JSON bytes and build a `Jetro` query handle.
     /// When the `simd-json` feature is enabled the bytes are not parsed eagerly;
-    /// the tape is built lazily on the first query that needs it.
+    /// the tape is built lazily on the first query that needs it. The vector is
+    /// kept as the document's source without being copied.
     pub fn from_bytes(bytes: Vec<u8>) -> std::result::Result<Self, serde_json::Error> {
-        
-        
+        Self::from_raw(Arc::new(bytes))
+    }
+
+    /// Parse `bytes` written with the JSON extensions `options` allows, such
+    /// as JSONC comments and trailing commas or JSON5 keys, strings, and
+    /// numbers. The document is the same tree its plain JSON spelling would
+    /// give `from_bytes`, so queries behave identically. The syntax is checked
+    /// eagerly, and errors carry the line and column in `bytes`.
+    pub fn from_bytes_with(
+        bytes: Vec<u8>,
+        options: ParseOptions,
+    ) -> std::result::Result<Self, JsonSyntaxError> {
+        let json = data::lenient::to_json(&bytes, options)?;
+        Ok(Self::from_bytes(json)?)
+    }
+
+    /// Read CSV (or, with `CsvOptions::tsv`, TSV) text as an array of
+    /// objects, one per record, keyed by the header row. The rows are built
+    /// column-wise from the start, so columnar pipelines apply on the first
+    /// query. Parsing is eager, and errors carry the line and column in
+    /// `bytes`.
+    pub fn from_csv(bytes: &[u8], options: CsvOptions) -> std::result::Result<Self, CsvError> {
+        Ok(Self::from_val(data::csv::parse(bytes, options)?))
+    }
+
+    /// Decode a MessagePack document straight into the engine's value tree.
+    /// `bin` values become base64 strings, extension values
+    /// `{"ext": type, "data": base64}` objects, and non-string map keys their
+    /// text; see `collect_msgpack` for the way back.
+    ///
+    /// ```rust
+    /// use jetro_core::Jetro;
+    /// // {"ids": [1, 2, 3]}
+    /// let bytes = [0x81, 0xa3, b'i', b'd', b's', 0x93, 0x01, 0x02, 0x03];
+    /// let j = Jetro::from_msgpack(&bytes).unwrap();
+    /// assert_eq!(j.collect("$.ids.sum()").unwrap(), serde_json::json!(6));
+    /// assert_eq!(j.collect_msgpack("$").unwrap(), bytes);
+    /// ```
+    pub fn from_msgpack(bytes: &[u8]) -> std::result::Result<Self, DecodeError> {
+        Ok(Self::from_val(data::msgpack::decode(bytes)?))
+    }
+
+    /// Decode a CBOR data item straight into the engine's value tree. Byte
+    /// strings become base64 strings, bignums integers (or floats past the
+    /// `i64` range), other tags their content, and non-string map keys their
+    /// text; see `collect_cbor` for the way back.
+    pub fn from_cbor(bytes: &[u8]) -> std::result::Result<Self, DecodeError> {
+        Ok(Self::from_val(data::cbor::decode(bytes)?))
+    }
+
+    /// Like `from_bytes`, but reads a buffer the caller keeps sharing, such as
+    /// an `Arc<[u8]>`, a `bytes::Bytes`, a memory map, or a `&'static [u8]`.
+    /// The document holds a reference to `bytes` instead of a copy; the only
+    /// copy made is the private one simd-json parses in place, on the first
+    /// query that needs the tape.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use jetro_core::Jetro;
+    ///
+    /// let shared: Arc<[u8]> = Arc::from(&br#"{"ids":[1,2,3]}"#[..]);
+    /// let j = Jetro::from_shared_bytes(Arc::clone(&shared)).unwrap();
+    /// assert_eq!(j.collect("$.ids.sum()").unwrap(), serde_json::json!(6));
+    /// assert_eq!(Arc::strong_count(&shared), 2);
+    /// ```
+    pub fn from_shared_bytes<B>(bytes: B) -> std::result::Result<Self, serde_json::Error>
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        Self::from_raw(Arc::new(bytes))
+    }
+
+    /// Shared body of `from_bytes` and `from_shared_bytes`.
+    fn from_raw(raw: SharedBytes) -> std::result::Result<Self, serde_json::Error> {
         #[cfg(feature = "simd-json")]
         {
             return Ok(Self {
                 document: Value::Null,
-                root_val: OnceCell::new(),
+                root_val: OnceLock::new(),
                 objvec_cache: Default::default(),
-                raw_bytes: Some(Arc::from(bytes.into_boxed_slice())),
-                tape: OnceCell::new(),
-                structural_index: OnceCell::new(),
+                #[cfg(test)]
+                builds: Default::default(),
+                #[cfg(test)]
+                source_scans: Default::default(),
+                raw_bytes: Some(raw),
+                tape: OnceLock::new(),
+                structural_index: OnceLock::new(),
             });
         }
         #[allow(unreachable_code)]
         {
-            let document: Value = serde_json::from_slice(&bytes)?;
+            let document: Value = serde_json::from_slice((*raw).as_ref())?;
             Ok(Self {
                 document,
-                root_val: OnceCell::new(),
+                root_val: OnceLock::new(),
                 objvec_cache: Default::default(),
-                raw_bytes: Some(Arc::from(bytes.into_boxed_slice())),
-                tape: OnceCell::new(),
-                structural_index: OnceCell::new(),
+                #[cfg(test)]
+                builds: Default::default(),
+                #[cfg(test)]
+                source_scans: Default::default(),
+                raw_bytes: Some(raw),
+                tape: OnceLock::new(),
+                structural_index: OnceLock::new(),
             })
         }
     }
 
+    /// Build a `Jetro` handle from any `serde::Serialize` value.
+    ///
+    /// The document is serialized straight into the engine's value tree, with
+    /// no JSON text and no `serde_json::Value` in between. A sequence of
+    /// same-shape structs is stored column-wise from the start, so columnar
+    /// pipelines apply to it on the first query.
+    ///
+    /// ```rust
+    /// use jetro_core::Jetro;
+    /// #[derive(serde::Serialize)]
+    /// struct Book { title: &'static str, price: u32 }
+    /// #[derive(serde::Serialize)]
+    /// struct Shelf { books: Vec<Book> }
+    /// let shelf = Shelf {
+    ///     books: vec![Book { title: "Dune", price: 12 }, Book { title: "Emma", price: 30 }],
+    /// };
+    /// let j = Jetro::from_serialize(&shelf).unwrap();
+    /// assert_eq!(j.collect("$.books.filter(price > 20).map(title)").unwrap(), serde_json::json!(["Emma"]));
+    /// ```
+    pub fn from_serialize<T>(value: &T) -> std::result::Result<Self, EvalError>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        Ok(Self::from_val(data::ser::to_val(value)?))
+    }
+
+    /// Wrap an already-built `Val` tree as a document.
+    pub(crate) fn from_val(root: Val) -> Self {
+        let j = Self::new(Value::Null);
+        let _ = j.root_val.set(root);
+        j
+    }
+
     /// Return the raw JSON byte slice if this handle was constructed from bytes,
     /// or `None` if it was constructed from a `serde_json::Value`.
     pub(crate) fn raw_bytes(&self) -> Option<&[u8]> {
-        self.raw_bytes.as_deref()
+        self.raw_bytes.as_deref().map(|raw| raw.as_ref())
     }
 
     /// Return a reference to the lazily built `StructuralIndex` for key-presence
@@ -390,28 +999,23 @@ impl Jetro {
     pub(crate) fn lazy_structural_index(
         &self,
     ) -> std::result::Result<Option<&Arc<jetro_experimental::StructuralIndex>>, EvalError> {
-        if let Some(result) = self.structural_index.get() {
-            return result
-                .as_ref()
-                .map(Some)
-                .map_err(|err| EvalError(format!("Invalid JSON: {err}")));
-        }
-        let Some(raw) = self.raw_bytes.as_ref() else {
+        let Some(raw) = self.raw_bytes() else {
             return Ok(None);
         };
-        let built = jetro_experimental::from_bytes_with(
-            raw.as_ref(),
-            jetro_experimental::BuildOptions::keys_only(),
-        )
-        .map(Arc::new)
-        .map_err(|err| err.to_string());
-        let _ = self.structural_index.set(built);
         self.structural_index
-            .get()
-            .expect("structural index cache initialized")
+            .get_or_init(|| {
+                #[cfg(test)]
+                LazyBuilds::record(&self.builds.structural_index);
+                jetro_experimental::from_bytes_with(
+                    raw,
+                    jetro_experimental::BuildOptions::keys_only(),
+                )
+                .map(Arc::new)
+                .map_err(|err| err.to_string())
+            })
             .as_ref()
             .map(Some)
-            .map_err(|err| EvalError(format!("Invalid JSON: {err}")))
+            .map_err(|err| EvalError::invalid_json(format!("Invalid JSON: {err}")))
     }
 
     /// Return the root `Val` for the document, building and caching it from the
@@ -420,37 +1024,32 @@ impl Jetro {
         if let Some(root) = self.root_val.get() {
             return Ok(root.clone());
         }
-        let root = {
-            #[cfg(feature = "simd-json")]
-            {
-                if let Some(tape) = self.lazy_tape()? {
-                    Val::from_tape_data(tape)
-                } else {
-                    Val::from(&self.document)
-                }
-            }
-            #[cfg(not(feature = "simd-json"))]
-            {
-                Val::from(&self.document)
-            }
-        };
-        let _ = self.root_val.set(root);
-        Ok(self.root_val.get().expect("root val initialized").clone())
+        #[cfg(feature = "simd-json")]
+        if let Some(tape) = self.lazy_tape()? {
+            return Ok(self
+                .root_val
+                .get_or_init(|| Val::from_checked_tape(tape))
+                .clone());
+        }
+        Ok(self
+            .root_val
+            .get_or_init(|| Val::from(&self.document))
+            .clone())
     }
 
-    /// Return `true` if the `Val` tree has already been materialised; used in
-    /// tests to assert that lazy evaluation is working correctly.
-    #[cfg(test)]
+    /// Return `true` if the `Val` tree has already been materialised; used by
+    /// the query profile and by tests asserting that evaluation stays lazy.
     pub(crate) fn root_val_is_materialized(&self) -> bool {
         self.root_val.get().is_some()
     }
 
-    #[cfg(test)]
+    /// Return `true` once the structural index has been built (or failed to build).
     pub(crate) fn structural_index_is_built(&self) -> bool {
         self.structural_index.get().is_some()
     }
 
-    #[cfg(all(test, feature = "simd-json"))]
+    /// Return `true` once the simd-json tape has been parsed (or failed to parse).
+    #[cfg(feature = "simd-json")]
     pub(crate) fn tape_is_built(&self) -> bool {
         self.tape.get().is_some()
     }
@@ -462,13 +1061,14 @@ impl Jetro {
         }
     }
 
-    #[cfg(all(test, feature = "simd-json"))]
+    /// Number of tape subtrees materialised into `Val` so far; never forces the
+    /// tape to be parsed.
+    #[cfg(feature = "simd-json")]
     pub(crate) fn tape_materialized_subtrees(&self) -> usize {
-        self.lazy_tape()
-            .ok()
-            .flatten()
-            .map(|tape| tape.materialized_subtrees())
-            .unwrap_or(0)
+        match self.tape.get() {
+            Some(Ok(tape)) => tape.materialized_subtrees(),
+            _ => 0,
+        }
     }
 
     /// Evaluate a Jetro expression against this document and return the result
@@ -477,6 +1077,122 @@ impl Jetro {
     pub fn collect<S: AsRef<str>>(&self, expr: S) -> std::result::Result<Value, EvalError> {
         exec::router::collect_json(self, expr.as_ref())
     }
+
+    /// Evaluate `expr` and deserialize the result directly into `T`, skipping the
+    /// intermediate `serde_json::Value` tree that `collect` would build.
+    ///
+    /// ```rust
+    /// use jetro_core::Jetro;
+    /// let j = Jetro::from_bytes(br#"{"xs":[1,2,3]}"#.to_vec()).unwrap();
+    /// let total: i64 = j.collect_as("$.xs.sum()").unwrap();
+    /// assert_eq!(total, 6);
+    /// ```
+    pub fn collect_as<T, S>(&self, expr: S) -> std::result::Result<T, EvalError>
+    where
+        T: serde::de::DeserializeOwned,
+        S: AsRef<str>,
+    {
+        let val = exec::router::collect_val(self, expr.as_ref())?;
+        data::de::from_val(&val)
+    }
+
+    /// Evaluate `expr` and stream the result as compact JSON into `writer`, without
+    /// building a `serde_json::Value` tree. Columnar results (`ObjVec`, `IntVec`,
+    /// `StrSliceVec`, ...) are written row by row straight from their lanes.
+    ///
+    /// Object keys keep their document order, whereas `collect` returns a
+    /// `serde_json::Value` whose map type may reorder them.
+    pub fn collect_to_writer<S, W>(&self, expr: S, writer: W) -> std::result::Result<(), EvalError>
+    where
+        S: AsRef<str>,
+        W: std::io::Write,
+    {
+        let val = exec::router::collect_val(self, expr.as_ref())?;
+        val.write_json(writer, false).map_err(EvalError::from_write)
+    }
+
+    /// Like `collect_to_writer`, but indents the output.
+    pub fn collect_to_writer_pretty<S, W>(
+        &self,
+        expr: S,
+        writer: W,
+    ) -> std::result::Result<(), EvalError>
+    where
+        S: AsRef<str>,
+        W: std::io::Write,
+    {
+        let val = exec::router::collect_val(self, expr.as_ref())?;
+        val.write_json(writer, true).map_err(EvalError::from_write)
+    }
+
+    /// Evaluate `expr` and return the result as compact JSON bytes.
+    ///
+    /// ```rust
+    /// use jetro_core::Jetro;
+    /// let j = Jetro::from_bytes(br#"{"xs":[3,1,2]}"#.to_vec()).unwrap();
+    /// assert_eq!(j.collect_bytes("$.xs.sort()").unwrap(), b"[1,2,3]");
+    /// ```
+    pub fn collect_bytes<S: AsRef<str>>(&self, expr: S) -> std::result::Result<Vec<u8>, EvalError> {
+        let mut out = Vec::new();
+        self.collect_to_writer(expr, &mut out)?;
+        Ok(out)
+    }
+
+    /// Evaluate `expr` and return the result as indented JSON bytes.
+    pub fn collect_bytes_pretty<S: AsRef<str>>(
+        &self,
+        expr: S,
+    ) -> std::result::Result<Vec<u8>, EvalError> {
+        let mut out = Vec::new();
+        self.collect_to_writer_pretty(expr, &mut out)?;
+        Ok(out)
+    }
+
+    /// Evaluate `expr` and return the result encoded as MessagePack. Floats,
+    /// including non-finite ones, are written as float 64, and columnar
+    /// results are written straight from their lanes.
+    pub fn collect_msgpack<S: AsRef<str>>(
+        &self,
+        expr: S,
+    ) -> std::result::Result<Vec<u8>, EvalError> {
+        let val = exec::router::collect_val(self, expr.as_ref())?;
+        Ok(data::msgpack::encode(&val))
+    }
+
+    /// Evaluate `expr` and return the result encoded as CBOR, with definite
+    /// lengths and 64-bit floats.
+    pub fn collect_cbor<S: AsRef<str>>(&self, expr: S) -> std::result::Result<Vec<u8>, EvalError> {
+        let val = exec::router::collect_val(self, expr.as_ref())?;
+        Ok(data::cbor::encode(&val))
+    }
+
+    /// Evaluate `expr` with external variables bound as enclosing `let` identifiers.
+    /// Values are never spliced into the expression text, so callers can pass
+    /// per-request thresholds or ids without quoting or injection concerns.
+    /// As with `let`, a bare name refers to the variable even where the current
+    /// row has a field of that name; reach the field as `@.name`.
+    ///
+    /// ```rust
+    /// use jetro_core::Jetro;
+    /// use serde_json::json;
+    /// let j = Jetro::from_bytes(br#"{"rows":[{"n":1},{"n":5}]}"#.to_vec()).unwrap();
+    /// let vars = json!({"min": 2});
+    /// let out = j.collect_with("$.rows.filter(n >= min).len()", vars.as_object().unwrap()).unwrap();
+    /// assert_eq!(out, json!(1));
+    /// ```
+    pub fn collect_with<'v, S, K, I>(
+        &self,
+        expr: S,
+        vars: I,
+    ) -> std::result::Result<Value, EvalError>
+    where
+        S: AsRef<str>,
+        K: AsRef<str>,
+        I: IntoIterator<Item = (K, &'v Value)>,
+    {
+        let bindings = exec::router::bindings_from_json(vars);
+        exec::router::collect_json_with_bindings(self, expr.as_ref(), bindings)
+    }
 }
 
 /// Wrap an existing `serde_json::Value` in a `Jetro` handle without raw bytes.
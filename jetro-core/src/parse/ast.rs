@@ -6,9 +6,14 @@
 //! refcount bump. Sub-expressions are `Box<Expr>` so the compiler can
 //! rewrite them in place (`reorder_and_operands`).
 
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
 /// Complete expression AST. The parser produces one of these for every
 /// syntactically valid Jetro expression.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expr {
     /// The `null` literal; evaluates to `Val::Null`.
     Null,
@@ -155,6 +160,8 @@ pub enum Expr {
         name: String,
         /// Positional and named arguments.
         args: Vec<Arg>,
+        /// Source range of the call, from the name through the closing `)`.
+        span: Span,
     },
 
     /// Explicit type-cast `expr as <type>`; may return null on failure.
@@ -180,7 +187,7 @@ pub enum Expr {
 
 
 /// A single write operation inside a `Patch` expression.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PatchOp {
     /// Navigation path identifying the target node.
     pub path: Vec<PathStep>,
@@ -191,7 +198,7 @@ pub struct PatchOp {
 }
 
 /// One segment of a patch path — mirrors `Step` but restricted to write-safe forms.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PathStep {
     /// Static field name lookup.
     Field(String),
@@ -209,7 +216,7 @@ pub enum PathStep {
 
 
 /// Target type for an `as` cast expression.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CastType {
     /// Cast to `i64`.
     Int,
@@ -231,7 +238,7 @@ pub enum CastType {
 
 
 /// One stage in a `Pipeline` expression.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PipeStep {
     /// Pass the current value through an expression (`| expr`).
     Forward(Expr),
@@ -240,7 +247,7 @@ pub enum PipeStep {
 }
 
 /// Destructuring pattern used by a `PipeStep::Bind`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BindTarget {
     /// Bind the whole value to a single name (`as $x`).
     Name(String),
@@ -257,7 +264,7 @@ pub enum BindTarget {
 
 
 /// One part of an `FString` template.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FStringPart {
     /// A literal string segment between interpolation sites.
     Lit(String),
@@ -266,7 +273,7 @@ pub enum FStringPart {
 }
 
 /// Formatting directive attached to an FString interpolation site.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FmtSpec {
     /// Python-style format spec string (e.g. `:.2f`).
     Spec(String),
@@ -276,7 +283,7 @@ pub enum FmtSpec {
 
 
 /// One element inside an array literal.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ArrayElem {
     /// A single expression contributing one element.
     Expr(Expr),
@@ -286,7 +293,7 @@ pub enum ArrayElem {
 
 
 /// Controls how `.first` / `.one` quantifiers resolve a multi-value result.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QuantifierKind {
     /// Return the first element, or null if the array is empty.
     First,
@@ -295,7 +302,7 @@ pub enum QuantifierKind {
 }
 
 /// One postfix navigation step in a `Chain` expression.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Step {
     /// `.field` — mandatory field access; propagates null if the key is absent.
     Field(String),
@@ -312,9 +319,9 @@ pub enum Step {
     /// `[start:end]` — array slice; either bound may be absent (open range).
     Slice(Option<i64>, Option<i64>),
     /// `.method(args…)` — method call dispatched through the builtin / custom registry.
-    Method(String, Vec<Arg>),
+    Method(String, Vec<Arg>, Span),
     /// `.method?(args…)` — optional method call; errors become null.
-    OptMethod(String, Vec<Arg>),
+    OptMethod(String, Vec<Arg>, Span),
     /// `[pred]` — inline filter; keeps array elements for which `pred` is truthy.
     InlineFilter(Box<Expr>),
     /// `.first` / `.one` — quantifier that collapses an array to a scalar.
@@ -322,8 +329,49 @@ pub enum Step {
 }
 
 
+/// Byte range of a call in the query source, recorded by the parser so
+/// runtime errors can point at the call that raised them. Nodes the parser
+/// synthesises (desugarings, planner rewrites) carry `Span::default()`, which
+/// means "unknown". Spans take no part in equality or hashing: the same call
+/// written at two offsets is the same expression.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset one past the last character.
+    pub end: usize,
+}
+
+impl Span {
+    /// Span covering `range`.
+    pub fn new(range: Range<usize>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// The byte range, or `None` for a synthesised node.
+    pub fn range(self) -> Option<Range<usize>> {
+        (self.end > self.start).then_some(self.start..self.end)
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+impl Hash for Span {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+
 /// One argument in a method or global-function call.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Arg {
     /// A positional argument.
     Pos(Expr),
@@ -333,7 +381,7 @@ pub enum Arg {
 
 
 /// One field in an object literal.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ObjField {
     /// A full `key: value` pair with optional omit-if-null and conditional flags.
     Kv {
@@ -358,7 +406,7 @@ pub enum ObjField {
 
 
 /// Binary infix operator. Variants map 1-to-1 to opcodes after compilation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinOp {
     /// Numeric addition or string concatenation.
     Add,
@@ -392,7 +440,7 @@ pub enum BinOp {
 
 
 /// Runtime type tag used with `is` / `is not` kind-check expressions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KindType {
     /// Matches `Val::Null`.
     Null,
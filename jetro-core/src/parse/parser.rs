@@ -20,38 +20,261 @@ use super::ast::*;
 pub struct V2Parser;
 
 
-/// Returned by `parse` when the input does not conform to the grammar or when
-/// a semantic constraint (e.g. unknown cast type) is violated.
-#[derive(Debug)]
-pub struct ParseError(pub String);
+/// Returned by `parse` when the input does not conform to the grammar.
+/// Carries the failure location and the grammar rules pest expected there,
+/// so editors can underline the offending position without re-parsing.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// Short description of the failure, e.g. `expected ident or number`.
+    pub message: String,
+    /// Byte offset into the query source where parsing failed.
+    pub offset: usize,
+    /// 1-based line of `offset`.
+    pub line: usize,
+    /// 1-based column of `offset`, counted in characters.
+    pub column: usize,
+    /// Grammar rules that would have been accepted at `offset`.
+    pub expected: Vec<String>,
+    /// The offending source line followed by a caret line marking `column`.
+    pub snippet: String,
+}
 
 impl fmt::Display for ParseError {
-    /// Format the error as a human-readable message including the source snippet.
+    /// Format as `parse error at L:C: message` followed by the caret snippet.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "parse error: {}", self.0)
+        write!(
+            f,
+            "parse error at {}:{}: {}\n{}",
+            self.line, self.column, self.message, self.snippet
+        )
     }
 }
 
 impl std::error::Error for ParseError {}
 
 impl From<pest::error::Error<Rule>> for ParseError {
-    /// Convert a pest parse error into a `ParseError`, preserving the full
-    /// location and message pest provides.
+    /// Convert a pest parse error into a `ParseError`, keeping the position,
+    /// the expected-rule list, and a caret-annotated copy of the source line.
     fn from(e: pest::error::Error<Rule>) -> Self {
-        ParseError(e.to_string())
+        let offset = match e.location {
+            pest::error::InputLocation::Pos(pos) => pos,
+            pest::error::InputLocation::Span((start, _)) => start,
+        };
+        let ((line, column), span_end) = match e.line_col {
+            pest::error::LineColLocation::Pos(pos) => (pos, None),
+            pest::error::LineColLocation::Span(start, end) => (start, Some(end)),
+        };
+        let (expected, message) = match &e.variant {
+            pest::error::ErrorVariant::ParsingError { positives, .. } => {
+                let expected: Vec<String> =
+                    positives.iter().map(|rule| format!("{:?}", rule)).collect();
+                let message = if expected.is_empty() {
+                    "unexpected input".to_string()
+                } else {
+                    format!("expected {}", expected.join(" or "))
+                };
+                (expected, message)
+            }
+            pest::error::ErrorVariant::CustomError { message } => (Vec::new(), message.clone()),
+        };
+        let source_line = e.line().trim_end_matches(['\r', '\n']);
+        let width = match span_end {
+            Some((end_line, end_col)) if end_line == line && end_col > column => end_col - column,
+            _ => 1,
+        };
+        let gutter = line.to_string();
+        let pad = " ".repeat(gutter.len());
+        let snippet = format!(
+            "{gutter} | {source_line}\n{pad} | {}{}",
+            " ".repeat(column.saturating_sub(1)),
+            "^".repeat(width)
+        );
+        ParseError {
+            message,
+            offset,
+            line,
+            column,
+            expected,
+            snippet,
+        }
     }
 }
 
 
 /// Parse a Jetro query string into an `Expr` AST. This is the primary public
 /// entry point; all other `parse_*` functions are internal helpers.
+///
+/// Queries nesting deeper than the thread's limit (see
+/// `ExecutionLimits::max_nesting`) are rejected before the recursive walk.
 pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let max_nesting = crate::exec::limits::max_nesting();
+    check_source_nesting(input, max_nesting)?;
     let mut pairs = V2Parser::parse(Rule::program, input)?;
     let program = pairs.next().unwrap();
+    check_tree_nesting(&program, input, max_nesting)?;
     let expr_pair = program.into_inner().next().unwrap();
     Ok(parse_expr(expr_pair))
 }
 
+/// Build the `ParseError` reported for input nesting deeper than `max`.
+fn nesting_error(input: &str, offset: usize, max: usize) -> ParseError {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    let source_line = input[line_start..]
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('\r');
+    let gutter = line.to_string();
+    let pad = " ".repeat(gutter.len());
+    let snippet = format!(
+        "{gutter} | {source_line}\n{pad} | {}^",
+        " ".repeat(column - 1)
+    );
+    ParseError {
+        message: format!("expression nests deeper than the limit of {} levels", max),
+        offset,
+        line,
+        column,
+        expected: Vec::new(),
+        snippet,
+    }
+}
+
+/// Build the `ParseError` reported for operand chains deeper than `MAX_CHAIN_DEPTH`.
+fn chain_depth_error(input: &str, offset: usize) -> ParseError {
+    ParseError {
+        message: format!(
+            "operator chains nest deeper than the limit of {} operands",
+            MAX_CHAIN_DEPTH
+        ),
+        ..nesting_error(input, offset, MAX_CHAIN_DEPTH)
+    }
+}
+
+/// Reject input whose brackets or prefix operators (`-`, `not`) nest deeper
+/// than `max` before pest sees it; the grammar recurses once per level, so
+/// a pathological query would otherwise overflow the stack inside pest.
+fn check_source_nesting(input: &str, max: usize) -> Result<(), ParseError> {
+    let bytes = input.as_bytes();
+    let mut brackets = 0usize;
+    let mut prefix = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                prefix = 0;
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'(' | b'[' | b'{' => {
+                brackets += 1;
+                prefix = 0;
+            }
+            b')' | b']' | b'}' => {
+                brackets = brackets.saturating_sub(1);
+                prefix = 0;
+            }
+            b'-' => prefix += 1,
+            b' ' | b'\t' | b'\n' | b'\r' => {}
+            b if b.is_ascii_alphanumeric() || b == b'_' => {
+                while i + 1 < bytes.len() && (bytes[i + 1].is_ascii_alphanumeric() || bytes[i + 1] == b'_') {
+                    i += 1;
+                }
+                if &input[start..=i] == "not" {
+                    prefix += 1;
+                } else {
+                    prefix = 0;
+                }
+            }
+            _ => prefix = 0,
+        }
+        if brackets + prefix > max {
+            return Err(nesting_error(input, start, max));
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Reject parse trees whose `Expr` would nest deeper than `max`, walking the
+/// tree with an explicit stack. Counts sub-expressions, prefix operators and
+/// conditionals; a flat chain of operands (`a + b + …`, `x as int as …`,
+/// `let a = …, b = … in …`) counts as a balanced tree of them, so long chains
+/// are not mistaken for nesting. The left-leaning trees those chains do build
+/// are bounded separately by `MAX_CHAIN_DEPTH`.
+fn check_tree_nesting(program: &Pair<Rule>, input: &str, max: usize) -> Result<(), ParseError> {
+    let mut stack = vec![(program.clone(), 0usize, 0usize)];
+    while let Some((pair, depth, tree_depth)) = stack.pop() {
+        let weight = nesting_weight(&pair);
+        let depth = depth + weight.nesting;
+        if depth > max {
+            return Err(nesting_error(input, pair.as_span().start(), max));
+        }
+        let tree_depth = tree_depth + weight.tree;
+        if tree_depth > MAX_CHAIN_DEPTH {
+            return Err(chain_depth_error(input, pair.as_span().start()));
+        }
+        stack.extend(pair.into_inner().map(|child| (child, depth, tree_depth)));
+    }
+    Ok(())
+}
+
+/// Depth of the `Expr` tree a query may build out of operand chains before
+/// it is rejected. Chains count only their balanced depth against
+/// `max_nesting`, but the left-leaning tree they fold into is still walked
+/// recursively by the rewrite and planning passes, so its real depth keeps a
+/// fixed bound.
+const MAX_CHAIN_DEPTH: usize = 1024;
+
+/// Levels of nesting one parse-tree node adds on top of its parent.
+struct NestingWeight {
+    /// Counted against `max_nesting`.
+    nesting: usize,
+    /// Depth the node adds to the `Expr` tree, counted against `MAX_CHAIN_DEPTH`.
+    tree: usize,
+}
+
+/// Levels of `Expr` nesting `pair` adds on top of its parent.
+fn nesting_weight(pair: &Pair<Rule>) -> NestingWeight {
+    let nested = |levels: usize| NestingWeight {
+        nesting: levels,
+        tree: levels,
+    };
+    match pair.as_rule() {
+        Rule::expr | Rule::try_expr => nested(1),
+        Rule::not_expr | Rule::unary_expr | Rule::cond_expr => {
+            nested(usize::from(pair.clone().into_inner().nth(1).is_some()))
+        }
+        Rule::coalesce_expr | Rule::or_expr | Rule::and_expr => {
+            let terms = pair.clone().into_inner().filter(|p| !is_kw(p.as_rule())).count();
+            nested(balanced_depth(terms))
+        }
+        Rule::add_expr | Rule::mul_expr | Rule::cast_expr | Rule::let_expr => {
+            let terms = pair
+                .clone()
+                .into_inner()
+                .filter(|p| !is_kw(p.as_rule()) && !matches!(p.as_rule(), Rule::add_op | Rule::mul_op))
+                .count();
+            NestingWeight {
+                nesting: balanced_depth(terms),
+                tree: terms.saturating_sub(1),
+            }
+        }
+        _ => nested(0),
+    }
+}
+
+/// Depth `fold_balanced` adds when joining `terms` operands: `ceil(log2(terms))`.
+fn balanced_depth(terms: usize) -> usize {
+    (usize::BITS - terms.saturating_sub(1).leading_zeros()) as usize
+}
+
 
 /// Return `true` when `rule` is a keyword terminal (`and`, `or`, `not`, …).
 /// Used to skip keyword tokens that appear as decoration in binary/unary rules.
@@ -158,10 +381,11 @@ fn parse_pipeline(pair: Pair<Rule>) -> Expr {
             Rule::pipe_forward => {
                 let inner_pair = inner_step.into_inner().next().unwrap();
                 let expr = if inner_pair.as_rule() == Rule::pipe_method_call {
+                    let span = span_of(&inner_pair);
                     let mut mi = inner_pair.into_inner();
                     let name = mi.next().unwrap().as_str().to_string();
                     let args = mi.next().map(parse_arg_list).unwrap_or_default();
-                    Expr::Chain(Box::new(Expr::Current), vec![Step::Method(name, args)])
+                    Expr::Chain(Box::new(Expr::Current), vec![Step::Method(name, args, span)])
                 } else {
                     parse_expr(inner_pair)
                 };
@@ -224,35 +448,45 @@ fn parse_bind_target(pair: Pair<Rule>) -> BindTarget {
 }
 
 
-/// Parse a coalesce expression `a ?? b ?? c` left-associatively into nested
+/// Parse a coalesce expression `a ?? b ?? c` into a balanced tree of
 /// `Expr::Coalesce` nodes, returning the first non-null result at runtime.
 fn parse_coalesce(pair: Pair<Rule>) -> Expr {
-    let mut inner = pair.into_inner();
-    let first = parse_expr(inner.next().unwrap());
-    inner.fold(first, |acc, rhs| {
-        Expr::Coalesce(Box::new(acc), Box::new(parse_expr(rhs)))
-    })
+    let terms = pair.into_inner().map(parse_expr).collect();
+    fold_balanced(terms, |l, r| Expr::Coalesce(Box::new(l), Box::new(r)))
 }
 
 
-/// Parse an `or` expression `a or b or c` left-associatively, filtering keyword
-/// tokens, into nested `Expr::BinOp(_, BinOp::Or, _)` nodes.
+/// Parse an `or` expression `a or b or c`, filtering keyword tokens, into a
+/// balanced tree of `Expr::BinOp(_, BinOp::Or, _)` nodes.
 fn parse_or(pair: Pair<Rule>) -> Expr {
-    let mut inner = pair.into_inner().filter(|p| !is_kw(p.as_rule()));
-    let first = parse_expr(inner.next().unwrap());
-    inner.fold(first, |acc, rhs| {
-        Expr::BinOp(Box::new(acc), BinOp::Or, Box::new(parse_expr(rhs)))
-    })
+    let terms = pair.into_inner().filter(|p| !is_kw(p.as_rule())).map(parse_expr).collect();
+    fold_balanced(terms, |l, r| Expr::BinOp(Box::new(l), BinOp::Or, Box::new(r)))
 }
 
-/// Parse an `and` expression left-associatively, filtering keyword tokens,
-/// into nested `Expr::BinOp(_, BinOp::And, _)` nodes.
+/// Parse an `and` expression, filtering keyword tokens, into a balanced tree
+/// of `Expr::BinOp(_, BinOp::And, _)` nodes.
 fn parse_and(pair: Pair<Rule>) -> Expr {
-    let mut inner = pair.into_inner().filter(|p| !is_kw(p.as_rule()));
-    let first = parse_expr(inner.next().unwrap());
-    inner.fold(first, |acc, rhs| {
-        Expr::BinOp(Box::new(acc), BinOp::And, Box::new(parse_expr(rhs)))
-    })
+    let terms = pair.into_inner().filter(|p| !is_kw(p.as_rule())).map(parse_expr).collect();
+    fold_balanced(terms, |l, r| Expr::BinOp(Box::new(l), BinOp::And, Box::new(r)))
+}
+
+/// Join `terms` pairwise, level by level, into a tree `ceil(log2(n))` deep.
+/// Only used for associative operators whose operands still evaluate left
+/// to right under any grouping (`and`, `or`, `??`), so a long flat chain
+/// does not become a deep left-leaning `Expr`.
+fn fold_balanced(mut terms: Vec<Expr>, join: impl Fn(Expr, Expr) -> Expr) -> Expr {
+    while terms.len() > 1 {
+        let mut level = Vec::with_capacity(terms.len().div_ceil(2));
+        let mut pending = terms.into_iter();
+        while let Some(lhs) = pending.next() {
+            level.push(match pending.next() {
+                Some(rhs) => join(lhs, rhs),
+                None => lhs,
+            });
+        }
+        terms = level;
+    }
+    terms.pop().expect("chain has at least one term")
 }
 
 /// Parse a `not` expression; wraps the operand in `Expr::Not` when the first
@@ -307,6 +541,7 @@ fn parse_kind(pair: Pair<Rule>) -> Expr {
 /// `.includes(rhs)` method on the left-hand side. Returns `lhs` when no
 /// operator is present.
 fn parse_contains(pair: Pair<Rule>) -> Expr {
+    let span = span_of(&pair);
     let mut inner = pair.into_inner();
     let lhs = parse_expr(inner.next().unwrap());
     match inner.next() {
@@ -315,7 +550,7 @@ fn parse_contains(pair: Pair<Rule>) -> Expr {
             let rhs = parse_expr(inner.next().unwrap());
             Expr::Chain(
                 Box::new(lhs),
-                vec![Step::Method("includes".to_string(), vec![Arg::Pos(rhs)])],
+                vec![Step::Method("includes".to_string(), vec![Arg::Pos(rhs)], span)],
             )
         }
     }
@@ -454,9 +689,9 @@ fn parse_postfix_expr(pair: Pair<Rule>) -> Expr {
                             steps.push(Step::OptField(k));
                         }
                     }
-                    Some(Step::Method(_, _)) => {
-                        if let Some(Step::Method(n, a)) = steps.pop() {
-                            steps.push(Step::OptMethod(n, a));
+                    Some(Step::Method(..)) => {
+                        if let Some(Step::Method(n, a, span)) = steps.pop() {
+                            steps.push(Step::OptMethod(n, a, span));
                         }
                     }
                     _ => {
@@ -485,7 +720,7 @@ fn classify_chain_write(base: &Expr, steps: &[Step]) -> Option<Expr> {
     }
     let last = steps.last()?;
     let (name, args) = match last {
-        Step::Method(n, a) => (n.as_str(), a),
+        Step::Method(n, a, _) => (n.as_str(), a),
         _ => return None,
     };
     if !is_terminal_write(name) {
@@ -595,7 +830,7 @@ fn build_write_op(name: &str, args: &[Arg], path: Vec<PathStep>) -> Option<Patch
             };
             let v = Expr::Chain(
                 Box::new(Expr::Current),
-                vec![Step::Method(method, vec![Arg::Pos(arg)])],
+                vec![Step::Method(method, vec![Arg::Pos(arg)], Span::default())],
             );
             Some(PatchOp {
                 path,
@@ -649,6 +884,7 @@ fn parse_postfix_step(pair: Pair<Rule>) -> Vec<Step> {
         }
         Rule::deep_method => {
             // `$..find(pred)` etc. are parsed here and mapped to `deep_*` method names.
+            let span = span_of(&inner_pair);
             let mut mi = inner_pair.into_inner();
             let name = mi.next().unwrap().as_str().to_string();
             let args = mi.next().map(parse_arg_list).unwrap_or_default();
@@ -658,7 +894,7 @@ fn parse_postfix_step(pair: Pair<Rule>) -> Vec<Step> {
                 "like" => "deep_like".to_string(),
                 other => format!("deep_{}", other),
             };
-            vec![Step::Method(mapped, args)]
+            vec![Step::Method(mapped, args, span)]
         }
         Rule::inline_filter => {
             let expr = parse_expr(inner_pair.into_inner().next().unwrap());
@@ -673,10 +909,11 @@ fn parse_postfix_step(pair: Pair<Rule>) -> Vec<Step> {
             }
         }
         Rule::method_call => {
+            let span = span_of(&inner_pair);
             let mut mi = inner_pair.into_inner();
             let name = mi.next().unwrap().as_str().to_string();
             let args = mi.next().map(parse_arg_list).unwrap_or_default();
-            vec![Step::Method(name, args)]
+            vec![Step::Method(name, args, span)]
         }
         Rule::index_access => {
             let bi = inner_pair.into_inner().next().unwrap();
@@ -689,6 +926,7 @@ fn parse_postfix_step(pair: Pair<Rule>) -> Vec<Step> {
         Rule::map_into_shape => {
             // `[if pred] { body }` desugars to an optional `.filter(pred)` step
             // followed by a `.map(body)` step.
+            let span = span_of(&inner_pair);
             let mut guard: Option<Expr> = None;
             let mut body: Option<Expr> = None;
             let mut saw_if = false;
@@ -708,9 +946,9 @@ fn parse_postfix_step(pair: Pair<Rule>) -> Vec<Step> {
             let body = body.expect("map_into_shape requires body");
             let mut steps = Vec::new();
             if let Some(g) = guard {
-                steps.push(Step::Method("filter".into(), vec![Arg::Pos(g)]));
+                steps.push(Step::Method("filter".into(), vec![Arg::Pos(g)], span));
             }
-            steps.push(Step::Method("map".into(), vec![Arg::Pos(body)]));
+            steps.push(Step::Method("map".into(), vec![Arg::Pos(body)], span));
             steps
         }
         r => panic!("unexpected postfix rule: {:?}", r),
@@ -1157,10 +1395,17 @@ fn parse_arr(pair: Pair<Rule>) -> Expr {
 /// `Expr::GlobalCall`, used for functions that are not dot-method syntax
 /// (e.g. `coalesce(…)`, `range(…)`).
 fn parse_global_call(pair: Pair<Rule>) -> Expr {
+    let span = span_of(&pair);
     let mut inner = pair.into_inner();
     let name = inner.next().unwrap().as_str().to_string();
     let args = inner.next().map(parse_arg_list).unwrap_or_default();
-    Expr::GlobalCall { name, args }
+    Expr::GlobalCall { name, args, span }
+}
+
+/// Source range covered by `pair`, recorded on call nodes for error reporting.
+fn span_of(pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+    Span::new(span.start()..span.end())
 }
 
 
@@ -386,7 +386,7 @@ fn apply_op(op: &Opcode, stack: &mut Vec<AbstractVal>) {
             stack.push(av);
         }
         Opcode::PipelineRun { .. } => stack.push(AbstractVal::UNKNOWN),
-        Opcode::DeleteMarkErr => stack.push(AbstractVal::UNKNOWN),
+        Opcode::DeleteMarkErr | Opcode::NestingLimitErr(_) => stack.push(AbstractVal::UNKNOWN),
     }
 }
 
@@ -409,8 +409,8 @@ pub fn method_result_type(m: BuiltinMethod) -> AbstractVal {
         Upper | Lower | Capitalize | TitleCase | Trim | TrimLeft | TrimRight | ToString
         | ToJson | ToBase64 | FromBase64 | UrlEncode | UrlDecode | HtmlEscape | HtmlUnescape
         | Repeat | PadLeft | PadRight | Replace | ReplaceAll | StripPrefix | StripSuffix
-        | Indent | Dedent | Join | ToCsv | ToTsv | Type | SnakeCase | KebabCase | CamelCase
-        | PascalCase | ReverseStr | Center => AbstractVal::scalar(VType::Str),
+        | Indent | Dedent | Join | ToCsv | ToTsv | ToMsgpack | ToCbor | Type | SnakeCase
+        | KebabCase | CamelCase | PascalCase | ReverseStr | Center => AbstractVal::scalar(VType::Str),
         // Float-returning methods.
         Avg | ParseFloat => AbstractVal::scalar(VType::Float),
         // Polymorphic-numeric methods; exact type depends on input.
@@ -425,7 +425,7 @@ pub fn method_result_type(m: BuiltinMethod) -> AbstractVal {
         | Skip | Accumulate | Zip | ZipLongest | Diff | Intersect | Union | Append | Prepend
         | Remove | Matches | Scan | Slice | Bytes | IndicesOf | Explode | Implode | RollingSum
         | RollingAvg | RollingMin | RollingMax | Lag | Lead | DiffWindow | PctChange | CumMax
-        | CumMin | Zscore => AbstractVal::array(),
+        | CumMin | Zscore | FromCsv => AbstractVal::array(),
         // Object-returning methods.
         FromPairs | Invert | Pick | Omit | Merge | DeepMerge | Defaults | Rename
         | TransformKeys | TransformValues | FilterKeys | FilterValues | Pivot | GroupBy
@@ -773,7 +773,7 @@ pub fn expr_uses_ident(expr: &crate::parse::ast::Expr, name: &str) -> bool {
             }
             steps.iter().any(|s| match s {
                 Step::DynIndex(e) | Step::InlineFilter(e) => expr_uses_ident(e, name),
-                Step::Method(_, args) | Step::OptMethod(_, args) => args.iter().any(|a| match a {
+                Step::Method(_, args, _) | Step::OptMethod(_, args, _) => args.iter().any(|a| match a {
                     Arg::Pos(e) | Arg::Named(_, e) => expr_uses_ident(e, name),
                 }),
                 _ => false,
@@ -926,7 +926,7 @@ pub fn expr_is_pure(expr: &crate::parse::ast::Expr) -> bool {
             }
             steps.iter().all(|s| match s {
                 Step::DynIndex(e) | Step::InlineFilter(e) => expr_is_pure(e),
-                Step::Method(_, args) | Step::OptMethod(_, args) => args.iter().all(|a| match a {
+                Step::Method(_, args, _) | Step::OptMethod(_, args, _) => args.iter().all(|a| match a {
                     Arg::Pos(e) | Arg::Named(_, e) => expr_is_pure(e),
                 }),
                 _ => true,
@@ -934,6 +934,8 @@ pub fn expr_is_pure(expr: &crate::parse::ast::Expr) -> bool {
         }
         Expr::BinOp(l, _, r) | Expr::Coalesce(l, r) => expr_is_pure(l) && expr_is_pure(r),
         Expr::UnaryNeg(e) | Expr::Not(e) | Expr::Kind { expr: e, .. } => expr_is_pure(e),
+        // Impure host calls are lowered to global calls, so never elide one.
+        Expr::GlobalCall { .. } => false,
         // Conservatively treat all other forms (lambdas, patches, comprehensions) as pure
         // since they don't mutate shared state in the current runtime.
         _ => true,
@@ -1096,6 +1098,7 @@ fn rewrite_call(
         sub_progs: new_subs.into(),
         orig_args: c.orig_args.clone(),
         demand_max_keep: c.demand_max_keep,
+        span: c.span,
     })
 }
 
@@ -1191,7 +1194,7 @@ pub fn opcode_cost(op: &Opcode) -> u32 {
         Opcode::Quantifier(_) => 2,
         Opcode::CastOp(_) => 2,
         Opcode::PatchEval(_) => 50,
-        Opcode::DeleteMarkErr => 1,
+        Opcode::DeleteMarkErr | Opcode::NestingLimitErr(_) => 1,
         Opcode::PipelineRun { base, steps } => {
             program_cost(base)
                 + steps
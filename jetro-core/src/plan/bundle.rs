@@ -0,0 +1,312 @@
+//! Versioned binary bundles of a `JetroEngine`'s cached plans.
+//!
+//! A bundle is the magic `JETROPLN`, a little-endian `u32` format version, a
+//! bincode header, the bincode-encoded plans least recently used first, and a
+//! little-endian `u64` FNV-1a checksum of the plan bytes. Plans carry their
+//! compiled `Program`s, so importing a bundle skips both planning and bytecode
+//! compilation; inline-cache slots are not written, and decoding rebuilds them
+//! cold, one per opcode or step. The header records everything a plan bakes in
+//! at planning time — the crate version, the builtin table, the engine's
+//! host-function signatures and its nesting limit — and import refuses a bundle
+//! whose header does not match the importing engine, before decoding any plan.
+//! Decoding is bounded by the bytes actually present, so a corrupt bundle is
+//! reported as malformed rather than allocating whatever lengths it claims.
+
+use std::hash::Hasher;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::builtins::host::HostRegistry;
+use crate::ir::physical::QueryPlan;
+use crate::plan::cache::{CachedPlan, PlanCache};
+use crate::plan::physical::PlanningContext;
+
+/// First bytes of every bundle.
+const MAGIC: &[u8; 8] = b"JETROPLN";
+/// Bumped whenever the bundle layout itself changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Error returned by `JetroEngine::export_cache` and `JetroEngine::import_cache`.
+#[derive(Debug)]
+pub enum CacheBundleError {
+    /// Reading or writing the bundle failed.
+    Io(std::io::Error),
+    /// The input does not start with the bundle magic.
+    NotABundle,
+    /// The bundle was written in a layout this build cannot read.
+    FormatVersion { found: u32, expected: u32 },
+    /// The bundle was written by a different version of this crate.
+    CrateVersion {
+        found: String,
+        expected: &'static str,
+    },
+    /// The bundle was compiled against a different builtin table.
+    BuiltinTable,
+    /// The bundle was compiled against different host functions than the
+    /// importing engine has registered.
+    HostFunctions,
+    /// The bundle was compiled under a different nesting limit.
+    NestingLimit { found: usize, expected: usize },
+    /// The bundle is corrupt: its header or plans could not be decoded, or
+    /// the plans do not match their checksum.
+    Malformed(String),
+}
+
+impl std::fmt::Display for CacheBundleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "plan bundle I/O error: {}", err),
+            Self::NotABundle => write!(f, "input is not a plan bundle"),
+            Self::FormatVersion { found, expected } => write!(
+                f,
+                "plan bundle format version {} is not supported (expected {})",
+                found, expected
+            ),
+            Self::CrateVersion { found, expected } => write!(
+                f,
+                "plan bundle was written by jetro {} (this is {})",
+                found, expected
+            ),
+            Self::BuiltinTable => {
+                write!(f, "plan bundle was built against a different builtin table")
+            }
+            Self::HostFunctions => {
+                write!(f, "plan bundle was built against different host functions")
+            }
+            Self::NestingLimit { found, expected } => write!(
+                f,
+                "plan bundle was built with nesting limit {} (engine uses {})",
+                found, expected
+            ),
+            Self::Malformed(msg) => write!(f, "malformed plan bundle: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CacheBundleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CacheBundleError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<bincode::Error> for CacheBundleError {
+    fn from(err: bincode::Error) -> Self {
+        match *err {
+            bincode::ErrorKind::Io(err) => Self::Io(err),
+            other => Self::Malformed(other.to_string()),
+        }
+    }
+}
+
+/// Everything after the format version that must match the importing engine.
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct Header {
+    crate_version: String,
+    builtins: u64,
+    host_functions: u64,
+    max_nesting: u64,
+}
+
+impl Header {
+    /// Describe the engine that owns `host` and plans under `max_nesting`.
+    fn current(host: &HostRegistry, max_nesting: usize) -> Self {
+        let mut builtins = Fnv1a::default();
+        crate::builtins::registry::hash_table(&mut builtins);
+        let mut host_functions = Fnv1a::default();
+        host.hash_signatures(&mut host_functions);
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            builtins: builtins.finish(),
+            host_functions: host_functions.finish(),
+            max_nesting: max_nesting as u64,
+        }
+    }
+
+    /// Explain why a bundle written with `self` cannot load into `expected`.
+    fn check(self, expected: &Self) -> Result<(), CacheBundleError> {
+        if self.crate_version != expected.crate_version {
+            return Err(CacheBundleError::CrateVersion {
+                found: self.crate_version,
+                expected: env!("CARGO_PKG_VERSION"),
+            });
+        }
+        if self.builtins != expected.builtins {
+            return Err(CacheBundleError::BuiltinTable);
+        }
+        if self.host_functions != expected.host_functions {
+            return Err(CacheBundleError::HostFunctions);
+        }
+        if self.max_nesting != expected.max_nesting {
+            return Err(CacheBundleError::NestingLimit {
+                found: self.max_nesting as usize,
+                expected: expected.max_nesting as usize,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One plan as stored in a bundle.
+#[derive(Serialize, Deserialize)]
+struct BundledPlan {
+    context: String,
+    bound: Vec<Arc<str>>,
+    expr: String,
+    build_time: Duration,
+    plan: Arc<QueryPlan>,
+}
+
+/// Write every plan in `cache` to `writer`, returning how many were written.
+pub(crate) fn export<W: Write>(
+    cache: &PlanCache,
+    host: &HostRegistry,
+    max_nesting: usize,
+    mut writer: W,
+) -> Result<usize, CacheBundleError> {
+    let plans: Vec<BundledPlan> = cache
+        .export()
+        .into_iter()
+        .map(|cached| BundledPlan {
+            context: cached.context.cache_key().to_string(),
+            bound: cached.bound.into_vec(),
+            expr: cached.expr,
+            build_time: cached.build_time,
+            plan: cached.plan,
+        })
+        .collect();
+    let body = codec().serialize(&plans)?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    codec().serialize_into(&mut writer, &Header::current(host, max_nesting))?;
+    writer.write_all(&body)?;
+    writer.write_all(&checksum(&body).to_le_bytes())?;
+    writer.flush()?;
+    Ok(plans.len())
+}
+
+/// Validate the bundle in `reader` against the importing engine and add its
+/// plans to `cache`, returning how many were added. Nothing is added unless
+/// the whole bundle decodes.
+pub(crate) fn import<R: Read>(
+    cache: &PlanCache,
+    host: &HostRegistry,
+    max_nesting: usize,
+    mut reader: R,
+) -> Result<usize, CacheBundleError> {
+    let mut magic = [0u8; 8];
+    match reader.read_exact(&mut magic) {
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Err(CacheBundleError::NotABundle)
+        }
+        other => other?,
+    }
+    if &magic != MAGIC {
+        return Err(CacheBundleError::NotABundle);
+    }
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        return Err(CacheBundleError::FormatVersion {
+            found: version,
+            expected: FORMAT_VERSION,
+        });
+    }
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest)?;
+    let mut input = rest.as_slice();
+    let header: Header = codec()
+        .with_limit(input.len() as u64)
+        .deserialize_from(&mut input)
+        .map_err(malformed)?;
+    header.check(&Header::current(host, max_nesting))?;
+
+    let Some(split) = input.len().checked_sub(8) else {
+        return Err(CacheBundleError::Malformed("missing checksum".to_string()));
+    };
+    let (body, sum) = input.split_at(split);
+    if checksum(body).to_le_bytes() != sum {
+        return Err(CacheBundleError::Malformed("checksum mismatch".to_string()));
+    }
+    let plans: Vec<BundledPlan> = codec()
+        .with_limit(body.len() as u64)
+        .deserialize(body)
+        .map_err(malformed)?;
+    let plans = plans
+        .into_iter()
+        .map(|bundled| {
+            let context = PlanningContext::from_cache_key(&bundled.context).ok_or_else(|| {
+                CacheBundleError::Malformed(format!(
+                    "unknown planning context `{}`",
+                    bundled.context
+                ))
+            })?;
+            Ok(CachedPlan {
+                context,
+                bound: bundled.bound.into(),
+                expr: bundled.expr,
+                build_time: bundled.build_time,
+                plan: bundled.plan,
+            })
+        })
+        .collect::<Result<Vec<_>, CacheBundleError>>()?;
+    let count = plans.len();
+    for cached in plans {
+        cache.insert(cached);
+    }
+    Ok(count)
+}
+
+/// Bincode configuration for headers and plans: fixed-width integers, so
+/// header fields sit at stable offsets.
+fn codec() -> impl Options {
+    bincode::DefaultOptions::new().with_fixint_encoding()
+}
+
+/// Any failure to decode bytes already read, including running off their end.
+fn malformed(err: bincode::Error) -> CacheBundleError {
+    CacheBundleError::Malformed(err.to_string())
+}
+
+/// Checksum of the encoded plans.
+pub(crate) fn checksum(body: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(body);
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a, used for header fingerprints because, unlike `DefaultHasher`,
+/// its output is fixed across Rust releases.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,292 @@
+//! Bounded LRU cache of physical plans owned by a `JetroEngine`.
+//!
+//! Entries are keyed on the planning context, the externally bound variable
+//! names, and the expression source with insignificant whitespace removed, so
+//! `$.a.map( x )` and `$.a.map(x)` share one plan. Plans are stored behind an
+//! `Arc`, making a hit a refcount bump rather than a deep clone. Hit, miss and
+//! eviction counters, plus the planning time hits avoided, are kept in atomics
+//! so reading them never contends with lookups.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ir::physical::QueryPlan;
+use crate::plan::physical::PlanningContext;
+
+/// Point-in-time counters for a `JetroEngine`'s plan cache, returned by
+/// `JetroEngine::plan_cache_stats`. Counters are cumulative over the engine's
+/// lifetime; `clear_cache` drops entries but does not reset them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlanCacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that had to plan the expression.
+    pub misses: u64,
+    /// Entries dropped to make room for newer ones.
+    pub evictions: u64,
+    /// Plans currently cached.
+    pub entries: usize,
+    /// Most plans the cache holds; 0 means caching is disabled.
+    pub capacity: usize,
+    /// Planning time avoided by hits, measured from each plan's own build time.
+    pub time_saved: Duration,
+}
+
+/// Identity of a cached plan; see the module docs for what it covers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct PlanKey {
+    context: &'static str,
+    bound: Box<[Arc<str>]>,
+    expr: String,
+}
+
+/// A cached plan plus what it cost to build and its position in the LRU order.
+struct Entry {
+    plan: Arc<QueryPlan>,
+    build_time: Duration,
+    tick: u64,
+}
+
+/// Entries plus their recency index; `order` maps each entry's last-use tick
+/// to its key, so the least-recently-used entry is always `order`'s first.
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<PlanKey, Entry>,
+    order: BTreeMap<u64, PlanKey>,
+    next_tick: u64,
+}
+
+impl Lru {
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+}
+
+/// One cached plan together with its key, as exported to or imported from a
+/// plan bundle.
+pub(crate) struct CachedPlan {
+    pub(crate) context: PlanningContext,
+    pub(crate) bound: Box<[Arc<str>]>,
+    pub(crate) expr: String,
+    pub(crate) build_time: Duration,
+    pub(crate) plan: Arc<QueryPlan>,
+}
+
+/// Bounded LRU of `Arc<QueryPlan>` with usage counters.
+pub(crate) struct PlanCache {
+    lru: Mutex<Lru>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    saved_nanos: AtomicU64,
+}
+
+impl PlanCache {
+    /// Create an empty cache holding at most `capacity` plans; 0 disables caching.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            lru: Mutex::new(Lru::default()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            saved_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the cached plan for `expr`, or build it with `plan` and cache it.
+    /// `plan` runs without the cache lock held, so a slow plan never blocks
+    /// lookups of other expressions.
+    pub(crate) fn get_or_plan(
+        &self,
+        expr: &str,
+        context: PlanningContext,
+        bound: &[Arc<str>],
+        plan: impl FnOnce() -> QueryPlan,
+    ) -> Arc<QueryPlan> {
+        let key = PlanKey {
+            context: context.cache_key(),
+            bound: bound.into(),
+            expr: normalize_expr(expr),
+        };
+        {
+            let mut lru = self.lock();
+            let tick = lru.tick();
+            if let Some(entry) = lru.entries.get_mut(&key) {
+                let old = std::mem::replace(&mut entry.tick, tick);
+                let found = Arc::clone(&entry.plan);
+                let saved = entry.build_time;
+                let key = lru.order.remove(&old).expect("lru order tracks every entry");
+                lru.order.insert(tick, key);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                self.saved_nanos
+                    .fetch_add(saved.as_nanos() as u64, Ordering::Relaxed);
+                return found;
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let built = Arc::new(plan());
+        let build_time = started.elapsed();
+        if self.capacity == 0 {
+            return built;
+        }
+
+        let mut lru = self.lock();
+        if let Some(entry) = lru.entries.get(&key) {
+            // another thread planned the same expression while we were planning
+            return Arc::clone(&entry.plan);
+        }
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        let tick = lru.tick();
+        lru.order.insert(tick, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                plan: Arc::clone(&built),
+                build_time,
+                tick,
+            },
+        );
+        built
+    }
+
+    /// Drop every cached plan, keeping the counters.
+    pub(crate) fn clear(&self) {
+        let mut lru = self.lock();
+        lru.entries.clear();
+        lru.order.clear();
+    }
+
+    /// Return every cached plan with its key, least recently used first.
+    pub(crate) fn export(&self) -> Vec<CachedPlan> {
+        let lru = self.lock();
+        lru.order
+            .values()
+            .map(|key| {
+                let entry = &lru.entries[key];
+                CachedPlan {
+                    context: PlanningContext::from_cache_key(key.context)
+                        .expect("cache keys come from PlanningContext::cache_key"),
+                    bound: key.bound.clone(),
+                    expr: key.expr.clone(),
+                    build_time: entry.build_time,
+                    plan: Arc::clone(&entry.plan),
+                }
+            })
+            .collect()
+    }
+
+    /// Cache `cached` as the most recently used plan, replacing any plan under
+    /// the same key and evicting as `get_or_plan` would. Counts neither a hit
+    /// nor a miss.
+    pub(crate) fn insert(&self, cached: CachedPlan) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = PlanKey {
+            context: cached.context.cache_key(),
+            bound: cached.bound,
+            expr: normalize_expr(&cached.expr),
+        };
+        let mut lru = self.lock();
+        if let Some(old) = lru.entries.remove(&key) {
+            lru.order.remove(&old.tick);
+        }
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        let tick = lru.tick();
+        lru.order.insert(tick, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                plan: cached.plan,
+                build_time: cached.build_time,
+                tick,
+            },
+        );
+    }
+
+    /// Snapshot the counters and current size.
+    pub(crate) fn stats(&self) -> PlanCacheStats {
+        PlanCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.lock().entries.len(),
+            capacity: self.capacity,
+            time_saved: Duration::from_nanos(self.saved_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Return every cached plan, least recently used first.
+    #[cfg(test)]
+    pub(crate) fn plans(&self) -> Vec<Arc<QueryPlan>> {
+        let lru = self.lock();
+        lru.order
+            .values()
+            .map(|key| Arc::clone(&lru.entries[key].plan))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().expect("plan cache poisoned")
+    }
+}
+
+/// Remove whitespace that cannot affect how `expr` parses: leading and
+/// trailing runs, and runs next to a bracket, `,` or `:`. Any other run
+/// collapses to one space, since it may separate tokens (`$.a - b` is not
+/// `$.a-b`). String literals are copied verbatim.
+pub(crate) fn normalize_expr(expr: &str) -> String {
+    let mut out = String::with_capacity(expr.len());
+    let mut chars = expr.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if !c.is_ascii_whitespace() {
+            if c == '"' || c == '\'' {
+                quote = Some(c);
+            }
+            out.push(c);
+            continue;
+        }
+        while chars.next_if(char::is_ascii_whitespace).is_some() {}
+        let (Some(prev), Some(&next)) = (out.chars().next_back(), chars.peek()) else {
+            continue;
+        };
+        // `.{` is one token, so `. {` must keep its space
+        let separates = prev == '.' && next == '{';
+        if separates || !(is_separator(prev) || is_separator(next)) {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// Characters that always form a token on their own.
+fn is_separator(c: char) -> bool {
+    matches!(c, '(' | ')' | '[' | ']' | '{' | '}' | ',' | ':')
+}
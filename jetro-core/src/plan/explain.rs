@@ -0,0 +1,609 @@
+//! `EXPLAIN` support: a printable, serialisable view of a physical `QueryPlan`.
+//!
+//! `JetroEngine::explain` plans an expression exactly as `collect` would and
+//! turns the result into an `Explain` tree: one `ExplainNode` per `PlanNode`
+//! with its backend preferences, capabilities, and `ExecutionFacts`, plus the
+//! stage strategies and sink demand of every pipeline node.
+//!
+//! Rewrite passes report themselves through `record_pass`. Recording is a
+//! no-op unless `record_passes` has installed a log on the current thread, so
+//! the hot planning path only pays a thread-local check.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::builtins::host::HostRegistry;
+use crate::exec::pipeline::{
+    compute_strategies_with_kernels, select_exec_path, sink_name, PhysicalExecPath, Pipeline,
+    PipelineBody, Sink, Stage,
+};
+use crate::ir::physical::{
+    BackendPreference, BackendSet, ExecutionFacts, NodeId, PhysicalArrayElem, PhysicalChainStep,
+    PhysicalObjField, PhysicalPathStep, PipelinePlanSource, PlanNode, QueryPlan, QueryRoot,
+};
+use crate::parse::parser::{self, ParseError};
+use crate::plan::physical::{plan_query_with_bindings, PlanningContext};
+
+/// Every backend, in the order the planner prefers them by default.
+const ALL_BACKENDS: [BackendPreference; 8] = [
+    BackendPreference::Structural,
+    BackendPreference::TapeView,
+    BackendPreference::TapeRows,
+    BackendPreference::TapePath,
+    BackendPreference::ValView,
+    BackendPreference::MaterializedSource,
+    BackendPreference::FastChildren,
+    BackendPreference::Interpreted,
+];
+
+/// The physical plan chosen for one expression, as returned by
+/// `JetroEngine::explain`. `Display` prints an indented tree; `Serialize`
+/// yields the same information as structured data.
+///
+/// ```rust
+/// use jetro_core::{Jetro, JetroEngine};
+///
+/// let engine = JetroEngine::new();
+/// let j = Jetro::from_bytes(br#"{"books":[{"price":12},{"price":3}]}"#.to_vec()).unwrap();
+/// let plan = engine.explain("$.books.sort_by(price).first()", &j).unwrap();
+/// let pipeline = plan.root.pipeline.as_ref().unwrap();
+/// assert_eq!(pipeline.stages[0].strategy, "SortTopK(1)");
+/// println!("{plan}");
+/// ```
+#[derive(Clone, Debug, Serialize)]
+pub struct Explain {
+    /// Input representation the plan was built for: `"bytes"` or `"val"`.
+    pub input: &'static str,
+    /// Root of the plan tree.
+    pub root: ExplainNode,
+    /// Rewrite passes that changed the query while it was planned, in the
+    /// order they first fired.
+    pub passes: Vec<&'static str>,
+}
+
+/// One node of an `Explain` tree.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExplainNode {
+    /// `PlanNode` variant name, e.g. `"Pipeline"`, `"RootPath"`, `"Vm"`.
+    pub kind: &'static str,
+    /// Variant-specific detail: a path, method name, operator, or literal.
+    pub detail: Option<String>,
+    /// Backends the executor will try for this node, in order.
+    pub backends: Vec<&'static str>,
+    /// Every backend this node kind could use in some input mode.
+    pub capabilities: Vec<&'static str>,
+    /// Facts the planner derived for this node.
+    pub facts: ExplainFacts,
+    /// Stage and sink layout for `Pipeline` nodes.
+    pub pipeline: Option<ExplainPipeline>,
+    /// Child nodes in evaluation order.
+    pub children: Vec<ExplainNode>,
+}
+
+/// Planner facts attached to an `ExplainNode`; mirrors `ExecutionFacts`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ExplainFacts {
+    /// The node can run without materialising the whole document.
+    pub can_avoid_root_materialization: bool,
+    /// The node produces a streamable row sequence.
+    pub can_stream_rows: bool,
+    /// The node can read straight from the simd-json tape.
+    pub can_use_tape: bool,
+    /// Some descendant unconditionally falls through to the VM.
+    pub contains_vm_fallback: bool,
+    /// The node may need to materialise its source array.
+    pub may_materialize_source: bool,
+}
+
+/// Stage and sink layout of a `Pipeline` node.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExplainPipeline {
+    /// `$.a.b` for field-chain sources; `"expr"` when the first child
+    /// computes the receiver.
+    pub source: String,
+    /// Specialised executor tried first: `Indexed`, `Columnar`, `Composed`,
+    /// or `Legacy`.
+    pub exec_path: &'static str,
+    /// Stages after optimisation, in execution order.
+    pub stages: Vec<ExplainStage>,
+    /// Terminal sink, e.g. `"collect"`, `"first"`, `"nth(2)"`, `"sum"`.
+    pub sink: String,
+    /// How many inputs the sink pulls, e.g. `All`, `FirstInput(1)`, `NthInput(2)`.
+    pub sink_demand: String,
+    /// The sink demand after propagating through every stage: what the
+    /// source actually has to produce.
+    pub source_demand: String,
+}
+
+/// One optimised pipeline stage.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExplainStage {
+    /// Builtin the stage runs, e.g. `"filter"`, `"sort"`, `"take"`.
+    pub name: &'static str,
+    /// Execution strategy: `Default`, or a bounded sort such as `SortTopK(3)`.
+    pub strategy: String,
+}
+
+thread_local! {
+    static PASS_LOG: RefCell<Option<Vec<&'static str>>> = const { RefCell::new(None) };
+}
+
+/// Note that the rewrite pass `name` changed the plan being built.
+#[inline]
+pub(crate) fn record_pass(name: &'static str) {
+    PASS_LOG.with(|log| {
+        if let Some(log) = log.borrow_mut().as_mut() {
+            log.push(name);
+        }
+    });
+}
+
+/// Return a marker for the current end of the pass log, so a speculative
+/// lowering that is later abandoned can `discard_passes_since` it.
+#[inline]
+pub(crate) fn pass_mark() -> usize {
+    PASS_LOG.with(|log| log.borrow().as_ref().map_or(0, Vec::len))
+}
+
+/// Drop passes recorded after `mark`.
+#[inline]
+pub(crate) fn discard_passes_since(mark: usize) {
+    PASS_LOG.with(|log| {
+        if let Some(log) = log.borrow_mut().as_mut() {
+            log.truncate(mark);
+        }
+    });
+}
+
+/// Restores the previous pass log when dropped, so unwinding out of the
+/// planner never leaves recording switched on.
+struct PassLogScope {
+    previous: Option<Vec<&'static str>>,
+}
+
+impl Drop for PassLogScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        PASS_LOG.with(|log| *log.borrow_mut() = previous);
+    }
+}
+
+/// Run `f` with pass recording enabled and return the distinct passes that
+/// fired, in first-fired order.
+fn record_passes<R>(f: impl FnOnce() -> R) -> (R, Vec<&'static str>) {
+    let scope = PassLogScope {
+        previous: PASS_LOG.with(|log| log.borrow_mut().replace(Vec::new())),
+    };
+    let out = f();
+    let fired = PASS_LOG
+        .with(|log| log.borrow_mut().take())
+        .unwrap_or_default();
+    drop(scope);
+    let mut passes: Vec<&'static str> = Vec::with_capacity(fired.len());
+    for name in fired {
+        if !passes.contains(&name) {
+            passes.push(name);
+        }
+    }
+    (out, passes)
+}
+
+/// Plan `expr` for `context` the way `JetroEngine` does and describe the result.
+pub(crate) fn explain_query(
+    expr: &str,
+    context: PlanningContext,
+    host: &Arc<HostRegistry>,
+) -> Result<Explain, ParseError> {
+    parser::parse(expr)?;
+    let (plan, passes) = record_passes(|| plan_query_with_bindings(expr, context, &[], Some(host)));
+    let root = match plan.root() {
+        QueryRoot::Node(id) => explain_node(&plan, *id),
+        QueryRoot::SourceVm(source) => ExplainNode {
+            kind: "SourceVm",
+            detail: Some(source.to_string()),
+            backends: vec![backend_name(BackendPreference::Interpreted)],
+            capabilities: vec![backend_name(BackendPreference::Interpreted)],
+            facts: ExplainFacts {
+                contains_vm_fallback: true,
+                ..ExplainFacts::default()
+            },
+            pipeline: None,
+            children: Vec::new(),
+        },
+    };
+    Ok(Explain {
+        input: context.cache_key(),
+        root,
+        passes,
+    })
+}
+
+/// Describe node `id` and, recursively, its children.
+fn explain_node(plan: &QueryPlan, id: NodeId) -> ExplainNode {
+    let node = plan.node(id);
+    let outline = node_outline(plan, id);
+    ExplainNode {
+        kind: kind_name(node),
+        detail: outline.detail,
+        backends: plan
+            .backend_preferences(id)
+            .iter()
+            .map(|backend| backend_name(*backend))
+            .collect(),
+        capabilities: capability_names(plan.backend_capabilities(id)),
+        facts: plan.execution_facts(id).into(),
+        pipeline: outline.pipeline,
+        children: outline
+            .children
+            .into_iter()
+            .map(|child| explain_node(plan, child))
+            .collect(),
+    }
+}
+
+/// The plan-shape half of an `ExplainNode`, shared with the runtime profile.
+pub(crate) struct NodeOutline {
+    /// Variant-specific detail, as in `ExplainNode::detail`.
+    pub(crate) detail: Option<String>,
+    /// Child node ids in evaluation order.
+    pub(crate) children: Vec<NodeId>,
+    /// Stage and sink layout for `Pipeline` nodes.
+    pub(crate) pipeline: Option<ExplainPipeline>,
+}
+
+/// Describe node `id` without recursing into its children.
+pub(crate) fn node_outline(plan: &QueryPlan, id: NodeId) -> NodeOutline {
+    let node = plan.node(id);
+    let mut children = Vec::new();
+    let mut pipeline = None;
+    let detail = match node {
+        PlanNode::Literal(val) => Some(serde_json::Value::from(val.clone()).to_string()),
+        PlanNode::Root | PlanNode::Current | PlanNode::Structural { .. } => None,
+        PlanNode::Ident(name) | PlanNode::Local(name) => Some(name.to_string()),
+        PlanNode::Pipeline { source, body } => {
+            let source = match source {
+                PipelinePlanSource::FieldChain { keys } => {
+                    let mut path = String::from("$");
+                    for key in keys.iter() {
+                        path.push('.');
+                        path.push_str(key);
+                    }
+                    path
+                }
+                PipelinePlanSource::Expr(source) => {
+                    children.push(*source);
+                    "expr".to_string()
+                }
+            };
+            let described = explain_pipeline(source, body);
+            let detail = described.source.clone();
+            pipeline = Some(described);
+            Some(detail)
+        }
+        PlanNode::SharedScan {
+            keys,
+            prefix,
+            branches,
+        } => {
+            let source = crate::exec::interpreted::field_chain_path(keys);
+            pipeline = prefix
+                .as_ref()
+                .map(|prefix| explain_pipeline(source.clone(), prefix));
+            Some(format!("{source} ({} branches)", branches.len()))
+        }
+        PlanNode::ScanSlot { scan, slot } => {
+            children.push(*scan);
+            if let PlanNode::SharedScan { keys, branches, .. } = plan.node(*scan) {
+                let source = crate::exec::interpreted::field_chain_path(keys);
+                pipeline = Some(explain_pipeline(source, &branches[*slot]));
+            }
+            Some(format!("slot {slot}"))
+        }
+        PlanNode::RootPath(steps) => {
+            let mut path = String::from("$");
+            for step in steps {
+                match step {
+                    PhysicalPathStep::Field(key) => {
+                        path.push('.');
+                        path.push_str(key);
+                    }
+                    PhysicalPathStep::Index(idx) => path.push_str(&format!("[{idx}]")),
+                }
+            }
+            Some(path)
+        }
+        PlanNode::Chain { base, steps } => {
+            children.push(*base);
+            let mut path = String::new();
+            for step in steps {
+                match step {
+                    PhysicalChainStep::Field(key) => {
+                        path.push('.');
+                        path.push_str(key);
+                    }
+                    PhysicalChainStep::Index(idx) => path.push_str(&format!("[{idx}]")),
+                    PhysicalChainStep::DynIndex(idx) => {
+                        children.push(*idx);
+                        path.push_str("[..]");
+                    }
+                }
+            }
+            Some(path)
+        }
+        PlanNode::Call {
+            receiver,
+            call,
+            optional,
+            ..
+        } => {
+            children.push(*receiver);
+            let name = call.method.name();
+            Some(if *optional {
+                format!("{name}?")
+            } else {
+                name.to_string()
+            })
+        }
+        PlanNode::UnaryNeg(inner) | PlanNode::Not(inner) => {
+            children.push(*inner);
+            None
+        }
+        PlanNode::Binary { lhs, op, rhs } => {
+            children.extend([*lhs, *rhs]);
+            Some(format!("{op:?}"))
+        }
+        PlanNode::Kind { expr, ty, negate } => {
+            children.push(*expr);
+            Some(if *negate {
+                format!("not {ty:?}")
+            } else {
+                format!("{ty:?}")
+            })
+        }
+        PlanNode::Coalesce { lhs, rhs } => {
+            children.extend([*lhs, *rhs]);
+            None
+        }
+        PlanNode::IfElse { cond, then_, else_ } => {
+            children.extend([*cond, *then_, *else_]);
+            None
+        }
+        PlanNode::Try { body, default } => {
+            children.extend([*body, *default]);
+            None
+        }
+        PlanNode::Object(fields) => {
+            let mut keys = Vec::with_capacity(fields.len());
+            for field in fields {
+                match field {
+                    PhysicalObjField::Kv { key, val, cond, .. } => {
+                        keys.push(key.to_string());
+                        children.extend(cond.iter().copied());
+                        children.push(*val);
+                    }
+                    PhysicalObjField::Short(key) => keys.push(key.to_string()),
+                    PhysicalObjField::Dynamic { key, val } => {
+                        keys.push("[..]".to_string());
+                        children.extend([*key, *val]);
+                    }
+                    PhysicalObjField::Spread(inner) => {
+                        keys.push("...".to_string());
+                        children.push(*inner);
+                    }
+                    PhysicalObjField::SpreadDeep(inner) => {
+                        keys.push("**".to_string());
+                        children.push(*inner);
+                    }
+                }
+            }
+            Some(keys.join(", "))
+        }
+        PlanNode::Array(elems) => {
+            for elem in elems {
+                match elem {
+                    PhysicalArrayElem::Expr(inner) | PhysicalArrayElem::Spread(inner) => {
+                        children.push(*inner)
+                    }
+                }
+            }
+            None
+        }
+        PlanNode::Let { name, init, body } => {
+            children.extend([*init, *body]);
+            Some(name.to_string())
+        }
+        PlanNode::Vm(program) => Some(format!("{} ops", program.ops.len())),
+    };
+    NodeOutline {
+        detail,
+        children,
+        pipeline,
+    }
+}
+
+/// Describe the stages, strategies, and demand of one pipeline body.
+fn explain_pipeline(source: String, body: &PipelineBody) -> ExplainPipeline {
+    let strategies = compute_strategies_with_kernels(&body.stages, &body.stage_kernels, &body.sink);
+    let stages = body
+        .stages
+        .iter()
+        .zip(strategies)
+        .map(|(stage, strategy)| ExplainStage {
+            name: stage_name(stage),
+            strategy: format!("{strategy:?}"),
+        })
+        .collect();
+    let exec_path = select_exec_path(&body.stages, &body.sink);
+    let source_demand = Pipeline::segment_source_demand(&body.stages, &body.sink);
+    ExplainPipeline {
+        source,
+        exec_path: match exec_path {
+            PhysicalExecPath::Indexed => "Indexed",
+            PhysicalExecPath::Columnar => "Columnar",
+            PhysicalExecPath::Composed => "Composed",
+            PhysicalExecPath::Legacy => "Legacy",
+        },
+        stages,
+        sink: sink_label(&body.sink),
+        sink_demand: format!("{:?}", body.sink.demand().chain.pull),
+        source_demand: format!("{:?}", source_demand.chain.pull),
+    }
+}
+
+/// Builtin name for `stage`; synthetic stages get a descriptive label.
+fn stage_name(stage: &Stage) -> &'static str {
+    match stage {
+        Stage::CompiledMap(_) => "compiled_map",
+        Stage::SortedDedup(_) => "sorted_dedup",
+        _ => stage
+            .descriptor()
+            .and_then(|desc| desc.method)
+            .map_or("stage", |method| method.name()),
+    }
+}
+
+/// Short label for `sink`, including the index for `nth`.
+fn sink_label(sink: &Sink) -> String {
+    match sink {
+        Sink::Nth(idx) => format!("nth({idx})"),
+        Sink::Terminal(method) => method.name().to_string(),
+        _ => sink_name(sink).to_string(),
+    }
+}
+
+/// `PlanNode` variant name.
+pub(crate) fn kind_name(node: &PlanNode) -> &'static str {
+    match node {
+        PlanNode::Literal(_) => "Literal",
+        PlanNode::Root => "Root",
+        PlanNode::Current => "Current",
+        PlanNode::Ident(_) => "Ident",
+        PlanNode::Local(_) => "Local",
+        PlanNode::Pipeline { .. } => "Pipeline",
+        PlanNode::SharedScan { .. } => "SharedScan",
+        PlanNode::ScanSlot { .. } => "ScanSlot",
+        PlanNode::Structural { .. } => "Structural",
+        PlanNode::RootPath(_) => "RootPath",
+        PlanNode::Chain { .. } => "Chain",
+        PlanNode::Call { .. } => "Call",
+        PlanNode::UnaryNeg(_) => "UnaryNeg",
+        PlanNode::Not(_) => "Not",
+        PlanNode::Binary { .. } => "Binary",
+        PlanNode::Kind { .. } => "Kind",
+        PlanNode::Coalesce { .. } => "Coalesce",
+        PlanNode::IfElse { .. } => "IfElse",
+        PlanNode::Try { .. } => "Try",
+        PlanNode::Object(_) => "Object",
+        PlanNode::Array(_) => "Array",
+        PlanNode::Let { .. } => "Let",
+        PlanNode::Vm(_) => "Vm",
+    }
+}
+
+/// `BackendPreference` variant name.
+pub(crate) fn backend_name(backend: BackendPreference) -> &'static str {
+    match backend {
+        BackendPreference::Structural => "Structural",
+        BackendPreference::TapeView => "TapeView",
+        BackendPreference::TapeRows => "TapeRows",
+        BackendPreference::TapePath => "TapePath",
+        BackendPreference::ValView => "ValView",
+        BackendPreference::MaterializedSource => "MaterializedSource",
+        BackendPreference::FastChildren => "FastChildren",
+        BackendPreference::Interpreted => "Interpreted",
+    }
+}
+
+/// Names of every backend in `set`.
+fn capability_names(set: BackendSet) -> Vec<&'static str> {
+    ALL_BACKENDS
+        .into_iter()
+        .filter(|backend| set.contains(backend.backend_set()))
+        .map(backend_name)
+        .collect()
+}
+
+impl From<ExecutionFacts> for ExplainFacts {
+    fn from(facts: ExecutionFacts) -> Self {
+        Self {
+            can_avoid_root_materialization: facts.can_avoid_root_materialization,
+            can_stream_rows: facts.can_stream_rows,
+            can_use_tape: facts.can_use_tape,
+            contains_vm_fallback: facts.contains_vm_fallback,
+            may_materialize_source: facts.may_materialize_source,
+        }
+    }
+}
+
+impl ExplainFacts {
+    /// Names of the facts that hold, for compact printing.
+    fn names(&self) -> Vec<&'static str> {
+        [
+            (self.can_avoid_root_materialization, "avoids_root"),
+            (self.can_stream_rows, "streams_rows"),
+            (self.can_use_tape, "uses_tape"),
+            (self.contains_vm_fallback, "vm_fallback"),
+            (self.may_materialize_source, "materializes_source"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
+impl fmt::Display for Explain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input: {}", self.input)?;
+        if self.passes.is_empty() {
+            writeln!(f, "passes: none")?;
+        } else {
+            writeln!(f, "passes: {}", self.passes.join(", "))?;
+        }
+        self.root.write_tree(f, 0)
+    }
+}
+
+impl fmt::Display for ExplainNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+impl ExplainNode {
+    /// Write this node and its subtree, indented two spaces per level.
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let pad = "  ".repeat(depth);
+        write!(f, "{pad}{}", self.kind)?;
+        if let Some(detail) = &self.detail {
+            write!(f, " {detail}")?;
+        }
+        write!(f, " backends=[{}]", self.backends.join(", "))?;
+        let facts = self.facts.names();
+        if !facts.is_empty() {
+            write!(f, " facts=[{}]", facts.join(", "))?;
+        }
+        writeln!(f)?;
+        if let Some(pipeline) = &self.pipeline {
+            writeln!(
+                f,
+                "{pad}  exec_path={} source_demand={}",
+                pipeline.exec_path, pipeline.source_demand
+            )?;
+            for stage in &pipeline.stages {
+                writeln!(f, "{pad}  stage {} strategy={}", stage.name, stage.strategy)?;
+            }
+            writeln!(
+                f,
+                "{pad}  sink {} demand={}",
+                pipeline.sink, pipeline.sink_demand
+            )?;
+        }
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
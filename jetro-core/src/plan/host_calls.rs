@@ -0,0 +1,326 @@
+//! Host-call lowering: rewrites calls to registered host functions into
+//! shapes the existing planners already understand.
+//!
+//! Two rewrites run before physical planning:
+//!
+//! - An element-wise host method applied to a `$`-rooted field chain whose
+//!   prefix is still pipeline-shaped, `$.prices.to_usd()`, becomes
+//!   `$.prices.map(@.to_usd())`, so the pipeline streams through it exactly as
+//!   it does for element-wise builtins like `upper()`. When the receiver is a
+//!   bare field chain, which may hold a scalar, the call instead becomes
+//!   `if $.prices kind array then $.prices.map(@.to_usd()) else
+//!   $.prices.to_usd()`, so `$.price.to_usd()` stays a plain method call.
+//!   This is only an optimisation: the VM maps element-wise functions over
+//!   array receivers itself, so the rewritten and original calls agree.
+//! - An impure host method call `recv.f(args)` becomes the global call
+//!   `f(recv, args)`. Both compile to the same `CallMethod`, but `GlobalCall`
+//!   is already opaque to the symbolic optimiser, so impure calls are never
+//!   substituted into neighbouring stages or evaluated twice.
+//!
+//! Everything else is left untouched; the VM resolves the remaining
+//! `BuiltinMethod::Unknown` calls against the active host registry.
+
+use crate::builtins::host::HostRegistry;
+use crate::builtins::registry::{pipeline_accepts_arity, BuiltinId};
+use crate::builtins::BuiltinMethod;
+use crate::parse::ast::{
+    Arg, ArrayElem, Expr, FStringPart, KindType, ObjField, PatchOp, PathStep, PipeStep, Span, Step,
+};
+
+/// Rewrite every host call in `expr` against `registry`.
+pub(crate) fn lower_host_calls(expr: Expr, registry: &HostRegistry) -> Expr {
+    if registry.is_empty() {
+        return expr;
+    }
+    rewrite(expr, registry)
+}
+
+/// Recursive walker; only `Chain` does real work, every other variant just
+/// rewrites its children.
+fn rewrite(expr: Expr, reg: &HostRegistry) -> Expr {
+    let sub = |e: Box<Expr>| Box::new(rewrite(*e, reg));
+    match expr {
+        Expr::Null
+        | Expr::Bool(_)
+        | Expr::Int(_)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::Root
+        | Expr::Current
+        | Expr::Ident(_)
+        | Expr::DeleteMark => expr,
+        Expr::FString(parts) => Expr::FString(
+            parts
+                .into_iter()
+                .map(|part| match part {
+                    FStringPart::Interp { expr, fmt } => FStringPart::Interp {
+                        expr: rewrite(expr, reg),
+                        fmt,
+                    },
+                    lit => lit,
+                })
+                .collect(),
+        ),
+        Expr::Chain(base, steps) => {
+            let base = rewrite(*base, reg);
+            let steps = steps.into_iter().map(|step| rewrite_step(step, reg)).collect();
+            lower_chain(base, steps, reg)
+        }
+        Expr::BinOp(l, op, r) => Expr::BinOp(sub(l), op, sub(r)),
+        Expr::UnaryNeg(e) => Expr::UnaryNeg(sub(e)),
+        Expr::Not(e) => Expr::Not(sub(e)),
+        Expr::Kind { expr, ty, negate } => Expr::Kind {
+            expr: sub(expr),
+            ty,
+            negate,
+        },
+        Expr::Coalesce(l, r) => Expr::Coalesce(sub(l), sub(r)),
+        Expr::Object(fields) => Expr::Object(
+            fields
+                .into_iter()
+                .map(|field| match field {
+                    ObjField::Kv {
+                        key,
+                        val,
+                        optional,
+                        cond,
+                    } => ObjField::Kv {
+                        key,
+                        val: rewrite(val, reg),
+                        optional,
+                        cond: cond.map(|c| rewrite(c, reg)),
+                    },
+                    ObjField::Short(name) => ObjField::Short(name),
+                    ObjField::Dynamic { key, val } => ObjField::Dynamic {
+                        key: rewrite(key, reg),
+                        val: rewrite(val, reg),
+                    },
+                    ObjField::Spread(e) => ObjField::Spread(rewrite(e, reg)),
+                    ObjField::SpreadDeep(e) => ObjField::SpreadDeep(rewrite(e, reg)),
+                })
+                .collect(),
+        ),
+        Expr::Array(elems) => Expr::Array(
+            elems
+                .into_iter()
+                .map(|elem| match elem {
+                    ArrayElem::Expr(e) => ArrayElem::Expr(rewrite(e, reg)),
+                    ArrayElem::Spread(e) => ArrayElem::Spread(rewrite(e, reg)),
+                })
+                .collect(),
+        ),
+        Expr::Pipeline { base, steps } => Expr::Pipeline {
+            base: sub(base),
+            steps: steps
+                .into_iter()
+                .map(|step| match step {
+                    PipeStep::Forward(e) => PipeStep::Forward(rewrite(e, reg)),
+                    bind => bind,
+                })
+                .collect(),
+        },
+        Expr::ListComp {
+            expr,
+            vars,
+            iter,
+            cond,
+        } => Expr::ListComp {
+            expr: sub(expr),
+            vars,
+            iter: sub(iter),
+            cond: cond.map(sub),
+        },
+        Expr::DictComp {
+            key,
+            val,
+            vars,
+            iter,
+            cond,
+        } => Expr::DictComp {
+            key: sub(key),
+            val: sub(val),
+            vars,
+            iter: sub(iter),
+            cond: cond.map(sub),
+        },
+        Expr::SetComp {
+            expr,
+            vars,
+            iter,
+            cond,
+        } => Expr::SetComp {
+            expr: sub(expr),
+            vars,
+            iter: sub(iter),
+            cond: cond.map(sub),
+        },
+        Expr::GenComp {
+            expr,
+            vars,
+            iter,
+            cond,
+        } => Expr::GenComp {
+            expr: sub(expr),
+            vars,
+            iter: sub(iter),
+            cond: cond.map(sub),
+        },
+        Expr::Lambda { params, body } => Expr::Lambda {
+            params,
+            body: sub(body),
+        },
+        Expr::Let { name, init, body } => Expr::Let {
+            name,
+            init: sub(init),
+            body: sub(body),
+        },
+        Expr::IfElse { cond, then_, else_ } => Expr::IfElse {
+            cond: sub(cond),
+            then_: sub(then_),
+            else_: sub(else_),
+        },
+        Expr::Try { body, default } => Expr::Try {
+            body: sub(body),
+            default: sub(default),
+        },
+        Expr::GlobalCall { name, args, span } => Expr::GlobalCall {
+            name,
+            args: rewrite_args(args, reg),
+            span,
+        },
+        Expr::Cast { expr, ty } => Expr::Cast { expr: sub(expr), ty },
+        Expr::Patch { root, ops } => Expr::Patch {
+            root: sub(root),
+            ops: ops
+                .into_iter()
+                .map(|PatchOp { path, val, cond }| PatchOp {
+                    path: path
+                        .into_iter()
+                        .map(|step| match step {
+                            PathStep::DynIndex(e) => PathStep::DynIndex(rewrite(e, reg)),
+                            PathStep::WildcardFilter(e) => PathStep::WildcardFilter(sub(e)),
+                            other => other,
+                        })
+                        .collect(),
+                    val: rewrite(val, reg),
+                    cond: cond.map(|c| rewrite(c, reg)),
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Rewrite the expressions nested inside one chain step.
+fn rewrite_step(step: Step, reg: &HostRegistry) -> Step {
+    match step {
+        Step::DynIndex(e) => Step::DynIndex(Box::new(rewrite(*e, reg))),
+        Step::InlineFilter(e) => Step::InlineFilter(Box::new(rewrite(*e, reg))),
+        Step::Method(name, args, span) => Step::Method(name, rewrite_args(args, reg), span),
+        Step::OptMethod(name, args, span) => Step::OptMethod(name, rewrite_args(args, reg), span),
+        other => other,
+    }
+}
+
+/// Rewrite each argument expression.
+fn rewrite_args(args: Vec<Arg>, reg: &HostRegistry) -> Vec<Arg> {
+    args.into_iter()
+        .map(|arg| match arg {
+            Arg::Pos(e) => Arg::Pos(rewrite(e, reg)),
+            Arg::Named(name, e) => Arg::Named(name, rewrite(e, reg)),
+        })
+        .collect()
+}
+
+/// Apply the element-wise and impure-call rewrites to one chain whose
+/// children have already been rewritten.
+fn lower_chain(base: Expr, steps: Vec<Step>, reg: &HostRegistry) -> Expr {
+    let mut streaming = matches!(base, Expr::Root);
+    let mut seen_field = false;
+    let mut fields_only = streaming;
+    let mut receiver = base;
+    let mut out: Vec<Step> = Vec::with_capacity(steps.len());
+
+    let mut steps = steps.into_iter();
+    while let Some(step) = steps.next() {
+        let host = match &step {
+            Step::Method(name, ..) => reg.get(name),
+            _ => None,
+        };
+        let Some(host) = host else {
+            streaming = streaming && keeps_streaming(&step);
+            seen_field |= matches!(step, Step::Field(_));
+            fields_only &= matches!(step, Step::Field(_));
+            out.push(step);
+            continue;
+        };
+        let Step::Method(name, args, span) = step else {
+            unreachable!("host lookup only matches method steps");
+        };
+
+        if fields_only && seen_field && host.is_elementwise() {
+            crate::plan::explain::record_pass("host_elementwise_map");
+            let rest: Vec<Step> = steps.collect();
+            let body = host_call(Expr::Current, name.clone(), args.clone(), span, host.is_pure());
+            let mut mapped = out.clone();
+            mapped.push(Step::Method("map".to_string(), vec![Arg::Pos(body)], Span::default()));
+            mapped.extend(rest.iter().cloned());
+            let fields = receiver.maybe_chain(out);
+            let scalar = host_call(fields.clone(), name, args, span, host.is_pure());
+            return Expr::IfElse {
+                cond: Box::new(Expr::Kind {
+                    expr: Box::new(fields),
+                    ty: KindType::Array,
+                    negate: false,
+                }),
+                then_: Box::new(lower_chain(Expr::Root, mapped, reg)),
+                else_: Box::new(lower_chain(scalar, rest, reg)),
+            };
+        }
+
+        if streaming && seen_field && host.is_elementwise() {
+            crate::plan::explain::record_pass("host_elementwise_map");
+            let body = host_call(Expr::Current, name, args, span, host.is_pure());
+            out.push(Step::Method("map".to_string(), vec![Arg::Pos(body)], Span::default()));
+            continue;
+        }
+
+        streaming = false;
+        if host.is_pure() {
+            out.push(Step::Method(name, args, span));
+        } else {
+            crate::plan::explain::record_pass("host_impure_call");
+            let recv = std::mem::replace(&mut receiver, Expr::Null).maybe_chain(std::mem::take(&mut out));
+            receiver = host_call(recv, name, args, span, false);
+        }
+    }
+    receiver.maybe_chain(out)
+}
+
+/// Build `recv.name(args)` for pure calls or `name(recv, args)` for impure ones.
+fn host_call(recv: Expr, name: String, args: Vec<Arg>, span: Span, pure: bool) -> Expr {
+    if pure {
+        return Expr::Chain(Box::new(recv), vec![Step::Method(name, args, span)]);
+    }
+    let mut call_args = Vec::with_capacity(args.len() + 1);
+    call_args.push(Arg::Pos(recv));
+    call_args.extend(args);
+    Expr::GlobalCall {
+        name,
+        args: call_args,
+        span,
+    }
+}
+
+/// Return `true` when `step` keeps a `$`-rooted chain in per-element pipeline
+/// shape: a field access or a non-terminal pipeline builtin.
+fn keeps_streaming(step: &Step) -> bool {
+    match step {
+        Step::Field(_) => true,
+        Step::Method(name, args, _) => {
+            let method = BuiltinMethod::from_name(name);
+            method != BuiltinMethod::Unknown
+                && pipeline_accepts_arity(BuiltinId::from_method(method), args.len(), false)
+        }
+        _ => false,
+    }
+}
@@ -13,9 +13,13 @@ use crate::ir::logical::LogicalPlan;
 use crate::exec::pipeline::{SortSpec, Source};
 
 /// Try to lower a pipeline-shaped `Expr` to a `LogicalPlan`.
-/// Returns `None` for expressions that are not pipeline-shaped.
+/// Returns `None` for expressions that are not pipeline-shaped, and for
+/// chains whose demand would skip impure host calls.
 pub(crate) fn try_lower(expr: &Expr) -> Option<LogicalPlan> {
     let (source, steps) = extract_source_and_steps(expr)?;
+    if crate::exec::pipeline::Pipeline::steps_limit_impure_stage(steps) {
+        return None;
+    }
     let base = LogicalPlan::Source(source);
     apply_steps(base, steps)
 }
@@ -64,7 +68,7 @@ fn apply_steps(mut plan: LogicalPlan, steps: &[Step]) -> Option<LogicalPlan> {
 
 fn apply_step(plan: LogicalPlan, step: &Step) -> Option<LogicalPlan> {
     match step {
-        Step::Method(name, args) => apply_method(plan, name.as_str(), args),
+        Step::Method(name, args, _) => apply_method(plan, name.as_str(), args),
         // Field, OptField, Index, etc. — cannot classify as pipeline stage
         _ => None,
     }
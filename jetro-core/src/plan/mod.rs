@@ -2,9 +2,16 @@
 //!
 //! `logical` lowers an `Expr` to the logical IR; `physical` chooses an
 //! executable shape for it; `optimize` rewrites the resulting plans;
-//! `analysis` provides shared shape, nullability, and selectivity passes.
+//! `analysis` provides shared shape, nullability, and selectivity passes;
+//! `host_calls` lowers calls to engine-registered host functions; `explain`
+//! describes a finished plan and records which rewrite passes fired; `cache`
+//! keeps a `JetroEngine`'s finished plans for reuse.
 
 pub(crate) mod analysis;
+pub(crate) mod bundle;
+pub(crate) mod cache;
+pub(crate) mod explain;
+pub(crate) mod host_calls;
 pub(crate) mod logical;
 pub(crate) mod optimize;
 pub(crate) mod patch_fusion;
@@ -8,6 +8,9 @@ use crate::ir::logical::LogicalPlan;
 
 /// A single rewrite rule applied to a `LogicalPlan` node.
 pub(crate) trait Rule: Send + Sync {
+    /// Short snake_case name reported by `JetroEngine::explain` when the rule fires.
+    fn name(&self) -> &'static str;
+
     /// Try to rewrite `plan`. Return `Ok(new_plan)` if the rule fired; `Err(unchanged)` if not.
     fn apply(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlan>;
 }
@@ -60,6 +63,7 @@ impl Optimizer {
             for rule in &self.rules {
                 match rule.apply(plan) {
                     Ok(new_plan) => {
+                        crate::plan::explain::record_pass(rule.name());
                         plan = new_plan;
                         changed = true;
                     }
@@ -85,6 +89,10 @@ pub(crate) mod rules {
     pub(crate) struct StrengthReduce;
 
     impl Rule for StrengthReduce {
+        fn name(&self) -> &'static str {
+            "strength_reduce"
+        }
+
         fn apply(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlan> {
             // Match Take(1, Sort(...)) or First(Sort(...))
             // Track which outer form we had so we can rebuild correctly on Err.
@@ -141,6 +149,10 @@ pub(crate) mod rules {
     pub(crate) struct RedundantOps;
 
     impl Rule for RedundantOps {
+        fn name(&self) -> &'static str {
+            "redundant_ops"
+        }
+
         fn apply(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlan> {
             match plan {
                 LogicalPlan::Reverse { input } => {
@@ -171,6 +183,10 @@ pub(crate) mod rules {
     pub(crate) struct FilterBeforeMap;
 
     impl Rule for FilterBeforeMap {
+        fn name(&self) -> &'static str {
+            "filter_before_map"
+        }
+
         fn apply(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlan> {
             // Filter { input: Map { input: x, projection: f }, predicate: p }
             // →  Map { input: Filter { input: x, predicate: p }, projection: f }
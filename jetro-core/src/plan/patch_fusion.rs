@@ -17,7 +17,7 @@
 //! write so the scheduler can recognise it as targeting `$`.
 
 use crate::parse::ast::{
-    Arg, ArrayElem, BindTarget, Expr, FStringPart, ObjField, PatchOp, PathStep, PipeStep, Step,
+    Arg, ArrayElem, BindTarget, Expr, FStringPart, ObjField, PatchOp, PathStep, PipeStep, Span, Step,
 };
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -474,7 +474,7 @@ impl EffectAnalyzer {
             | Step::Slice(_, _)
             | Step::Quantifier(_) => EffectSummary::default(),
             Step::DynIndex(e) | Step::InlineFilter(e) => self.visit(e),
-            Step::Method(_, args) | Step::OptMethod(_, args) => {
+            Step::Method(_, args, _) | Step::OptMethod(_, args, _) => {
                 let mut s = EffectSummary::default();
                 for a in args {
                     s.merge(self.visit_arg(a));
@@ -759,6 +759,9 @@ impl FuseCtx {
         // let — preserves source order for nested reads.
         let drained: Vec<(RootRef, PendingBatch)> = self.pending.drain(..).collect();
         for (_root, batch) in drained.into_iter().rev() {
+            if batch.ops.len() > 1 {
+                crate::plan::explain::record_pass("patch_fusion");
+            }
             wrapped = Expr::Let {
                 name: batch.binding,
                 init: Box::new(Expr::Patch {
@@ -776,6 +779,9 @@ impl FuseCtx {
     /// before the read evaluates.
     fn flush_root(&mut self, root: &RootRef, body: Expr) -> Expr {
         if let Some(batch) = self.pending.shift_remove(root) {
+            if batch.ops.len() > 1 {
+                crate::plan::explain::record_pass("patch_fusion");
+            }
             Expr::Let {
                 name: batch.binding,
                 init: Box::new(Expr::Patch {
@@ -943,8 +949,9 @@ fn fuse_recursive(expr: Expr, ctx: &mut FuseCtx) -> Expr {
             body: Box::new(fuse_subtree(*body, ctx)),
             default: Box::new(fuse_subtree(*default, ctx)),
         },
-        Expr::GlobalCall { name, args } => Expr::GlobalCall {
+        Expr::GlobalCall { name, args, span } => Expr::GlobalCall {
             name,
+            span,
             args: args
                 .into_iter()
                 .map(|a| match a {
@@ -973,7 +980,7 @@ fn fuse_step(step: Step, ctx: &mut FuseCtx) -> Step {
     match step {
         Step::DynIndex(e) => Step::DynIndex(Box::new(fuse_recursive(*e, ctx))),
         Step::InlineFilter(e) => Step::InlineFilter(Box::new(fuse_recursive(*e, ctx))),
-        Step::Method(n, args) => Step::Method(
+        Step::Method(n, args, span) => Step::Method(
             n,
             args.into_iter()
                 .map(|a| match a {
@@ -981,8 +988,9 @@ fn fuse_step(step: Step, ctx: &mut FuseCtx) -> Step {
                     Arg::Named(n, e) => Arg::Named(n, fuse_recursive(e, ctx)),
                 })
                 .collect(),
+            span,
         ),
-        Step::OptMethod(n, args) => Step::OptMethod(
+        Step::OptMethod(n, args, span) => Step::OptMethod(
             n,
             args.into_iter()
                 .map(|a| match a {
@@ -990,6 +998,7 @@ fn fuse_step(step: Step, ctx: &mut FuseCtx) -> Step {
                     Arg::Named(n, e) => Arg::Named(n, fuse_recursive(e, ctx)),
                 })
                 .collect(),
+            span,
         ),
         other => other,
     }
@@ -1061,7 +1070,7 @@ fn lift_chain_write_pipe_stage(stage: Expr) -> Result<Expr, Expr> {
         None => return Err(Expr::Chain(Box::new(base), steps)),
     };
     let (name, args) = match last {
-        Step::Method(n, a) => (n.clone(), a.clone()),
+        Step::Method(n, a, _) => (n.clone(), a.clone()),
         _ => return Err(Expr::Chain(Box::new(base), steps)),
     };
     if !is_write_terminal(&name) {
@@ -1333,7 +1342,7 @@ fn unlift_chain_write_pipe_stage(patch: &Expr) -> Expr {
         // Plain `set(v)` — recover the Method step.
         v => ("set".to_string(), vec![Arg::Pos(v.clone())]),
     };
-    steps.push(Step::Method(method_name, method_args));
+    steps.push(Step::Method(method_name, method_args, Span::default()));
     Expr::Chain(Box::new(root.clone()), steps)
 }
 
@@ -1616,7 +1625,7 @@ fn try_lift_chain_write(expr: &Expr) -> Option<Expr> {
     };
     let last = steps.last()?;
     let (name, args) = match last {
-        Step::Method(n, a) => (n.clone(), a.clone()),
+        Step::Method(n, a, _) => (n.clone(), a.clone()),
         _ => return None,
     };
     if !is_write_terminal(&name) {
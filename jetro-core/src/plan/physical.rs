@@ -12,13 +12,15 @@ use std::sync::Arc;
 use crate::plan::analysis;
 use crate::parse::ast::{ArrayElem, Expr, ObjField, Step};
 use crate::builtins::{BuiltinCall, BuiltinMethod};
+use crate::builtins::host::HostRegistry;
 use crate::compile::compiler::Compiler;
 use crate::parse::parser;
 use crate::ir::physical::{
     BackendPlan, ExecutionFacts, NodeId, PhysicalArrayElem, PhysicalChainStep, PhysicalNode,
     PhysicalObjField, PhysicalPathStep, PipelinePlanSource, PlanNode, QueryPlan,
 };
-use crate::exec::pipeline::{Pipeline, Source};
+use crate::exec::pipeline::fused_exec;
+use crate::exec::pipeline::{Pipeline, PipelineBody, Sink, Source, Stage};
 use crate::exec::structural::{StructuralPathStep, StructuralPlan};
 use crate::data::value::Val;
 
@@ -103,6 +105,15 @@ impl PlanningContext {
             InputMode::Val => "val",
         }
     }
+
+    /// Inverse of `cache_key`; `None` for any other string.
+    pub(crate) fn from_cache_key(key: &str) -> Option<Self> {
+        match key {
+            "bytes" => Some(Self::bytes()),
+            "val" => Some(Self::val()),
+            _ => None,
+        }
+    }
 }
 
 impl PlanBuilder {
@@ -179,6 +190,7 @@ impl PlanBuilder {
                 ExecutionFacts::combine_all(children)
             }
             PlanNode::Local(_) => ExecutionFacts::constant(),
+            PlanNode::ScanSlot { scan, .. } => self.node_facts(*scan),
             PlanNode::Call { receiver, .. }
             | PlanNode::UnaryNeg(receiver)
             | PlanNode::Not(receiver)
@@ -255,6 +267,10 @@ fn select_backend_plan(
         (InputMode::Val, PlanNode::RootPath(_) | PlanNode::Structural { .. }) => {
             BackendPlan::new(&[crate::ir::physical::BackendPreference::Interpreted])
         }
+        (InputMode::Val, PlanNode::SharedScan { .. }) => {
+            BackendPlan::new(&[crate::ir::physical::BackendPreference::Interpreted])
+        }
+        (InputMode::Bytes, PlanNode::SharedScan { .. }) => BackendPlan::for_node(node),
         (InputMode::Bytes, PlanNode::Structural { .. }) => {
             BackendPlan::new(&[
                 crate::ir::physical::BackendPreference::Structural,
@@ -325,8 +341,10 @@ fn lower_expr(builder: &mut PlanBuilder, expr: &Expr) -> NodeId {
 /// Tries the logical path (`logical_planner → optimizer → logical_lower`) first; falls back
 /// to the legacy `Pipeline::lower()` for shapes the logical planner cannot classify.
 fn try_lower_pipeline(builder: &PlanBuilder, expr: &Expr) -> Option<PlanNode> {
+    let mark = crate::plan::explain::pass_mark();
     let pipeline = lower_via_logical(expr).or_else(|| Pipeline::lower(expr))?;
     if is_trivial_collect_pipeline(&pipeline) {
+        crate::plan::explain::discard_passes_since(mark);
         return None;
     }
     let (source, mut body) = pipeline.into_source_body();
@@ -338,8 +356,13 @@ fn try_lower_pipeline(builder: &PlanBuilder, expr: &Expr) -> Option<PlanNode> {
 /// any stage cannot classify the expression.
 fn lower_via_logical(expr: &Expr) -> Option<Pipeline> {
     let logical = crate::plan::logical::try_lower(expr)?;
+    let mark = crate::plan::explain::pass_mark();
     let optimized = crate::plan::optimize::Optimizer::default_rules().optimize(logical);
-    crate::exec::pipeline::logical_lower::try_lower(optimized)
+    let lowered = crate::exec::pipeline::logical_lower::try_lower(optimized);
+    if lowered.is_none() {
+        crate::plan::explain::discard_passes_since(mark);
+    }
+    lowered
 }
 
 /// Converts a decomposed pipeline `(source, body)` pair into a `PlanNode::Pipeline`, returning
@@ -482,22 +505,24 @@ fn try_lower_structural_chain_prefix(builder: &mut PlanBuilder, expr: &Expr) ->
             Step::DynIndex(expr) => {
                 out.push(PhysicalChainStep::DynIndex(lower_expr(builder, expr)));
             }
-            Step::Method(name, args) => {
+            Step::Method(name, args, span) => {
                 let call = BuiltinCall::from_literal_ast_args(name, args)?;
                 cur = flush_chain(builder, cur, &mut out);
                 cur = builder.push(PlanNode::Call {
                     receiver: cur,
                     call,
                     optional: false,
+                    span: *span,
                 });
             }
-            Step::OptMethod(name, args) => {
+            Step::OptMethod(name, args, span) => {
                 let call = BuiltinCall::from_literal_ast_args(name, args)?;
                 cur = flush_chain(builder, cur, &mut out);
                 cur = builder.push(PlanNode::Call {
                     receiver: cur,
                     call,
                     optional: true,
+                    span: *span,
                 });
             }
             _ => return None,
@@ -524,7 +549,7 @@ fn lower_structural_prefix(
                 anchor.push(StructuralPathStep::Field(Arc::from(key.as_str())));
             }
             Step::Index(index) => anchor.push(StructuralPathStep::Index(*index)),
-            Step::Method(name, args) | Step::OptMethod(name, args) => {
+            Step::Method(name, args, _) | Step::OptMethod(name, args, _) => {
                 let anchor = Arc::from(anchor);
                 let method = BuiltinMethod::from_name(name);
                 let plan = StructuralPlan::lower_builtin(anchor, method, args)?;
@@ -613,22 +638,24 @@ fn try_lower_chain(builder: &mut PlanBuilder, expr: &Expr) -> Option<NodeId> {
             Step::DynIndex(expr) => {
                 out.push(PhysicalChainStep::DynIndex(lower_expr(builder, expr)))
             }
-            Step::Method(name, args) => {
+            Step::Method(name, args, span) => {
                 let call = BuiltinCall::from_literal_ast_args(name, args)?;
                 cur = flush_chain(builder, cur, &mut out);
                 cur = builder.push(PlanNode::Call {
                     receiver: cur,
                     call,
                     optional: false,
+                    span: *span,
                 });
             }
-            Step::OptMethod(name, args) => {
+            Step::OptMethod(name, args, span) => {
                 let call = BuiltinCall::from_literal_ast_args(name, args)?;
                 cur = flush_chain(builder, cur, &mut out);
                 cur = builder.push(PlanNode::Call {
                     receiver: cur,
                     call,
                     optional: true,
+                    span: *span,
                 });
             }
             _ => return None,
@@ -714,17 +741,48 @@ fn try_lower_scalar(builder: &mut PlanBuilder, expr: &Expr) -> Option<NodeId> {
 fn try_lower_structural(builder: &mut PlanBuilder, expr: &Expr) -> Option<NodeId> {
     match expr {
         Expr::Object(fields) => {
-            let fields = fields
+            let mut fields: Vec<PhysicalObjField> = fields
                 .iter()
                 .map(|field| plan_obj_field(builder, field))
                 .collect();
+            let children: Vec<NodeId> = fields
+                .iter()
+                .filter_map(|field| match field {
+                    PhysicalObjField::Kv {
+                        val, cond: None, ..
+                    } => Some(*val),
+                    _ => None,
+                })
+                .collect();
+            let shared = share_sibling_scans(builder, &children);
+            for field in &mut fields {
+                if let PhysicalObjField::Kv {
+                    val, cond: None, ..
+                } = field
+                {
+                    *val = shared_child(&shared, *val);
+                }
+            }
             Some(builder.push(PlanNode::Object(fields)))
         }
         Expr::Array(elems) => {
-            let elems = elems
+            let mut elems: Vec<PhysicalArrayElem> = elems
                 .iter()
                 .map(|elem| plan_array_elem(builder, elem))
                 .collect();
+            let children: Vec<NodeId> = elems
+                .iter()
+                .filter_map(|elem| match elem {
+                    PhysicalArrayElem::Expr(id) => Some(*id),
+                    PhysicalArrayElem::Spread(_) => None,
+                })
+                .collect();
+            let shared = share_sibling_scans(builder, &children);
+            for elem in &mut elems {
+                if let PhysicalArrayElem::Expr(id) = elem {
+                    *id = shared_child(&shared, *id);
+                }
+            }
             Some(builder.push(PlanNode::Array(elems)))
         }
         Expr::Let { name, init, body } => {
@@ -739,6 +797,120 @@ fn try_lower_structural(builder: &mut PlanBuilder, expr: &Expr) -> Option<NodeId
     }
 }
 
+/// Source keys of a prospective `SharedScan` and the constructor children reading them.
+type ScanGroup = (Arc<[Arc<str>]>, Vec<NodeId>);
+
+/// Groups the constructor children in `children` that are field-chain pipelines over the same
+/// source into one `SharedScan` each, returning `(pipeline, ScanSlot)` replacement pairs.
+///
+/// Leading filters shared by every member of a group move into the scan's prefix so they run
+/// once per row. On byte input, pipelines that can stream over the tape without materialising
+/// rows are left alone: one tape walk per pipeline is cheaper than materialising every row once.
+fn share_sibling_scans(builder: &mut PlanBuilder, children: &[NodeId]) -> Vec<(NodeId, NodeId)> {
+    let mut groups: Vec<ScanGroup> = Vec::new();
+    for &id in children {
+        let Some(keys) = shareable_scan_source(builder, id) else {
+            continue;
+        };
+        match groups.iter_mut().find(|(group, _)| **group == **keys) {
+            Some((_, members)) => members.push(id),
+            None => groups.push((Arc::clone(keys), vec![id])),
+        }
+    }
+
+    let mut replacements = Vec::new();
+    for (keys, members) in groups {
+        if members.len() < 2 {
+            continue;
+        }
+        let bodies: Vec<&PipelineBody> = members
+            .iter()
+            .map(|id| match builder.nodes[id.0].kind() {
+                PlanNode::Pipeline { body, .. } => body,
+                _ => unreachable!("shareable scan members are pipelines"),
+            })
+            .collect();
+        let shared = shared_filter_prefix(&bodies);
+        let prefix = (shared > 0).then(|| split_body(bodies[0], 0..shared, Sink::Collect));
+        let branches = bodies
+            .iter()
+            .map(|body| split_body(body, shared..body.stages.len(), body.sink.clone()))
+            .collect();
+        crate::plan::explain::record_pass("shared_scan");
+        let scan = builder.push(PlanNode::SharedScan {
+            keys,
+            prefix,
+            branches,
+        });
+        for (slot, id) in members.into_iter().enumerate() {
+            replacements.push((id, builder.push(PlanNode::ScanSlot { scan, slot })));
+        }
+    }
+    replacements
+}
+
+/// Returns the replacement for constructor child `id` chosen by `share_sibling_scans`, if any.
+fn shared_child(replacements: &[(NodeId, NodeId)], id: NodeId) -> NodeId {
+    replacements
+        .iter()
+        .find(|(old, _)| *old == id)
+        .map_or(id, |(_, new)| *new)
+}
+
+/// Returns the source keys of node `id` when it is a field-chain pipeline that may join a
+/// `SharedScan`.
+fn shareable_scan_source(builder: &PlanBuilder, id: NodeId) -> Option<&Arc<[Arc<str>]>> {
+    let PlanNode::Pipeline {
+        source: PipelinePlanSource::FieldChain { keys },
+        body,
+    } = builder.nodes[id.0].kind()
+    else {
+        return None;
+    };
+    if !fused_exec::can_fuse(body) {
+        return None;
+    }
+    if builder.context.input == InputMode::Bytes && builder.node_facts(id).can_stream_rows {
+        return None;
+    }
+    Some(keys)
+}
+
+/// Counts the leading `Filter` stages that every body in `bodies` has in common, comparing
+/// their source expressions structurally (spans ignored); stages whose expression was
+/// rewritten away never match.
+fn shared_filter_prefix(bodies: &[&PipelineBody]) -> usize {
+    let first = bodies[0];
+    (0..first.stages.len())
+        .take_while(|&idx| {
+            let Some(Some(expr)) = first.stage_exprs.get(idx) else {
+                return false;
+            };
+            bodies.iter().all(|body| {
+                matches!(body.stages.get(idx), Some(Stage::Filter(..)))
+                    && matches!(body.stage_exprs.get(idx), Some(Some(other)) if other == expr)
+            })
+        })
+        .count()
+}
+
+/// Copies the stages in `range` of `body`, with their expressions and kernels, into a new
+/// body ending in `sink`.
+fn split_body(body: &PipelineBody, range: std::ops::Range<usize>, sink: Sink) -> PipelineBody {
+    let sink_kernels = if range.end == body.stages.len() {
+        body.sink_kernels.clone()
+    } else {
+        Vec::new()
+    };
+    PipelineBody {
+        stages: body.stages[range.clone()].to_vec(),
+        stage_exprs: body.stage_exprs[range.clone()].to_vec(),
+        sink,
+        stage_kernels: body.stage_kernels[range].to_vec(),
+        sink_kernels,
+    }
+}
+
 /// Creates a `PlanNode::Vm` wrapping a compiled `Program` as the last-resort fallback for
 /// expressions that no specialised lowering path could handle.
 fn fallback_vm(builder: &mut PlanBuilder, expr: &Expr) -> NodeId {
@@ -799,9 +971,40 @@ pub fn plan_query(expr: &str) -> QueryPlan {
 /// Falls back to a `SourceVm` plan when parsing fails, so callers always receive a usable plan.
 #[inline]
 pub(crate) fn plan_query_with_context(expr: &str, context: PlanningContext) -> QueryPlan {
+    plan_query_with_bindings(expr, context, &[], None)
+}
+
+/// Like `plan_query_with_context`, but treats every name in `bound` as an enclosing
+/// `let`-binding whose value is supplied per call. The plan depends only on the names,
+/// never on the values, so it can be cached and reused across bindings. When `host` is
+/// set, calls to its functions are lowered first (see `plan::host_calls`).
+pub(crate) fn plan_query_with_bindings(
+    expr: &str,
+    context: PlanningContext,
+    bound: &[Arc<str>],
+    host: Option<&Arc<HostRegistry>>,
+) -> QueryPlan {
     let Ok(ast) = parser::parse(expr) else {
         return QueryPlan::source_vm(expr);
     };
+    let Some(registry) = host else {
+        return plan_ast_with_bindings(ast, context, bound);
+    };
+    // Pipeline lowering looks up host purity in the active registry.
+    let _host = crate::builtins::host::enter(registry);
+    let ast = crate::plan::host_calls::lower_host_calls(ast, registry);
+    plan_ast_with_bindings(ast, context, bound)
+}
+
+/// Walks an already-parsed AST through `PlanBuilder` and returns a `QueryPlan`.
+///
+/// Used by callers that parsed `expr` themselves to surface `ParseError`s up front.
+pub(crate) fn plan_ast_with_context(ast: Expr, context: PlanningContext) -> QueryPlan {
+    plan_ast_with_bindings(ast, context, &[])
+}
+
+/// Plans an already-parsed AST with `bound` names pre-registered as `let` locals.
+fn plan_ast_with_bindings(ast: Expr, context: PlanningContext, bound: &[Arc<str>]) -> QueryPlan {
     // Phase B: fuse contiguous same-root chain-writes into multi-op
     // `Expr::Patch` nodes before lowering. The resulting Patches are
     // automatically routed to Phase D's PathTrie execution path by the
@@ -813,7 +1016,7 @@ pub(crate) fn plan_query_with_context(expr: &str, context: PlanningContext) -> Q
     let mut builder = PlanBuilder {
         nodes: Vec::new(),
         context,
-        locals: Vec::new(),
+        locals: bound.to_vec(),
     };
     if let Some(pipeline) = lower_via_logical(&ast).or_else(|| Pipeline::lower(&ast)) {
         let (source, mut body) = pipeline.into_source_body();
@@ -0,0 +1,174 @@
+//! Prepared query handles.
+//!
+//! `Query` owns everything needed to evaluate one expression — the physical
+//! `QueryPlan` for each input mode — so that repeated evaluation skips both
+//! the per-call planning in `Jetro::collect` and the hashed, mutex-guarded
+//! plan-cache lookup in `JetroEngine::collect`.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::data::context::EvalError;
+use crate::exec::router;
+use crate::ir::physical::QueryPlan;
+use crate::parse::parser::{self, ParseError};
+use crate::plan::physical::{plan_ast_with_context, PlanningContext};
+use crate::Jetro;
+
+/// A parsed and planned Jetro expression that can be evaluated against any
+/// number of documents. Cloning is O(1); the handle is `Send + Sync` so a
+/// single instance can be shared across worker threads.
+///
+/// ```rust
+/// use jetro_core::{Jetro, Query};
+/// let q = Query::compile("$.books.len()").unwrap();
+/// let j = Jetro::from_bytes(br#"{"books":[{"price":12}]}"#.to_vec()).unwrap();
+/// assert_eq!(q.run(&j).unwrap(), serde_json::json!(1));
+/// ```
+#[derive(Clone)]
+pub struct Query {
+    inner: Arc<QueryInner>,
+}
+
+/// Shared state behind a `Query` handle.
+struct QueryInner {
+    /// The expression source the handle was compiled from.
+    source: Arc<str>,
+    /// Plan for documents backed by raw bytes (tape / structural eligible).
+    bytes_plan: QueryPlan,
+    /// Plan for documents backed by an in-memory `Val` tree.
+    val_plan: QueryPlan,
+}
+
+impl Query {
+    /// Parse and plan `expr`. Syntax errors surface immediately as a
+    /// structured `ParseError`; no document is needed.
+    pub fn compile<S: AsRef<str>>(expr: S) -> Result<Self, ParseError> {
+        let expr = expr.as_ref();
+        let ast = parser::parse(expr)?;
+        Ok(Self {
+            inner: Arc::new(QueryInner {
+                source: Arc::from(expr),
+                bytes_plan: plan_ast_with_context(ast.clone(), PlanningContext::bytes()),
+                val_plan: plan_ast_with_context(ast, PlanningContext::val()),
+            }),
+        })
+    }
+
+    /// Check that `expr` is valid Jetro syntax without planning or compiling it.
+    pub fn validate<S: AsRef<str>>(expr: S) -> Result<(), ParseError> {
+        parser::parse(expr.as_ref()).map(|_| ())
+    }
+
+    /// Return the expression source this handle was compiled from.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.inner.source
+    }
+
+    /// Evaluate the prepared expression against `document` and return the
+    /// result as a `serde_json::Value`.
+    pub fn run(&self, document: &Jetro) -> Result<Value, EvalError> {
+        router::collect_plan_val(document, self.plan_for(document)).map(Value::from)
+    }
+
+    /// Select the plan matching `document`'s backing representation.
+    #[inline]
+    pub(crate) fn plan_for(&self, document: &Jetro) -> &QueryPlan {
+        if router::planning_context(document) == PlanningContext::bytes() {
+            &self.inner.bytes_plan
+        } else {
+            &self.inner.val_plan
+        }
+    }
+}
+
+impl std::fmt::Debug for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Query").field(&self.inner.source).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Query;
+    use crate::Jetro;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn query_is_send_sync() {
+        assert_send_sync::<Query>();
+    }
+
+    #[test]
+    fn query_runs_against_bytes_and_value_documents() {
+        let q = Query::compile("$.rows.filter(score > 900).map(name)").unwrap();
+        let doc = json!({
+            "rows": [
+                {"name": "low", "score": 1},
+                {"name": "ada", "score": 901}
+            ]
+        });
+        let from_bytes = Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap();
+        let from_value = Jetro::from(doc);
+
+        assert_eq!(q.run(&from_bytes).unwrap(), json!(["ada"]));
+        assert_eq!(q.run(&from_value).unwrap(), json!(["ada"]));
+    }
+
+    #[test]
+    fn query_matches_collect_across_documents() {
+        let exprs = [
+            "$.a.b",
+            "$.xs.sum()",
+            "let n = $.xs.len() in n * 2",
+            r#"{"first": $.xs.first(), "total": $.xs.sum()}"#,
+        ];
+        let docs = [
+            json!({"a": {"b": 1}, "xs": [1, 2, 3]}),
+            json!({"a": {"b": "x"}, "xs": [10, 20]}),
+        ];
+        for expr in exprs {
+            let q = Query::compile(expr).unwrap();
+            for doc in &docs {
+                let j = Jetro::from(doc.clone());
+                assert_eq!(q.run(&j).unwrap(), j.collect(expr).unwrap(), "{expr}");
+            }
+        }
+    }
+
+    #[test]
+    fn query_compile_rejects_invalid_syntax() {
+        assert!(Query::compile("$.a.(").is_err());
+    }
+
+    #[test]
+    fn query_compile_reports_parse_error_location() {
+        let err = Query::compile("$.a\n  .b +").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.offset, "$.a\n  .b +".len());
+        assert_eq!(err.column, 7);
+        assert!(!err.expected.is_empty());
+        assert_eq!(err.snippet, "2 |   .b +\n  |       ^");
+    }
+
+    #[test]
+    fn query_validate_accepts_valid_and_rejects_invalid() {
+        assert!(Query::validate("$.books.filter(price > 10).map(title)").is_ok());
+        let err = Query::validate("$.books.filter(price >").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.to_string().starts_with("parse error at 1:"));
+    }
+
+    #[test]
+    fn query_clone_shares_compiled_state() {
+        let q = Query::compile("$.x").unwrap();
+        let q2 = q.clone();
+        assert!(std::sync::Arc::ptr_eq(&q.inner, &q2.inner));
+        assert_eq!(q2.source(), "$.x");
+    }
+}
@@ -0,0 +1,348 @@
+//! MessagePack and CBOR documents (`Jetro::from_msgpack` / `from_cbor`) and
+//! results (`collect_msgpack` / `collect_cbor`, `to_msgpack()` / `to_cbor()`).
+
+use serde_json::{json, Value};
+
+use super::common::books;
+use crate::builtins::helpers::base64_decode;
+use crate::data::value::Val;
+use crate::data::{cbor, msgpack};
+use crate::{DecodeError, Jetro};
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn json_of(val: Val) -> Value {
+    val.into()
+}
+
+fn from_msgpack(s: &str) -> Value {
+    json_of(msgpack::decode(&hex(s)).unwrap())
+}
+
+fn from_cbor(s: &str) -> Value {
+    json_of(cbor::decode(&hex(s)).unwrap())
+}
+
+/// Documents paired with queries to compare across encodings.
+fn fixtures() -> Vec<(Value, &'static [&'static str])> {
+    vec![
+        (
+            books(),
+            &[
+                "$.store.books.filter(price > 10).map(title)",
+                "$.store.books.map(tags).flatten().unique()",
+                "$.store.books.group_by(genre).keys()",
+                "$.store.books.sort_by(rating).last().title",
+                "$.user.score + $.user.age",
+            ],
+        ),
+        (
+            json!({
+                "xs": [3, 1, 2],
+                "mixed": [1, "a", null, true, 2.5, [], {}],
+                "rows": [
+                    {"id": 1, "tag": "x", "score": 1.5},
+                    {"id": 2, "tag": "y", "score": 2.5}
+                ],
+                "wide": [i64::MIN, i64::MAX, -129, 65_536, 4_294_967_296_i64],
+                "text": ["", "é😀", "x".repeat(40), "y".repeat(300)],
+                "nested": {"a": {"b": {"c": [[1], [2, [3]]]}}}
+            }),
+            &[
+                "$.xs.sort()",
+                "$.rows.filter(score > 2).map(tag)",
+                "$.rows.map(score).sum()",
+                "$..c",
+                "$.wide.max()",
+                "$.text.map(len())",
+            ],
+        ),
+    ]
+}
+
+#[test]
+fn fixtures_round_trip() {
+    for (doc, queries) in fixtures() {
+        let j = Jetro::from(doc.clone());
+        let packed = j.collect_msgpack("$").unwrap();
+        let cbor = j.collect_cbor("$").unwrap();
+        let decoded = [
+            Jetro::from_msgpack(&packed).unwrap(),
+            Jetro::from_cbor(&cbor).unwrap(),
+        ];
+        for d in &decoded {
+            assert_eq!(d.collect("$").unwrap(), doc);
+        }
+        for expr in queries {
+            let expected = j.collect(expr).unwrap();
+            for d in &decoded {
+                assert_eq!(d.collect(expr).unwrap(), expected, "{expr}");
+            }
+        }
+        assert_eq!(decoded[0].collect_msgpack("$").unwrap(), packed);
+        assert_eq!(decoded[1].collect_cbor("$").unwrap(), cbor);
+    }
+}
+
+#[test]
+fn arrays_decode_to_columnar_lanes() {
+    let j = Jetro::from(json!([{"id": 1, "n": "a"}, {"id": 2, "n": "b"}]));
+    for val in [
+        msgpack::decode(&j.collect_msgpack("$").unwrap()).unwrap(),
+        cbor::decode(&j.collect_cbor("$").unwrap()).unwrap(),
+    ] {
+        let Val::ObjVec(data) = val else {
+            panic!("expected ObjVec");
+        };
+        assert_eq!(data.nrows(), 2);
+    }
+    assert!(matches!(
+        msgpack::decode(&hex("93010203")).unwrap(),
+        Val::IntVec(_)
+    ));
+    assert!(matches!(
+        cbor::decode(&hex("8261616162")).unwrap(),
+        Val::StrVec(_)
+    ));
+}
+
+#[test]
+fn msgpack_encodings() {
+    let cases: &[(Value, &str)] = &[
+        (json!(0), "00"),
+        (json!(127), "7f"),
+        (json!(128), "cc80"),
+        (json!(256), "cd0100"),
+        (json!(65_536), "ce00010000"),
+        (json!(4_294_967_296_i64), "cf0000000100000000"),
+        (json!(-32), "e0"),
+        (json!(-33), "d0df"),
+        (json!(-129), "d1ff7f"),
+        (json!(-32_769), "d2ffff7fff"),
+        (json!(-2_147_483_649_i64), "d3ffffffff7fffffff"),
+        (json!(1.5), "cb3ff8000000000000"),
+        (json!(null), "c0"),
+        (json!([true, false]), "92c3c2"),
+        (json!("a"), "a161"),
+        (json!({"a": []}), "81a16190"),
+    ];
+    for (value, expected) in cases {
+        let out = Jetro::from(value.clone()).collect_msgpack("$").unwrap();
+        assert_eq!(out, hex(expected), "{value}");
+        assert_eq!(&from_msgpack(expected), value);
+    }
+    let long = Jetro::from(json!(["x".repeat(31), "x".repeat(32), vec![0; 16]]))
+        .collect_msgpack("$")
+        .unwrap();
+    assert_eq!(&long[..2], &[0x93, 0xbf]);
+    assert_eq!(&long[33..35], &[0xd9, 0x20]);
+    assert_eq!(&long[67..70], &[0xdc, 0x00, 0x10]);
+}
+
+#[test]
+fn msgpack_values_without_a_json_spelling() {
+    assert_eq!(from_msgpack("c403010203"), json!("AQID"));
+    assert_eq!(from_msgpack("c50000"), json!(""));
+    assert_eq!(from_msgpack("d401ff"), json!({"ext": 1, "data": "/w=="}));
+    assert_eq!(
+        from_msgpack("d6ff00000001"),
+        json!({"ext": -1, "data": "AAAAAQ=="})
+    );
+    assert_eq!(
+        from_msgpack("c70205aabb"),
+        json!({"ext": 5, "data": "qrs="})
+    );
+    assert_eq!(
+        from_msgpack("cfffffffffffffffff"),
+        json!(18_446_744_073_709_551_615.0)
+    );
+    assert_eq!(from_msgpack("ca3fc00000"), json!(1.5));
+    assert_eq!(
+        from_msgpack("8401a161c3a162c0a163920102a164"),
+        json!({"1": "a", "true": "b", "null": "c", "[1,2]": "d"})
+    );
+    assert_eq!(from_msgpack("82a16101a16102"), json!({"a": 2}));
+
+    let inf = hex("cb7ff0000000000000");
+    assert_eq!(
+        Jetro::from_msgpack(&inf)
+            .unwrap()
+            .collect_msgpack("$")
+            .unwrap(),
+        inf
+    );
+}
+
+#[test]
+fn cbor_rfc_8949_examples() {
+    let cases: &[(&str, Value)] = &[
+        ("00", json!(0)),
+        ("17", json!(23)),
+        ("1818", json!(24)),
+        ("1903e8", json!(1000)),
+        ("1b000000e8d4a51000", json!(1_000_000_000_000_i64)),
+        ("1bffffffffffffffff", json!(18_446_744_073_709_551_615.0)),
+        (
+            "c249010000000000000000",
+            json!(18_446_744_073_709_551_616.0),
+        ),
+        ("3bffffffffffffffff", json!(-18_446_744_073_709_551_616.0)),
+        (
+            "c349010000000000000000",
+            json!(-18_446_744_073_709_551_617.0),
+        ),
+        ("c24101", json!(1)),
+        ("20", json!(-1)),
+        ("3903e7", json!(-1000)),
+        ("f90000", json!(0.0)),
+        ("f93c00", json!(1.0)),
+        ("f9c400", json!(-4.0)),
+        ("f97bff", json!(65504.0)),
+        ("f90001", json!(5.960_464_477_539_063e-8)),
+        ("fa47c35000", json!(100000.0)),
+        ("fb3ff199999999999a", json!(1.1)),
+        ("f4", json!(false)),
+        ("f5", json!(true)),
+        ("f6", json!(null)),
+        ("f7", json!(null)),
+        ("f0", json!(null)),
+        ("f8ff", json!(null)),
+        (
+            "c074323031332d30332d32315432303a30343a30305a",
+            json!("2013-03-21T20:04:00Z"),
+        ),
+        ("c11a514b67b0", json!(1_363_896_240)),
+        (
+            "d82076687474703a2f2f7777772e6578616d706c652e636f6d",
+            json!("http://www.example.com"),
+        ),
+        ("4401020304", json!("AQIDBA==")),
+        ("5f42010243030405ff", json!("AQIDBAU=")),
+        ("62c3bc", json!("ü")),
+        ("7f657374726561646d696e67ff", json!("streaming")),
+        ("8301820203820405", json!([1, [2, 3], [4, 5]])),
+        ("9f018202039f0405ffff", json!([1, [2, 3], [4, 5]])),
+        ("a201020304", json!({"1": 2, "3": 4})),
+        ("bf61610161629f0203ffff", json!({"a": 1, "b": [2, 3]})),
+        ("a1f5a0", json!({"true": {}})),
+    ];
+    for (encoded, expected) in cases {
+        assert_eq!(&from_cbor(encoded), expected, "{encoded}");
+    }
+    assert!(matches!(cbor::decode(&hex("f97e00")).unwrap(), Val::Float(f) if f.is_nan()));
+}
+
+#[test]
+fn cbor_encodings() {
+    for (value, expected) in [
+        (json!(0), "00"),
+        (json!(23), "17"),
+        (json!(24), "1818"),
+        (json!(1000), "1903e8"),
+        (json!(1_000_000), "1a000f4240"),
+        (json!(1_000_000_000_000_i64), "1b000000e8d4a51000"),
+        (json!(-1), "20"),
+        (json!(-1000), "3903e7"),
+        (json!(i64::MIN), "3b7fffffffffffffff"),
+        (json!(1.1), "fb3ff199999999999a"),
+        (json!("IETF"), "6449455446"),
+        (json!([1, [2, 3]]), "8201820203"),
+        (json!({"a": [null, true]}), "a1616182f6f5"),
+    ] {
+        let out = Jetro::from(value.clone()).collect_cbor("$").unwrap();
+        assert_eq!(out, hex(expected), "{value}");
+    }
+}
+
+#[test]
+fn builtins_yield_base64() {
+    let j = Jetro::from(books());
+    let rows = j.collect("$.store.books").unwrap();
+    let Value::String(packed) = j.collect("$.store.books.to_msgpack()").unwrap() else {
+        panic!("expected base64 text");
+    };
+    let Value::String(cbor) = j.collect("$.store.books.to_cbor()").unwrap() else {
+        panic!("expected base64 text");
+    };
+    let packed = base64_decode(&packed).unwrap();
+    let cbor = base64_decode(&cbor).unwrap();
+    assert_eq!(packed, j.collect_msgpack("$.store.books").unwrap());
+    assert_eq!(cbor, j.collect_cbor("$.store.books").unwrap());
+    assert_eq!(
+        Jetro::from_msgpack(&packed).unwrap().collect("$").unwrap(),
+        rows
+    );
+    assert_eq!(Jetro::from_cbor(&cbor).unwrap().collect("$").unwrap(), rows);
+    assert_eq!(j.collect("$.user.age.to_cbor()").unwrap(), json!("GB4="));
+    assert_eq!(
+        j.collect("$.user.name.to_msgpack()").unwrap(),
+        json!("pUFsaWNl")
+    );
+}
+
+#[test]
+fn malformed_input_reports_the_offset() {
+    let err = |r: Result<Val, DecodeError>| {
+        let err = r.unwrap_err();
+        (err.offset(), err.message().to_owned())
+    };
+    assert_eq!(
+        err(msgpack::decode(&hex("92a26869"))),
+        (4, "unexpected end of input".into())
+    );
+    assert_eq!(
+        err(msgpack::decode(&hex("91c1"))),
+        (1, "reserved marker byte 0xc1".into())
+    );
+    assert_eq!(
+        err(msgpack::decode(&hex("c0c0"))),
+        (1, "trailing bytes after document".into())
+    );
+    assert_eq!(
+        err(msgpack::decode(&hex("81a2ff00"))),
+        (2, "invalid UTF-8 in string".into())
+    );
+    assert_eq!(
+        err(msgpack::decode(&hex("ddffffffff"))),
+        (5, "unexpected end of input".into())
+    );
+    assert_eq!(
+        err(cbor::decode(&hex("82ff"))),
+        (1, "unexpected break".into())
+    );
+    assert_eq!(
+        err(cbor::decode(&hex("5f4101610200ff"))),
+        (3, "invalid chunk in indefinite-length string".into())
+    );
+    assert_eq!(
+        err(cbor::decode(&hex("1c"))),
+        (0, "reserved additional information".into())
+    );
+    assert_eq!(
+        err(cbor::decode(&hex("1f"))),
+        (0, "unexpected indefinite length".into())
+    );
+    assert_eq!(
+        err(cbor::decode(&hex("9f01"))),
+        (2, "unexpected end of input".into())
+    );
+
+    let deep = vec![0x91; 100_000];
+    let Err(e) = Jetro::from_msgpack(&deep) else {
+        panic!("deep document accepted");
+    };
+    assert!(e.message().contains("nests deeper than the limit"), "{e}");
+    let Err(e) = Jetro::from_cbor(&vec![0xc6; 100_000]) else {
+        panic!("deeply tagged document accepted");
+    };
+    assert!(e.message().contains("nests deeper than the limit"), "{e}");
+    let Err(e) = Jetro::from_cbor(&hex("a1616182")) else {
+        panic!("truncated document accepted");
+    };
+    assert_eq!(e.to_string(), "unexpected end of input at byte 4");
+}
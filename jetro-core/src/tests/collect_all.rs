@@ -0,0 +1,163 @@
+//! `JetroEngine::collect_all`: several queries over one document, with
+//! pipelines over a shared field chain fused into one scan.
+
+use serde_json::{json, Value};
+
+use crate::{ExecutionLimits, Jetro, JetroEngine};
+
+fn payload() -> Value {
+    json!({
+        "orders": [
+            {"id": 1, "total": 120, "status": "paid", "items": ["a", "b"]},
+            {"id": 2, "total": 40, "status": "open", "items": ["c"]},
+            {"id": 3, "total": 75.5, "status": "paid", "items": []},
+            {"id": 4, "total": 300, "status": "refunded", "items": ["d", "e", "f"]}
+        ],
+        "refunds": [{"id": 4, "amount": 300}],
+        "threshold": 50
+    })
+}
+
+fn docs() -> [Jetro; 2] {
+    [
+        Jetro::from_bytes(serde_json::to_vec(&payload()).unwrap()).unwrap(),
+        Jetro::from(payload()),
+    ]
+}
+
+/// Metrics over `$.orders` (fused together), one over `$.refunds`, and a few
+/// that are not pipelines at all.
+const METRICS: &[&str] = &[
+    "$.orders.count()",
+    "$.orders.map(total).sum()",
+    "$.orders.map(total).avg()",
+    "$.orders.map(total).max()",
+    "$.orders.filter(status == \"paid\").count()",
+    "$.orders.filter(total > 50).map(id)",
+    "$.orders.map(id).first()",
+    "$.orders.map(id).last()",
+    "$.orders.take(2).map(id)",
+    "$.orders.filter(total > $.threshold).map(id)",
+    "$.orders.map({id, n: items.len()})",
+    "$.orders.sort_by(total).map(id)",
+    "$.refunds.map(amount).sum()",
+    "$.threshold",
+    "{n: $.orders.len(), t: $.threshold}",
+];
+
+#[test]
+fn matches_collecting_each_query_separately() {
+    let engine = JetroEngine::new();
+    for j in docs() {
+        let all = engine.collect_all(&j, METRICS);
+        assert_eq!(all.len(), METRICS.len());
+        for (expr, result) in METRICS.iter().zip(all) {
+            assert_eq!(
+                result.unwrap(),
+                JetroEngine::new().collect(&j, expr).unwrap(),
+                "{expr}"
+            );
+        }
+    }
+}
+
+#[test]
+fn early_exit_sinks_do_not_cut_other_queries_short() {
+    let engine = JetroEngine::new();
+    for j in docs() {
+        let all = engine.collect_all(
+            &j,
+            &[
+                "$.orders.map(id).first()",
+                "$.orders.map(id).last()",
+                "$.orders.count()",
+            ],
+        );
+        let all: Vec<Value> = all.into_iter().map(Result::unwrap).collect();
+        assert_eq!(all, vec![json!(1), json!(4), json!(4)]);
+    }
+}
+
+#[test]
+fn failing_query_does_not_stop_the_others() {
+    let engine = JetroEngine::new();
+    for j in docs() {
+        let all = engine.collect_all(
+            &j,
+            &[
+                "$.orders.count()",
+                "$.orders.map(-status)",
+                "$.orders.(",
+                "$.orders.map(total).sum()",
+            ],
+        );
+        assert_eq!(all[0].as_ref().unwrap(), &json!(4));
+        assert!(all[1].is_err());
+        assert!(all[2].is_err());
+        assert_eq!(all[3].as_ref().unwrap(), &json!(535.5));
+    }
+}
+
+#[test]
+fn missing_source_gives_each_query_its_own_empty_result() {
+    let engine = JetroEngine::new();
+    for j in docs() {
+        let all = engine.collect_all(&j, &["$.nope.count()", "$.nope.map(id)"]);
+        for (expr, result) in ["$.nope.count()", "$.nope.map(id)"].iter().zip(all) {
+            assert_eq!(result.unwrap(), engine.collect(&j, expr).unwrap(), "{expr}");
+        }
+    }
+}
+
+#[test]
+fn fused_byte_queries_do_not_materialize_the_root() {
+    let engine = JetroEngine::new();
+    let j = Jetro::from_bytes(serde_json::to_vec(&payload()).unwrap()).unwrap();
+    let all = engine.collect_all(
+        &j,
+        &[
+            "$.orders.count()",
+            "$.orders.map(total).sum()",
+            "$.orders.map(id)",
+        ],
+    );
+    assert!(all.iter().all(Result::is_ok));
+    #[cfg(feature = "simd-json")]
+    assert!(!j.root_val_is_materialized());
+}
+
+#[test]
+fn plans_are_cached_and_limits_apply() {
+    let mut engine = JetroEngine::new();
+    let j = Jetro::from(payload());
+    engine.collect_all(&j, METRICS);
+    engine.collect_all(&j, METRICS);
+    assert_eq!(engine.plan_cache_stats().hits, METRICS.len() as u64);
+
+    engine.set_limits(ExecutionLimits::new().max_output_elements(2));
+    let all = engine.collect_all(&j, &["$.orders.map(id)", "$.orders.count()"]);
+    assert!(all[0].is_err());
+    assert_eq!(all[1].as_ref().unwrap(), &json!(4));
+}
+
+#[test]
+fn keyed_reducers_match_collect() {
+    let queries = [
+        "$.orders.count()",
+        "$.orders.count_by(status)",
+        "$.orders.index_by(id)",
+        "$.orders.group_by(status)",
+        "$.orders.map(total).sum()",
+    ];
+    let engine = JetroEngine::new();
+    for j in docs() {
+        for (expr, result) in queries.iter().zip(engine.collect_all(&j, &queries)) {
+            assert_eq!(result.unwrap(), engine.collect(&j, expr).unwrap(), "{expr}");
+        }
+        let alone = engine.collect_all(&j, &["$.orders.count_by(status)"]);
+        assert_eq!(
+            alone.into_iter().next().unwrap().unwrap(),
+            json!({"paid": 2, "open": 1, "refunded": 1})
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! `Jetro` documents and `JetroEngine`s shared across threads.
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::data::value::Val;
+use crate::vm::VmPool;
+use crate::{Jetro, JetroEngine};
+
+const THREADS: usize = 8;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+/// Run `query` against `doc` from `THREADS` threads released together, and
+/// return every thread's result.
+fn collect_concurrently(doc: &Arc<Jetro>, query: &'static str) -> Vec<Value> {
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let doc = Arc::clone(doc);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                doc.collect(query).unwrap()
+            })
+        })
+        .collect();
+    workers.into_iter().map(|w| w.join().unwrap()).collect()
+}
+
+fn books_bytes() -> Vec<u8> {
+    br#"{"books":[{"title":"a","price":12},{"title":"b","price":3},{"title":"c","price":7}]}"#
+        .to_vec()
+}
+
+#[test]
+fn documents_and_values_are_send_and_sync() {
+    assert_send_sync::<Jetro>();
+    assert_send_sync::<JetroEngine>();
+    assert_send_sync::<Val>();
+    #[cfg(feature = "simd-json")]
+    assert_send_sync::<crate::data::tape::TapeData>();
+}
+
+#[cfg(feature = "simd-json")]
+#[test]
+fn concurrent_queries_parse_the_tape_once() {
+    let doc = Arc::new(Jetro::from_bytes(books_bytes()).unwrap());
+    let results = collect_concurrently(&doc, "$.books.filter(price > 5).map(title)");
+    assert!(
+        results.iter().all(|r| *r == json!(["a", "c"])),
+        "{results:?}"
+    );
+    assert_eq!(doc.builds.tape.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn concurrent_queries_build_the_structural_index_once() {
+    let doc = Arc::new(
+        Jetro::from_bytes(
+            br#"{"users":[{"email":"a@x","role":"lead"},{"team":{"email":"b@x"}}]}"#.to_vec(),
+        )
+        .unwrap(),
+    );
+    let results = collect_concurrently(&doc, "$.deep_shape({email})");
+    let expected = json!([{"email": "a@x", "role": "lead"}, {"email": "b@x"}]);
+    assert!(results.iter().all(|r| *r == expected), "{results:?}");
+    assert_eq!(doc.builds.structural_index.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn concurrent_queries_promote_an_array_to_columns_once() {
+    let doc = Arc::new(Jetro::from(json!({
+        "rows": (0..64).map(|i| json!({"id": i, "score": i % 7})).collect::<Vec<_>>()
+    })));
+    let results = collect_concurrently(&doc, "let rows = $.rows in rows.filter(score > 3).count()");
+    assert!(results.iter().all(|r| *r == json!(27)), "{results:?}");
+    assert_eq!(doc.builds.objvec.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn shared_engine_and_document_serve_many_threads() {
+    let engine = Arc::new(JetroEngine::new());
+    let doc = Arc::new(Jetro::from_bytes(books_bytes()).unwrap());
+    let workers: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let engine = Arc::clone(&engine);
+            let doc = Arc::clone(&doc);
+            thread::spawn(move || {
+                let query = if i % 2 == 0 {
+                    "$.books.map(price).sum()"
+                } else {
+                    "$.books.len()"
+                };
+                (i, engine.collect(&doc, query).unwrap())
+            })
+        })
+        .collect();
+    for worker in workers {
+        let (i, value) = worker.join().unwrap();
+        assert_eq!(value, if i % 2 == 0 { json!(22) } else { json!(3) });
+    }
+}
+
+#[test]
+fn pooled_vms_share_one_compile_cache() {
+    let pool = VmPool::new();
+    let mut first = pool.checkout();
+    let mut second = pool.checkout();
+    let a = first.get_or_compile("$.books.len()").unwrap();
+    let b = second.get_or_compile("$.books.len()").unwrap();
+    assert!(Arc::ptr_eq(&a, &b));
+    assert_eq!(pool.compiled_len(), 1);
+}
+
+#[test]
+fn pooled_vms_hit_the_compile_cache_concurrently() {
+    let pool = Arc::new(VmPool::new());
+    let warm = pool.checkout().get_or_compile("$.books.len()").unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let pool = Arc::clone(&pool);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let mut vm = pool.checkout();
+                barrier.wait();
+                (0..100)
+                    .map(|_| vm.get_or_compile("$.books.len()").unwrap())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    for worker in workers {
+        assert!(worker.join().unwrap().iter().all(|prog| Arc::ptr_eq(prog, &warm)));
+    }
+    assert_eq!(pool.compiled_len(), 1);
+}
+
+#[test]
+fn checked_in_vms_are_reused() {
+    let pool = VmPool::new();
+    drop(pool.checkout());
+    assert_eq!(pool.idle_len(), 1);
+    let vm = pool.checkout();
+    assert_eq!(pool.idle_len(), 0);
+    drop(vm);
+    assert_eq!(pool.idle_len(), 1);
+}
+
+#[test]
+fn engine_runs_plans_on_pooled_vms_without_leaking_paths_between_documents() {
+    let engine = JetroEngine::new();
+    let query = "$.a | @.b";
+    let first = Jetro::from(json!({"a": {"b": 1}}));
+    let second = Jetro::from(json!({"a": {"b": 2}}));
+    assert_eq!(engine.collect(&first, query).unwrap(), json!(1));
+    assert_eq!(engine.vms.idle_len(), 1);
+    let (_, paths) = engine.vms.checkout().cache_stats();
+    assert_eq!(paths, 1, "the plan's fallback node ran on the pooled VM");
+    assert_eq!(engine.collect(&second, query).unwrap(), json!(2));
+    assert_eq!(engine.collect(&first, query).unwrap(), json!(1));
+}
+
+#[test]
+fn collect_many_keeps_input_order_and_isolates_errors() {
+    let engine = JetroEngine::new();
+    let documents: Vec<Jetro> = (0..37)
+        .map(|i| {
+            if i == 5 {
+                Jetro::from(json!({"n": "five"}))
+            } else {
+                Jetro::from(json!({"n": i}))
+            }
+        })
+        .collect();
+    let results = engine.collect_many(&documents, "$.n * 2");
+    assert_eq!(results.len(), documents.len());
+    for (i, result) in results.iter().enumerate() {
+        if i == 5 {
+            assert!(result.is_err());
+        } else {
+            assert_eq!(result.as_ref().unwrap(), &json!(i * 2));
+        }
+    }
+}
+
+#[test]
+fn collect_many_handles_empty_and_single_batches() {
+    let engine = JetroEngine::new();
+    assert!(engine.collect_many(&[], "$.n * 2").is_empty());
+    let one = [Jetro::from(json!({"n": 1}))];
+    assert_eq!(engine.collect_many(&one, "$.n * 2")[0].as_ref().unwrap(), &json!(2));
+}
@@ -0,0 +1,214 @@
+//! CSV and TSV text read by `Jetro::from_csv` and the `from_csv()` builtin.
+
+use serde_json::{json, Value};
+
+use crate::data::csv::parse;
+use crate::data::value::{ObjVecCol, Val};
+use crate::{CsvOptions, EvalErrorKind, Jetro, JetroEngine};
+
+fn rows(src: &str, options: CsvOptions) -> Value {
+    Jetro::from_csv(src.as_bytes(), options)
+        .unwrap()
+        .collect("$")
+        .unwrap()
+}
+
+/// `(line, column, message)` of the error `src` fails with.
+fn error(src: &[u8], options: CsvOptions) -> (usize, usize, String) {
+    let err = parse(src, options).unwrap_err();
+    (err.line(), err.column(), err.message().to_owned())
+}
+
+#[test]
+fn columns_are_typed_from_their_fields() {
+    let src = "id,price,ok,zip,note,empty\n\
+               1,2,true,02139,a,\n\
+               -2,2.5,FALSE,10001,\"7\",\n\
+               3,,false,,b,\n";
+    assert_eq!(
+        rows(src, CsvOptions::new()),
+        json!([
+            {"id": 1, "price": 2.0, "ok": true, "zip": "02139", "note": "a", "empty": null},
+            {"id": -2, "price": 2.5, "ok": false, "zip": "10001", "note": "7", "empty": null},
+            {"id": 3, "price": null, "ok": false, "zip": null, "note": "b", "empty": null}
+        ])
+    );
+    assert_eq!(
+        rows("n,x\n1e3,inf\n.5,NaN\n", CsvOptions::new()),
+        json!([{"n": 1000.0, "x": "inf"}, {"n": 0.5, "x": "NaN"}])
+    );
+    assert_eq!(
+        rows("a,b\n1,\n", CsvOptions::new().infer_types(false)),
+        json!([{"a": "1", "b": ""}])
+    );
+}
+
+#[test]
+fn quoting_delimiters_and_line_endings() {
+    let src =
+        "name;quote\r\n\"Smith; J\";\"say \"\"hi\"\"\"\r\n\r\n\"multi\r\nline\";\"\"\"\"\rlast;x";
+    assert_eq!(
+        rows(src, CsvOptions::new().delimiter(b';')),
+        json!([
+            {"name": "Smith; J", "quote": "say \"hi\""},
+            {"name": "multi\r\nline", "quote": "\""},
+            {"name": "last", "quote": "x"}
+        ])
+    );
+    assert_eq!(
+        rows(
+            "a|b\n'x|y'|'it''s'\n",
+            CsvOptions::new().delimiter(b'|').quote(b'\'')
+        ),
+        json!([{"a": "x|y", "b": "it's"}])
+    );
+    assert_eq!(
+        rows("1\té\n2\t\n", CsvOptions::tsv().header(false)),
+        json!([{"c0": 1, "c1": "é"}, {"c0": 2, "c1": null}])
+    );
+    assert_eq!(rows("a,b\n", CsvOptions::new()), json!([]));
+    assert_eq!(rows("", CsvOptions::new()), json!([]));
+}
+
+#[test]
+fn rows_are_stored_column_wise() {
+    let Val::ObjVec(data) = parse(b"id,price,name\n1,2,a\n2,3.5,b\n", CsvOptions::new()).unwrap()
+    else {
+        panic!("expected ObjVec");
+    };
+    assert_eq!(&*data.keys[0], "id");
+    let cols = data.typed_cols.as_ref().unwrap();
+    assert!(matches!(&cols[0], ObjVecCol::Ints(xs) if xs == &[1, 2]));
+    assert!(matches!(&cols[1], ObjVecCol::Floats(xs) if xs == &[2.0, 3.5]));
+    assert!(matches!(&cols[2], ObjVecCol::Strs(_)));
+}
+
+#[test]
+fn queries_match_the_json_spelling() {
+    let mut csv = String::from("id,group,price,active\n");
+    let mut objs = Vec::new();
+    for n in 0..2_000 {
+        let group = format!("g{}", n % 5);
+        let price = n as f64 / 4.0;
+        csv.push_str(&format!("{},{},{},{}\n", n, group, price, n % 3 == 0));
+        objs.push(json!({"id": n, "group": group, "price": price, "active": n % 3 == 0}));
+    }
+    let j = Jetro::from_csv(csv.as_bytes(), CsvOptions::new()).unwrap();
+    let engine = JetroEngine::new();
+    let doc = json!({ "rows": objs });
+    for expr in [
+        "$.filter(price > 100).map(id)",
+        "$.filter(active).count()",
+        "$.map(price * 2).sum()",
+        "$.group_by(group).transform_values(@.len())",
+        "$.sort_by(price).last().id",
+        "$.map({id, group}).take(3)",
+    ] {
+        let expected = engine
+            .collect_value(doc.clone(), expr.replace('$', "$.rows"))
+            .unwrap();
+        assert_eq!(engine.collect(&j, expr).unwrap(), expected, "{expr}");
+    }
+
+    let (_, profile) = engine
+        .collect_profiled(&j, "$.filter(price > 100).map(id)")
+        .unwrap();
+    let pipeline = profile.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.executors[0].name, "columnar");
+}
+
+#[test]
+fn from_csv_builtin_parses_strings() {
+    let j =
+        Jetro::from_bytes(br#"{"report": "sku,qty\nA1,3\nB2,10\n", "bad": "a,b\n1\n"}"#.to_vec())
+            .unwrap();
+    assert_eq!(
+        j.collect("$.report.from_csv().filter(qty > 5).map(sku)")
+            .unwrap(),
+        json!(["B2"])
+    );
+    assert_eq!(
+        j.collect("$.report.parse_csv().map(qty).sum()").unwrap(),
+        json!(13)
+    );
+    let err = j.collect("$.bad.from_csv()").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::InvalidArgument);
+    assert!(err
+        .message()
+        .contains("expected 2 fields, found 1 at line 2 column 1"));
+    let err = j.collect("$.report.len().from_csv()").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::TypeMismatch);
+}
+
+#[test]
+fn round_trips_through_to_csv() {
+    let src = "1,\"a, b\",true\n2,\"say \"\"x\"\"\",false";
+    let j = Jetro::from_csv(src.as_bytes(), CsvOptions::new().header(false)).unwrap();
+    assert_eq!(j.collect("$.to_csv()").unwrap(), json!(src));
+}
+
+#[test]
+fn leading_byte_order_mark_is_skipped() {
+    assert_eq!(
+        rows("\u{feff}a,b\n1,2\n", CsvOptions::new()),
+        json!([{"a": 1, "b": 2}])
+    );
+    assert_eq!(
+        rows("\u{feff}\"a\",b\n1,2\n", CsvOptions::new()),
+        json!([{"a": 1, "b": 2}])
+    );
+    assert_eq!(
+        rows("\u{feff}x\ty\n", CsvOptions::tsv().header(false)),
+        json!([{"c0": "x", "c1": "y"}])
+    );
+    assert_eq!(
+        error("\u{feff}a,\"b\"c\n".as_bytes(), CsvOptions::new()),
+        (1, 6, "expected delimiter after closing quote".into())
+    );
+}
+
+#[test]
+fn errors_point_at_line_and_column() {
+    let csv = CsvOptions::new();
+    assert_eq!(
+        error(b"a,b\n1,2\n3\n", csv),
+        (3, 1, "expected 2 fields, found 1".into())
+    );
+    assert_eq!(
+        error(b"a,b\n\"x\ny,2\n", csv),
+        (2, 1, "unterminated quoted field".into())
+    );
+    assert_eq!(
+        error(b"a,b\n\xc3\xa9,\"x\"y\n", csv),
+        (2, 6, "expected delimiter after closing quote".into())
+    );
+    assert_eq!(
+        error(b"a,a\n1,2\n", csv),
+        (1, 1, "duplicate column `a`".into())
+    );
+    assert_eq!(error(b"a\nok\n\xff\n", csv), (3, 1, "invalid UTF-8".into()));
+    let err = Jetro::from_csv(b"a\n\"", csv).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "unterminated quoted field at line 2 column 1"
+    );
+}
+
+#[test]
+fn unusable_delimiter_or_quote_is_an_error() {
+    let ascii = "must be an ASCII byte other than a line break";
+    for (options, message) in [
+        (CsvOptions::new().delimiter(0xC3), format!("delimiter {ascii}")),
+        (CsvOptions::new().delimiter(b'\n'), format!("delimiter {ascii}")),
+        (CsvOptions::new().quote(0xFF), format!("quote {ascii}")),
+        (CsvOptions::new().quote(b'\r'), format!("quote {ascii}")),
+        (CsvOptions::new().quote(b','), "delimiter and quote must differ".into()),
+        (CsvOptions::tsv().quote(b'\t'), "delimiter and quote must differ".into()),
+    ] {
+        assert_eq!(error(b"a,b\n1,2\n", options), (1, 1, message));
+    }
+    assert_eq!(
+        rows("a;b\n'x;y';2\n", CsvOptions::new().delimiter(b';').quote(b'\'')),
+        json!([{"a": "x;y", "b": 2}])
+    );
+}
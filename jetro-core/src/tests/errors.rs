@@ -0,0 +1,138 @@
+//! Structured `EvalError` coverage: error kinds, failing operation, source
+//! span, and JSON path, across the VM and physical-plan execution paths.
+
+use serde_json::json;
+
+use crate::data::context::{EvalError, EvalErrorKind};
+use crate::data::value::Val;
+use crate::{Jetro, JetroEngine, JetroEngineError};
+
+fn bytes_doc() -> Jetro {
+    let doc = json!({
+        "n": 5,
+        "s": "abc",
+        "xs": [1, 2, 3],
+        "orders": [{"total": 1}, {"total": "x"}]
+    });
+    Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap()
+}
+
+fn err(expr: &str) -> EvalError {
+    bytes_doc().collect(expr).unwrap_err()
+}
+
+#[test]
+fn division_by_zero_reports_kind_opcode_and_pipeline_path() {
+    let e = err("$.xs.map(@ / 0)");
+    assert_eq!(e.kind(), EvalErrorKind::DivisionByZero);
+    assert_eq!(e.operation(), Some("Div"));
+    assert_eq!(e.path(), Some("$.xs"));
+    assert_eq!(e.message(), "division by zero");
+}
+
+#[test]
+fn type_mismatch_inside_pipeline_row() {
+    let e = err("$.orders.map(total * 2)");
+    assert_eq!(e.kind(), EvalErrorKind::TypeMismatch);
+    assert_eq!(e.path(), Some("$.orders"));
+}
+
+#[test]
+fn builtin_failure_records_operation_and_span() {
+    let expr = "$.xs.rolling_sum(0)";
+    let e = err(expr);
+    assert_eq!(e.kind(), EvalErrorKind::InvalidArgument);
+    assert_eq!(e.operation(), Some("rolling_sum"));
+    let span = e.span().expect("span");
+    assert_eq!(&expr[span], ".rolling_sum(0)");
+}
+
+#[test]
+fn repeated_operation_spans_the_failing_call() {
+    let expr = "[$.xs.rolling_sum(1), $.xs.rolling_sum(0)]";
+    let e = err(expr);
+    assert_eq!(e.operation(), Some("rolling_sum"));
+    let span = e.span().expect("span");
+    assert_eq!(span.start, expr.rfind(".rolling_sum").unwrap());
+    assert_eq!(&expr[span], ".rolling_sum(0)");
+}
+
+#[test]
+fn call_name_inside_string_literal_does_not_move_span() {
+    let expr = r#"["rolling_sum(0)", $.xs.rolling_sum(0)]"#;
+    let e = err(expr);
+    let span = e.span().expect("span");
+    assert_eq!(span.start, expr.find(".rolling_sum").unwrap());
+    assert_eq!(&expr[span], ".rolling_sum(0)");
+}
+
+#[test]
+fn global_call_failure_records_span() {
+    let expr = r#"{a: 1, b: from_json("{")}"#;
+    let e = err(expr);
+    assert_eq!(e.operation(), Some("from_json"));
+    assert_eq!(&expr[e.span().expect("span")], r#"from_json("{")"#);
+}
+
+#[test]
+fn unknown_method_kind() {
+    let e = err("$.s.no_such_method()");
+    assert_eq!(e.kind(), EvalErrorKind::UnknownMethod);
+    assert_eq!(e.operation(), Some("no_such_method"));
+}
+
+#[test]
+fn delete_outside_patch_kind() {
+    let e = err("{a: DELETE}");
+    assert_eq!(e.kind(), EvalErrorKind::DeleteOutsidePatch);
+}
+
+#[test]
+fn invalid_json_kinds() {
+    let e = err(r#"from_json("{")"#);
+    assert_eq!(e.kind(), EvalErrorKind::InvalidJson);
+    assert_eq!(e.operation(), Some("from_json"));
+
+    let broken = Jetro::from_bytes(b"{\"a\": [1, 2".to_vec()).unwrap();
+    assert_eq!(broken.collect("$.a").unwrap_err().kind(), EvalErrorKind::InvalidJson);
+}
+
+#[test]
+fn invalid_regex_kind() {
+    let e = crate::builtins::ops::regex::try_re_match_apply(&Val::Str("abc".into()), "[").unwrap_err();
+    assert_eq!(e.kind(), EvalErrorKind::InvalidRegex);
+}
+
+#[test]
+fn parse_failure_records_offset_as_span() {
+    let expr = "$.xs.map(@ +)";
+    let e = err(expr);
+    assert_eq!(e.kind(), EvalErrorKind::Parse);
+    let offset = crate::Query::validate(expr).unwrap_err().offset;
+    assert_eq!(e.span(), Some(offset..offset));
+}
+
+#[test]
+fn display_keeps_message_format() {
+    let e = EvalError::type_mismatch("filter: expected array");
+    assert_eq!(e.to_string(), "eval error: filter: expected array");
+    let cloned = e.clone();
+    assert_eq!(cloned.kind(), EvalErrorKind::TypeMismatch);
+    let _: &dyn std::error::Error = &cloned;
+}
+
+#[test]
+fn engine_error_exposes_eval_detail() {
+    let engine = JetroEngine::new();
+    let e = engine
+        .collect_bytes(br#"{"xs": [1, 2]}"#.to_vec(), "$.xs.rolling_sum(0)")
+        .unwrap_err();
+    assert_eq!(e.kind(), EvalErrorKind::InvalidArgument);
+    assert_eq!(e.operation(), Some("rolling_sum"));
+    assert!(e.span().is_some());
+    assert!(std::error::Error::source(&e).is_some());
+
+    let json_err = JetroEngineError::from(serde_json::from_slice::<serde_json::Value>(b"{").unwrap_err());
+    assert_eq!(json_err.kind(), EvalErrorKind::InvalidJson);
+    assert!(json_err.eval_error().is_none());
+}
@@ -0,0 +1,174 @@
+//! Plan descriptions returned by `JetroEngine::explain`.
+
+use serde_json::json;
+
+use crate::{EvalErrorKind, ExplainNode, HostFunction, Jetro, JetroEngine};
+
+fn bytes_doc() -> Jetro {
+    Jetro::from_bytes(
+        br#"{"books":[{"title":"a","price":12},{"title":"b","price":3}],"meta":{"n":2}}"#.to_vec(),
+    )
+    .unwrap()
+}
+
+fn find<'a>(node: &'a ExplainNode, kind: &str) -> Option<&'a ExplainNode> {
+    if node.kind == kind {
+        return Some(node);
+    }
+    node.children.iter().find_map(|child| find(child, kind))
+}
+
+#[test]
+fn explain_reports_bounded_sort_strategy_and_sink_demand() {
+    let engine = JetroEngine::new();
+    let plan = engine
+        .explain("$.books.sort_by(price).first()", &bytes_doc())
+        .unwrap();
+    assert_eq!(plan.input, "bytes");
+    assert_eq!(plan.root.kind, "Pipeline");
+    let pipeline = plan.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.source, "$.books");
+    assert_eq!(pipeline.stages.len(), 1);
+    assert_eq!(pipeline.stages[0].name, "sort");
+    assert_eq!(pipeline.stages[0].strategy, "SortTopK(1)");
+    assert_eq!(pipeline.sink, "first");
+    assert_eq!(pipeline.sink_demand, "FirstInput(1)");
+}
+
+#[test]
+fn explain_reports_nth_demand_and_exec_path() {
+    let engine = JetroEngine::new();
+    let plan = engine
+        .explain("$.books.filter(price > 1).map(title).nth(1)", &bytes_doc())
+        .unwrap();
+    let pipeline = plan.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.sink, "nth(1)");
+    assert_eq!(pipeline.sink_demand, "NthInput(1)");
+    assert_eq!(pipeline.exec_path, "Columnar");
+    let names: Vec<_> = pipeline.stages.iter().map(|stage| stage.name).collect();
+    assert_eq!(names, ["filter", "map"]);
+}
+
+#[test]
+fn explain_backends_follow_input_mode() {
+    let engine = JetroEngine::new();
+    let expr = "$.books.filter(price > 1)";
+    let bytes = engine.explain(expr, &bytes_doc()).unwrap();
+    assert_eq!(bytes.root.backends[0], "TapeView");
+    assert!(bytes.root.facts.can_use_tape);
+
+    let val = engine
+        .explain(expr, &Jetro::from(json!({"books": []})))
+        .unwrap();
+    assert_eq!(val.input, "val");
+    assert_eq!(val.root.backends, ["ValView", "Interpreted"]);
+    assert!(val.root.capabilities.contains(&"TapeView"));
+}
+
+#[test]
+fn explain_describes_composite_nodes_and_vm_fallback() {
+    let engine = JetroEngine::new();
+    let plan = engine
+        .explain(r#"{"n": $.meta.n, "deep": $..price}"#, &bytes_doc())
+        .unwrap();
+    assert_eq!(plan.root.kind, "Object");
+    assert_eq!(plan.root.detail.as_deref(), Some("n, deep"));
+    assert_eq!(plan.root.children.len(), 2);
+    let vm = find(&plan.root, "Vm").expect("deep search falls back to the VM");
+    assert!(vm.facts.contains_vm_fallback);
+    assert_eq!(vm.backends, ["Interpreted"]);
+
+    let structural = engine
+        .explain("$.deep_shape({email})", &bytes_doc())
+        .unwrap();
+    assert_eq!(structural.root.kind, "Structural");
+    assert_eq!(structural.root.backends[0], "Structural");
+}
+
+#[test]
+fn explain_lists_optimizer_passes_that_fired() {
+    let engine = JetroEngine::new();
+    let doc = bytes_doc();
+
+    let plan = engine
+        .explain("$.books.map(price).filter(@ > 2).count()", &doc)
+        .unwrap();
+    assert!(
+        plan.passes.contains(&"filter_before_map"),
+        "{:?}",
+        plan.passes
+    );
+
+    let plan = engine
+        .explain("$.books.sort().reverse().map(price).sum()", &doc)
+        .unwrap();
+    assert!(
+        plan.passes.contains(&"drop_unused_order"),
+        "{:?}",
+        plan.passes
+    );
+    assert!(
+        plan.passes.contains(&"map_into_reducer"),
+        "{:?}",
+        plan.passes
+    );
+
+    let plan = engine.explain("$.meta.n", &doc).unwrap();
+    assert!(plan.passes.is_empty(), "{:?}", plan.passes);
+}
+
+#[test]
+fn explain_records_host_call_lowering() {
+    let mut engine = JetroEngine::new();
+    engine
+        .register_function(
+            "half",
+            HostFunction::new(0, |v, _| Ok(json!(v.as_f64().unwrap_or(0.0) / 2.0))).elementwise(),
+        )
+        .unwrap();
+    let plan = engine
+        .explain("$.books.map(price).half()", &bytes_doc())
+        .unwrap();
+    assert!(
+        plan.passes.contains(&"host_elementwise_map"),
+        "{:?}",
+        plan.passes
+    );
+}
+
+#[test]
+fn explain_prints_and_serializes() {
+    let engine = JetroEngine::new();
+    let plan = engine
+        .explain("$.books.sort_by(price).first()", &bytes_doc())
+        .unwrap();
+    let text = plan.to_string();
+    assert!(text.starts_with("input: bytes\npasses: "), "{text}");
+    assert!(
+        text.contains("Pipeline $.books backends=[TapeView"),
+        "{text}"
+    );
+    assert!(text.contains("  stage sort strategy=SortTopK(1)"), "{text}");
+    assert!(text.contains("  sink first demand=FirstInput(1)"), "{text}");
+
+    let value = serde_json::to_value(&plan).unwrap();
+    assert_eq!(value["root"]["kind"], "Pipeline");
+    assert_eq!(
+        value["root"]["pipeline"]["stages"][0]["strategy"],
+        "SortTopK(1)"
+    );
+    assert_eq!(value["root"]["facts"]["contains_vm_fallback"], false);
+}
+
+#[test]
+fn explain_rejects_invalid_syntax_and_leaves_cache_untouched() {
+    let engine = JetroEngine::new();
+    let doc = bytes_doc();
+    let err = engine
+        .explain("$.books.(", &doc)
+        .expect_err("parse error");
+    assert_eq!(err.kind(), EvalErrorKind::Parse);
+
+    engine.explain("$.books.len()", &doc).unwrap();
+    assert_eq!(engine.plan_cache_stats().entries, 0);
+}
@@ -0,0 +1,270 @@
+//! Host functions registered through `JetroEngine::register_function`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::ir::physical::{PlanNode, QueryRoot};
+use crate::{EvalError, EvalErrorKind, HostFunction, Jetro, JetroEngine};
+
+fn engine() -> JetroEngine {
+    let mut engine = JetroEngine::new();
+    engine
+        .register_function(
+            "to_usd",
+            HostFunction::new(0, |cents, _| {
+                Ok(json!(cents.as_i64().unwrap_or(0) as f64 / 100.0))
+            })
+            .elementwise(),
+        )
+        .unwrap();
+    engine
+        .register_function(
+            "fx",
+            HostFunction::new(1, |amount, args| {
+                let rate = match args[0].as_str() {
+                    Some("EUR") => 0.5,
+                    Some("GBP") => 0.25,
+                    _ => return Err(EvalError::invalid_argument("fx: unknown currency")),
+                };
+                Ok(json!(amount.as_f64().unwrap_or(0.0) * rate))
+            }),
+        )
+        .unwrap();
+    engine
+        .register_function(
+            "enabled",
+            HostFunction::new(0, |flag, _| Ok(json!(flag.as_str() == Some("beta")))),
+        )
+        .unwrap();
+    engine
+}
+
+fn docs(doc: Value) -> [Jetro; 2] {
+    [
+        Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+        Jetro::from(doc),
+    ]
+}
+
+#[test]
+fn host_function_callable_as_method_and_global() {
+    let engine = engine();
+    for j in docs(json!({"amount": 10, "nested": {"amount": 4}})) {
+        assert_eq!(engine.collect(&j, r#"fx($.amount, "EUR")"#).unwrap(), json!(5.0));
+        assert_eq!(
+            engine.collect(&j, r#"let a = $.nested.amount in a.fx("GBP")"#).unwrap(),
+            json!(1.0)
+        );
+    }
+}
+
+#[test]
+fn elementwise_host_function_maps_root_chain() {
+    let engine = engine();
+    for j in docs(json!({"prices": [150, 200, 5]})) {
+        assert_eq!(
+            engine.collect(&j, "$.prices.to_usd()").unwrap(),
+            json!([1.5, 2.0, 0.05])
+        );
+        assert_eq!(
+            engine.collect(&j, "$.prices.filter(@ > 100).to_usd().sum()").unwrap(),
+            json!(3.5)
+        );
+    }
+    for j in docs(json!({"price": 150, "tags": ["a"], "rows": [{"p": 250}]})) {
+        assert_eq!(engine.collect(&j, "$.price.to_usd()").unwrap(), json!(1.5));
+        assert_eq!(engine.collect(&j, "$.price.to_usd() * 2").unwrap(), json!(3.0));
+        assert_eq!(engine.collect(&j, "$.missing.to_usd()").unwrap(), json!(0.0));
+        assert_eq!(engine.collect(&j, "$.tags.to_usd()").unwrap(), json!([0.0]));
+        assert_eq!(
+            engine.collect(&j, "$.rows.map(p).to_usd()").unwrap(),
+            json!([2.5])
+        );
+    }
+}
+
+#[test]
+fn elementwise_host_function_maps_in_every_call_form() {
+    let engine = engine();
+    let doc = json!({"prices": [150, 200], "items": [{"p": [150, 200]}], "grid": [[150], [200, 5]]});
+    for j in docs(doc) {
+        let mapped = json!([1.5, 2.0]);
+        for query in [
+            "$.prices.to_usd()",
+            "to_usd($.prices)",
+            "$.prices.to_usd()?",
+            "let xs = $.prices in xs.to_usd()",
+            "$.items.map(@.p.to_usd()).first()",
+        ] {
+            assert_eq!(engine.collect(&j, query).unwrap(), mapped, "{query}");
+        }
+        let grid = json!([[1.5], [2.0, 0.05]]);
+        for query in ["$.grid.to_usd()", "to_usd($.grid)", "$.grid.map(@.to_usd())"] {
+            assert_eq!(engine.collect(&j, query).unwrap(), grid, "{query}");
+        }
+    }
+}
+
+#[test]
+fn elementwise_host_function_plans_as_pipeline() {
+    let engine = engine();
+    let j = Jetro::from_bytes(br#"{"prices":[150]}"#.to_vec()).unwrap();
+    engine.collect(&j, "$.prices.to_usd()").unwrap();
+    let plans = engine.plan_cache.plans();
+    let plan = &plans[0];
+    let QueryRoot::Node(root) = plan.root() else {
+        panic!("expected a planned root");
+    };
+    // arrays take the mapped branch; scalars call the function directly
+    let PlanNode::IfElse { then_, .. } = plan.node(*root) else {
+        panic!("expected a branch on the receiver's kind");
+    };
+    assert!(matches!(plan.node(*then_), PlanNode::Pipeline { .. }));
+}
+
+#[test]
+fn host_function_inside_pipeline_predicate() {
+    let engine = engine();
+    let doc = json!({
+        "users": [
+            {"name": "ada", "flag": "beta"},
+            {"name": "bob", "flag": "ga"},
+            {"name": "cy", "flag": "beta"}
+        ]
+    });
+    for j in docs(doc.clone()) {
+        assert_eq!(
+            engine.collect(&j, "$.users.filter(flag.enabled()).map(name)").unwrap(),
+            json!(["ada", "cy"])
+        );
+    }
+}
+
+#[test]
+fn host_function_arity_is_checked() {
+    let engine = engine();
+    let j = Jetro::from(json!({"amount": 10}));
+    let err = engine.collect(&j, "fx($.amount)").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::InvalidArgument);
+    assert_eq!(err.operation(), Some("fx"));
+}
+
+#[test]
+fn host_function_errors_propagate() {
+    let engine = engine();
+    let j = Jetro::from(json!({"amount": 10}));
+    let err = engine.collect(&j, r#"fx($.amount, "JPY")"#).unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::InvalidArgument);
+    assert_eq!(err.message(), "fx: unknown currency");
+}
+
+#[test]
+fn host_functions_are_scoped_to_their_engine() {
+    let _engine = engine();
+    let j = Jetro::from(json!({"prices": [150]}));
+    let err = j.collect(r#"fx(1, "EUR")"#).unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::UnknownMethod);
+    assert!(JetroEngine::new().collect(&j, r#"fx(1, "EUR")"#).is_err());
+}
+
+#[test]
+fn register_rejects_builtin_and_invalid_names() {
+    let mut engine = JetroEngine::new();
+    let f = || HostFunction::new(0, |v, _| Ok(v.clone()));
+    assert_eq!(
+        engine.register_function("len", f()).unwrap_err().kind(),
+        EvalErrorKind::InvalidArgument
+    );
+    assert!(engine.register_function("range", f()).is_err());
+    assert!(engine.register_function("1x", f()).is_err());
+    assert!(engine.register_function("a-b", f()).is_err());
+    assert!(engine.register_function("my_fn2", f()).is_ok());
+}
+
+#[test]
+fn register_clears_plan_cache() {
+    let mut engine = JetroEngine::new();
+    let j = Jetro::from(json!({"n": [1]}));
+    assert!(engine.collect(&j, "$.n.double()").is_err());
+    engine
+        .register_function(
+            "double",
+            HostFunction::new(0, |v, _| Ok(json!(v.as_i64().unwrap_or(0) * 2))).elementwise(),
+        )
+        .unwrap();
+    assert_eq!(engine.collect(&j, "$.n.double()").unwrap(), json!([2]));
+}
+
+#[test]
+fn impure_host_function_runs_once_per_element() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let mut engine = JetroEngine::new();
+    engine
+        .register_function(
+            "tick",
+            HostFunction::new(0, move |v, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(v.clone())
+            })
+            .impure(),
+        )
+        .unwrap();
+    let j = Jetro::from_bytes(br#"{"xs":[1,2,3]}"#.to_vec()).unwrap();
+    let out = engine
+        .collect(&j, r#"$.xs.map(@.tick()).filter(@.type() == "number")"#)
+        .unwrap();
+    assert_eq!(out, json!([1, 2, 3]));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    calls.store(0, Ordering::SeqCst);
+    engine.collect(&j, "let t = $.xs.tick() in 1").unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn impure_host_function_runs_on_rows_that_demand_would_skip() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let mut engine = JetroEngine::new();
+    engine
+        .register_function(
+            "tick",
+            HostFunction::new(0, move |v, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(v.clone())
+            })
+            .impure(),
+        )
+        .unwrap();
+    let doc = json!({"rows": [{"p": 1}, {"p": 2}, {"p": 3}]});
+    let docs = [
+        Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+        Jetro::from(doc),
+    ];
+    for j in &docs {
+        for (expr, expected) in [
+            ("$.rows.map(p.tick()).first()", json!(1)),
+            ("$.rows.map(tick(p)).first()", json!(1)),
+            ("$.rows.map(p.tick()).last()", json!(3)),
+            ("$.rows.map(p.tick()).take(1)", json!([1])),
+            ("$.rows.map({a: p.tick()}).first()", json!({"a": 1})),
+            ("$.rows.filter(p.tick() > 0).first()", json!({"p": 1})),
+            ("$.rows.sort_by(p).map(p.tick()).filter(@ > 1).first()", json!(2)),
+        ] {
+            calls.store(0, Ordering::SeqCst);
+            assert_eq!(engine.collect(j, expr).unwrap(), expected, "{expr}");
+            assert_eq!(calls.load(Ordering::SeqCst), 3, "{expr}");
+        }
+    }
+}
+
+#[test]
+fn host_function_metadata_accessors() {
+    let f = HostFunction::new(2, |v, _| Ok(v.clone())).impure().elementwise();
+    assert_eq!(f.arity(), 2);
+    assert!(!f.is_pure());
+    assert!(f.is_elementwise());
+}
@@ -0,0 +1,186 @@
+//! Concatenated JSON streams: back-to-back top-level documents read from a
+//! `std::io::Read`, evaluated one at a time or bound together as `$`.
+
+use std::io::Read;
+
+use serde_json::{json, Value};
+
+use crate::data::json_stream::Documents;
+use crate::{JetroEngine, JsonStreamError};
+
+/// Yields its input `step` bytes per `read` call.
+struct Trickle<'a> {
+    input: &'a [u8],
+    step: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.step.min(buf.len()).min(self.input.len());
+        buf[..n].copy_from_slice(&self.input[..n]);
+        self.input = &self.input[n..];
+        Ok(n)
+    }
+}
+
+fn split(input: &[u8], step: usize) -> Vec<(usize, u64, String)> {
+    Documents::new(Trickle { input, step })
+        .map(|doc| {
+            let (pos, bytes) = doc.unwrap();
+            (pos.index, pos.offset, String::from_utf8(bytes).unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn splits_back_to_back_values() {
+    let input = br#"{"a":"}{\"[","b":[1,{"c":2}]}[1,2] "x\"y"  42 true{"d":null}
+ -1.5e3null[]"#;
+    let expected: Vec<&str> = vec![
+        r#"{"a":"}{\"[","b":[1,{"c":2}]}"#,
+        "[1,2]",
+        r#""x\"y""#,
+        "42",
+        "true",
+        r#"{"d":null}"#,
+        "-1.5e3",
+        "null",
+        "[]",
+    ];
+    for step in [1, 3, 7, 4096] {
+        let docs = split(input, step);
+        let texts: Vec<&str> = docs.iter().map(|doc| doc.2.as_str()).collect();
+        assert_eq!(texts, expected, "step {step}");
+        assert_eq!(docs[1].1, 29);
+        assert_eq!(docs.last().unwrap().0, 8);
+    }
+    assert!(split(b" \n\t ", 2).is_empty());
+}
+
+#[test]
+fn adjacent_scalars_split_at_token_ends() {
+    let input = b"-1-2 0.5e-3-7e+2truefalse null1 12 007 1.x -";
+    let expected = vec![
+        "-1", "-2", "0.5e-3", "-7e+2", "true", "false", "null", "1", "12", "0", "0", "7", "1.x",
+        "-",
+    ];
+    for step in [1, 2, 5, 4096] {
+        let docs = split(input, step);
+        let texts: Vec<&str> = docs.iter().map(|doc| doc.2.as_str()).collect();
+        assert_eq!(texts, expected, "step {step}");
+    }
+
+    let engine = JetroEngine::new();
+    let sums: Vec<Value> = engine
+        .collect_json_stream_docs(&b"-1-2 3"[..], "$ * 10")
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(sums, vec![json!(-10), json!(-20), json!(30)]);
+    let err = engine
+        .collect_json_stream(&b"1 tru 2"[..], "$.len()")
+        .unwrap_err();
+    assert_eq!((err.index(), err.offset()), (Some(1), Some(2)));
+}
+
+#[test]
+fn docs_are_evaluated_one_at_a_time() {
+    let input = br#"{"n":1}{"n":2}[{"n":3}]{"n":}{"n":"x"} {"n":6}"#;
+    let engine = JetroEngine::new();
+    let out: Vec<_> = engine
+        .collect_json_stream_docs(Trickle { input, step: 5 }, "$.n + 1")
+        .collect();
+    assert_eq!(out.len(), 6);
+    assert_eq!(out[0].as_ref().unwrap(), &json!(2));
+    assert_eq!(out[1].as_ref().unwrap(), &json!(3));
+    assert!(out[2].is_err());
+    let err = out[3].as_ref().unwrap_err();
+    assert_eq!((err.index(), err.offset()), (Some(3), Some(23)));
+    assert!(err.to_string().starts_with("document 3 at byte 23: "));
+    assert_eq!(out[4].as_ref().unwrap_err().index(), Some(4));
+    assert_eq!(out[5].as_ref().unwrap(), &json!(7));
+}
+
+#[test]
+fn slurped_stream_matches_the_array() {
+    let docs: Vec<Value> = (0..5_000)
+        .map(|n| json!({"n": n, "group": format!("g{}", n % 4), "tags": ["a", "}"]}))
+        .collect();
+    let input: String = docs.iter().map(Value::to_string).collect();
+    let engine = JetroEngine::new();
+    let array = json!({ "rows": docs });
+    for expr in [
+        "$.count()",
+        "$.map(n).sum()",
+        "$.filter(n > 2500).count_by(group)",
+        "$.map(n).take(4)",
+        "$.sort_by(n).last().n",
+        "$[4999].group",
+    ] {
+        let expected = engine
+            .collect_value(array.clone(), expr.replace('$', "$.rows"))
+            .unwrap();
+        let out = engine
+            .collect_json_stream(
+                Trickle {
+                    input: input.as_bytes(),
+                    step: 1000,
+                },
+                expr,
+            )
+            .unwrap();
+        assert_eq!(out, expected, "{expr}");
+    }
+}
+
+#[test]
+fn truncated_and_malformed_streams_report_the_document() {
+    let engine = JetroEngine::new();
+    let err = engine
+        .collect_json_stream(&br#"{"n":1} {"n":2} {"n":"#[..], "$.map(n).sum()")
+        .unwrap_err();
+    assert_eq!((err.index(), err.offset()), (Some(2), Some(16)));
+    assert!(matches!(err, JsonStreamError::Document { .. }));
+
+    let err = engine
+        .collect_json_stream(&b"[1] ]"[..], "$.len()")
+        .unwrap_err();
+    assert_eq!(err.index(), Some(1));
+
+    // a stray closing bracket is one malformed document, then the scan moves on
+    for step in [1, 4096] {
+        let texts: Vec<String> = split(br#"{"a":1}}{"a":2}"#, step)
+            .into_iter()
+            .map(|doc| doc.2)
+            .collect();
+        assert_eq!(texts, vec![r#"{"a":1}"#, "}", r#"{"a":2}"#], "step {step}");
+        let texts: Vec<String> = split(b"1 ] 2", step).into_iter().map(|doc| doc.2).collect();
+        assert_eq!(texts, vec!["1", "]", "2"], "step {step}");
+    }
+    let out: Vec<_> = engine
+        .collect_json_stream_docs(&br#"{"a":1}}{"a":2}"#[..], "$.a + 1")
+        .collect();
+    assert_eq!(out.len(), 3);
+    assert_eq!(out[0].as_ref().unwrap(), &json!(2));
+    assert_eq!(out[1].as_ref().unwrap_err().offset(), Some(7));
+    assert_eq!(out[2].as_ref().unwrap(), &json!(3));
+    let out: Vec<_> = engine
+        .collect_json_stream_docs(&b"1 ] 2"[..], "$")
+        .collect();
+    assert_eq!(out.len(), 3);
+    assert_eq!(out[1].as_ref().unwrap_err().index(), Some(1));
+    assert_eq!(out[2].as_ref().unwrap(), &json!(2));
+
+    let err = engine
+        .collect_json_stream(&b"[1]"[..], "$.map(")
+        .unwrap_err();
+    assert!(matches!(err, JsonStreamError::Eval(_)));
+
+    // a bad query is one error for the whole stream, not one per document
+    let mut input = &b"[1] [2] [3]"[..];
+    let out: Vec<_> = engine
+        .collect_json_stream_docs(&mut input, "$.map(")
+        .collect();
+    assert_eq!(out.len(), 1);
+    assert!(matches!(out[0], Err(JsonStreamError::Eval(_))));
+    assert_eq!(input.len(), 11);
+}
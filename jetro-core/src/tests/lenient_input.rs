@@ -0,0 +1,219 @@
+//! JSONC and JSON5 documents read with `Jetro::from_bytes_with`.
+
+use serde_json::{json, Value};
+
+use crate::data::lenient::to_json;
+use crate::{Jetro, ParseOptions};
+
+fn parse(src: &str, options: ParseOptions) -> Value {
+    serde_json::from_slice(&to_json(src.as_bytes(), options).unwrap()).unwrap()
+}
+
+/// `(line, column, message)` of the error `src` fails with.
+fn error(src: &str, options: ParseOptions) -> (usize, usize, String) {
+    let err = to_json(src.as_bytes(), options).unwrap_err();
+    (err.line(), err.column(), err.message().to_owned())
+}
+
+#[test]
+fn jsonc_comments_and_trailing_commas() {
+    let src = r#"// service config
+{
+    "name": "api", /* inline */ "ports": [80, 443,],
+    "url": "http://x/*not a comment*/", // trailing
+    "nested": {"a": [], "b": {},},
+}
+"#;
+    let expected = json!({
+        "name": "api",
+        "ports": [80, 443],
+        "url": "http://x/*not a comment*/",
+        "nested": {"a": [], "b": {}}
+    });
+    assert_eq!(parse(src, ParseOptions::jsonc()), expected);
+    assert_eq!(parse(src, ParseOptions::json5()), expected);
+}
+
+#[test]
+fn json5_keys_strings_and_numbers() {
+    let src = r#"{
+        unquoted: 1, $dollar_1: 2, 'single': 'it\'s "quoted"',
+        hex: 0xFF, neg: -0x10, plus: +5, lead: .5, trail: 5., exp: 1.5E+3,
+        esc: '\x41\v\0 line \
+continued',
+        unicode: "é😀", utf8: 'héllo',
+        literals: [true, false, null],
+    }"#;
+    assert_eq!(
+        parse(src, ParseOptions::json5()),
+        json!({
+            "unquoted": 1, "$dollar_1": 2, "single": "it's \"quoted\"",
+            "hex": 255, "neg": -16, "plus": 5, "lead": 0.5, "trail": 5.0, "exp": 1.5e3,
+            "esc": "A\u{b}\u{0} line continued",
+            "unicode": "é😀", "utf8": "héllo",
+            "literals": [true, false, null]
+        })
+    );
+}
+
+#[test]
+fn documents_match_their_strict_spelling() {
+    let lenient =
+        br#"{orders: [{id: 1, total: 0x78, tags: ['a',],}, {id: 2, total: 40.5,},], /* end */}"#;
+    let strict = br#"{"orders":[{"id":1,"total":120,"tags":["a"]},{"id":2,"total":40.5}]}"#;
+    let j = Jetro::from_bytes_with(lenient.to_vec(), ParseOptions::json5()).unwrap();
+    let expected = Jetro::from_bytes(strict.to_vec()).unwrap();
+    for query in [
+        "$.orders.map(total).sum()",
+        "$.orders.filter(total > 100).map(id)",
+        "$.orders[0].tags",
+        "$..id",
+        "$.orders.map(keys())",
+    ] {
+        assert_eq!(
+            j.collect(query).unwrap(),
+            expected.collect(query).unwrap(),
+            "{query}"
+        );
+    }
+}
+
+#[test]
+fn extensions_are_opt_in() {
+    let strict = ParseOptions::new();
+    assert_eq!(
+        parse(r#"{"a": [1, 2.5e-3, "x\n"]}"#, strict),
+        json!({"a": [1, 2.5e-3, "x\n"]})
+    );
+    assert_eq!(
+        error("// c\n1", strict),
+        (1, 1, "comments are not allowed".into())
+    );
+    assert_eq!(
+        error("[1,\n 2,\n]", strict),
+        (3, 1, "trailing comma".into())
+    );
+    assert_eq!(
+        error("{a: 1}", ParseOptions::jsonc()).2,
+        "expected object key"
+    );
+    assert_eq!(
+        error("['x']", ParseOptions::jsonc()).2,
+        "single-quoted strings are not allowed"
+    );
+    assert_eq!(error("0x10", ParseOptions::jsonc()).2, "invalid number");
+    assert_eq!(error(".5", ParseOptions::jsonc()).2, "invalid number");
+    let custom = ParseOptions::new().trailing_commas(true);
+    assert_eq!(parse("[1,]", custom), json!([1]));
+    assert!(to_json(b"[1,] // x", custom).is_err());
+}
+
+#[test]
+fn errors_point_at_line_and_column() {
+    let json5 = ParseOptions::json5();
+    assert_eq!(
+        error("{\n  a: 1,\n  b: [1 2]\n}", json5),
+        (3, 9, "expected `,` or `]`".into())
+    );
+    assert_eq!(
+        error("{\n  'é': tru\n}", json5),
+        (2, 8, "expected value".into())
+    );
+    assert_eq!(
+        error("[1] 2", json5),
+        (1, 5, "trailing characters after document".into())
+    );
+    assert_eq!(
+        error("/* open", json5),
+        (1, 1, "unterminated block comment".into())
+    );
+    assert_eq!(error("{a: 'x", json5), (1, 5, "unterminated string".into()));
+    assert_eq!(error("[01]", json5).2, "leading zeros are not allowed");
+    assert_eq!(
+        error("[Infinity]", json5).2,
+        "`Infinity` and `NaN` cannot be represented in JSON"
+    );
+    assert_eq!(error("", json5), (1, 1, "unexpected end of input".into()));
+    assert_eq!(error("{a: 1", json5).2, "unexpected end of input");
+
+    let Err(err) = Jetro::from_bytes_with(b"{\"a\": [1,]}".to_vec(), ParseOptions::new()) else {
+        panic!("trailing comma accepted");
+    };
+    assert_eq!(err.to_string(), "trailing comma at line 1 column 10");
+}
+
+#[test]
+fn deep_nesting_does_not_recurse() {
+    let depth = 200_000;
+    let src = "[".repeat(depth) + &"]".repeat(depth);
+    assert_eq!(
+        to_json(src.as_bytes(), ParseOptions::json5())
+            .unwrap()
+            .len(),
+        2 * depth
+    );
+}
+
+#[test]
+fn json5_identifier_escapes_and_byte_order_mark() {
+    let json5 = ParseOptions::json5();
+    assert_eq!(
+        parse(
+            r#"{\u0061b: 1, a\u0062c: 2, \u00e9t\u00E9: 3, $\u005f: 4}"#,
+            json5
+        ),
+        json!({"ab": 1, "abc": 2, "été": 3, "$_": 4})
+    );
+    assert_eq!(
+        error("{\\u0031a: 1}", json5),
+        (1, 2, "escape is not an identifier character".into())
+    );
+    assert_eq!(
+        error("{a\\u002d: 1}", json5).2,
+        "escape is not an identifier character"
+    );
+    assert_eq!(
+        error("{a\\x41: 1}", json5),
+        (1, 3, "invalid escape in key".into())
+    );
+
+    for options in [ParseOptions::new(), ParseOptions::jsonc(), json5] {
+        assert_eq!(parse("\u{feff}{\"a\": [1]}", options), json!({"a": [1]}));
+    }
+    let Err(err) = Jetro::from_bytes_with(b"\xEF\xBB\xBF[1,]".to_vec(), ParseOptions::new()) else {
+        panic!("trailing comma accepted");
+    };
+    assert_eq!(err.message(), "trailing comma");
+}
+
+#[test]
+fn out_of_range_numbers_and_non_finite_literals_are_located() {
+    let json5 = ParseOptions::json5();
+    assert_eq!(
+        error("{\n  \"a\": [1, 1e400]\n}", ParseOptions::new()),
+        (2, 12, "number out of range".into())
+    );
+    assert_eq!(
+        error("-1.5E+309", json5),
+        (1, 1, "number out of range".into())
+    );
+    let huge = format!("[{}]", "9".repeat(400));
+    assert_eq!(error(&huge, json5), (1, 2, "number out of range".into()));
+    assert_eq!(
+        parse("[1e-400, 1.7e308, -1e308]", json5),
+        json!([0.0, 1.7e308, -1e308])
+    );
+    assert!(Jetro::from_bytes_with(b"{\"a\": 1e400}".to_vec(), ParseOptions::jsonc()).is_err());
+
+    for src in ["Infinity", "-Infinity", "+Infinity", "NaN", "[1, -NaN]"] {
+        assert_eq!(
+            error(src, json5).2,
+            "`Infinity` and `NaN` cannot be represented in JSON",
+            "{src}"
+        );
+    }
+    assert_eq!(
+        parse("{Infinity: 1, NaN: 2}", json5),
+        json!({"Infinity": 1, "NaN": 2})
+    );
+}
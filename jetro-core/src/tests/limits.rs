@@ -0,0 +1,229 @@
+//! `ExecutionLimits` and `CancellationToken` enforced by `JetroEngine`.
+
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::{CancellationToken, EvalErrorKind, ExecutionLimits, Jetro, JetroEngine};
+
+fn engine_with(limits: ExecutionLimits) -> JetroEngine {
+    let mut engine = JetroEngine::new();
+    engine.set_limits(limits);
+    engine
+}
+
+fn rows_doc() -> Jetro {
+    Jetro::from(json!({"rows": [{"n": 3}, {"n": 1}, {"n": 2}], "deep": [[[1]]]}))
+}
+
+#[test]
+fn max_steps_stops_the_vm() {
+    let engine = engine_with(ExecutionLimits::new().max_steps(1_000));
+    let err = engine
+        .collect(&rows_doc(), "range(100000).map(@ * 2).sum()")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert!(err.message().contains("limit of 1000 steps"), "{err}");
+
+    assert_eq!(
+        engine.collect(&rows_doc(), "$.rows.len()").unwrap(),
+        json!(3)
+    );
+}
+
+#[test]
+fn max_array_len_rejects_products_and_ranges_before_building_them() {
+    let engine = engine_with(ExecutionLimits::new().max_array_len(1_000));
+    let doc = Jetro::from(json!({"a": (0..100).collect::<Vec<_>>()}));
+    let err = engine
+        .collect(&doc, "product($.a, $.a, $.a, $.a, $.a)")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert!(err.message().contains("10000000000 elements"), "{err}");
+
+    let err = engine.collect(&doc, "range(1000000000)").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert_eq!(engine.collect(&doc, "range(3)").unwrap(), json!([0, 1, 2]));
+}
+
+#[test]
+fn max_array_len_applies_to_barrier_stages() {
+    let engine = engine_with(ExecutionLimits::new().max_array_len(2));
+    let err = engine
+        .collect(&rows_doc(), "$.rows.sort_by(n).map(n)")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+}
+
+#[test]
+fn max_depth_bounds_nested_evaluation() {
+    let engine = engine_with(ExecutionLimits::new().max_depth(2));
+    let err = engine
+        .collect(&rows_doc(), "$.deep.map(@.map(@.map(@ + 1)))")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert!(err.message().contains("depth of 2"), "{err}");
+
+    let engine = engine_with(ExecutionLimits::new().max_depth(3));
+    assert_eq!(
+        engine
+            .collect(&rows_doc(), "$.deep.map(@.map(@.map(@ + 1)))")
+            .unwrap(),
+        json!([[[2]]])
+    );
+}
+
+#[test]
+fn output_limits_check_elements_and_serialized_bytes() {
+    let engine = engine_with(ExecutionLimits::new().max_output_elements(2));
+    let err = engine.collect(&rows_doc(), "$.rows.map(n)").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert_eq!(
+        engine
+            .collect(&rows_doc(), "$.rows.map(n).first()")
+            .unwrap(),
+        json!(3)
+    );
+
+    let engine = engine_with(ExecutionLimits::new().max_output_bytes(8));
+    assert_eq!(
+        engine.collect(&rows_doc(), "$.rows.len()").unwrap(),
+        json!(3)
+    );
+    let err = engine.collect(&rows_doc(), "$.rows").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    let err = engine
+        .collect_as::<serde_json::Value, _>(&rows_doc(), "$.rows")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+}
+
+#[test]
+fn expired_deadline_stops_pipelines_on_every_input_mode() {
+    let engine = engine_with(ExecutionLimits::new().timeout(Duration::ZERO));
+    let bytes = Jetro::from_bytes(br#"{"rows":[{"n":3},{"n":1}]}"#.to_vec()).unwrap();
+    for doc in [&bytes, &rows_doc()] {
+        let err = engine
+            .collect(doc, "$.rows.filter(n > 1).map(n)")
+            .unwrap_err();
+        assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+        assert!(err.message().contains("deadline"), "{err}");
+    }
+}
+
+#[test]
+fn cancelled_token_stops_the_query() {
+    let engine = JetroEngine::new();
+    let token = CancellationToken::new();
+    assert_eq!(
+        engine
+            .collect_cancellable(&rows_doc(), "$.rows.map(n).sum()", &token)
+            .unwrap(),
+        json!(6)
+    );
+
+    token.cancel();
+    let err = engine
+        .collect_cancellable(&rows_doc(), "$.rows.map(n).sum()", &token)
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::Cancelled);
+    assert_eq!(
+        engine.collect(&rows_doc(), "$.rows.map(n).sum()").unwrap(),
+        json!(6)
+    );
+}
+
+#[test]
+fn cancellation_from_another_thread_interrupts_a_running_query() {
+    let engine = JetroEngine::new();
+    let token = CancellationToken::new();
+    let canceller = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            token.cancel();
+        })
+    };
+    let started = Instant::now();
+    let err = engine
+        .collect_cancellable(
+            &rows_doc(),
+            "range(1000000).map(range(1000).sum()).sum()",
+            &token,
+        )
+        .unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(err.kind(), EvalErrorKind::Cancelled);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn limits_do_not_outlive_the_call() {
+    let engine = engine_with(ExecutionLimits::new().max_steps(1));
+    assert!(engine.collect(&rows_doc(), "range(10).sum()").is_err());
+    assert!(!crate::exec::limits::is_active());
+    assert_eq!(
+        JetroEngine::new()
+            .collect(&rows_doc(), "range(10).sum()")
+            .unwrap(),
+        json!(45)
+    );
+    assert_eq!(engine.limits(), &ExecutionLimits::new().max_steps(1));
+}
+
+fn wide_rows(rows: usize) -> serde_json::Value {
+    let rows: Vec<_> = (0..rows).map(|n| json!({"a": n})).collect();
+    json!({ "rows": rows })
+}
+
+#[test]
+fn max_steps_counts_pipeline_rows() {
+    let engine = engine_with(ExecutionLimits::new().max_steps(10));
+    let rows = wide_rows(100);
+    let bytes = Jetro::from_bytes(serde_json::to_vec(&rows).unwrap()).unwrap();
+    for doc in [&Jetro::from(rows), &bytes] {
+        for expr in ["$.rows.filter(a > 5)", "$.rows.map(a)", "$..a"] {
+            let err = engine.collect(doc, expr).unwrap_err();
+            assert_eq!(err.kind(), EvalErrorKind::LimitExceeded, "{expr}");
+            assert!(err.message().contains("limit of 10 steps"), "{expr}: {err}");
+        }
+    }
+}
+
+#[test]
+fn max_array_len_applies_to_literals_and_collected_results() {
+    let engine = engine_with(ExecutionLimits::new().max_array_len(3));
+    let rows = wide_rows(100);
+    let bytes = Jetro::from_bytes(serde_json::to_vec(&rows).unwrap()).unwrap();
+    for doc in [&Jetro::from(rows), &bytes] {
+        for expr in [
+            "[1, 2, 3, 4, 5]",
+            "$.rows.map(a)",
+            "$.rows.filter(a > 5)",
+            "$.rows.map([a, a])",
+            "{x: $.rows.map(a)}",
+        ] {
+            let err = engine.collect(doc, expr).unwrap_err();
+            assert_eq!(err.kind(), EvalErrorKind::LimitExceeded, "{expr}");
+            assert!(err.message().contains("limit of 3"), "{expr}: {err}");
+        }
+        assert_eq!(engine.collect(doc, "$.rows.map(a).sum()").unwrap(), json!(4950));
+        assert_eq!(engine.collect(doc, "$.rows.filter(a > 97).map(a)").unwrap(), json!([98, 99]));
+        assert_eq!(engine.collect(doc, "[1, 2, 3]").unwrap(), json!([1, 2, 3]));
+    }
+}
+
+#[test]
+fn deadline_stops_recursive_descent() {
+    let doc = Jetro::from(wide_rows(1_000_000));
+    // Convert the document outside the timed calls.
+    JetroEngine::new().collect(&doc, "$.rows.len()").unwrap();
+    let engine = engine_with(ExecutionLimits::new().timeout(Duration::from_millis(1)));
+    for expr in ["$..a", "$..find(@.a == 999999)"] {
+        let started = Instant::now();
+        let err = engine.collect(&doc, expr).unwrap_err();
+        assert_eq!(err.kind(), EvalErrorKind::LimitExceeded, "{expr}");
+        assert!(err.message().contains("deadline"), "{expr}: {err}");
+        assert!(started.elapsed() < Duration::from_millis(250), "{expr}");
+    }
+}
@@ -3,18 +3,76 @@
 //!
 //! Splits:
 //! - `regression` — the original mixed-feature test corpus.
+//! - `binary_formats` — MessagePack and CBOR documents and results.
 //! - `chain_write` — `patch $ { ... }` and chain-style write semantics.
+//! - `collect_all` — several queries over one document, fused into shared scans.
+//! - `concurrency` — `Jetro` documents and `JetroEngine`s shared across threads.
+//! - `csv_input` — CSV and TSV text read by `Jetro::from_csv` and `from_csv()`.
 //! - `deep_search` — `$..find` / `simd_scan` / route-C fallthrough.
+//! - `errors` — structured `EvalError` kinds, operations, spans, and paths.
+//! - `explain` — plan descriptions returned by `JetroEngine::explain`.
+//! - `host_functions` — closures registered on `JetroEngine`.
+//! - `json_stream` — concatenated top-level JSON documents read from a stream.
+//! - `lenient_input` — JSONC and JSON5 documents read with `from_bytes_with`.
+//! - `limits` — `ExecutionLimits` and cancellation enforced by `JetroEngine`.
+//! - `ndjson` — newline-delimited JSON streams, per record or bound as `$`.
+//! - `nesting` — nesting limits on queries and documents, deep-value walks.
+//! - `output` — results serialized straight to writers and byte buffers.
+//! - `parallel` — pipelines over large arrays split across threads.
+//! - `plan_bundle` — plan bundles written by `export_cache`, read by `import_cache`.
+//! - `plan_cache` — `JetroEngine`'s LRU plan cache, its counters, and `warm`.
+//! - `profile` — runtime profiles returned by `JetroEngine::collect_profiled`.
+//! - `shared_bytes` — documents read from shared byte buffers without copies.
+//! - `shared_scan` — sibling pipelines in one constructor sharing a single scan.
+//! - `variables` — external variables bound through `collect_with`.
 //! - `common` — shared helpers (`vm_query`, fixture builders).
 
 #[cfg(test)]
 pub(crate) mod common;
 
+#[cfg(test)]
+mod binary_formats;
 #[cfg(test)]
 mod chain_write;
 #[cfg(test)]
+mod collect_all;
+#[cfg(test)]
+mod concurrency;
+#[cfg(test)]
+mod csv_input;
+#[cfg(test)]
 mod deep_search;
 #[cfg(test)]
+mod errors;
+#[cfg(test)]
+mod explain;
+#[cfg(test)]
+mod host_functions;
+#[cfg(test)]
+mod json_stream;
+#[cfg(test)]
+mod lenient_input;
+#[cfg(test)]
+mod limits;
+#[cfg(test)]
+mod ndjson;
+#[cfg(test)]
+mod nesting;
+#[cfg(test)]
+mod output;
+#[cfg(test)]
+mod parallel;
+#[cfg(test)]
+mod plan_bundle;
+#[cfg(test)]
+mod plan_cache;
+#[cfg(test)]
+mod profile;
+#[cfg(test)]
+mod shared_bytes;
+#[cfg(test)]
+mod shared_scan;
+#[cfg(test)]
 mod patch_fusion_phase_c;
 #[cfg(test)]
 mod patch_fusion_phase_e;
@@ -24,3 +82,5 @@ mod patch_fusion_soundness;
 mod examples;
 #[cfg(test)]
 mod regression;
+#[cfg(test)]
+mod variables;
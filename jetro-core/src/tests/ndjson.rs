@@ -0,0 +1,198 @@
+//! Newline-delimited JSON input: per-record evaluation with
+//! `JetroEngine::collect_ndjson_lines`, and the whole stream bound as `$`
+//! with `JetroEngine::collect_ndjson`.
+
+use std::io::{BufRead, BufReader, Read};
+
+use serde_json::{json, Value};
+
+use crate::data::value::Val;
+use crate::exec::pipeline::stream_exec::RootStream;
+use crate::{JetroEngine, NdjsonError};
+
+fn logs() -> String {
+    (0..10_000)
+        .map(|n| {
+            json!({
+                "n": n,
+                "level": if n % 10 == 0 { "error" } else { "info" },
+                "service": format!("s{}", n % 3),
+            })
+            .to_string()
+                + "\n"
+        })
+        .collect()
+}
+
+/// `expr` evaluated over the records of `input` held as the array `$.rows`.
+fn materialized(input: &str, expr: &str) -> Value {
+    let rows: Vec<Value> = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    JetroEngine::new()
+        .collect_value(json!({ "rows": rows }), expr.replace('$', "$.rows"))
+        .unwrap()
+}
+
+fn streams(expr: &str) -> bool {
+    RootStream::lower(&crate::parse::parser::parse(expr).unwrap()).is_some()
+}
+
+/// Counts how many bytes were read from the inner reader.
+struct Counting<R> {
+    inner: R,
+    read: usize,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn lines_evaluate_each_record() {
+    let input = "{\"a\": 1}\n\n  \r\n{\"a\": 2}\r\n{\"a\": \n{\"a\": \"x\"}\n{\"a\": 4}";
+    let engine = JetroEngine::new();
+    let out: Vec<_> = engine
+        .collect_ndjson_lines(input.as_bytes(), "$.a + 1")
+        .collect();
+    assert_eq!(out.len(), 5);
+    assert_eq!(out[0].as_ref().unwrap(), &json!(2));
+    assert_eq!(out[1].as_ref().unwrap(), &json!(3));
+    assert_eq!(out[2].as_ref().unwrap_err().line(), Some(5));
+    let err = out[3].as_ref().unwrap_err();
+    assert_eq!(err.line(), Some(6));
+    assert!(matches!(err, NdjsonError::Record { error, .. } if error.eval_error().is_some()));
+    assert!(err.to_string().starts_with("line 6: "));
+    assert_eq!(out[4].as_ref().unwrap(), &json!(5));
+}
+
+#[test]
+fn streamed_pipelines_match_the_materialized_array() {
+    let input = logs();
+    let engine = JetroEngine::new();
+    for expr in [
+        r#"$.filter(level == "error").count_by(service)"#,
+        r#"$.filter(level == "error").group_by(service).keys()"#,
+        "$.index_by(service)",
+        "$.count()",
+        "$.len()",
+        "$.map(n).sum()",
+        r#"$.filter(level == "error").map(n).avg()"#,
+        "$.filter(n > 9990).map({n, service})",
+        "$.map(n).take(3)",
+        "$.filter(n > 5).first()",
+    ] {
+        let expected = materialized(&input, expr);
+        assert_eq!(
+            engine.collect_ndjson(input.as_bytes(), expr).unwrap(),
+            expected,
+            "{expr}"
+        );
+    }
+    assert!(streams(r#"$.filter(level == "error").count_by(service)"#));
+    assert!(streams("$.map(n).sum()"));
+}
+
+#[test]
+fn other_shapes_materialize_the_stream() {
+    let input = logs();
+    let engine = JetroEngine::new();
+    for expr in [
+        "$[2].n",
+        "$.sort_by(n).last().n",
+        "$.filter(n > $.len() - 3).map(n)",
+        "{total: $.count(), errors: $.filter(level == \"error\").count()}",
+    ] {
+        assert!(!streams(expr), "{expr}");
+        assert_eq!(
+            engine.collect_ndjson(input.as_bytes(), expr).unwrap(),
+            materialized(&input, expr),
+            "{expr}"
+        );
+    }
+}
+
+#[test]
+fn stream_stops_reading_once_the_sink_is_done() {
+    let input = logs();
+    let mut reader = BufReader::with_capacity(
+        256,
+        Counting {
+            inner: input.as_bytes(),
+            read: 0,
+        },
+    );
+    let out = JetroEngine::new()
+        .collect_ndjson(&mut reader, "$.map(n).take(2)")
+        .unwrap();
+    assert_eq!(out, json!([0, 1]));
+    assert!(reader.get_ref().read < 1024);
+    assert!(!reader.fill_buf().unwrap().is_empty());
+}
+
+#[test]
+fn malformed_records_and_bad_queries_are_reported() {
+    let input = "{\"n\": 1}\n{\"n\": 2}\n\n{\"n\": 3,}\n{\"n\": 4}\n";
+    let engine = JetroEngine::new();
+    for expr in ["$.map(n).sum()", "$.sort_by(n)"] {
+        let err = engine.collect_ndjson(input.as_bytes(), expr).unwrap_err();
+        assert_eq!(err.line(), Some(4), "{expr}");
+    }
+    // the malformed record is never reached
+    assert_eq!(
+        engine
+            .collect_ndjson(input.as_bytes(), "$.first()")
+            .unwrap(),
+        json!({"n": 1})
+    );
+
+    let err = engine
+        .collect_ndjson(input.as_bytes(), "$.map(")
+        .unwrap_err();
+    assert!(matches!(err, NdjsonError::Eval(_)));
+    assert_eq!(err.line(), None);
+
+    let err = engine
+        .collect_ndjson("{\"n\": \"x\"}\n".as_bytes(), "$.map(-n)")
+        .unwrap_err();
+    assert!(matches!(err, NdjsonError::Eval(_)));
+}
+
+#[test]
+fn bad_query_is_reported_once_before_reading() {
+    let input = logs();
+    let mut reader = BufReader::new(Counting {
+        inner: input.as_bytes(),
+        read: 0,
+    });
+    let out: Vec<_> = JetroEngine::new()
+        .collect_ndjson_lines(&mut reader, "$.map(")
+        .collect();
+    assert_eq!(out.len(), 1);
+    assert!(matches!(out[0], Err(NdjsonError::Eval(_))));
+    assert_eq!(reader.get_ref().read, 0);
+}
+
+#[test]
+fn empty_stream_is_an_empty_array() {
+    let engine = JetroEngine::new();
+    assert_eq!(
+        engine.collect_ndjson(&b"\n\n"[..], "$.count()").unwrap(),
+        json!(0)
+    );
+    assert_eq!(
+        engine.collect_ndjson(&b""[..], "$.map(n)").unwrap(),
+        json!([])
+    );
+    assert_eq!(
+        engine.collect_ndjson(&b""[..], "$.count_by(n)").unwrap(),
+        Value::from(Val::obj(Default::default()))
+    );
+    assert_eq!(engine.collect_ndjson_lines(&b"\n"[..], "$").count(), 0);
+}
@@ -0,0 +1,259 @@
+//! Nesting limits on queries and documents, and deep-value walks.
+
+use serde_json::{json, Value};
+
+use crate::compile::compiler::Compiler;
+use crate::parse::ast::Expr;
+use crate::tests::common::vm_query;
+use crate::{EvalErrorKind, ExecutionLimits, Jetro, JetroEngine, DEFAULT_MAX_NESTING};
+
+fn nested_array_bytes(depth: usize) -> Vec<u8> {
+    format!("{}1{}", "[".repeat(depth), "]".repeat(depth)).into_bytes()
+}
+
+fn nested_object(depth: usize, leaf: Value) -> Value {
+    (0..depth).fold(leaf, |inner, _| json!({ "a": inner }))
+}
+
+#[test]
+fn deeply_nested_queries_fail_to_parse_instead_of_overflowing() {
+    let doc = Jetro::from(json!({}));
+    for query in [
+        format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+        format!("{}1", "- ".repeat(100_000)),
+        format!("{}true", "not ".repeat(100_000)),
+    ] {
+        let err = doc.collect(&query).unwrap_err();
+        assert_eq!(err.kind(), EvalErrorKind::Parse);
+        assert!(
+            err.message().contains(&format!(
+                "nests deeper than the limit of {} levels",
+                DEFAULT_MAX_NESTING
+            )),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn overlong_operator_chains_fail_to_parse_instead_of_overflowing() {
+    let doc = Jetro::from(json!({}));
+    let err = doc.collect(vec!["1"; 100_000].join(" + ")).unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::Parse);
+    assert!(err.message().contains("operator chains nest deeper"), "{err}");
+}
+
+#[test]
+fn long_flat_arithmetic_chains_are_not_nesting() {
+    let doc = Jetro::from(json!({"a": 2, "one": 1}));
+    let terms = DEFAULT_MAX_NESTING + 1;
+    assert_eq!(
+        doc.collect(vec!["$.a"; terms].join(" + ")).unwrap(),
+        json!(2 * terms)
+    );
+    assert_eq!(
+        doc.collect(vec!["$.one"; terms].join(" * ")).unwrap(),
+        json!(1)
+    );
+    assert_eq!(
+        doc.collect(format!("{}{}", "$.a", " as string as int".repeat(terms)))
+            .unwrap(),
+        json!(2)
+    );
+    // a thousand-operand chain walks the rewrite passes a thousand levels
+    // deep, more than the default test-thread stack holds in a debug build
+    let long = std::thread::Builder::new()
+        .stack_size(64 << 20)
+        .spawn(|| {
+            Jetro::from(json!({"a": 1}))
+                .collect(vec!["$.a"; 1000].join(" - "))
+                .unwrap()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(long, json!(-998));
+}
+
+#[test]
+fn queries_within_the_nesting_limit_still_evaluate() {
+    let doc = Jetro::from(json!({}));
+    assert_eq!(
+        doc.collect(format!("{}1", "- ".repeat(100))).unwrap(),
+        json!(1)
+    );
+    assert_eq!(doc.collect(vec!["1"; 100].join(" + ")).unwrap(), json!(100));
+    assert_eq!(
+        doc.collect("[1, [2, [3, [4]]]].flatten(5)").unwrap(),
+        json!([1, 2, 3, 4])
+    );
+}
+
+#[test]
+fn long_flat_logical_chains_are_not_nesting() {
+    let doc = Jetro::from(json!({"rows": [{"id": 3}, {"id": 250}, {"id": 999}]}));
+    let ids = (0..200).map(|i| format!("id == {i}")).collect::<Vec<_>>().join(" or ");
+    assert_eq!(
+        doc.collect(format!("$.rows.filter({ids}).map(id)")).unwrap(),
+        json!([3])
+    );
+    let all = (0..200).map(|i| format!("id > {i}")).collect::<Vec<_>>().join(" and ");
+    assert_eq!(
+        doc.collect(format!("$.rows.filter({all}).map(id)")).unwrap(),
+        json!([250, 999])
+    );
+    let fallbacks = vec!["null"; 10_000].join(" ?? ");
+    assert_eq!(doc.collect(format!("{fallbacks} ?? 7")).unwrap(), json!(7));
+}
+
+#[test]
+fn engine_max_nesting_bounds_queries() {
+    let mut engine = JetroEngine::new();
+    let doc = Jetro::from(json!({"a": 1}));
+    let query = "[[[[$.a]]]]";
+    assert_eq!(engine.collect(&doc, query).unwrap(), json!([[[[1]]]]));
+
+    engine.set_limits(ExecutionLimits::new().max_nesting(4));
+    let err = engine.collect(&doc, query).unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::Parse);
+    assert!(err.message().contains("limit of 4 levels"), "{err}");
+    assert_eq!(engine.collect(&doc, "[$.a]").unwrap(), json!([1]));
+}
+
+#[test]
+fn set_limits_drops_programs_compiled_under_the_old_limit() {
+    let mut engine = JetroEngine::new();
+    let doc = Jetro::from(json!({"a": 1}));
+    let query = "[[[[$.a]]]]";
+    assert_eq!(engine.collect(&doc, query).unwrap(), json!([[[[1]]]]));
+    engine.vms.checkout().get_or_compile(query).unwrap();
+    assert_eq!(engine.vms.compiled_len(), 1);
+
+    engine.set_limits(ExecutionLimits::new().max_nesting(4));
+    assert_eq!(engine.vms.compiled_len(), 0);
+    let err = {
+        let _limits = crate::exec::limits::enter(engine.limits(), None);
+        engine.vms.checkout().get_or_compile(query).unwrap_err()
+    };
+    assert_eq!(err.kind(), EvalErrorKind::Parse);
+    assert!(err.message().contains("limit of 4 levels"), "{err}");
+    assert!(engine.collect(&doc, query).is_err());
+}
+
+#[test]
+fn compiler_replaces_subexpressions_past_the_limit_with_an_error() {
+    let expr =
+        (0..DEFAULT_MAX_NESTING + 8).fold(Expr::Int(1), |inner, _| Expr::UnaryNeg(Box::new(inner)));
+    let program = Compiler::compile(&expr, "deep");
+    let err = crate::vm::VM::new()
+        .execute(&program, &json!(null))
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert!(err.message().contains("expression nests deeper"), "{err}");
+}
+
+#[test]
+fn deeply_nested_documents_fail_instead_of_overflowing() {
+    let mut engine = JetroEngine::new();
+    engine.set_limits(ExecutionLimits::new().max_steps(u64::MAX));
+    let doc = Jetro::from_bytes(nested_array_bytes(100_000)).unwrap();
+    let err = engine.collect(&doc, "$.len()").unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+    assert!(err.message().contains("document nests deeper"), "{err}");
+
+    let doc = Jetro::from_bytes(nested_array_bytes(DEFAULT_MAX_NESTING)).unwrap();
+    assert_eq!(engine.collect(&doc, "$.len()").unwrap(), json!(1));
+}
+
+#[test]
+fn plain_queries_reject_documents_past_the_default_limit() {
+    let objects = format!("{}1{}", r#"{"x":"#.repeat(100_000), "}".repeat(100_000));
+    let cases = [
+        (nested_array_bytes(100_000), "$.len()"),
+        (objects.clone().into_bytes(), "$..x"),
+        (objects.into_bytes(), "$..x.first()"),
+    ];
+    for (bytes, query) in cases {
+        let err = Jetro::from_bytes(bytes).unwrap().collect(query).unwrap_err();
+        assert_eq!(err.kind(), EvalErrorKind::LimitExceeded, "{query}");
+        assert!(err.message().contains("document nests deeper"), "{err}");
+    }
+
+    let err = vm_query("$.a", &nested_object(DEFAULT_MAX_NESTING + 1, json!(1))).unwrap_err();
+    assert!(err.to_string().contains("document nests deeper"), "{err}");
+    let doc = Jetro::from_bytes(nested_array_bytes(DEFAULT_MAX_NESTING)).unwrap();
+    assert_eq!(doc.collect("$.len()").unwrap(), json!(1));
+}
+
+#[test]
+fn descent_visits_every_level_up_to_the_limit() {
+    let depth = DEFAULT_MAX_NESTING;
+    let doc = format!("{}1{}", r#"{"x":"#.repeat(depth), "}".repeat(depth));
+    let doc = Jetro::from_bytes(doc.into_bytes()).unwrap();
+    assert_eq!(doc.collect("$..x.len()").unwrap(), json!(depth));
+    assert_eq!(doc.collect("$..x.last()").unwrap(), json!(1));
+}
+
+#[test]
+fn engine_max_nesting_bounds_documents() {
+    let mut engine = JetroEngine::new();
+    let bytes = nested_array_bytes(200);
+    engine.set_limits(ExecutionLimits::new().max_nesting(150));
+    let err = engine
+        .collect(&Jetro::from_bytes(bytes.clone()).unwrap(), "$.len()")
+        .unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::LimitExceeded);
+
+    engine.set_limits(ExecutionLimits::new().max_nesting(256));
+    assert_eq!(
+        engine
+            .collect(&Jetro::from_bytes(bytes).unwrap(), "$.len()")
+            .unwrap(),
+        json!(1)
+    );
+}
+
+#[test]
+fn vm_rejects_values_nested_past_the_limit_under_limits() {
+    let limits = ExecutionLimits::new().max_nesting(DEFAULT_MAX_NESTING);
+    let _scope = crate::exec::limits::enter(&limits, None);
+    let err = vm_query("$.a", &nested_object(DEFAULT_MAX_NESTING + 1, json!(1))).unwrap_err();
+    assert!(err.to_string().contains("document nests deeper"), "{err}");
+    assert!(vm_query("$.a", &nested_object(DEFAULT_MAX_NESTING, json!(1))).is_ok());
+}
+
+#[test]
+fn from_json_rejects_documents_past_the_limit() {
+    let mut engine = JetroEngine::new();
+    engine.set_limits(ExecutionLimits::new().max_nesting(DEFAULT_MAX_NESTING));
+    let doc = Jetro::from(json!({
+        "raw": String::from_utf8(nested_array_bytes(1_000)).unwrap()
+    }));
+    let err = engine.collect(&doc, "$.raw.from_json()").unwrap_err();
+    assert!(err.message().contains("nests deeper"), "{err}");
+}
+
+#[test]
+fn deep_walks_find_and_merge_keep_document_order() {
+    let deep = nested_object(100, json!({"leaf": [1, 2]}));
+    let doc = Jetro::from(json!({"deep": deep, "other": {"a": {"x": 1}}}));
+    assert_eq!(
+        doc.collect("$.deep.deep_find(@.leaf kind array).len()")
+            .unwrap(),
+        json!(1)
+    );
+    assert_eq!(
+        doc.collect("$.deep.walk(@ * 10 if @ kind number else @).deep_find(@.leaf kind array)")
+            .unwrap(),
+        json!([{"leaf": [10, 20]}])
+    );
+
+    let merged = doc
+        .collect("let o = $.other in o.deep_merge({a: {y: 2}, b: 3})")
+        .unwrap();
+    assert_eq!(merged, json!({"a": {"x": 1, "y": 2}, "b": 3}));
+    let merged = doc
+        .collect("let d = $.deep in d.deep_merge(d).deep_find(@.leaf kind array)")
+        .unwrap();
+    assert_eq!(merged, json!([{"leaf": [1, 2]}]));
+}
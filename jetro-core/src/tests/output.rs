@@ -0,0 +1,88 @@
+//! Direct result serialization through `Jetro::collect_to_writer` and
+//! `Jetro::collect_bytes`.
+
+use serde_json::{json, Value};
+
+use crate::{EvalErrorKind, Jetro};
+
+fn docs(doc: Value) -> [Jetro; 2] {
+    [
+        Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+        Jetro::from(doc),
+    ]
+}
+
+#[test]
+fn output_bytes_round_trip_to_collect() {
+    let doc = json!({
+        "xs": [3, 1, 2],
+        "names": ["b", "a"],
+        "rows": [
+            {"id": 1, "tag": "x", "score": 1.5},
+            {"id": 2, "tag": "y", "score": 2.5}
+        ]
+    });
+    let exprs = [
+        "$.xs.sort()",
+        "$.names.map(upper())",
+        "$.rows.map({id, tag})",
+        "$.rows.filter(score > 2).map(tag)",
+        "$.rows.len()",
+        "$.missing",
+    ];
+    for j in docs(doc) {
+        for expr in exprs {
+            let expected = j.collect(expr).unwrap();
+            let compact = j.collect_bytes(expr).unwrap();
+            let pretty = j.collect_bytes_pretty(expr).unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&compact).unwrap(), expected, "{expr}");
+            assert_eq!(serde_json::from_slice::<Value>(&pretty).unwrap(), expected, "{expr}");
+            assert_eq!(compact, serde_json::to_vec(&expected).unwrap(), "{expr}");
+        }
+    }
+}
+
+#[test]
+fn output_pretty_is_indented() {
+    let j = Jetro::from(json!({"o": {"a": 1}}));
+    let pretty = String::from_utf8(j.collect_bytes_pretty("$.o").unwrap()).unwrap();
+    assert_eq!(pretty, "[\n  {\n    \"a\": 1\n  }\n]");
+}
+
+#[test]
+fn output_preserves_document_key_order() {
+    let j = Jetro::from_bytes(br#"{"o":{"z":1,"a":2,"m":3}}"#.to_vec()).unwrap();
+    assert_eq!(j.collect_bytes("$.o").unwrap(), br#"[{"z":1,"a":2,"m":3}]"#);
+}
+
+#[test]
+fn output_writes_into_writer() {
+    let j = Jetro::from(json!({"xs": [1, 2, 3]}));
+    let mut out = b"xs=".to_vec();
+    j.collect_to_writer("$.xs.sum()", &mut out).unwrap();
+    assert_eq!(out, b"xs=6");
+}
+
+#[test]
+fn output_writer_failure_is_io_error() {
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let j = Jetro::from(json!({"xs": [1, 2]}));
+    let err = j.collect_to_writer("$.xs", Broken).unwrap_err();
+    assert_eq!(err.kind(), EvalErrorKind::Io);
+    assert!(err.to_string().contains("disk full"));
+}
+
+#[test]
+fn output_surfaces_evaluation_errors() {
+    let j = Jetro::from(json!({}));
+    assert!(j.collect_bytes("$.a.(").is_err());
+}
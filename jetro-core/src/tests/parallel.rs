@@ -0,0 +1,249 @@
+//! Opt-in parallel execution of pipelines over large arrays, configured with
+//! `JetroEngine::set_parallelism`.
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+use crate::data::value::Val;
+use crate::exec::{parallel, pipeline::Pipeline};
+use crate::{ExecutionLimits, HostFunction, Jetro, JetroEngine, Parallelism};
+
+const ROWS: i64 = 1000;
+
+fn payload() -> Value {
+    let rows: Vec<Value> = (0..ROWS)
+        .map(|n| {
+            json!({
+                "id": n,
+                "group": format!("g{}", n % 7),
+                "score": if n % 3 == 0 { json!(n as f64 + 0.5) } else { json!(n) },
+                "tags": (0..n % 3).map(|t| format!("t{t}")).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({"rows": rows, "cutoff": 500})
+}
+
+fn parallel_engine() -> JetroEngine {
+    let mut engine = JetroEngine::new();
+    engine.set_parallelism(Parallelism::new().threads(4).min_rows(100));
+    engine
+}
+
+/// Executors that ran the root pipeline of `expr`.
+fn executors(engine: &JetroEngine, doc: &Jetro, expr: &str) -> Vec<&'static str> {
+    let (_, profile) = engine.collect_profiled(doc, expr).unwrap();
+    profile
+        .root
+        .pipeline
+        .map(|pipeline| pipeline.executors.iter().map(|exec| exec.name).collect())
+        .unwrap_or_default()
+}
+
+const SPLIT: &[&str] = &[
+    "$.rows.map(id)",
+    "$.rows.filter(id % 2 == 0).map({id, group})",
+    "$.rows.count()",
+    "$.rows.filter(score > $.cutoff).count()",
+    "$.rows.map(score).sum()",
+    "$.rows.map(id).sum()",
+    "$.rows.map(score).min()",
+    "$.rows.map(score).max()",
+    "$.rows.map(score).avg()",
+    "$.rows.filter(id > 10).avg(id)",
+    "$.rows.map(group).approx_count_distinct()",
+    "$.rows.map(id % 300).approx_count_distinct()",
+    "$.rows.count_by(group)",
+    "$.rows.filter(id > 10).group_by(group)",
+    "$.rows.index_by(group)",
+];
+
+#[test]
+fn parallel_results_match_sequential() {
+    let engine = parallel_engine();
+    let doc = Jetro::from(payload());
+    for expr in SPLIT {
+        let expected = JetroEngine::new().collect(&doc, expr).unwrap();
+        assert_eq!(engine.collect(&doc, expr).unwrap(), expected, "{expr}");
+        assert!(
+            executors(&engine, &doc, expr).contains(&"parallel"),
+            "{expr} did not run in parallel"
+        );
+    }
+}
+
+#[test]
+fn unsplittable_pipelines_stay_sequential() {
+    let engine = parallel_engine();
+    let doc = Jetro::from(payload());
+    for expr in [
+        "$.rows.take(5).map(id)",
+        "$.rows.skip(990).map(id)",
+        "$.rows.take_while(id < 20).count()",
+        "$.rows.map(group).unique()",
+        "$.rows.sort_by(score).map(id).last()",
+        "$.rows.map(id).first()",
+        "$.rows.map(id).last()",
+    ] {
+        let expected = JetroEngine::new().collect(&doc, expr).unwrap();
+        assert_eq!(engine.collect(&doc, expr).unwrap(), expected, "{expr}");
+        assert!(
+            !executors(&engine, &doc, expr).contains(&"parallel"),
+            "{expr} ran in parallel"
+        );
+    }
+}
+
+#[test]
+fn small_inputs_and_default_engines_stay_sequential() {
+    let doc = Jetro::from(payload());
+    let expr = "$.rows.map(id).sum()";
+
+    let mut engine = JetroEngine::new();
+    assert!(!executors(&engine, &doc, expr).contains(&"parallel"));
+
+    engine.set_parallelism(Parallelism::new().threads(4).min_rows(ROWS as usize + 1));
+    assert!(!executors(&engine, &doc, expr).contains(&"parallel"));
+
+    engine.set_parallelism(Parallelism::new().threads(4).min_rows(ROWS as usize));
+    assert!(executors(&engine, &doc, expr).contains(&"parallel"));
+    assert_eq!(
+        engine.parallelism(),
+        &Parallelism::new().threads(4).min_rows(1000)
+    );
+}
+
+#[test]
+fn limits_keep_queries_sequential_and_enforced() {
+    let mut engine = parallel_engine();
+    engine.set_limits(ExecutionLimits::new().max_output_elements(10));
+    let doc = Jetro::from(payload());
+    assert!(engine.collect(&doc, "$.rows.map(id)").is_err());
+    assert_eq!(engine.collect(&doc, "$.rows.count()").unwrap(), json!(ROWS));
+}
+
+#[test]
+fn errors_and_host_functions_reach_every_partition() {
+    let mut engine = parallel_engine();
+    engine
+        .register_function(
+            "double",
+            HostFunction::new(0, |n, _| Ok(json!(n.as_i64().unwrap_or(0) * 2))).elementwise(),
+        )
+        .unwrap();
+    let doc = Jetro::from(payload());
+    assert_eq!(
+        engine
+            .collect(&doc, "$.rows.map(id.double()).sum()")
+            .unwrap(),
+        json!(ROWS * (ROWS - 1))
+    );
+    assert!(engine
+        .collect(&doc, "$.rows.map(id / (id - 500)).sum()")
+        .is_err());
+}
+
+#[test]
+fn impure_host_functions_run_in_order() {
+    let mut engine = parallel_engine();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&calls);
+    engine
+        .register_function(
+            "record",
+            HostFunction::new(0, move |n, _| {
+                log.lock().unwrap().push(n.as_i64().unwrap_or(-1));
+                Ok(n.clone())
+            })
+            .elementwise()
+            .impure(),
+        )
+        .unwrap();
+    let doc = Jetro::from(payload());
+    let in_order: Vec<i64> = (0..ROWS).collect();
+    for expr in [
+        "$.rows.map(id.record())",
+        "$.rows.map(record(id)).sum()",
+        "$.rows.map(id).record()",
+        "$.rows.filter(record(id) >= 0).count()",
+        "$.rows.map({n: id.record()}).count_by(n)",
+    ] {
+        calls.lock().unwrap().clear();
+        engine.collect(&doc, expr).unwrap();
+        assert_eq!(*calls.lock().unwrap(), in_order, "{expr}");
+        assert!(
+            !executors(&engine, &doc, expr).contains(&"parallel"),
+            "{expr} ran in parallel"
+        );
+    }
+}
+
+#[test]
+fn objvec_sources_split_by_row() {
+    let rows = Val::from(&payload()["rows"]);
+    let Val::Arr(rows) = rows else {
+        panic!("expected array rows");
+    };
+    let objvec = Pipeline::try_promote_objvec_arr(&rows).expect("uniform rows promote");
+    let mut root = indexmap::IndexMap::new();
+    root.insert(Arc::<str>::from("rows"), Val::ObjVec(objvec));
+    let root = Val::obj(root);
+    let plain = Val::from(&payload());
+
+    let _parallel = parallel::enter(&Parallelism::new().threads(3).min_rows(10));
+    for expr in [
+        "$.rows.filter(id % 5 == 0).map(id)",
+        "$.rows.map(score).sum()",
+        "$.rows.map(score).avg()",
+        "$.rows.map(id % 50).approx_count_distinct()",
+        "$.rows.filter(id > 10).group_by(group)",
+    ] {
+        let pipeline = Pipeline::lower(&crate::parse::parser::parse(expr).unwrap()).unwrap();
+        let expected = {
+            let _sequential = parallel::enter(&Parallelism::new());
+            pipeline.run(&plain).unwrap()
+        };
+        let out = pipeline.run(&root).unwrap();
+        assert_eq!(format!("{out:?}"), format!("{expected:?}"), "{expr}");
+    }
+}
+
+#[test]
+fn parallelism_never_changes_results() {
+    let rows: Vec<Value> = (0..5000)
+        .map(|n| json!({"n": n, "g": format!("g{}", n % 5)}))
+        .collect();
+    let doc = Jetro::from(json!({ "rows": rows }));
+    let mut engine = JetroEngine::new();
+    engine.set_parallelism(Parallelism::new().threads(4).min_rows(10));
+    for expr in [
+        "$.rows.map(n / (n - 2500))",
+        "$.rows.map(n / (n - 2500)).count()",
+        "$.rows.filter(n / (n - 2500) > 0).count()",
+        "$.rows.map(g).count_by(@)",
+        "$.rows.map(g).group_by(@)",
+        "$.rows.map(g).index_by(@)",
+        "$.rows.count_by(g)",
+        "$.rows.avg(n)",
+        "$.rows.filter(n % 3 == 0).map(g).approx_count_distinct()",
+    ] {
+        let sequential = JetroEngine::new().collect(&doc, expr);
+        let parallel = engine.collect(&doc, expr);
+        assert_eq!(
+            format!("{parallel:?}"),
+            format!("{sequential:?}"),
+            "{expr}"
+        );
+        assert!(
+            executors(&engine, &doc, expr).contains(&"parallel"),
+            "{expr} did not run in parallel"
+        );
+    }
+    for expr in [
+        "$.rows.map(n / (n - 2500)).sum()",
+        "$.rows.map(n / (n - 2500)).avg()",
+    ] {
+        assert!(engine.collect(&doc, expr).is_err(), "{expr}");
+    }
+}
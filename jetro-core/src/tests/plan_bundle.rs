@@ -0,0 +1,269 @@
+//! Plan bundles written by `JetroEngine::export_cache` and loaded by
+//! `import_cache`: round trips and rejection of incompatible bundles.
+
+use serde_json::json;
+
+use crate::{CacheBundleError, ExecutionLimits, HostFunction, Jetro, JetroEngine};
+
+/// Queries spanning the plan shapes a bundle has to carry: pipelines,
+/// literals, object and f-string kernels, patches, comprehensions, and
+/// structural deep search.
+const QUERIES: &[&str] = &[
+    "$.books.filter(price > 10).map(title)",
+    "$.books.sort_by(price).map({t: title, p: price * 2})",
+    "$.books.map(f\"{title}: {price}\")",
+    "$.books.group_by(genre)",
+    "$.books.map(price).sum()",
+    "$..title",
+    "[b.title for b in $.books if b.price < 20]",
+    "patch $ { books[*].price: @ + 1 }",
+    "let cheap = $.books.filter(price < 10) in {n: cheap.len(), tags: [1, \"a\", null]}",
+    "$.meta.version",
+];
+
+fn docs() -> [Jetro; 2] {
+    let doc = json!({
+        "books": [
+            {"title": "A", "price": 8, "genre": "sf"},
+            {"title": "B", "price": 15, "genre": "crime"},
+            {"title": "C", "price": 22, "genre": "sf"}
+        ],
+        "meta": {"version": 3}
+    });
+    [
+        Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+        Jetro::from(doc),
+    ]
+}
+
+fn exported(engine: &JetroEngine) -> Vec<u8> {
+    let mut bundle = Vec::new();
+    engine.export_cache(&mut bundle).unwrap();
+    bundle
+}
+
+#[test]
+fn imported_plans_are_hits_with_identical_results() {
+    let source = JetroEngine::new();
+    source.warm(QUERIES).unwrap();
+    let mut bundle = Vec::new();
+    let written = source.export_cache(&mut bundle).unwrap();
+    assert_eq!(written, QUERIES.len() * 2);
+
+    let target = JetroEngine::new();
+    assert_eq!(target.import_cache(bundle.as_slice()).unwrap(), written);
+    assert_eq!(target.plan_cache_stats().entries, written);
+    for j in docs() {
+        for query in QUERIES {
+            assert_eq!(
+                target.collect(&j, query).unwrap(),
+                source.collect(&j, query).unwrap(),
+                "{query}"
+            );
+        }
+    }
+    let stats = target.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (written as u64, 0));
+}
+
+#[test]
+fn import_respects_the_target_capacity() {
+    let source = JetroEngine::new();
+    source.warm(QUERIES).unwrap();
+    let target = JetroEngine::with_plan_cache_limit(3);
+    target.import_cache(exported(&source).as_slice()).unwrap();
+    assert_eq!(target.plan_cache_stats().entries, 3);
+}
+
+#[test]
+fn rejects_input_that_is_not_a_bundle() {
+    let engine = JetroEngine::new();
+    for input in [&b""[..], b"JETRO", b"not a plan bundle at all"] {
+        assert!(matches!(
+            engine.import_cache(input),
+            Err(CacheBundleError::NotABundle)
+        ));
+    }
+}
+
+#[test]
+fn rejects_other_format_versions() {
+    let source = JetroEngine::new();
+    source.warm(["$.meta.version"]).unwrap();
+    let mut bundle = exported(&source);
+    bundle[8..12].copy_from_slice(&99u32.to_le_bytes());
+    let target = JetroEngine::new();
+    let err = target.import_cache(bundle.as_slice()).unwrap_err();
+    assert!(matches!(
+        err,
+        CacheBundleError::FormatVersion {
+            found: 99,
+            expected: 1
+        }
+    ));
+    assert_eq!(target.plan_cache_stats().entries, 0);
+}
+
+#[test]
+fn rejects_a_different_builtin_table() {
+    let source = JetroEngine::new();
+    source.warm(["$.meta.version"]).unwrap();
+    let mut bundle = exported(&source);
+    // header: 12-byte prefix, then the crate version (u64 length + bytes),
+    // then the builtin-table fingerprint
+    let version_len = env!("CARGO_PKG_VERSION").len();
+    bundle[12 + 8 + version_len] ^= 0xff;
+    let err = JetroEngine::new()
+        .import_cache(bundle.as_slice())
+        .unwrap_err();
+    assert!(matches!(err, CacheBundleError::BuiltinTable), "{err}");
+}
+
+#[test]
+fn rejects_different_host_functions() {
+    let mut source = JetroEngine::new();
+    source
+        .register_function(
+            "double",
+            HostFunction::new(0, |v, _| Ok(json!(v.as_i64().unwrap() * 2))),
+        )
+        .unwrap();
+    source.warm(["$.meta.version.double()"]).unwrap();
+    let bundle = exported(&source);
+
+    let err = JetroEngine::new()
+        .import_cache(bundle.as_slice())
+        .unwrap_err();
+    assert!(matches!(err, CacheBundleError::HostFunctions), "{err}");
+
+    let mut target = JetroEngine::new();
+    target
+        .register_function(
+            "double",
+            HostFunction::new(0, |v, _| Ok(json!(v.as_i64().unwrap() * 2))),
+        )
+        .unwrap();
+    target.import_cache(bundle.as_slice()).unwrap();
+    assert_eq!(
+        target
+            .collect(&docs()[0], "$.meta.version.double()")
+            .unwrap(),
+        json!(6)
+    );
+}
+
+#[test]
+fn rejects_a_different_nesting_limit() {
+    let source = JetroEngine::new();
+    source.warm(["$.meta.version"]).unwrap();
+    let mut target = JetroEngine::new();
+    target.set_limits(ExecutionLimits::new().max_nesting(16));
+    let err = target
+        .import_cache(exported(&source).as_slice())
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            CacheBundleError::NestingLimit {
+                found: 128,
+                expected: 16
+            }
+        ),
+        "{err}"
+    );
+}
+
+/// Offset of the first plan byte: the 12-byte prefix, then the header's
+/// crate version (u64 length + bytes) and three u64 fields.
+fn plans_offset() -> usize {
+    12 + 8 + env!("CARGO_PKG_VERSION").len() + 3 * 8
+}
+
+#[test]
+fn corrupted_bundle_bytes_are_rejected() {
+    let source = JetroEngine::new();
+    source.warm(QUERIES).unwrap();
+    let bundle = exported(&source);
+    let target = JetroEngine::new();
+    for at in 0..bundle.len() {
+        let mut corrupt = bundle.clone();
+        corrupt[at] ^= 0xa5;
+        let result = target.import_cache(corrupt.as_slice());
+        if at >= plans_offset() {
+            assert!(
+                matches!(result, Err(CacheBundleError::Malformed(_))),
+                "byte {at}: {result:?}"
+            );
+        } else {
+            assert!(result.is_err(), "byte {at}");
+        }
+    }
+    assert_eq!(target.plan_cache_stats().entries, 0);
+}
+
+#[test]
+fn corrupted_plans_with_a_valid_checksum_do_not_panic() {
+    let source = JetroEngine::new();
+    source.warm(QUERIES).unwrap();
+    let bundle = exported(&source);
+    let (start, end) = (plans_offset(), bundle.len() - 8);
+    let target = JetroEngine::new();
+    for at in start..end {
+        let mut corrupt = bundle.clone();
+        corrupt[at] ^= 0xa5;
+        let sum = crate::plan::bundle::checksum(&corrupt[start..end]);
+        corrupt[end..].copy_from_slice(&sum.to_le_bytes());
+        match target.import_cache(corrupt.as_slice()) {
+            Ok(_) | Err(CacheBundleError::Malformed(_)) => {}
+            Err(err) => panic!("byte {at}: {err}"),
+        }
+    }
+}
+
+#[test]
+fn truncated_bundle_adds_nothing() {
+    let source = JetroEngine::new();
+    source.warm(QUERIES).unwrap();
+    let bundle = exported(&source);
+    let target = JetroEngine::new();
+    assert!(target.import_cache(&bundle[..bundle.len() / 2]).is_err());
+    assert_eq!(target.plan_cache_stats().entries, 0);
+}
+
+
+/// Checks every inline-cache slice in `program` against what it indexes.
+fn assert_slots_match(program: &crate::vm::Program) {
+    use crate::vm::{CompiledObjEntry, Opcode};
+    assert_eq!(program.ics.len(), program.ops.len(), "{}", program.source);
+    for op in program.ops.iter() {
+        match op {
+            Opcode::FieldChain(chain) => assert_eq!(chain.ics.len(), chain.keys.len()),
+            Opcode::MakeObj(entries) => {
+                for entry in entries.iter() {
+                    if let CompiledObjEntry::KvPath { steps, ics, .. } = entry {
+                        assert_eq!(ics.len(), steps.len());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn decoding_rebuilds_inline_cache_slots_from_the_program() {
+    use crate::compile::compiler::Compiler;
+    for expr in ["$.a.b.c", "$.rows.map({t: @.a.b, n: @.c})", "{x: $.a, y: @.b.c}"] {
+        let program = Compiler::compile_str(expr).unwrap();
+        let bytes = bincode::serialize(&program).unwrap();
+        let decoded: crate::vm::Program = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.ops.len(), program.ops.len());
+        assert_slots_match(&decoded);
+        // Dropping the opcodes leaves no slot count behind to disagree with.
+        let mut short = crate::vm::Program::new(Vec::new(), expr);
+        short.ops = decoded.ops.clone();
+        let bytes = bincode::serialize(&short).unwrap();
+        let decoded: crate::vm::Program = bincode::deserialize(&bytes).unwrap();
+        assert_slots_match(&decoded);
+    }
+}
@@ -0,0 +1,122 @@
+//! `JetroEngine`'s LRU plan cache: eviction order, counters, key
+//! normalisation, and `warm`.
+
+use serde_json::json;
+
+use crate::plan::cache::normalize_expr;
+use crate::{Jetro, JetroEngine};
+
+fn doc() -> Jetro {
+    Jetro::from(json!({"a": [1, 2, 3], "b": {"c": "x  y"}}))
+}
+
+#[test]
+fn evicts_least_recently_used_plan() {
+    let engine = JetroEngine::with_plan_cache_limit(2);
+    let j = doc();
+    engine.collect(&j, "$.a.len()").unwrap();
+    engine.collect(&j, "$.a.sum()").unwrap();
+    engine.collect(&j, "$.a.len()").unwrap();
+    engine.collect(&j, "$.a.max()").unwrap();
+
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 3, 1));
+    assert_eq!(stats.entries, 2);
+
+    // `$.a.sum()` was the least recently used, so `$.a.len()` survived
+    engine.collect(&j, "$.a.len()").unwrap();
+    assert_eq!(engine.plan_cache_stats().hits, 2);
+    engine.collect(&j, "$.a.sum()").unwrap();
+    assert_eq!(engine.plan_cache_stats().misses, 4);
+}
+
+#[test]
+fn hits_share_one_plan_and_report_time_saved() {
+    let engine = JetroEngine::new();
+    let j = doc();
+    let context = crate::exec::router::planning_context(&j);
+    let first = engine.cached_plan("$.a.filter(@ > 1).map(@ * 2)", context, &[]);
+    let second = engine.cached_plan("$.a.filter(@ > 1).map(@ * 2)", context, &[]);
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert!(stats.time_saved > std::time::Duration::ZERO);
+}
+
+#[test]
+fn whitespace_variants_share_an_entry() {
+    let engine = JetroEngine::new();
+    let j = doc();
+    for expr in [
+        "$.a.map(@ * 2)",
+        "  $.a.map( @ * 2 )",
+        "$.a.map(@\n  *\t2)",
+    ] {
+        assert_eq!(engine.collect(&j, expr).unwrap(), json!([2, 4, 6]));
+    }
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (2, 1, 1));
+}
+
+#[test]
+fn normalisation_keeps_significant_whitespace() {
+    assert_eq!(normalize_expr(" { a : [1 , 2] } "), "{a:[1,2]}");
+    assert_eq!(normalize_expr("$.a  -  b"), "$.a - b");
+    assert_eq!(normalize_expr("$.a-b"), "$.a-b");
+    assert_eq!(normalize_expr("x  and\n y"), "x and y");
+    assert_eq!(normalize_expr("$. {a}"), "$. {a}");
+    assert_eq!(normalize_expr(r#"$.b.c == "x  y" "#), r#"$.b.c == "x  y""#);
+    assert_eq!(normalize_expr("f'( a )' ( 1 )"), "f'( a )'(1)");
+}
+
+#[test]
+fn string_literals_are_not_normalised() {
+    let engine = JetroEngine::new();
+    let j = doc();
+    assert_eq!(engine.collect(&j, r#"$.b.c == "x  y""#).unwrap(), json!(true));
+    assert_eq!(engine.collect(&j, r#"$.b.c == "x y""#).unwrap(), json!(false));
+    assert_eq!(engine.plan_cache_stats().entries, 2);
+}
+
+#[test]
+fn warm_preplans_for_both_input_modes() {
+    let engine = JetroEngine::new();
+    engine.warm(["$.a.len()", "$.a.sum()"]).unwrap();
+    let warmed = engine.plan_cache_stats();
+    assert_eq!((warmed.misses, warmed.entries), (4, 4));
+
+    engine.collect(&doc(), "$.a.len()").unwrap();
+    let bytes = Jetro::from_bytes(br#"{"a":[1,2]}"#.to_vec()).unwrap();
+    engine.collect(&bytes, "$.a.sum()").unwrap();
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (2, 4));
+}
+
+#[test]
+fn warm_reports_the_first_parse_error() {
+    let engine = JetroEngine::new();
+    let err = engine.warm(["$.a.len()", "$.a.(", "$.a.sum()"]).unwrap_err();
+    assert!(err.offset > 0);
+    assert_eq!(engine.plan_cache_stats().entries, 2);
+}
+
+#[test]
+fn zero_limit_disables_caching_but_counts_misses() {
+    let engine = JetroEngine::with_plan_cache_limit(0);
+    let j = doc();
+    engine.collect(&j, "$.a.len()").unwrap();
+    engine.collect(&j, "$.a.len()").unwrap();
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (0, 2, 0));
+}
+
+#[test]
+fn clear_cache_keeps_counters() {
+    let engine = JetroEngine::new();
+    let j = doc();
+    engine.collect(&j, "$.a.len()").unwrap();
+    engine.collect(&j, "$.a.len()").unwrap();
+    engine.clear_cache();
+    let stats = engine.plan_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 0));
+}
@@ -0,0 +1,155 @@
+//! Runtime profiles returned by `JetroEngine::collect_profiled`.
+
+use serde_json::json;
+
+use crate::{EvalErrorKind, Jetro, JetroEngine, ProfileNode};
+
+fn bytes_doc() -> Jetro {
+    Jetro::from_bytes(
+        br#"{"books":[{"title":"a","price":12},{"title":"b","price":3},{"title":"c","price":7}],"meta":{"n":3}}"#
+            .to_vec(),
+    )
+    .unwrap()
+}
+
+fn find<'a>(node: &'a ProfileNode, kind: &str) -> Option<&'a ProfileNode> {
+    if node.kind == kind {
+        return Some(node);
+    }
+    node.children.iter().find_map(|child| find(child, kind))
+}
+
+#[test]
+fn profile_reports_backend_rows_and_executors() {
+    let engine = JetroEngine::new();
+    let doc = bytes_doc();
+    let (value, profile) = engine
+        .collect_profiled(&doc, "$.books.filter(price > 5).map(title)")
+        .unwrap();
+    assert_eq!(value, json!(["a", "c"]));
+    assert_eq!(profile.input, "bytes");
+    assert_eq!(profile.root.kind, "Pipeline");
+    assert_eq!(profile.root.backend, Some("TapeView"));
+    assert_eq!(profile.root.calls, 1);
+
+    let pipeline = profile.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.rows_pulled, 3);
+    assert_eq!(pipeline.rows_emitted, 2);
+    assert_eq!(pipeline.executors[0].name, "view");
+    let names: Vec<_> = pipeline.stages.iter().map(|stage| stage.name).collect();
+    assert_eq!(names, ["filter", "map"]);
+    assert!(pipeline
+        .stages
+        .iter()
+        .all(|stage| stage.executor == Some("view")));
+}
+
+#[test]
+fn profile_stops_pulling_once_demand_is_met() {
+    let engine = JetroEngine::new();
+    let (value, profile) = engine
+        .collect_profiled(&bytes_doc(), "$.books.filter(price > 5).first()")
+        .unwrap();
+    assert_eq!(value, json!({"title": "a", "price": 12}));
+    let pipeline = profile.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.rows_pulled, 1);
+    assert_eq!(pipeline.rows_emitted, 1);
+}
+
+#[test]
+fn profile_records_backend_and_executor_fallbacks() {
+    let engine = JetroEngine::new();
+    let doc = Jetro::from(json!({"prices": [12, 3, 7]}));
+    let (value, profile) = engine.collect_profiled(&doc, "$.prices.window(2)").unwrap();
+    assert_eq!(value, json!([[12, 3], [3, 7]]));
+    assert_eq!(profile.input, "val");
+    assert_eq!(profile.root.backend, Some("Interpreted"));
+    assert_eq!(profile.root.fallbacks, ["ValView"]);
+
+    let pipeline = profile.root.pipeline.as_ref().unwrap();
+    assert!(pipeline.fallbacks.contains(&"composed"), "{pipeline:?}");
+    assert_eq!(pipeline.executors.len(), 1);
+    assert_eq!(pipeline.executors[0].name, "materialized");
+    assert_eq!(pipeline.rows_pulled, 3);
+    assert_eq!(pipeline.rows_emitted, 2);
+    assert!(pipeline
+        .stages
+        .iter()
+        .all(|stage| stage.executor == Some("materialized")));
+}
+
+#[test]
+fn profile_reports_suffix_executor_after_view_prefix() {
+    let engine = JetroEngine::new();
+    let doc = Jetro::from(json!({"books": [{"price": 12}, {"price": 3}, {"price": 7}]}));
+    let (value, profile) = engine
+        .collect_profiled(&doc, "$.books.filter(price > 5).map(price).reverse()")
+        .unwrap();
+    assert_eq!(value, json!([7, 12]));
+    let pipeline = profile.root.pipeline.as_ref().unwrap();
+    assert_eq!(pipeline.stages.len(), 3);
+    let reverse = &pipeline.stages[2];
+    assert_eq!(reverse.name, "reverse");
+    assert!(reverse.executor.is_some(), "{pipeline:?}");
+    assert_eq!(pipeline.rows_pulled, 3);
+    assert_eq!(pipeline.rows_emitted, 2);
+}
+
+#[test]
+fn profile_tracks_document_materialization() {
+    let engine = JetroEngine::new();
+    let doc = bytes_doc();
+    let (_, profile) = engine.collect_profiled(&doc, "$.meta").unwrap();
+    assert!(profile.materialized.tape);
+    assert!(!profile.materialized.val);
+
+    let (_, profile) = engine
+        .collect_profiled(&doc, r#"{"meta": $.meta, "deep": $..price}"#)
+        .unwrap();
+    assert!(!profile.materialized.tape, "tape was already built");
+    assert!(profile.materialized.val);
+    let vm = find(&profile.root, "Vm").unwrap();
+    assert_eq!(vm.backend, Some("Interpreted"));
+    assert!(vm.materialized.val);
+    assert_eq!(profile.root.children.len(), 2);
+    assert!(profile.root.children.iter().all(|child| child.calls == 1));
+}
+
+#[test]
+fn profile_counts_materialized_tape_subtrees() {
+    let engine = JetroEngine::new();
+    let (_, profile) = engine.collect_profiled(&bytes_doc(), "$.books[0]").unwrap();
+    assert_eq!(profile.materialized.tape_subtrees, 1);
+    assert_eq!(profile.root.materialized.tape_subtrees, 1);
+}
+
+#[test]
+fn profile_prints_and_serializes() {
+    let engine = JetroEngine::new();
+    let (_, profile) = engine
+        .collect_profiled(&bytes_doc(), "$.books.filter(price > 5).count()")
+        .unwrap();
+    let text = profile.to_string();
+    assert!(text.starts_with("input: bytes\nelapsed: "), "{text}");
+    assert!(text.contains("Pipeline $.books backend=TapeView"), "{text}");
+    assert!(text.contains("  rows pulled=3 emitted=2"), "{text}");
+    assert!(text.contains("  stage filter executor=view"), "{text}");
+
+    let value = serde_json::to_value(&profile).unwrap();
+    assert_eq!(value["root"]["backend"], "TapeView");
+    assert_eq!(value["root"]["pipeline"]["rows_pulled"], 3);
+    assert_eq!(value["materialized"]["tape"], true);
+}
+
+#[test]
+fn profile_is_off_for_plain_collect_and_reports_errors() {
+    let engine = JetroEngine::new();
+    let doc = bytes_doc();
+    assert!(!crate::exec::profile::is_recording());
+    let err = engine
+        .collect_profiled(&doc, "$.books.(")
+        .expect_err("parse error");
+    assert_eq!(err.kind(), EvalErrorKind::Parse);
+    assert!(!crate::exec::profile::is_recording());
+    assert_eq!(engine.collect(&doc, "$.books.len()").unwrap(), json!(3));
+}
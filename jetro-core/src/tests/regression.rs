@@ -2427,6 +2427,24 @@ mod tests {
         assert_eq!(vm.cache_stats().0, 2);
     }
 
+    #[test]
+    fn lru_compile_cache_keeps_recently_used_programs() {
+        use crate::vm::VM;
+        use std::sync::Arc;
+        let mut vm = VM::with_capacity(16, 16);
+        let first = vm.get_or_compile("$.k0").unwrap();
+        for i in 1..16 {
+            vm.get_or_compile(&format!("$.k{i}")).unwrap();
+        }
+        assert!(Arc::ptr_eq(&first, &vm.get_or_compile("$.k0").unwrap()));
+        vm.get_or_compile("$.k16").unwrap();
+        assert_eq!(vm.cache_stats().0, 15, "evicts an eighth at once");
+        assert!(Arc::ptr_eq(&first, &vm.get_or_compile("$.k0").unwrap()));
+        let k1 = vm.get_or_compile("$.k1").unwrap();
+        assert_eq!(vm.cache_stats().0, 16, "k1 was recompiled");
+        assert!(Arc::ptr_eq(&k1, &vm.get_or_compile("$.k1").unwrap()));
+    }
+
     #[test]
     fn optimized_equi_join_hash_probe() {
         
@@ -3165,4 +3183,25 @@ mod tests {
         
         assert_eq!(vm_query("range(1, 10).sum()", &doc).unwrap(), json!(45));
     }
+
+    #[test]
+    fn columnar_lanes_behave_like_arrays_in_chained_calls() {
+        // `xs` and `ss` load as `IntVec` / `StrVec`, `map({..})` yields an `ObjVec`.
+        let j = crate::Jetro::from(json!({"xs": [1, 2, 3], "ss": ["a", "b"], "rows": [{"id": 1}, {"id": 2}]}));
+        let cases = [
+            ("$.xs.first().type()", json!("number")),
+            ("$.ss.first().upper()", json!("A")),
+            ("$.xs.first(2)", json!([1, 2])),
+            ("$.ss[1:]", json!(["b"])),
+            ("[x * 2 for x in $.xs]", json!([2, 4, 6])),
+            ("let r = $.rows.map({id}) in r[1:]", json!([{"id": 2}])),
+            ("let r = $.rows.map({id}) in r.any(id > 1)", json!(true)),
+            ("let r = $.rows.map({id}) in (r + r).len()", json!(4)),
+            ("let r = $.rows.map({id}) in r.reverse()[0].id", json!(2)),
+            ("let r = $.rows.map({id}) in r.pick(id)", json!([{"id": 1}, {"id": 2}])),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(j.collect(expr).unwrap(), expected, "{expr}");
+        }
+    }
 }
@@ -0,0 +1,61 @@
+//! Byte-backed documents built from shared buffers without copying them.
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::Jetro;
+
+const DOC: &[u8] = br#"{"books":[{"title":"a","price":12},{"title":"b\"c","price":3}]}"#;
+
+#[test]
+fn from_bytes_keeps_the_vector_allocation() {
+    let bytes = DOC.to_vec();
+    let ptr = bytes.as_ptr();
+    let j = Jetro::from_bytes(bytes).unwrap();
+    assert_eq!(j.raw_bytes().unwrap().as_ptr(), ptr);
+}
+
+#[test]
+fn shared_buffers_are_referenced_not_copied() {
+    let shared: Arc<[u8]> = Arc::from(DOC);
+    let j = Jetro::from_shared_bytes(Arc::clone(&shared)).unwrap();
+    assert_eq!(Arc::strong_count(&shared), 2);
+    assert_eq!(j.raw_bytes().unwrap().as_ptr(), shared.as_ptr());
+
+    let expected = Jetro::from_bytes(DOC.to_vec()).unwrap();
+    for query in ["$.books.map(title)", "$.books.map(price).sum()", "$..price"] {
+        assert_eq!(
+            j.collect(query).unwrap(),
+            expected.collect(query).unwrap(),
+            "{query}"
+        );
+    }
+    assert_eq!(j.collect("$.books[1].title").unwrap(), json!("b\"c"));
+    assert_eq!(Arc::strong_count(&shared), 2);
+
+    drop(j);
+    assert_eq!(Arc::strong_count(&shared), 1);
+}
+
+#[test]
+fn static_slices_and_invalid_input() {
+    let j = Jetro::from_shared_bytes(DOC).unwrap();
+    assert_eq!(j.raw_bytes().unwrap().as_ptr(), DOC.as_ptr());
+    assert_eq!(j.collect("$.books.len()").unwrap(), json!(2));
+
+    let bad = Jetro::from_shared_bytes(&br#"{"a":"#[..]);
+    assert!(bad.map_or(true, |j| j.collect("$.a").is_err()));
+}
+
+#[cfg(feature = "simd-json")]
+#[test]
+fn tape_parses_a_private_copy_of_the_source() {
+    let shared: Arc<[u8]> = Arc::from(DOC);
+    let j = Jetro::from_shared_bytes(Arc::clone(&shared)).unwrap();
+    let tape = j.lazy_tape().unwrap().unwrap();
+    assert_ne!(tape.bytes_buf.as_ptr(), shared.as_ptr());
+    // simd-json unescaped the string in its copy; the source is untouched
+    assert_eq!(&*shared, DOC);
+    assert_eq!(j.collect("$.books[1].title").unwrap(), json!("b\"c"));
+}
@@ -0,0 +1,266 @@
+//! Sibling pipelines inside one object or array constructor that read the
+//! same field chain, fused by the planner into a single `SharedScan`.
+
+use std::sync::atomic::Ordering;
+
+use serde_json::{json, Value};
+
+use crate::ir::physical::{PhysicalObjField, PlanNode, QueryPlan, QueryRoot};
+use crate::plan::physical::{plan_query_with_context, PlanningContext};
+use crate::Jetro;
+
+fn payload() -> Value {
+    json!({
+        "orders": [
+            {"id": 1, "total": 120, "status": "paid"},
+            {"id": 2, "total": 40, "status": "open"},
+            {"id": 3, "total": 75.5, "status": "paid"},
+            {"id": 4, "total": 300, "status": "refunded"}
+        ],
+        "refunds": [{"id": 4, "amount": 300}],
+        "threshold": 50
+    })
+}
+
+fn val_doc() -> Jetro {
+    Jetro::from(payload())
+}
+
+fn byte_doc() -> Jetro {
+    Jetro::from_bytes(serde_json::to_vec(&payload()).unwrap()).unwrap()
+}
+
+fn scans(j: &Jetro) -> usize {
+    j.source_scans.load(Ordering::Relaxed)
+}
+
+/// Number of `SharedScan` nodes reachable from the constructor at the plan root.
+fn shared_scans(plan: &QueryPlan) -> usize {
+    let QueryRoot::Node(root) = plan.root() else {
+        panic!("expected physical expression plan");
+    };
+    let children: Vec<_> = match plan.node(*root) {
+        PlanNode::Object(fields) => fields
+            .iter()
+            .filter_map(|field| match field {
+                PhysicalObjField::Kv { val, .. } => Some(*val),
+                _ => None,
+            })
+            .collect(),
+        PlanNode::Array(elems) => elems
+            .iter()
+            .map(|elem| match elem {
+                crate::ir::physical::PhysicalArrayElem::Expr(id)
+                | crate::ir::physical::PhysicalArrayElem::Spread(id) => *id,
+            })
+            .collect(),
+        _ => panic!("expected constructor root"),
+    };
+    let mut seen = Vec::new();
+    for child in children {
+        if let PlanNode::ScanSlot { scan, .. } = plan.node(child) {
+            if !seen.contains(scan) {
+                seen.push(*scan);
+            }
+        }
+    }
+    seen.len()
+}
+
+#[test]
+fn sibling_aggregates_scan_the_source_once() {
+    let expr = "{count: $.orders.count(), total: $.orders.map(total).sum(), max: $.orders.map(total).max()}";
+    assert_eq!(
+        shared_scans(&plan_query_with_context(expr, PlanningContext::val())),
+        1
+    );
+    let j = val_doc();
+    assert_eq!(
+        j.collect(expr).unwrap(),
+        json!({"count": 4, "total": 535.5, "max": 300.0})
+    );
+    assert_eq!(scans(&j), 1);
+}
+
+#[test]
+fn results_match_the_unfused_pipelines() {
+    let fields = [
+        "$.orders.count()",
+        "$.orders.map(total).avg()",
+        "$.orders.map(id).first()",
+        "$.orders.map(id).last()",
+        "$.orders.take(2).map(id)",
+        "$.orders.filter(total > $.threshold).map(id)",
+        "$.orders.map({id, big: total > 100})",
+    ];
+    let object = format!(
+        "{{{}}}",
+        fields
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| format!("f{idx}: {field}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let array = format!("[{}]", fields.join(", "));
+    for j in [val_doc(), byte_doc()] {
+        let expected: Vec<Value> = fields
+            .iter()
+            .map(|field| val_doc().collect(*field).unwrap())
+            .collect();
+        let out = j.collect(&object).unwrap();
+        for (idx, value) in expected.iter().enumerate() {
+            assert_eq!(&out[format!("f{idx}")], value, "{}", fields[idx]);
+        }
+        assert_eq!(j.collect(&array).unwrap(), Value::from(expected));
+    }
+}
+
+#[test]
+fn shared_filter_prefix_runs_once_per_row() {
+    let expr = r#"{paid: $.orders.filter(status == "paid").count(),
+                  revenue: $.orders.filter(status == "paid").map(total).sum(),
+                  ids: $.orders.filter(status == "paid").map(id)}"#;
+    let plan = plan_query_with_context(expr, PlanningContext::val());
+    assert_eq!(shared_scans(&plan), 1);
+    let scan = (0..)
+        .map(crate::ir::physical::NodeId)
+        .find_map(|id| match plan.node(id) {
+            PlanNode::SharedScan {
+                prefix, branches, ..
+            } => Some((prefix.clone(), branches.clone())),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(scan.0.unwrap().stages.len(), 1);
+    let branch_stages: Vec<usize> = scan.1.iter().map(|branch| branch.stages.len()).collect();
+    assert_eq!(branch_stages, vec![0, 0, 1]);
+
+    let j = val_doc();
+    assert_eq!(
+        j.collect(expr).unwrap(),
+        json!({"paid": 2, "revenue": 195.5, "ids": [1, 3]})
+    );
+    assert_eq!(scans(&j), 1);
+}
+
+#[test]
+fn filter_prefix_matches_by_structure() {
+    let prefix_len = |expr: &str| {
+        let plan = plan_query_with_context(expr, PlanningContext::val());
+        (0..)
+            .map(crate::ir::physical::NodeId)
+            .find_map(|id| match plan.node(id) {
+                PlanNode::SharedScan { prefix, .. } => {
+                    Some(prefix.as_ref().map_or(0, |p| p.stages.len()))
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    // the same predicate written at different offsets, with different spacing
+    let same = r#"{a: $.orders.filter(status.starts_with("p")).count(),
+                  b: $.orders.filter( status.starts_with( "p" ) ).map(id)}"#;
+    assert_eq!(prefix_len(same), 1);
+    let differ = r#"{a: $.orders.filter(status.starts_with("p")).count(),
+                    b: $.orders.filter(status.starts_with("r")).map(id)}"#;
+    assert_eq!(prefix_len(differ), 0);
+
+    let j = val_doc();
+    assert_eq!(j.collect(same).unwrap(), json!({"a": 2, "b": [1, 3]}));
+    assert_eq!(j.collect(differ).unwrap(), json!({"a": 2, "b": [4]}));
+}
+
+#[test]
+fn array_constructor_and_separate_sources() {
+    let j = val_doc();
+    let out = j
+        .collect("[$.orders.count(), $.refunds.count(), $.orders.map(id), $.refunds.map(id)]")
+        .unwrap();
+    assert_eq!(out, json!([4, 1, [1, 2, 3, 4], [4]]));
+    assert_eq!(scans(&j), 2);
+
+    let j = val_doc();
+    j.collect("{n: $.orders.count(), r: $.refunds.count()}")
+        .unwrap();
+    assert_eq!(scans(&j), 2);
+}
+
+#[test]
+fn byte_input_keeps_tape_native_pipelines_separate() {
+    let native = "{count: $.orders.count(), total: $.orders.map(total).sum()}";
+    assert_eq!(
+        shared_scans(&plan_query_with_context(native, PlanningContext::bytes())),
+        0
+    );
+    let j = byte_doc();
+    assert_eq!(
+        j.collect(native).unwrap(),
+        json!({"count": 4, "total": 535.5})
+    );
+    #[cfg(feature = "simd-json")]
+    assert!(!j.root_val_is_materialized());
+
+    let rows = "{ids: $.orders.filter(total > $.threshold).map(id), n: $.orders.filter(total > $.threshold).count()}";
+    assert_eq!(
+        shared_scans(&plan_query_with_context(rows, PlanningContext::bytes())),
+        1
+    );
+    let j = byte_doc();
+    assert_eq!(j.collect(rows).unwrap(), json!({"ids": [1, 3, 4], "n": 3}));
+    assert_eq!(scans(&j), 1);
+}
+
+#[test]
+fn failing_branch_reports_its_source_path() {
+    let j = val_doc();
+    let err = j
+        .collect("{n: $.orders.count(), bad: $.orders.map(-status)}")
+        .unwrap_err();
+    assert_eq!(err.path(), Some("$.orders"));
+}
+
+#[test]
+fn missing_source_gives_each_branch_an_empty_result() {
+    let expr = "{n: $.nope.count(), ids: $.nope.map(id)}";
+    for j in [val_doc(), byte_doc()] {
+        assert_eq!(
+            j.collect(expr).unwrap(),
+            json!({
+                "n": val_doc().collect("$.nope.count()").unwrap(),
+                "ids": val_doc().collect("$.nope.map(id)").unwrap()
+            })
+        );
+    }
+}
+
+#[test]
+fn keyed_reducers_beside_a_sibling_sink_keep_their_result() {
+    let by_status = json!({"paid": 2, "open": 1, "refunded": 1});
+    for j in [val_doc(), byte_doc()] {
+        assert_eq!(
+            j.collect("{n: $.orders.count(), by: $.orders.count_by(status)}")
+                .unwrap(),
+            json!({"n": 4, "by": by_status})
+        );
+        assert_eq!(
+            j.collect("[$.orders.map(total).sum(), $.orders.count_by(status)]")
+                .unwrap(),
+            json!([535.5, by_status])
+        );
+        assert_eq!(
+            j.collect("{n: $.orders.count(), by: $.orders.index_by(id)}")
+                .unwrap(),
+            json!({"n": 4, "by": val_doc().collect("$.orders.index_by(id)").unwrap()})
+        );
+    }
+    assert_eq!(
+        val_doc()
+            .collect("$.orders.index_by(id)")
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .len(),
+        4
+    );
+}
@@ -0,0 +1,111 @@
+//! External variables bound through `Jetro::collect_with` and
+//! `JetroEngine::collect_with`.
+
+use serde_json::{json, Value};
+
+use crate::{Jetro, JetroEngine};
+
+fn docs(doc: Value) -> [Jetro; 2] {
+    [
+        Jetro::from_bytes(serde_json::to_vec(&doc).unwrap()).unwrap(),
+        Jetro::from(doc),
+    ]
+}
+
+#[test]
+fn variables_match_equivalent_let_binding() {
+    let doc = json!({"xs": [1, 4, 9, 16]});
+    let vars = json!({"n": 5});
+    for j in docs(doc) {
+        for expr in ["$.xs.filter(@ > n)", "$.xs.map(@ + n).sum()", "n * 2"] {
+            let with = j.collect_with(expr, vars.as_object().unwrap()).unwrap();
+            let inline = j.collect(format!("let n = 5 in {expr}")).unwrap();
+            assert_eq!(with, inline, "{expr}");
+        }
+    }
+}
+
+#[test]
+fn variables_drive_field_predicates() {
+    let doc = json!({
+        "rows": [
+            {"name": "a", "score": 10, "team": "red"},
+            {"name": "b", "score": 90, "team": "blue"},
+            {"name": "c", "score": 70, "team": "red"}
+        ]
+    });
+    let vars = json!({"min": 50, "team": "red"});
+    for j in docs(doc.clone()) {
+        let out = j
+            .collect_with(
+                "$.rows.filter(score >= min and @.team == team).map(name)",
+                vars.as_object().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(out, json!(["c"]));
+    }
+}
+
+#[test]
+fn variables_shadow_row_fields_like_let() {
+    let doc = json!({"rows": [{"team": "red"}, {"team": "blue"}]});
+    let vars = json!({"team": "green"});
+    for j in docs(doc) {
+        for expr in ["$.rows.map(team)", "$.rows.map(@.team)"] {
+            let with = j.collect_with(expr, vars.as_object().unwrap()).unwrap();
+            let inline = j.collect(format!("let team = \"green\" in {expr}")).unwrap();
+            assert_eq!(with, inline, "{expr}");
+        }
+        let bare = j.collect_with("$.rows.map(team)", vars.as_object().unwrap());
+        assert_eq!(bare.unwrap(), json!(["green", "green"]));
+        let field = j.collect_with("$.rows.map(@.team)", vars.as_object().unwrap());
+        assert_eq!(field.unwrap(), json!(["red", "blue"]));
+    }
+}
+
+#[test]
+fn variables_accept_any_json_value() {
+    let j = Jetro::from(json!({"ids": [1, 2, 3]}));
+    let allowed = json!([2, 3]);
+    let label = json!("picked");
+    let out = j
+        .collect_with(
+            r#"{"label": label, "hits": $.ids.filter(allowed.includes(@))}"#,
+            [("allowed", &allowed), ("label", &label)],
+        )
+        .unwrap();
+    assert_eq!(out, json!({"label": "picked", "hits": [2, 3]}));
+}
+
+#[test]
+fn variables_last_duplicate_wins() {
+    let j = Jetro::from(json!({}));
+    let (one, two) = (json!(1), json!(2));
+    let out = j.collect_with("x", [("x", &one), ("x", &two)]).unwrap();
+    assert_eq!(out, json!(2));
+}
+
+#[test]
+fn unbound_identifier_is_unaffected_without_variables() {
+    let j = Jetro::from_bytes(br#"{"x":3}"#.to_vec()).unwrap();
+    let none: [(&str, &Value); 0] = [];
+    assert_eq!(j.collect_with("$.x", none).unwrap(), j.collect("$.x").unwrap());
+}
+
+#[test]
+fn engine_reuses_plan_across_variable_values() {
+    let engine = JetroEngine::new();
+    let j = Jetro::from_bytes(br#"{"xs":[1,2,3,4,5]}"#.to_vec()).unwrap();
+    for (n, expected) in [(1, 4), (3, 2), (5, 0)] {
+        let vars = json!({ "n": n });
+        let out = engine
+            .collect_with(&j, "$.xs.filter(@ > n).len()", vars.as_object().unwrap())
+            .unwrap();
+        assert_eq!(out, json!(expected));
+    }
+    assert_eq!(engine.plan_cache_stats().entries, 1);
+
+    // A different variable set is planned separately from the unbound query.
+    engine.collect(&j, "$.xs.len()").unwrap();
+    assert_eq!(engine.plan_cache_stats().entries, 2);
+}
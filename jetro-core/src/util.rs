@@ -185,10 +185,15 @@ pub fn kind_matches(v: &Val, ty: KindType) -> bool {
             | (Val::Int(_), KindType::Number)
             | (Val::Float(_), KindType::Number)
             | (Val::Str(_), KindType::Str)
+            | (Val::StrSlice(_), KindType::Str)
             | (Val::Arr(_), KindType::Array)
             | (Val::IntVec(_), KindType::Array)
             | (Val::FloatVec(_), KindType::Array)
+            | (Val::StrVec(_), KindType::Array)
+            | (Val::StrSliceVec(_), KindType::Array)
+            | (Val::ObjVec(_), KindType::Array)
             | (Val::Obj(_), KindType::Object)
+            | (Val::ObjSmall(_), KindType::Object)
     )
 }
 
@@ -271,7 +276,13 @@ pub fn add_vals(a: Val, b: Val) -> Result<Val, EvalError> {
             v.extend_from_slice(&y);
             Ok(Val::arr(v))
         }
-        _ => Err(EvalError("+ not supported between these types".into())),
+        (x, y) => match (x.into_vals(), y.into_vals()) {
+            (Ok(mut v), Ok(w)) => {
+                v.extend(w);
+                Ok(Val::arr(v))
+            }
+            _ => Err(EvalError::type_mismatch("+ not supported between these types")),
+        },
     }
 }
 
@@ -287,7 +298,7 @@ where
         (Val::Float(x), Val::Float(y)) => Ok(Val::Float(ff(x, y))),
         (Val::Int(x), Val::Float(y)) => Ok(Val::Float(ff(x as f64, y))),
         (Val::Float(x), Val::Int(y)) => Ok(Val::Float(ff(x, y as f64))),
-        _ => Err(EvalError("arithmetic on non-numbers".into())),
+        _ => Err(EvalError::type_mismatch("arithmetic on non-numbers")),
     }
 }
 
@@ -455,44 +466,14 @@ pub fn field_exists_nested(v: &Val, path: &str) -> bool {
 /// Recursively merge `other` into `base` for object values; non-object
 /// values in `other` overwrite the corresponding entry in `base`.
 pub fn deep_merge(base: Val, other: Val) -> Val {
-    match (base, other) {
-        (Val::Obj(bm), Val::Obj(om)) => {
-            let mut map = Arc::try_unwrap(bm).unwrap_or_else(|m| (*m).clone());
-            for (k, v) in Arc::try_unwrap(om).unwrap_or_else(|m| (*m).clone()) {
-                let existing = map.shift_remove(&k);
-                map.insert(
-                    k,
-                    match existing {
-                        Some(e) => deep_merge(e, v),
-                        None => v,
-                    },
-                );
-            }
-            Val::obj(map)
-        }
-        (_, other) => other,
-    }
+    merge_objects(base, other, |_, other| other)
 }
 
 
 /// Like `deep_merge`, but array-typed values at the same key are concatenated
 /// rather than overwritten.
 pub fn deep_merge_concat(base: Val, other: Val) -> Val {
-    match (base, other) {
-        (Val::Obj(bm), Val::Obj(om)) => {
-            let mut map = Arc::try_unwrap(bm).unwrap_or_else(|m| (*m).clone());
-            for (k, v) in Arc::try_unwrap(om).unwrap_or_else(|m| (*m).clone()) {
-                let existing = map.shift_remove(&k);
-                map.insert(
-                    k,
-                    match existing {
-                        Some(e) => deep_merge_concat(e, v),
-                        None => v,
-                    },
-                );
-            }
-            Val::obj(map)
-        }
+    merge_objects(base, other, |base, other| match (base, other) {
         (Val::Arr(ba), Val::Arr(oa)) => {
             let mut a = Arc::try_unwrap(ba).unwrap_or_else(|a| (*a).clone());
             for v in Arc::try_unwrap(oa).unwrap_or_else(|a| (*a).clone()) {
@@ -512,6 +493,64 @@ pub fn deep_merge_concat(base: Val, other: Val) -> Val {
             Val::arr(a)
         }
         (_, other) => other,
+    })
+}
+
+
+/// Shared walk behind `deep_merge` and `deep_merge_concat`: objects at the
+/// same key merge key by key, any other pair is combined by `leaf`. Nested
+/// objects are merged with an explicit stack rather than by recursion.
+fn merge_objects(base: Val, other: Val, leaf: fn(Val, Val) -> Val) -> Val {
+    /// An object being merged: `map` holds `base` with the `other` entries
+    /// merged so far, `key` the entry whose nested merge is in progress.
+    struct Frame {
+        map: IndexMap<Arc<str>, Val>,
+        rest: indexmap::map::IntoIter<Arc<str>, Val>,
+        key: Option<Arc<str>>,
+    }
+
+    impl Frame {
+        fn new(base: Arc<IndexMap<Arc<str>, Val>>, other: Arc<IndexMap<Arc<str>, Val>>) -> Self {
+            Frame {
+                map: Arc::try_unwrap(base).unwrap_or_else(|m| (*m).clone()),
+                rest: Arc::try_unwrap(other)
+                    .unwrap_or_else(|m| (*m).clone())
+                    .into_iter(),
+                key: None,
+            }
+        }
+    }
+
+    let mut stack = match (base, other) {
+        (Val::Obj(bm), Val::Obj(om)) => vec![Frame::new(bm, om)],
+        (base, other) => return leaf(base, other),
+    };
+    loop {
+        let top = stack.last_mut().expect("merge stack is never empty");
+        match top.rest.next() {
+            Some((k, v)) => match (top.map.shift_remove(&k), v) {
+                (Some(Val::Obj(em)), Val::Obj(vm)) => {
+                    top.key = Some(k);
+                    stack.push(Frame::new(em, vm));
+                }
+                (Some(e), v) => {
+                    top.map.insert(k, leaf(e, v));
+                }
+                (None, v) => {
+                    top.map.insert(k, v);
+                }
+            },
+            None => {
+                let merged = Val::obj(stack.pop().expect("merge stack is never empty").map);
+                match stack.last_mut() {
+                    Some(parent) => {
+                        let key = parent.key.take().expect("parent awaits a nested merge");
+                        parent.map.insert(key, merged);
+                    }
+                    None => return merged,
+                }
+            }
+        }
     }
 }
 
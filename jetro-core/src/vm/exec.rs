@@ -5,7 +5,8 @@
 //! demand annotation). `VM` owns two caches: a compile cache keyed on the
 //! expression string, and a path-resolution cache keyed on document structure.
 //! Both caches accumulate over the thread's lifetime via the thread-local in
-//! `lib.rs`. The VM is the general scalar fallback; streamable chains are
+//! `lib.rs`, or across a `JetroEngine`'s `VmPool`, whose VMs share one
+//! compile cache. The VM is the general scalar fallback; streamable chains are
 //! handled by the pipeline IR in `pipeline.rs` / `composed.rs`.
 
 use indexmap::IndexMap;
@@ -20,8 +21,9 @@ use std::{
 
 use crate::parse::ast::*;
 use crate::builtins::BuiltinMethod;
-use crate::data::context::{Env, EvalError};
+use crate::data::context::{Env, EvalError, EvalErrorKind};
 use crate::data::runtime::call_builtin_method_compiled;
+use crate::exec::limits;
 use crate::util::{
     add_vals, cmp_vals_binop, is_truthy, kind_matches, num_op, obj2, val_to_key, val_to_string,
     vals_eq,
@@ -34,12 +36,13 @@ macro_rules! pop {
     ($stack:expr) => {
         $stack
             .pop()
-            .ok_or_else(|| EvalError("stack underflow".into()))?
+            .ok_or_else(|| EvalError::internal("stack underflow"))?
     };
 }
-/// Construct an `Err(EvalError(...))` from a format string, mirroring `format!` syntax.
+/// Construct an `Err(EvalError)` of the given `EvalErrorKind` variant from a
+/// format string, mirroring `format!` syntax after the kind.
 macro_rules! err {
-    ($($t:tt)*) => { Err(EvalError(format!($($t)*))) };
+    ($kind:ident, $($t:tt)*) => { Err(EvalError::new(EvalErrorKind::$kind, format!($($t)*))) };
 }
 
 
@@ -438,6 +441,13 @@ impl PathCache {
             .insert(ptr, val);
     }
 
+    /// Drop every entry cached under `doc_hash`.
+    fn forget(&mut self, doc_hash: u64) {
+        if self.docs.remove(&doc_hash).is_some() {
+            self.order.retain(|(hash, _)| *hash != doc_hash);
+        }
+    }
+
     /// Return the current number of cached entries; available in test builds only.
     #[cfg(test)]
     fn len(&self) -> usize {
@@ -447,17 +457,106 @@ impl PathCache {
 
 
 
-/// Stack-machine VM that owns both a compile cache and a path-resolution cache.
-/// Instances are long-lived (typically thread-local) so caches warm up across calls.
-pub struct VM {
-    /// Maps `(pass_config_hash, expression_string)` → compiled `Program`.
+/// LRU compile cache mapping `(pass_config_hash, expression_string)` to a
+/// compiled `Program`. Owned privately by a standalone `VM`, or shared by every
+/// `VM` in a `VmPool`.
+///
+/// Lookups take a read lock and record recency by storing a fresh generation
+/// stamp in the entry, so concurrent hits never wait on each other. Only
+/// inserts take the write lock; when full, one insert evicts the oldest eighth
+/// of the entries in a single pass, keeping eviction amortised O(1).
+pub struct ProgramCache {
     /// Avoids re-compiling the same expression string with the same pass config.
-    compile_cache: HashMap<(u64, String), Arc<Program>>,
+    programs: std::sync::RwLock<HashMap<(u64, String), CachedProgram>>,
+    /// Source of generation stamps; a larger stamp means more recently used.
+    clock: AtomicU64,
+    /// Maximum number of programs kept.
+    capacity: usize,
+}
+
+/// A compiled program plus the generation stamp of its last use.
+struct CachedProgram {
+    prog: Arc<Program>,
+    used: AtomicU64,
+}
+
+impl ProgramCache {
+    /// Create an empty cache holding at most `capacity` programs.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            programs: std::sync::RwLock::new(HashMap::with_capacity(capacity)),
+            clock: AtomicU64::new(0),
+            capacity,
+        }
+    }
+
+    /// Return the cached program for `key`, marking it most-recently-used.
+    fn get(&self, key: &(u64, String)) -> Option<Arc<Program>> {
+        let programs = self.programs.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let entry = programs.get(key)?;
+        entry.used.store(self.stamp(), Ordering::Relaxed);
+        Some(Arc::clone(&entry.prog))
+    }
+
+    /// Insert a compiled program, evicting least-recently-used entries when at
+    /// capacity. Keeps the existing entry if another `VM` raced us to it.
+    fn insert(&self, key: (u64, String), prog: Arc<Program>) -> Arc<Program> {
+        if self.capacity == 0 {
+            return prog;
+        }
+        let mut programs = self.programs.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(existing) = programs.get(&key) {
+            return Arc::clone(&existing.prog);
+        }
+        if programs.len() >= self.capacity {
+            Self::evict_oldest(&mut programs, (self.capacity / 8).max(1));
+        }
+        let used = AtomicU64::new(self.stamp());
+        programs.insert(key, CachedProgram { prog: Arc::clone(&prog), used });
+        prog
+    }
+
+    /// Drop the `count` entries with the oldest stamps.
+    fn evict_oldest(programs: &mut HashMap<(u64, String), CachedProgram>, count: usize) {
+        let mut stamps: Vec<u64> = programs
+            .values_mut()
+            .map(|entry| *entry.used.get_mut())
+            .collect();
+        let count = count.min(stamps.len());
+        let (_, &mut cutoff, _) = stamps.select_nth_unstable(count - 1);
+        let mut left = count;
+        programs.retain(|_, entry| {
+            if left > 0 && *entry.used.get_mut() <= cutoff {
+                left -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Drop every cached program.
+    pub(crate) fn clear(&self) {
+        self.programs.write().unwrap_or_else(std::sync::PoisonError::into_inner).clear();
+    }
+
+    /// Take the next generation stamp.
+    fn stamp(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Return the number of cached programs; available in test builds only.
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.programs.read().unwrap_or_else(std::sync::PoisonError::into_inner).len()
+    }
+}
 
-    /// LRU order for the compile cache; entries are moved to the back on access.
-    compile_lru: std::collections::VecDeque<(u64, String)>,
-    /// Maximum number of programs kept in the compile cache.
-    compile_cap: usize,
+/// Stack-machine VM that owns a compile cache and a path-resolution cache.
+/// Instances are long-lived (thread-local or pooled) so caches warm up across calls.
+pub struct VM {
+    /// Compiled programs; shared with the other `VM`s of a `VmPool`.
+    programs: Arc<ProgramCache>,
     /// Path-resolution cache for structural navigation results.
     path_cache: PathCache,
 
@@ -492,10 +591,13 @@ impl VM {
 
     /// Create a `VM` with explicit compile-cache and path-cache capacities.
     pub fn with_capacity(compile_cap: usize, path_cap: usize) -> Self {
+        Self::with_programs(Arc::new(ProgramCache::new(compile_cap)), path_cap)
+    }
+
+    /// Create a `VM` that compiles into `programs`, which other `VM`s may share.
+    pub fn with_programs(programs: Arc<ProgramCache>, path_cap: usize) -> Self {
         Self {
-            compile_cache: HashMap::with_capacity(compile_cap),
-            compile_lru: std::collections::VecDeque::with_capacity(compile_cap),
-            compile_cap,
+            programs,
             path_cache: PathCache::new(path_cap),
             root_chain_cache: HashMap::new(),
             doc_hash: 0,
@@ -529,7 +631,7 @@ impl VM {
         doc: &serde_json::Value,
     ) -> Result<serde_json::Value, EvalError> {
         let root = Val::from(doc);
-        self.doc_hash = self.compute_or_cache_root_hash(&root);
+        self.doc_hash = self.compute_or_cache_root_hash(&root)?;
         
         
         self.root_chain_cache.clear();
@@ -540,7 +642,7 @@ impl VM {
 
     /// Compute the structural hash of `root`, using the single-entry `root_hash_cache`
     /// to avoid rehashing when the same `Arc`-backed document is reused across calls.
-    fn compute_or_cache_root_hash(&mut self, root: &Val) -> u64 {
+    fn compute_or_cache_root_hash(&mut self, root: &Val) -> Result<u64, EvalError> {
         let ptr: Option<usize> = match root {
             Val::Obj(m) => Some(Arc::as_ptr(m) as *const () as usize),
             Val::Arr(a) => Some(Arc::as_ptr(a) as *const () as usize),
@@ -551,36 +653,35 @@ impl VM {
         if let Some(p) = ptr {
             if let Some((cp, h)) = self.root_hash_cache {
                 if cp == p {
-                    return h;
+                    return Ok(h);
                 }
             }
-            let h = hash_val_structure(root);
+            let h = hash_val_structure(root)?;
             self.root_hash_cache = Some((p, h));
-            h
+            Ok(h)
         } else {
             hash_val_structure(root)
         }
     }
 
-    /// Execute `program` against the given `Val` root and convert the result to
-    /// `serde_json::Value`. Clears the `root_chain_cache` before each run.
-    pub fn execute_val(
-        &mut self,
-        program: &Program,
-        root: Val,
-    ) -> Result<serde_json::Value, EvalError> {
-        Ok(self.execute_val_raw(program, root)?.into())
-    }
-
     /// Execute `program` against the given `Val` root and return the raw `Val` result
     /// without converting to `serde_json::Value`.
     pub fn execute_val_raw(&mut self, program: &Program, root: Val) -> Result<Val, EvalError> {
-        self.doc_hash = self.compute_or_cache_root_hash(&root);
+        self.doc_hash = self.compute_or_cache_root_hash(&root)?;
         self.root_chain_cache.clear();
         let env = self.make_env(root);
         self.exec(program, &env)
     }
 
+    /// Forget what the previous document left behind before a plan runs its fallback
+    /// nodes through `exec_in_env`. Those runs skip hashing the document, so their
+    /// path-cache entries live under hash 0 and must not reach the next document.
+    pub(crate) fn reset_document(&mut self) {
+        self.doc_hash = 0;
+        self.path_cache.forget(0);
+        self.root_chain_cache.clear();
+    }
+
     /// Execute `program` within an already-constructed `Env`, bypassing document-hash
     /// setup. Used by the runtime when the caller manages the environment directly.
     #[inline]
@@ -590,46 +691,20 @@ impl VM {
 
     /// Return a cached compiled program for `expr`, compiling and caching it if absent.
     /// The cache key includes the `PassConfig` hash so config changes produce distinct entries.
+    /// Compilation runs outside the cache lock so pooled `VM`s never wait on each other's compiles.
     pub fn get_or_compile(&mut self, expr: &str) -> Result<Arc<Program>, EvalError> {
         let key = (self.config.hash(), expr.to_string());
-        if let Some(p) = self.compile_cache.get(&key) {
-            let arc = Arc::clone(p);
-            self.touch_lru(&key);
-            return Ok(arc);
-        }
-        let prog = Compiler::compile_str_with_config(expr, self.config)?;
-        let arc = Arc::new(prog);
-        self.insert_compile(key, Arc::clone(&arc));
-        Ok(arc)
-    }
-
-    
-    /// Move `key` to the back of the LRU deque, marking it as most-recently-used.
-    fn touch_lru(&mut self, key: &(u64, String)) {
-        if let Some(pos) = self.compile_lru.iter().position(|k| k == key) {
-            let k = self.compile_lru.remove(pos).unwrap();
-            self.compile_lru.push_back(k);
+        if let Some(prog) = self.programs.get(&key) {
+            return Ok(prog);
         }
-    }
-
-    /// Insert a new compiled program into the cache, evicting least-recently-used
-    /// entries when the compile cache is at capacity.
-    fn insert_compile(&mut self, key: (u64, String), prog: Arc<Program>) {
-        while self.compile_cache.len() >= self.compile_cap && self.compile_cap > 0 {
-            if let Some(old) = self.compile_lru.pop_front() {
-                self.compile_cache.remove(&old);
-            } else {
-                break;
-            }
-        }
-        self.compile_lru.push_back(key.clone());
-        self.compile_cache.insert(key, prog);
+        let prog = Arc::new(Compiler::compile_str_with_config(expr, self.config)?);
+        Ok(self.programs.insert(key, prog))
     }
 
     /// Return `(compile_cache_len, path_cache_len)` for assertion in tests.
     #[cfg(test)]
     pub fn cache_stats(&self) -> (usize, usize) {
-        (self.compile_cache.len(), self.path_cache.len())
+        (self.programs.len(), self.path_cache.len())
     }
 
     /// Construct a fresh `Env` with `root` as both the root and current value.
@@ -637,18 +712,40 @@ impl VM {
         Env::new(root)
     }
 
-    /// Core interpreter loop: execute every opcode in `program` against `env`,
-    /// returning the single value left on the stack when the program completes.
+    /// Execute every opcode in `program` against `env`, returning the single
+    /// value left on the stack. Errors are tagged with the failing opcode name
+    /// unless an inner call already attached a more specific operation.
     pub fn exec(&mut self, program: &Program, env: &Env) -> Result<Val, EvalError> {
+        let _depth = limits::descend()?;
+        let mut cursor = 0usize;
+        self.exec_ops(program, env, &mut cursor).map_err(|err| match program.ops.get(cursor) {
+            Some(op) if err.operation().is_none() => err.or_operation(&opcode_name(op)),
+            _ => err,
+        })
+    }
+
+    /// Core interpreter loop behind `exec`; records the index of the opcode
+    /// being executed in `cursor` so failures can be attributed to it.
+    fn exec_ops(
+        &mut self,
+        program: &Program,
+        env: &Env,
+        cursor: &mut usize,
+    ) -> Result<Val, EvalError> {
         let mut stack: SmallVec<[Val; 16]> = SmallVec::new();
         let ops_slice: &[Opcode] = &program.ops;
         let mut skip_ahead: usize = 0;
+        let guarded = limits::is_active();
 
         for (op_idx, op) in ops_slice.iter().enumerate() {
             if skip_ahead > 0 {
                 skip_ahead -= 1;
                 continue;
             }
+            *cursor = op_idx;
+            if guarded {
+                limits::step(stack.last())?;
+            }
             match op {
                 
                 Opcode::PushNull => stack.push(Val::Null),
@@ -720,7 +817,7 @@ impl VM {
                     
                     if let Some(next) = ops_slice.get(op_idx + 1) {
                         if is_first_selector_op(next) {
-                            let hit = find_desc_first(&v, k.as_ref()).unwrap_or(Val::Null);
+                            let hit = find_desc_first(&v, k.as_ref())?.unwrap_or(Val::Null);
                             stack.push(hit);
                             skip_ahead = 1;
                             continue;
@@ -728,28 +825,21 @@ impl VM {
                     }
                     let mut found = Vec::new();
                     if from_root {
-                        let mut prefix = String::new();
                         let mut cached: Vec<(Arc<str>, Val)> = Vec::new();
-                        collect_desc_with_paths(
-                            &v,
-                            k.as_ref(),
-                            &mut prefix,
-                            &mut found,
-                            &mut cached,
-                        );
+                        collect_desc_with_paths(&v, k.as_ref(), &mut found, &mut cached)?;
                         let doc_hash = self.doc_hash;
                         for (ptr, val) in cached {
                             self.path_cache.insert(doc_hash, ptr, val);
                         }
                     } else {
-                        collect_desc(&v, k.as_ref(), &mut found);
+                        collect_desc(&v, k.as_ref(), &mut found)?;
                     }
                     stack.push(Val::arr(found));
                 }
                 Opcode::DescendAll => {
                     let v = pop!(stack);
                     let mut found = Vec::new();
-                    collect_all(&v, &mut found);
+                    collect_all(&v, &mut found)?;
                     stack.push(Val::arr(found));
                 }
                 Opcode::InlineFilter(pred) => {
@@ -781,6 +871,7 @@ impl VM {
                             Val::Arr(a) if a.len() == 1 => a[0].clone(),
                             Val::Arr(a) => {
                                 return err!(
+                                    TypeMismatch,
                                     "quantifier !: expected exactly one element, got {}",
                                     a.len()
                                 )
@@ -868,7 +959,7 @@ impl VM {
                     let l = pop!(stack);
                     let b = r.as_f64().unwrap_or(0.0);
                     if b == 0.0 {
-                        return err!("division by zero");
+                        return err!(DivisionByZero, "division by zero");
                     }
                     stack.push(Val::Float(l.as_f64().unwrap_or(0.0) / b));
                 }
@@ -929,7 +1020,7 @@ impl VM {
                     stack.push(match v {
                         Val::Int(n) => Val::Int(-n),
                         Val::Float(f) => Val::Float(-f),
-                        _ => return err!("unary minus requires a number"),
+                        _ => return err!(TypeMismatch, "unary minus requires a number"),
                     });
                 }
                 Opcode::CastOp(ty) => {
@@ -979,7 +1070,9 @@ impl VM {
                 
                 Opcode::CallMethod(call) => {
                     let recv = pop!(stack);
-                    let result = self.exec_call(recv, call, env)?;
+                    let result = self
+                        .exec_call(recv, call, env)
+                        .map_err(|e| e.or_call(&call.name, call.span))?;
                     stack.push(result);
                 }
                 Opcode::CallOptMethod(call) => {
@@ -987,7 +1080,10 @@ impl VM {
                     if recv.is_null() {
                         stack.push(Val::Null);
                     } else {
-                        stack.push(self.exec_call(recv, call, env)?);
+                        stack.push(
+                            self.exec_call(recv, call, env)
+                                .map_err(|e| e.or_call(&call.name, call.span))?,
+                        );
                     }
                 }
 
@@ -1256,28 +1352,46 @@ impl VM {
                     stack.push(result);
                 }
                 Opcode::DeleteMarkErr => {
-                    return err!("DELETE: only valid inside a patch-field value");
+                    return err!(DeleteOutsidePatch, "DELETE: only valid inside a patch-field value");
+                }
+                Opcode::NestingLimitErr(max) => {
+                    return Err(limits::nesting_exceeded("expression", *max));
                 }
             }
         }
 
+        if guarded {
+            if let Some(len) = stack.last().and_then(Val::array_len) {
+                limits::check_len(len)?;
+            }
+        }
         stack
             .pop()
-            .ok_or_else(|| EvalError("program produced no value".into()))
+            .ok_or_else(|| EvalError::internal("program produced no value"))
     }
 
     /// Dispatch a `CallMethod` opcode: applies fast numeric/typed specialisations first,
     /// then lambda-aware methods, then the general `call_builtin_method_compiled` fallback.
     fn exec_call(&mut self, recv: Val, call: &CompiledCall, env: &Env) -> Result<Val, EvalError> {
-        
+        // `ObjVec` is a pipeline-side columnar layout; VM builtins work on rows.
+        let recv = match recv {
+            Val::ObjVec(_) => recv.into_vals().map(Val::arr).unwrap_or_else(|v| v),
+            other => other,
+        };
+
         if call.method == BuiltinMethod::Unknown {
             
             
-            match call.name.as_ref() {
-                "coalesce" | "chain" | "join" | "zip" | "zip_longest" | "product" | "range" => {
-                    return crate::data::runtime::eval_global_compiled(self, call, env);
-                }
-                _ => {}
+            if crate::data::runtime::RESERVED_GLOBALS.contains(&call.name.as_ref()) {
+                return crate::data::runtime::eval_global_compiled(self, call, env);
+            }
+            if let Some(host) = crate::builtins::host::lookup(call.name.as_ref()) {
+                let args = call
+                    .sub_progs
+                    .iter()
+                    .map(|prog| self.exec(prog, env))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return host.call(call.name.as_ref(), &recv, args);
             }
             return call_builtin_method_compiled(self, recv, call, env);
         }
@@ -1294,7 +1408,7 @@ impl VM {
             let proj = call
                 .sub_progs
                 .first()
-                .ok_or_else(|| EvalError(format!("{}: requires projection", call.name)))?;
+                .ok_or_else(|| EvalError::invalid_argument(format!("{}: requires projection", call.name)))?;
             let lam_param: Option<&str> = match call.orig_args.first() {
                 Some(Arg::Pos(Expr::Lambda { params, .. })) if !params.is_empty() => {
                     Some(params[0].as_str())
@@ -1634,10 +1748,10 @@ impl VM {
 
         match call.method {
             BuiltinMethod::Filter => {
-                let pred = sub.ok_or_else(|| EvalError("filter: requires predicate".into()))?;
+                let pred = sub.ok_or_else(|| EvalError::invalid_argument("filter: requires predicate"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("filter: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("filter: expected array"))?;
                 let out =
                     crate::builtins::filter_apply_bounded(items, call.demand_max_keep, |item| {
                         self.exec_lam_body_scratch(pred, item, lam_param, &mut scratch)
@@ -1645,10 +1759,10 @@ impl VM {
                 Ok(Val::arr(out))
             }
             BuiltinMethod::Map => {
-                let mapper = sub.ok_or_else(|| EvalError("map: requires mapper".into()))?;
+                let mapper = sub.ok_or_else(|| EvalError::invalid_argument("map: requires mapper"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("map: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("map: expected array"))?;
                 let out =
                     crate::builtins::map_apply_bounded(items, call.demand_max_keep, |item| {
                         self.exec_lam_body_scratch(mapper, item, lam_param, &mut scratch)
@@ -1656,10 +1770,10 @@ impl VM {
                 Ok(Val::arr(out))
             }
             BuiltinMethod::FlatMap => {
-                let mapper = sub.ok_or_else(|| EvalError("flatMap: requires mapper".into()))?;
+                let mapper = sub.ok_or_else(|| EvalError::invalid_argument("flatMap: requires mapper"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("flatMap: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("flatMap: expected array"))?;
                 let out = crate::builtins::flat_map_apply(items, |item| {
                     self.exec_lam_body_scratch(mapper, item, lam_param, &mut scratch)
                 })?;
@@ -1678,7 +1792,7 @@ impl VM {
                     let cmp = call
                         .sub_progs
                         .first()
-                        .ok_or_else(|| EvalError("sort: requires comparator".into()))?
+                        .ok_or_else(|| EvalError::invalid_argument("sort: requires comparator"))?
                         .clone();
                     return crate::builtins::sort_comparator_apply(recv, |left, right| {
                         self.exec_pair_lam_body(&cmp, left, right, &call.orig_args[0], env)
@@ -1691,7 +1805,7 @@ impl VM {
                     let arg = call
                         .orig_args
                         .get(idx)
-                        .ok_or_else(|| EvalError("sort: missing key".into()))?;
+                        .ok_or_else(|| EvalError::invalid_argument("sort: missing key"))?;
                     let lam_param = match arg {
                         Arg::Pos(Expr::Lambda { params, .. })
                         | Arg::Named(_, Expr::Lambda { params, .. })
@@ -1705,8 +1819,8 @@ impl VM {
                 })
             }
             BuiltinMethod::Any => {
-                if let Val::Arr(a) = &recv {
-                    let pred = sub.ok_or_else(|| EvalError("any: requires predicate".into()))?;
+                if let Some(a) = recv.as_vals() {
+                    let pred = sub.ok_or_else(|| EvalError::invalid_argument("any: requires predicate"))?;
                     for item in a.iter() {
                         if crate::builtins::any_one(item, |v| {
                             self.exec_lam_body_scratch(pred, v, lam_param, &mut scratch)
@@ -1720,11 +1834,11 @@ impl VM {
                 }
             }
             BuiltinMethod::All => {
-                if let Val::Arr(a) = &recv {
+                if let Some(a) = recv.as_vals() {
                     if a.is_empty() {
                         return Ok(Val::Bool(true));
                     }
-                    let pred = sub.ok_or_else(|| EvalError("all: requires predicate".into()))?;
+                    let pred = sub.ok_or_else(|| EvalError::invalid_argument("all: requires predicate"))?;
                     for item in a.iter() {
                         if !crate::builtins::all_one(item, |v| {
                             self.exec_lam_body_scratch(pred, v, lam_param, &mut scratch)
@@ -1738,7 +1852,7 @@ impl VM {
                 }
             }
             BuiltinMethod::Count if !call.sub_progs.is_empty() => {
-                if let Val::Arr(a) = &recv {
+                if let Some(a) = recv.as_vals() {
                     let pred = &call.sub_progs[0];
                     let mut n: i64 = 0;
                     for item in a.iter() {
@@ -1754,10 +1868,10 @@ impl VM {
                 }
             }
             BuiltinMethod::GroupBy => {
-                let key_prog = sub.ok_or_else(|| EvalError("groupBy: requires key".into()))?;
+                let key_prog = sub.ok_or_else(|| EvalError::invalid_argument("groupBy: requires key"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("groupBy: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("groupBy: expected array"))?;
                 
                 
                 if let Some(param) = lam_param {
@@ -1775,7 +1889,7 @@ impl VM {
                                 let idx = match &item {
                                     Val::Int(n) => n.rem_euclid(k_lit) as usize,
                                     Val::Float(x) => (x.trunc() as i64).rem_euclid(k_lit) as usize,
-                                    _ => return err!("group_by(x % K): non-numeric item"),
+                                    _ => return err!(TypeMismatch, "group_by(x % K): non-numeric item"),
                                 };
                                 if !seen[idx] {
                                     seen[idx] = true;
@@ -1801,40 +1915,40 @@ impl VM {
                 Ok(Val::obj(map))
             }
             BuiltinMethod::CountBy => {
-                let key_prog = sub.ok_or_else(|| EvalError("countBy: requires key".into()))?;
+                let key_prog = sub.ok_or_else(|| EvalError::invalid_argument("countBy: requires key"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("countBy: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("countBy: expected array"))?;
                 let map = crate::builtins::count_by_apply(items, |item| {
                     self.exec_lam_body_scratch(key_prog, item, lam_param, &mut scratch)
                 })?;
                 Ok(Val::obj(map))
             }
             BuiltinMethod::IndexBy => {
-                let key_prog = sub.ok_or_else(|| EvalError("indexBy: requires key".into()))?;
+                let key_prog = sub.ok_or_else(|| EvalError::invalid_argument("indexBy: requires key"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("indexBy: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("indexBy: expected array"))?;
                 let map = crate::builtins::index_by_apply(items, |item| {
                     self.exec_lam_body_scratch(key_prog, item, lam_param, &mut scratch)
                 })?;
                 Ok(Val::obj(map))
             }
             BuiltinMethod::TakeWhile => {
-                let pred = sub.ok_or_else(|| EvalError("takeWhile: requires predicate".into()))?;
+                let pred = sub.ok_or_else(|| EvalError::invalid_argument("takeWhile: requires predicate"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("takeWhile: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("takeWhile: expected array"))?;
                 let out = crate::builtins::take_while_apply(items, |item| {
                     self.exec_lam_body_scratch(pred, item, lam_param, &mut scratch)
                 })?;
                 Ok(Val::arr(out))
             }
             BuiltinMethod::DropWhile => {
-                let pred = sub.ok_or_else(|| EvalError("dropWhile: requires predicate".into()))?;
+                let pred = sub.ok_or_else(|| EvalError::invalid_argument("dropWhile: requires predicate"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("dropWhile: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("dropWhile: expected array"))?;
                 let out = crate::builtins::drop_while_apply(items, |item| {
                     self.exec_lam_body_scratch(pred, item, lam_param, &mut scratch)
                 })?;
@@ -1844,7 +1958,7 @@ impl VM {
                 
                 
                 let lam_body =
-                    sub.ok_or_else(|| EvalError("accumulate: requires lambda".into()))?;
+                    sub.ok_or_else(|| EvalError::invalid_argument("accumulate: requires lambda"))?;
                 let (p1, p2) = match call.orig_args.first() {
                     Some(Arg::Pos(Expr::Lambda { params, .. })) if params.len() >= 2 => {
                         (params[0].as_str(), params[1].as_str())
@@ -1914,7 +2028,7 @@ impl VM {
                 }
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("accumulate: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("accumulate: expected array"))?;
                 let mut out = Vec::with_capacity(items.len());
                 if let Some(bop) = specialised_binop {
                     
@@ -2003,10 +2117,10 @@ impl VM {
                 Ok(Val::arr(out))
             }
             BuiltinMethod::Partition => {
-                let pred = sub.ok_or_else(|| EvalError("partition: requires predicate".into()))?;
+                let pred = sub.ok_or_else(|| EvalError::invalid_argument("partition: requires predicate"))?;
                 let items = recv
                     .into_vec()
-                    .ok_or_else(|| EvalError("partition: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("partition: expected array"))?;
                 let (yes, no) = crate::builtins::partition_apply(items, |item| {
                     self.exec_lam_body_scratch(pred, item, lam_param, &mut scratch)
                 })?;
@@ -2016,10 +2130,10 @@ impl VM {
                 Ok(Val::obj(m))
             }
             BuiltinMethod::TransformKeys => {
-                let lam = sub.ok_or_else(|| EvalError("transformKeys: requires lambda".into()))?;
+                let lam = sub.ok_or_else(|| EvalError::invalid_argument("transformKeys: requires lambda"))?;
                 let map = recv
                     .into_map()
-                    .ok_or_else(|| EvalError("transformKeys: expected object".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("transformKeys: expected object"))?;
                 let out = crate::builtins::transform_keys_apply(map, |k| {
                     self.exec_lam_body_scratch(lam, &Val::Str(k.clone()), lam_param, &mut scratch)
                 })?;
@@ -2027,12 +2141,12 @@ impl VM {
             }
             BuiltinMethod::TransformValues => {
                 let lam =
-                    sub.ok_or_else(|| EvalError("transformValues: requires lambda".into()))?;
+                    sub.ok_or_else(|| EvalError::invalid_argument("transformValues: requires lambda"))?;
                 
                 
                 let mut map = recv
                     .into_map()
-                    .ok_or_else(|| EvalError("transformValues: expected object".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("transformValues: expected object"))?;
                 
                 
                 let pat = match lam.ops.as_ref() {
@@ -2081,10 +2195,10 @@ impl VM {
                 Ok(Val::obj(map))
             }
             BuiltinMethod::FilterKeys => {
-                let lam = sub.ok_or_else(|| EvalError("filterKeys: requires predicate".into()))?;
+                let lam = sub.ok_or_else(|| EvalError::invalid_argument("filterKeys: requires predicate"))?;
                 let map = recv
                     .into_map()
-                    .ok_or_else(|| EvalError("filterKeys: expected object".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("filterKeys: expected object"))?;
                 let out = crate::builtins::filter_object_apply(map, |k, _v| {
                     crate::builtins::filter_one(&Val::Str(k.clone()), |item| {
                         self.exec_lam_body_scratch(lam, item, lam_param, &mut scratch)
@@ -2094,10 +2208,10 @@ impl VM {
             }
             BuiltinMethod::FilterValues => {
                 let lam =
-                    sub.ok_or_else(|| EvalError("filterValues: requires predicate".into()))?;
+                    sub.ok_or_else(|| EvalError::invalid_argument("filterValues: requires predicate"))?;
                 let map = recv
                     .into_map()
-                    .ok_or_else(|| EvalError("filterValues: expected object".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("filterValues: expected object"))?;
                 let out = crate::builtins::filter_object_apply(map, |_k, v| {
                     crate::builtins::filter_one(v, |item| {
                         self.exec_lam_body_scratch(lam, item, lam_param, &mut scratch)
@@ -2107,7 +2221,7 @@ impl VM {
             }
             BuiltinMethod::Pivot => call_builtin_method_compiled(self, recv, call, env),
             BuiltinMethod::Update => {
-                let lam = sub.ok_or_else(|| EvalError("update: requires lambda".into()))?;
+                let lam = sub.ok_or_else(|| EvalError::invalid_argument("update: requires lambda"))?;
                 self.exec_lam_body(lam, &recv, lam_param, env)
             }
             _ => call_builtin_method_compiled(self, recv, call, env),
@@ -2312,7 +2426,7 @@ impl VM {
                             IdxKey::Dynamic(prog) => {
                                 let r = self.exec(prog, env)?;
                                 r.as_i64().ok_or_else(|| {
-                                    EvalError(format!(
+                                    EvalError::type_mismatch(format!(
                                         "patch dyn-index: expected integer, got {}",
                                         r.type_name()
                                     ))
@@ -2472,7 +2586,7 @@ impl VM {
             CompiledPathStep::DynIndex(prog) => {
                 let idx_val = self.exec(prog, env)?;
                 let idx = idx_val.as_i64().ok_or_else(|| {
-                    EvalError(format!(
+                    EvalError::type_mismatch(format!(
                         "patch dyn-index: expected integer, got {}",
                         idx_val.type_name()
                     ))
@@ -2502,7 +2616,7 @@ impl VM {
             CompiledPathStep::Wildcard => {
                 let mut arr = v
                     .into_vec()
-                    .ok_or_else(|| EvalError("patch [*]: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("patch [*]: expected array"))?;
                 let mut write_idx = 0usize;
                 for read_idx in 0..arr.len() {
                     let item = std::mem::replace(&mut arr[read_idx], Val::Null);
@@ -2520,7 +2634,7 @@ impl VM {
             CompiledPathStep::WildcardFilter(pred) => {
                 let mut arr = v
                     .into_vec()
-                    .ok_or_else(|| EvalError("patch [* if]: expected array".into()))?;
+                    .ok_or_else(|| EvalError::type_mismatch("patch [* if]: expected array"))?;
                 let mut env_mut = env.clone();
                 let mut write_idx = 0usize;
                 for read_idx in 0..arr.len() {
@@ -2813,7 +2927,7 @@ impl VM {
                     .map(|(k, v)| obj2("key", Val::Str(k), "value", v))
                     .collect())
             }
-            other => Ok(vec![other]),
+            other => Ok(other.into_vals().unwrap_or_else(|scalar| vec![scalar])),
         }
     }
 }
@@ -2860,7 +2974,15 @@ fn exec_slice(v: Val, from: Option<i64>, to: Option<i64>) -> Val {
             let e = e.min(items.len());
             Val::float_vec(items[s..e].to_vec())
         }
-        _ => Val::Null,
+        other => match other.into_vals() {
+            Ok(items) => {
+                let len = items.len() as i64;
+                let s = resolve_idx(from.unwrap_or(0), len).min(items.len());
+                let e = resolve_idx(to.unwrap_or(len), len).min(items.len());
+                Val::arr(items[s..e].to_vec())
+            }
+            Err(_) => Val::Null,
+        },
     }
 }
 
@@ -2869,8 +2991,6 @@ fn resolve_idx(i: i64, len: i64) -> usize {
     (if i < 0 { (len + i).max(0) } else { i }) as usize
 }
 
-/// Recursively collect every value stored under `name` anywhere in the subtree of `v`,
-/// without recording path information (used for non-root `Descendant` traversal).
 /// Phase F: parent-level disposition for a trie child after pre-batch
 /// guard resolution. See `VM::trie_resolve_child`.
 enum ChildEffect<'a> {
@@ -2882,111 +3002,120 @@ enum ChildEffect<'a> {
     Apply(&'a TrieNode),
 }
 
-fn collect_desc(v: &Val, name: &str, out: &mut Vec<Val>) {
+/// Key or index a child sits under in its container, for descent paths.
+enum DescKey {
+    Field(Arc<str>),
+    Index(usize),
+}
+
+/// Return the `i`th child of `v` with its key, or `None` past the last child
+/// and for anything that is not an object, array or columnar row set.
+fn desc_child(v: &Val, i: usize) -> Option<(DescKey, Val)> {
     match v {
-        Val::Obj(m) => {
-            if let Some(v) = m.get(name) {
-                out.push(v.clone());
-            }
-            for v in m.values() {
-                collect_desc(v, name, out);
+        Val::Obj(m) => m
+            .get_index(i)
+            .map(|(k, child)| (DescKey::Field(Arc::clone(k)), child.clone())),
+        Val::Arr(a) => a.get(i).map(|child| (DescKey::Index(i), child.clone())),
+        Val::ObjVec(d) => (i < d.nrows()).then(|| (DescKey::Index(i), d.row_val(i))),
+        _ => None,
+    }
+}
+
+/// Visit `root` and every node below it in DFS pre-order, keeping the walk on an
+/// explicit stack of containers so deep documents cannot overflow the native
+/// stack. With `track_paths`, `visit` also gets the node's JSON pointer from
+/// `root`; it returns `true` to end the walk early.
+fn walk_desc(
+    root: &Val,
+    track_paths: bool,
+    mut visit: impl FnMut(&Val, &str) -> Result<bool, EvalError>,
+) -> Result<(), EvalError> {
+    let mut path = String::new();
+    limits::poll()?;
+    if visit(root, &path)? {
+        return Ok(());
+    }
+    // each frame holds a container, its next child, and the path length to restore
+    let mut frames: Vec<(Val, usize, usize)> = vec![(root.clone(), 0, 0)];
+    while let Some((container, next, _)) = frames.last_mut() {
+        let Some((key, child)) = desc_child(container, *next) else {
+            if let Some((_, _, restore)) = frames.pop() {
+                path.truncate(restore);
+            }
+            continue;
+        };
+        *next += 1;
+        let restore = path.len();
+        if track_paths {
+            path.push('/');
+            match key {
+                DescKey::Field(k) => path.push_str(&k),
+                DescKey::Index(i) => path.push_str(&i.to_string()),
             }
         }
-        Val::Arr(a) => {
-            for item in a.as_ref() {
-                collect_desc(item, name, out);
-            }
+        limits::poll()?;
+        if visit(&child, &path)? {
+            return Ok(());
         }
-        _ => {}
+        frames.push((child, 0, restore));
     }
+    Ok(())
 }
 
+/// Collect every value stored under `name` anywhere in the subtree of `v`,
+/// without recording path information (used for non-root `Descendant` traversal).
+fn collect_desc(v: &Val, name: &str, out: &mut Vec<Val>) -> Result<(), EvalError> {
+    walk_desc(v, false, |node, _| {
+        if let Val::Obj(m) = node {
+            if let Some(found) = m.get(name) {
+                out.push(found.clone());
+            }
+        }
+        Ok(false)
+    })
+}
 
 /// DFS pre-order search returning the first occurrence of `name` in the subtree of `v`.
 /// Used to optimise `Descendant` when followed by a `.first()` selector.
-fn find_desc_first(v: &Val, name: &str) -> Option<Val> {
-    match v {
-        Val::Obj(m) => {
-            if let Some(v) = m.get(name) {
-                return Some(v.clone());
-            }
-            for child in m.values() {
-                if let Some(hit) = find_desc_first(child, name) {
-                    return Some(hit);
-                }
-            }
-            None
-        }
-        Val::Arr(a) => {
-            for item in a.as_ref() {
-                if let Some(hit) = find_desc_first(item, name) {
-                    return Some(hit);
-                }
-            }
-            None
-        }
-        _ => None,
-    }
+fn find_desc_first(v: &Val, name: &str) -> Result<Option<Val>, EvalError> {
+    let mut hit = None;
+    walk_desc(v, false, |node, _| {
+        if let Val::Obj(m) = node {
+            hit = m.get(name).cloned();
+        }
+        Ok(hit.is_some())
+    })?;
+    Ok(hit)
 }
 
 /// Collect every node in the subtree of `v` (DFS pre-order) into `out`.
 /// Used by the `DescendAll` opcode to implement `$..**`.
-fn collect_all(v: &Val, out: &mut Vec<Val>) {
-    match v {
-        Val::Obj(m) => {
-            out.push(v.clone());
-            for child in m.values() {
-                collect_all(child, out);
-            }
+fn collect_all(v: &Val, out: &mut Vec<Val>) -> Result<(), EvalError> {
+    walk_desc(v, false, |node, _| {
+        if !matches!(node, Val::Arr(_) | Val::ObjVec(_)) {
+            out.push(node.clone());
         }
-        Val::Arr(a) => {
-            for item in a.as_ref() {
-                collect_all(item, out);
-            }
-        }
-        other => out.push(other.clone()),
-    }
+        Ok(false)
+    })
 }
 
-
 /// Like `collect_desc` but also records `(JSON-pointer, value)` pairs in `cached`
 /// for bulk insertion into the `PathCache` when traversing from the root document.
 fn collect_desc_with_paths(
     v: &Val,
     name: &str,
-    prefix: &mut String,
     out: &mut Vec<Val>,
     cached: &mut Vec<(Arc<str>, Val)>,
-) {
-    match v {
-        Val::Obj(m) => {
+) -> Result<(), EvalError> {
+    walk_desc(v, true, |node, path| {
+        if let Val::Obj(m) = node {
             if let Some(found) = m.get(name) {
-                let mut path = prefix.clone();
-                path.push('/');
-                path.push_str(name);
                 out.push(found.clone());
-                cached.push((Arc::from(path.as_str()), found.clone()));
-            }
-            for (k, child) in m.iter() {
-                let prev = prefix.len();
-                prefix.push('/');
-                prefix.push_str(k.as_ref());
-                collect_desc_with_paths(child, name, prefix, out, cached);
-                prefix.truncate(prev);
-            }
-        }
-        Val::Arr(a) => {
-            for (i, item) in a.iter().enumerate() {
-                let prev = prefix.len();
-                prefix.push('/');
-                let idx = i.to_string();
-                prefix.push_str(&idx);
-                collect_desc_with_paths(item, name, prefix, out, cached);
-                prefix.truncate(prev);
+                cached.push((Arc::from(format!("{}/{}", path, name)), found.clone()));
             }
         }
-        _ => {}
-    }
+        Ok(false)
+    })
 }
 
 /// Classification of a dict-comp key expression relative to the loop variable,
@@ -3077,10 +3206,10 @@ fn exec_cast(v: &Val, ty: crate::parse::ast::CastType) -> Result<Val, EvalError>
             Val::Str(s) => s
                 .parse::<f64>()
                 .map(Val::Float)
-                .map_err(|e| EvalError(format!("as float: {}", e))),
+                .map_err(|e| EvalError::type_mismatch(format!("as float: {}", e))),
             Val::Bool(b) => Ok(Val::Float(if *b { 1.0 } else { 0.0 })),
             Val::Null => Ok(Val::Float(0.0)),
-            _ => err!("as float: cannot convert"),
+            _ => err!(TypeMismatch, "as float: cannot convert"),
         },
         CastType::Int => match v {
             Val::Int(_) => Ok(v.clone()),
@@ -3089,10 +3218,10 @@ fn exec_cast(v: &Val, ty: crate::parse::ast::CastType) -> Result<Val, EvalError>
                 .parse::<i64>()
                 .map(Val::Int)
                 .or_else(|_| s.parse::<f64>().map(|f| Val::Int(f as i64)))
-                .map_err(|e| EvalError(format!("as int: {}", e))),
+                .map_err(|e| EvalError::type_mismatch(format!("as int: {}", e))),
             Val::Bool(b) => Ok(Val::Int(if *b { 1 } else { 0 })),
             Val::Null => Ok(Val::Int(0)),
-            _ => err!("as int: cannot convert"),
+            _ => err!(TypeMismatch, "as int: cannot convert"),
         },
         CastType::Array => match v {
             Val::Arr(_) => Ok(v.clone()),
@@ -3101,7 +3230,7 @@ fn exec_cast(v: &Val, ty: crate::parse::ast::CastType) -> Result<Val, EvalError>
         },
         CastType::Object => match v {
             Val::Obj(_) => Ok(v.clone()),
-            _ => err!("as object: cannot convert non-object"),
+            _ => err!(TypeMismatch, "as object: cannot convert non-object"),
         },
         CastType::Null => Ok(Val::Null),
     }
@@ -3149,17 +3278,29 @@ fn apply_fmt_spec(val: &Val, spec: &str) -> String {
 /// Compute a structural hash of a `Val` that distinguishes both shape AND leaf values.
 /// Two documents with the same shape but different primitive values must produce different
 /// hashes so the path-resolution cache does not return stale results across distinct docs.
-fn hash_val_structure(v: &Val) -> u64 {
+/// Walks the whole tree with an explicit stack and fails when it nests deeper than the
+/// thread's nesting limit.
+fn hash_val_structure(v: &Val) -> Result<u64, EvalError> {
+    let max = limits::max_nesting();
     let mut h = DefaultHasher::new();
-    hash_structure_into(v, &mut h, 0);
-    h.finish()
+    let mut stack = vec![(v, 0usize)];
+    while let Some((v, depth)) = stack.pop() {
+        hash_structure_into(v, depth, max, &mut h, &mut stack)?;
+    }
+    Ok(h.finish())
 }
 
-/// Recursive helper for `hash_val_structure`. Hashing is bounded to depth 8 to
-/// avoid O(n) cost on deeply nested documents while still capturing leaf values.
-fn hash_structure_into(v: &Val, h: &mut DefaultHasher, depth: usize) {
-    if depth > 8 {
-        return;
+/// Hash one node for `hash_val_structure`, pushing its children onto `stack` in
+/// reverse so they are hashed in document order.
+fn hash_structure_into<'a>(
+    v: &'a Val,
+    depth: usize,
+    max: usize,
+    h: &mut DefaultHasher,
+    stack: &mut Vec<(&'a Val, usize)>,
+) -> Result<(), EvalError> {
+    if depth >= max && matches!(v, Val::Arr(_) | Val::Obj(_) | Val::ObjSmall(_)) {
+        return Err(limits::nesting_exceeded("document", max));
     }
     match v {
         Val::Null => 0u8.hash(h),
@@ -3186,9 +3327,7 @@ fn hash_structure_into(v: &Val, h: &mut DefaultHasher, depth: usize) {
         Val::Arr(a) => {
             5u8.hash(h);
             a.len().hash(h);
-            for item in a.iter() {
-                hash_structure_into(item, h, depth + 1);
-            }
+            stack.extend(a.iter().rev().map(|item| (item, depth + 1)));
         }
         Val::IntVec(a) => {
             5u8.hash(h);
@@ -3232,18 +3371,19 @@ fn hash_structure_into(v: &Val, h: &mut DefaultHasher, depth: usize) {
         Val::Obj(m) => {
             6u8.hash(h);
             m.len().hash(h);
-            for (k, v) in m.iter() {
+            for k in m.keys() {
                 k.hash(h);
-                hash_structure_into(v, h, depth + 1);
             }
+            stack.extend(m.values().rev().map(|v| (v, depth + 1)));
         }
         Val::ObjSmall(p) => {
             6u8.hash(h);
             p.len().hash(h);
-            for (k, v) in p.iter() {
+            for (k, _) in p.iter() {
                 k.hash(h);
-                hash_structure_into(v, h, depth + 1);
             }
+            stack.extend(p.iter().rev().map(|(_, v)| (v, depth + 1)));
         }
     }
+    Ok(())
 }
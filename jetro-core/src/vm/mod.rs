@@ -1,14 +1,18 @@
 //! Bytecode compiler and stack-machine VM for Jetro expressions.
 //!
-//! Split into two submodules:
+//! Split into three submodules:
 //! - [`opcode`] — pure-data definitions (`Opcode`, `Program`, `Compiled*`,
 //!   `FieldChainData`, comprehension specs, patch ops) plus the small
 //!   helpers that operate only on those structures.
 //! - [`exec`] — the `VM` struct, its caches, the execution loop, and all
 //!   runtime helpers that consume opcodes.
+//! - [`pool`] — `VmPool`, the idle `VM`s a `JetroEngine` hands out to
+//!   concurrent callers, all sharing one compile cache.
 
 pub(crate) mod exec;
 pub(crate) mod opcode;
+pub(crate) mod pool;
 
 pub(crate) use exec::*;
 pub(crate) use opcode::*;
+pub(crate) use pool::VmPool;
@@ -15,6 +15,7 @@ use std::{
 };
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 use crate::parse::ast::*;
 pub use crate::builtins::BuiltinMethod;
@@ -24,7 +25,7 @@ pub use crate::builtins::BuiltinMethod;
 /// pre-compiled into `sub_progs` exactly once at compile time so the inner
 /// loop never re-compiles them. `demand_max_keep` is set by the demand-pass
 /// peephole when a `take(n)` follows this call.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompiledCall {
     /// Resolved built-in variant; `Unknown` when the name is not a built-in.
     pub method: BuiltinMethod,
@@ -36,13 +37,15 @@ pub struct CompiledCall {
     pub orig_args: Arc<[Arg]>,
     /// When set, `filter`/`map` may stop early after collecting this many results.
     pub demand_max_keep: Option<usize>,
+    /// Source range of the call, attached to errors it raises.
+    pub span: Span,
 }
 
 
 /// A field entry inside a `MakeObj` opcode. `Short` is the fast path for
 /// `{name}` shorthand — reads from `current` using an inline-cache hint;
 /// `KvPath` is the structural fast path for `{key: $.a.b}` chains.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompiledObjEntry {
     /// `{name}` shorthand: copies the field from `current`, using `ic` as an
     /// inline-cache slot to remember the last-seen map index.
@@ -50,6 +53,7 @@ pub enum CompiledObjEntry {
         /// Field name to read from the current object (or variable scope).
         name: Arc<str>,
         /// Inline-cache slot storing the last successful map index + 1 (0 = cold).
+        #[serde(skip)]
         ic: Arc<AtomicU64>,
     },
     /// General `{key: expr}` entry with an optional guard condition.
@@ -65,6 +69,10 @@ pub enum CompiledObjEntry {
     },
     /// Structural fast-path for `{key: @.a.b[0]}` — avoids spawning a sub-`exec` call
     /// when the value is a pure chain of field/index steps rooted at `@`.
+    #[serde(
+        serialize_with = "cold_ics::serialize_kv_path",
+        deserialize_with = "cold_ics::deserialize_kv_path"
+    )]
     KvPath {
         /// Output key name.
         key: Arc<str>,
@@ -91,7 +99,7 @@ pub enum CompiledObjEntry {
 
 /// A single traversal step in a `KvPath` entry, representing either a named
 /// field access or an integer index into an array.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum KvStep {
     /// Access an object field by name.
     Field(Arc<str>),
@@ -102,7 +110,7 @@ pub enum KvStep {
 
 /// A single segment of a compiled format-string (`f"..."`).
 /// Segments alternate between literal text and interpolated expressions.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompiledFSPart {
     /// A verbatim string fragment that is appended directly to the output buffer.
     Lit(Arc<str>),
@@ -118,7 +126,7 @@ pub enum CompiledFSPart {
 
 /// Specifies the destructuring pattern for an object bind step in a pipeline
 /// (`... | {a, b, ...rest} -> ...`).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BindObjSpec {
     /// Named fields that are extracted as individual variables.
     pub fields: Arc<[Arc<str>]>,
@@ -129,7 +137,7 @@ pub struct BindObjSpec {
 
 /// A single compiled step inside a `PipelineRun` opcode. Each step either
 /// transforms the current pipeline value or captures it into named variables.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompiledPipeStep {
     /// Pass the current value through an expression, updating the pipeline value.
     Forward(Arc<Program>),
@@ -144,7 +152,7 @@ pub enum CompiledPipeStep {
 
 /// Compiled specification for a list, set, or generator comprehension
 /// (`[expr for vars in iter if cond]`).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompSpec {
     /// Expression evaluated for each item to produce the output element.
     pub expr: Arc<Program>,
@@ -158,7 +166,7 @@ pub struct CompSpec {
 
 /// Compiled specification for a dictionary comprehension
 /// (`{key: val for vars in iter if cond}`).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DictCompSpec {
     /// Program evaluated to produce each output key; coerced to a string.
     pub key: Arc<Program>,
@@ -173,9 +181,20 @@ pub struct DictCompSpec {
 }
 
 
+/// Return the bare variant name of `op` (e.g. `"Div"`), used to attribute
+/// `EvalError`s to the opcode that raised them.
+pub(crate) fn opcode_name(op: &Opcode) -> String {
+    let debug = format!("{:?}", op);
+    let end = debug
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(debug.len());
+    debug[..end].to_string()
+}
+
+
 /// Single instruction in a compiled `Program`. The VM executes a flat
 /// `Arc<[Opcode]>` slice iteratively; no per-opcode stack frames.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Opcode {
     /// Push the literal `null` value onto the stack.
     PushNull,
@@ -332,12 +351,16 @@ pub enum Opcode {
 
     /// Guard that fires when a `DELETE` sentinel reaches execution outside a patch context.
     DeleteMarkErr,
+    /// Emitted in place of a sub-expression nested deeper than the compiler's
+    /// limit (the payload); fails with `LimitExceeded` when executed.
+    NestingLimitErr(usize),
 }
 
 
 /// A compiled, immutable bytecode program. Shared between the compile cache and
 /// the path-resolution cache via `Arc`; cloning is O(1).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "cold_ics::ProgramParts")]
 pub struct Program {
     /// The flat opcode slice executed by the VM.
     pub ops: Arc<[Opcode]>,
@@ -352,6 +375,7 @@ pub struct Program {
 
     /// Per-opcode inline-cache slots (one `AtomicU64` per opcode); used by `GetField`
     /// and `OptField` to remember the last-seen map index.
+    #[serde(skip_serializing)]
     pub ics: Arc<[AtomicU64]>,
 }
 
@@ -363,7 +387,7 @@ pub struct Program {
 /// multiple ops over disjoint or sibling fields, the executor compiles the
 /// op list into a `CompiledPatchTrie` once and reuses it on subsequent
 /// invocations to amortise the trie-build cost across cache hits.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CompiledPatch {
     /// Program that yields the base document to patch.
     pub root_prog: Arc<Program>,
@@ -373,6 +397,7 @@ pub struct CompiledPatch {
     /// only `Field`/`Index`/`DynIndex` path steps with no `cond` guard.
     /// `None` after build means the op set is not trie-eligible and the
     /// per-op fallback should always be used.
+    #[serde(skip)]
     pub trie: OnceLock<Option<CompiledPatchTrie>>,
 }
 
@@ -391,7 +416,7 @@ impl Clone for CompiledPatch {
 
 /// A single field-mutation within a `CompiledPatch`: a path, a replacement/delete
 /// value, and an optional runtime guard condition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompiledPatchOp {
     /// Sequence of path steps that locate the target node in the document.
     pub path: Vec<CompiledPathStep>,
@@ -402,7 +427,7 @@ pub struct CompiledPatchOp {
 }
 
 /// The replacement action for a single `CompiledPatchOp`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompiledPatchVal {
     /// Replace the node with the result of evaluating this program; `@` is the old value.
     Replace(Arc<Program>),
@@ -411,7 +436,7 @@ pub enum CompiledPatchVal {
 }
 
 /// A single step in the path portion of a `CompiledPatchOp`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompiledPathStep {
     /// Navigate into an object field by name.
     Field(Arc<str>),
@@ -626,12 +651,109 @@ impl Program {
             ics,
         }
     }
+
+    /// Return `true` when `pred` holds for some method call in this program
+    /// or any program nested inside it.
+    pub(crate) fn any_call(&self, pred: &mut dyn FnMut(&CompiledCall) -> bool) -> bool {
+        for op in self.ops.iter() {
+            if let Opcode::CallMethod(call) | Opcode::CallOptMethod(call) = op {
+                if pred(call) {
+                    return true;
+                }
+            }
+            for prog in op.nested_programs() {
+                if prog.any_call(pred) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
 }
 
 
+impl Opcode {
+    /// Every program embedded directly in this opcode.
+    fn nested_programs(&self) -> Vec<&Program> {
+        let mut out: Vec<&Program> = Vec::new();
+        match self {
+            Opcode::CallMethod(call) | Opcode::CallOptMethod(call) => {
+                out.extend(call.sub_progs.iter().map(|prog| &**prog));
+            }
+            Opcode::DynIndex(prog)
+            | Opcode::InlineFilter(prog)
+            | Opcode::AndOp(prog)
+            | Opcode::OrOp(prog)
+            | Opcode::CoalesceOp(prog)
+            | Opcode::LetExpr { body: prog, .. } => out.push(prog),
+            Opcode::IfElse { then_, else_ } => out.extend([&**then_, &**else_]),
+            Opcode::TryExpr { body, default } => out.extend([&**body, &**default]),
+            Opcode::MakeArr(items) => out.extend(items.iter().map(|(prog, _)| &**prog)),
+            Opcode::FString(parts) => {
+                for part in parts.iter() {
+                    if let CompiledFSPart::Interp { prog, .. } = part {
+                        out.push(prog);
+                    }
+                }
+            }
+            Opcode::MakeObj(entries) => {
+                for entry in entries.iter() {
+                    match entry {
+                        CompiledObjEntry::Short { .. } | CompiledObjEntry::KvPath { .. } => {}
+                        CompiledObjEntry::Kv { prog, cond, .. } => {
+                            out.push(prog);
+                            out.extend(cond.as_deref());
+                        }
+                        CompiledObjEntry::Dynamic { key, val } => out.extend([&**key, &**val]),
+                        CompiledObjEntry::Spread(prog) | CompiledObjEntry::SpreadDeep(prog) => {
+                            out.push(prog)
+                        }
+                    }
+                }
+            }
+            Opcode::PipelineRun { base, steps } => {
+                out.push(base);
+                for step in steps.iter() {
+                    if let CompiledPipeStep::Forward(prog) = step {
+                        out.push(prog);
+                    }
+                }
+            }
+            Opcode::ListComp(spec) | Opcode::SetComp(spec) => {
+                out.extend([&*spec.expr, &*spec.iter]);
+                out.extend(spec.cond.as_deref());
+            }
+            Opcode::DictComp(spec) => {
+                out.extend([&*spec.key, &*spec.val, &*spec.iter]);
+                out.extend(spec.cond.as_deref());
+            }
+            Opcode::PatchEval(patch) => {
+                out.push(&patch.root_prog);
+                for op in &patch.ops {
+                    for step in &op.path {
+                        if let CompiledPathStep::DynIndex(prog)
+                        | CompiledPathStep::WildcardFilter(prog) = step
+                        {
+                            out.push(prog);
+                        }
+                    }
+                    if let CompiledPatchVal::Replace(prog) = &op.val {
+                        out.push(prog);
+                    }
+                    out.extend(op.cond.as_deref());
+                }
+            }
+            _ => {}
+        }
+        out
+    }
+}
+
 /// Cached pointer-path data for a `FieldChain` opcode. Stores the ordered field
 /// keys and one inline-cache slot per key for fast map-index lookup.
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
+#[serde(from = "Arc<[Arc<str>]>")]
 pub struct FieldChainData {
     /// Ordered sequence of field names traversed by this chain.
     pub keys: Arc<[Arc<str>]>,
@@ -674,6 +796,72 @@ pub fn fresh_ics(len: usize) -> Arc<[AtomicU64]> {
 }
 
 
+/// Serde glue for inline-cache slots, which are never written: cached indices
+/// are only hints about documents seen by the process that wrote them, so
+/// decoding rebuilds cold slots sized from what they index. A decoded program
+/// therefore always has one slot per opcode, whatever its input claimed.
+mod cold_ics {
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{fresh_ics, KvStep, Opcode, Program};
+
+    /// `Program` as written: every field except the slots.
+    #[derive(Deserialize)]
+    pub struct ProgramParts {
+        ops: Arc<[Opcode]>,
+        source: Arc<str>,
+        id: u64,
+        is_structural: bool,
+    }
+
+    impl From<ProgramParts> for Program {
+        fn from(parts: ProgramParts) -> Self {
+            Self {
+                ics: fresh_ics(parts.ops.len()),
+                ops: parts.ops,
+                source: parts.source,
+                id: parts.id,
+                is_structural: parts.is_structural,
+            }
+        }
+    }
+
+    pub fn serialize_kv_path<S: Serializer>(
+        key: &Arc<str>,
+        steps: &Arc<[KvStep]>,
+        optional: &bool,
+        _ics: &Arc<[AtomicU64]>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        (key, steps, optional).serialize(s)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn deserialize_kv_path<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<(Arc<str>, Arc<[KvStep]>, bool, Arc<[AtomicU64]>), D::Error> {
+        let (key, steps, optional) = <(Arc<str>, Arc<[KvStep]>, bool)>::deserialize(d)?;
+        let ics = fresh_ics(steps.len());
+        Ok((key, steps, optional, ics))
+    }
+}
+
+impl From<Arc<[Arc<str>]>> for FieldChainData {
+    fn from(keys: Arc<[Arc<str>]>) -> Self {
+        Self::new(keys)
+    }
+}
+
+/// Written as its keys alone; see `cold_ics`.
+impl Serialize for FieldChainData {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.keys.serialize(s)
+    }
+}
+
 /// Return `true` when the `JETRO_DISABLE_OPCODE_FUSION` environment variable is set,
 /// suppressing all peephole fusion passes for debugging purposes.
 #[inline]
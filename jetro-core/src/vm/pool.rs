@@ -0,0 +1,110 @@
+//! Pool of `VM`s owned by a `JetroEngine`.
+//!
+//! Each `collect` checks a `VM` out for the duration of the call and runs the
+//! plan's VM fallback nodes on it, so concurrent callers run on separate VMs
+//! instead of queueing behind one, and a VM's caches and buffers outlive the
+//! call. Expressions the VMs compile from source go into one shared
+//! `ProgramCache`, so an expression compiled on one thread is reused on every
+//! other.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::exec::{ProgramCache, VM};
+
+/// Compile-cache capacity shared by the pool; matches a standalone `VM::new()`.
+const COMPILE_CAP: usize = 512;
+/// Path-cache capacity of each pooled `VM`; matches a standalone `VM::new()`.
+const PATH_CAP: usize = 4096;
+
+/// Idle `VM`s plus the compile cache they share.
+pub struct VmPool {
+    /// VMs not currently checked out; a fresh one is built when this is empty.
+    idle: Mutex<Vec<VM>>,
+    /// Compile cache shared by every `VM` this pool builds.
+    programs: Arc<ProgramCache>,
+    /// Most VMs kept idle; extras built under a burst are dropped on return.
+    max_idle: usize,
+}
+
+impl Default for VmPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VmPool {
+    /// Create an empty pool that keeps up to one idle `VM` per available core.
+    pub fn new() -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            programs: Arc::new(ProgramCache::new(COMPILE_CAP)),
+            max_idle: std::thread::available_parallelism().map_or(1, usize::from),
+        }
+    }
+
+    /// Take an idle `VM`, or build one sharing the pool's compile cache. The
+    /// `VM` goes back to the pool when the returned guard drops.
+    pub fn checkout(&self) -> PooledVm<'_> {
+        let vm = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_else(|| VM::with_programs(Arc::clone(&self.programs), PATH_CAP));
+        PooledVm {
+            pool: self,
+            vm: Some(vm),
+        }
+    }
+
+    /// Drop every program in the shared compile cache, so idle and
+    /// checked-out `VM`s alike recompile under the limits now in force.
+    pub fn clear_programs(&self) {
+        self.programs.clear();
+    }
+
+    /// Return the number of programs in the shared compile cache.
+    #[cfg(test)]
+    pub fn compiled_len(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Return the number of idle `VM`s.
+    #[cfg(test)]
+    pub fn idle_len(&self) -> usize {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+}
+
+/// A `VM` checked out of a `VmPool`; returned to the pool on drop.
+pub struct PooledVm<'p> {
+    pool: &'p VmPool,
+    vm: Option<VM>,
+}
+
+impl Deref for PooledVm<'_> {
+    type Target = VM;
+
+    fn deref(&self) -> &VM {
+        self.vm.as_ref().expect("pooled vm present until drop")
+    }
+}
+
+impl DerefMut for PooledVm<'_> {
+    fn deref_mut(&mut self) -> &mut VM {
+        self.vm.as_mut().expect("pooled vm present until drop")
+    }
+}
+
+impl Drop for PooledVm<'_> {
+    fn drop(&mut self) {
+        let Some(vm) = self.vm.take() else {
+            return;
+        };
+        let mut idle = self.pool.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        if idle.len() < self.pool.max_idle {
+            idle.push(vm);
+        }
+    }
+}
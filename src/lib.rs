@@ -4,10 +4,11 @@
 //! provides a minimal `Jetro` handle that accepts raw JSON bytes and surfaces
 //! `collect` as the single query entry point.
 
-pub use jetro_core::EvalError;
+pub use jetro_core::{EvalError, EvalErrorKind, ParseError, Query};
 
 /// Byte-oriented query handle. Wraps `jetro_core::Jetro` and exposes only
-/// the two public entry points needed by end users: `from_bytes` and `collect`.
+/// the entry points needed by end users: `from_bytes`, `collect`, `collect_with`,
+/// and `run`.
 pub struct Jetro {
     /// The underlying core handle that owns the parsed document and all lazy caches.
     inner: jetro_core::Jetro,
@@ -27,4 +28,19 @@ impl Jetro {
     pub fn collect<S: AsRef<str>>(&self, expr: S) -> Result<serde_json::Value, EvalError> {
         self.inner.collect(expr)
     }
+
+    /// Evaluate a Jetro expression with external variables bound as identifiers.
+    pub fn collect_with<'v, S, K, I>(&self, expr: S, vars: I) -> Result<serde_json::Value, EvalError>
+    where
+        S: AsRef<str>,
+        K: AsRef<str>,
+        I: IntoIterator<Item = (K, &'v serde_json::Value)>,
+    {
+        self.inner.collect_with(expr, vars)
+    }
+
+    /// Evaluate a prepared `Query` and return a `serde_json::Value`.
+    pub fn run(&self, query: &Query) -> Result<serde_json::Value, EvalError> {
+        query.run(&self.inner)
+    }
 }